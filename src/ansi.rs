@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::markdown::*;
+
+/// Bytes looked at to tell whether a file holds color escapes.
+const ESCAPE_SNIFF: u64 = 8 * 1024;
+
+/// Whether the file at `path` starts with terminal color escapes in it, as
+/// a log captured from a colored run does.
+pub fn has_escapes(path: &Path) -> bool {
+    let mut head = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(ESCAPE_SNIFF).read_to_end(&mut head));
+    read.is_ok() && head.windows(2).any(|w| w == b"\x1b[")
+}
+
+/// The line with its escapes taken out, the colors and styles they set
+/// kept as marks. Escapes doing anything else, such as moving the cursor,
+/// are dropped. Each line starts out unstyled, whatever the lines before
+/// it left on.
+pub fn render(line: &str) -> Rendered {
+    let mut rendered = String::with_capacity(line.len());
+    let mut marks = Vec::new();
+    let mut style = Style::default();
+    // Where the text in the current style starts, in characters.
+    let (mut start, mut len) = (0, 0);
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            rendered.push(c);
+            len += 1;
+            continue;
+        }
+        match chars.next() {
+            // A control sequence, up to its final letter.
+            Some('[') => {
+                let mut params = String::new();
+                let mut last = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        last = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if last != Some('m') {
+                    continue;
+                }
+                if len > start && style != Style::default() {
+                    marks.push((start..len, Mark::Styled(style)));
+                }
+                start = len;
+                style = apply(style, &params);
+            }
+            // An operating system command, up to the bell or the string
+            // terminator.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if len > start && style != Style::default() {
+        marks.push((start..len, Mark::Styled(style)));
+    }
+    (rendered, marks)
+}
+
+// The style after the select graphic rendition sequence with `params`.
+fn apply(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u8>().unwrap_or_default());
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            38 => match extended(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            48 => match extended(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            39 => Style { fg: None, ..style },
+            49 => Style { bg: None, ..style },
+            _ => style,
+        };
+    }
+    style
+}
+
+// A 256 color or an RGB one, following a 38 or a 48.
+fn extended(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()?)),
+        2 => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
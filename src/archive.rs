@@ -0,0 +1,291 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use crate::compression::{self, Compression};
+use crate::inflate;
+#[cfg(feature = "sqlite")]
+use crate::sqlite;
+
+/// Width of the column of sizes in the list of members.
+pub const SIZE_WIDTH: usize = 12;
+const TAR_BLOCK: usize = 512;
+// Signatures of the records of a zip file.
+const ZIP_END: u32 = 0x0605_4b50;
+const ZIP_ENTRY: u32 = 0x0201_4b50;
+const ZIP_LOCAL: u32 = 0x0403_4b50;
+
+/// Kinds of archives whose members can be listed and read. An SQLite
+/// database, with the `sqlite` feature, is taken as one holding its tables.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Zip,
+    Tar,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl Kind {
+    /// The kind of archive at `path`, told by its extension, that of what
+    /// it holds if it's compressed.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let path = compression::inner_path(path);
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "zip" | "jar" | "war" | "apk" | "whl" | "epub" => Some(Kind::Zip),
+            "tar" => Some(Kind::Tar),
+            #[cfg(feature = "sqlite")]
+            "sqlite" | "sqlite3" | "db" | "db3" => Some(Kind::Sqlite),
+            _ => None,
+        }
+    }
+
+    /// The file name the member `name` is read as. The rows of a table are
+    /// laid out as a TSV file.
+    pub fn member_file(self, name: &str) -> String {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Kind::Sqlite => format!("{name}.tsv"),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// A file or a directory held in an archive.
+pub struct Member {
+    pub name: String,
+    pub size: u64,
+    pub is_dir: bool,
+    // Where it's found in the archive: the local header of a zip entry, or
+    // the data of a tar one.
+    offset: usize,
+    // How a zip entry is compressed, and the bytes it takes.
+    method: u16,
+    compressed: usize,
+}
+
+/// The members of the archive at `path`, in the order they're held in.
+pub fn members(kind: Kind, path: &Path) -> anyhow::Result<Vec<Member>> {
+    let data = load(path)?;
+    match kind {
+        Kind::Zip => zip_members(&data),
+        Kind::Tar => tar_members(&data),
+        #[cfg(feature = "sqlite")]
+        Kind::Sqlite => Ok(sqlite::tables(&data)?
+            .into_iter()
+            .map(|(name, rows)| Member {
+                name,
+                size: rows,
+                is_dir: false,
+                offset: 0,
+                method: 0,
+                compressed: 0,
+            })
+            .collect()),
+    }
+}
+
+/// The statements the database at `path` was created by.
+#[cfg(feature = "sqlite")]
+pub fn schema(path: &Path) -> anyhow::Result<Vec<String>> {
+    sqlite::schema(&load(path)?)
+}
+
+/// The bytes of the member `name` of the archive at `path`, uncompressed.
+pub fn read(kind: Kind, path: &Path, name: &str) -> anyhow::Result<Vec<u8>> {
+    let data = load(path)?;
+    let members = match kind {
+        Kind::Zip => zip_members(&data)?,
+        Kind::Tar => tar_members(&data)?,
+        #[cfg(feature = "sqlite")]
+        Kind::Sqlite => return sqlite::rows(&data, name),
+    };
+    let member = members
+        .iter()
+        .find(|m| m.name == name && !m.is_dir)
+        .ok_or_else(|| anyhow!("No {name} in the archive"))?;
+    match kind {
+        Kind::Zip => zip_data(&data, member),
+        _ => member
+            .offset
+            .checked_add(member.size as usize)
+            .and_then(|end| data.get(member.offset..end))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| anyhow!("{name} runs past the end of the archive")),
+    }
+}
+
+/// A line of the list of members, its size before its name.
+pub fn listing(member: &Member) -> String {
+    match member.is_dir {
+        true => format!("{:>SIZE_WIDTH$}  {}", "", member.name),
+        false => format!("{:>SIZE_WIDTH$}  {}", member.size, member.name),
+    }
+}
+
+// The bytes of the archive at `path`, decompressed if it's compressed.
+fn load(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    match Compression::for_path(path) {
+        Some(compression) => compression.decompress(&data),
+        None => Ok(data),
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> anyhow::Result<u16> {
+    let bytes = data
+        .get(at..at + 2)
+        .ok_or_else(|| anyhow!("Zip record runs past the end"))?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], at: usize) -> anyhow::Result<u32> {
+    let bytes = data
+        .get(at..at + 4)
+        .ok_or_else(|| anyhow!("Zip record runs past the end"))?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// The entries of the central directory of a zip file, which the record
+// ending the file points to.
+fn zip_members(data: &[u8]) -> anyhow::Result<Vec<Member>> {
+    // The end record is followed by a comment of up to 64 KiB.
+    let lowest = data.len().saturating_sub(22 + 0xFFFF);
+    let end = (lowest..data.len().saturating_sub(21))
+        .rev()
+        .find(|&at| u32_at(data, at).is_ok_and(|sig| sig == ZIP_END))
+        .ok_or_else(|| anyhow!("Not a zip file"))?;
+    let count = u16_at(data, end + 10)? as usize;
+    let mut at = u32_at(data, end + 16)? as usize;
+    let mut members = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(data, at)? != ZIP_ENTRY {
+            return Err(anyhow!("Damaged zip directory"));
+        }
+        let method = u16_at(data, at + 10)?;
+        let compressed = u32_at(data, at + 20)? as usize;
+        let size = u64::from(u32_at(data, at + 24)?);
+        let name_len = u16_at(data, at + 28)? as usize;
+        let extra_len = u16_at(data, at + 30)? as usize;
+        let comment_len = u16_at(data, at + 32)? as usize;
+        let offset = u32_at(data, at + 42)? as usize;
+        let name = data
+            .get(at + 46..at + 46 + name_len)
+            .ok_or_else(|| anyhow!("Zip record runs past the end"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        members.push(Member {
+            is_dir: name.ends_with('/'),
+            name,
+            size,
+            offset,
+            method,
+            compressed,
+        });
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(members)
+}
+
+fn zip_data(data: &[u8], member: &Member) -> anyhow::Result<Vec<u8>> {
+    let at = member.offset;
+    if u32_at(data, at)? != ZIP_LOCAL {
+        return Err(anyhow!("Damaged zip entry for {}", member.name));
+    }
+    // The local header has its own lengths of the name and the extra field.
+    let start = at + 30 + u16_at(data, at + 26)? as usize + u16_at(data, at + 28)? as usize;
+    let bytes = data
+        .get(start..start + member.compressed)
+        .ok_or_else(|| anyhow!("{} runs past the end of the archive", member.name))?;
+    match member.method {
+        0 => Ok(bytes.to_vec()),
+        8 => inflate::inflate(bytes),
+        method => Err(anyhow!(
+            "{} is compressed with method {method}",
+            member.name
+        )),
+    }
+}
+
+// The headers of a tar file, each followed by the data of its member.
+fn tar_members(data: &[u8]) -> anyhow::Result<Vec<Member>> {
+    let mut members = Vec::new();
+    let mut at = 0;
+    // A name too long for its header, from the GNU or pax one before it.
+    let mut long_name = None;
+    while let Some(header) = data.get(at..at + TAR_BLOCK) {
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let size = tar_number(&header[124..136]).ok_or_else(|| anyhow!("Not a tar file"))?;
+        let start = at + TAR_BLOCK;
+        let body = start
+            .checked_add(size as usize)
+            .and_then(|end| data.get(start..end))
+            .ok_or_else(|| anyhow!("Tar entry runs past the end"))?;
+        at = start + (size as usize).div_ceil(TAR_BLOCK) * TAR_BLOCK;
+        match header[156] {
+            b'L' => {
+                long_name = Some(text(body));
+                continue;
+            }
+            b'x' => {
+                long_name = pax_path(body).or(long_name);
+                continue;
+            }
+            b'g' => continue,
+            _ => {}
+        }
+        let name = long_name.take().unwrap_or_else(|| {
+            let name = text(&header[..100]);
+            // The ustar format keeps the start of a long name apart.
+            match &header[257..262] == b"ustar" && header[345] != 0 {
+                true => format!("{}/{name}", text(&header[345..500])),
+                false => name,
+            }
+        });
+        let is_dir = header[156] == b'5' || name.ends_with('/');
+        // Links and devices hold no data of their own to show.
+        if !is_dir && !matches!(header[156], b'0' | 0 | b'7') {
+            continue;
+        }
+        members.push(Member {
+            name,
+            size: if is_dir { 0 } else { size },
+            is_dir,
+            offset: start,
+            method: 0,
+            compressed: size as usize,
+        });
+    }
+    Ok(members)
+}
+
+// A number of a tar header, in octal digits or else as big endian bytes
+// after a set high bit.
+fn tar_number(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        let value = field[1..].iter().fold(0u64, |n, &b| n << 8 | u64::from(b));
+        return Some(value);
+    }
+    let digits = text(field);
+    let digits = digits.trim_matches(|c: char| c == ' ' || c == '\0');
+    match digits.is_empty() {
+        true => Some(0),
+        false => u64::from_str_radix(digits, 8).ok(),
+    }
+}
+
+// The `path` record of a pax header, whose records read `<len> path=<name>`.
+fn pax_path(body: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(body)
+        .lines()
+        .find_map(|record| record.split_once(' ')?.1.strip_prefix("path="))
+        .map(str::to_string)
+}
+
+// A field of a tar header, up to its first NUL.
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
@@ -0,0 +1,64 @@
+use std::time::Instant;
+
+/// Step by which `+` and `-` change the rate, in lines per second.
+const RATE_STEP: f64 = 0.5;
+const MAX_RATE: f64 = 60.0;
+
+/// Hands-free reading mode, scrolling the View pane at a steady rate.
+pub struct AutoScroll {
+    running: bool,
+    // Lines per second.
+    rate: f64,
+    last: Instant,
+    // Fraction of a line scrolled by the ticks so far.
+    carry: f64,
+}
+
+impl AutoScroll {
+    pub fn new(rate: f64) -> Self {
+        AutoScroll {
+            running: false,
+            rate: rate.clamp(RATE_STEP, MAX_RATE),
+            last: Instant::now(),
+            carry: 0.0,
+        }
+    }
+
+    /// Start scrolling, or pause it keeping the current rate.
+    pub fn toggle(&mut self) {
+        self.running = !self.running;
+        self.last = Instant::now();
+        self.carry = 0.0;
+    }
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    #[inline]
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn faster(&mut self) {
+        self.rate = (self.rate + RATE_STEP).min(MAX_RATE);
+    }
+
+    pub fn slower(&mut self) {
+        self.rate = (self.rate - RATE_STEP).max(RATE_STEP);
+    }
+
+    /// The number of whole lines to scroll since the last tick.
+    pub fn tick(&mut self) -> usize {
+        if !self.running {
+            return 0;
+        }
+        let now = Instant::now();
+        self.carry += now.duration_since(self.last).as_secs_f64() * self.rate;
+        self.last = now;
+        let lines = self.carry.trunc();
+        self.carry -= lines;
+        lines as usize
+    }
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use crate::store::*;
+
+const BOOKMARKS_FILE: &str = "bookmarks";
+
+/// A named line of a file.
+#[derive(Clone)]
+pub struct Bookmark {
+    pub name: String,
+    pub line: usize,
+    pub path: PathBuf,
+}
+
+impl Bookmark {
+    // One bookmark per line: `name<TAB>line<TAB>path`.
+    fn parse(entry: &str) -> Option<Self> {
+        let mut fields = entry.splitn(3, '\t');
+        let name = fields.next()?.to_string();
+        let line = fields.next()?.parse().ok()?;
+        let path = PathBuf::from(fields.next()?);
+        Some(Bookmark { name, line, path })
+    }
+
+    fn serialize(&self) -> String {
+        format!("{}\t{}\t{}", self.name, self.line, self.path.display())
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}  {}:{}", self.name, self.path.display(), self.line)
+    }
+}
+
+/// Bookmarks persisted in the data directory so they outlive the session.
+#[derive(Default)]
+pub struct Bookmarks {
+    list: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Read the bookmarks file. Having none yet isn't an error.
+    pub fn load() -> anyhow::Result<Self> {
+        let file = data_file(BOOKMARKS_FILE)?;
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(anyhow!("Couldn't read bookmarks due to: {err}")),
+        };
+        let list = content.lines().filter_map(Bookmark::parse).collect();
+        Ok(Bookmarks { list })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let file = data_file(BOOKMARKS_FILE)?;
+        let content = self
+            .list
+            .iter()
+            .map(|b| b.serialize() + "\n")
+            .collect::<String>();
+        fs::write(file, content).map_err(|err| anyhow!("Couldn't save bookmarks due to: {err}"))
+    }
+
+    /// Add a bookmark, replacing the one with the same name.
+    pub fn add(&mut self, bookmark: Bookmark) {
+        match self.list.iter_mut().find(|b| b.name == bookmark.name) {
+            Some(old) => *old = bookmark,
+            None => self.list.push(bookmark),
+        }
+    }
+
+    /// Point the bookmarks of the file at `from` to `to`, after it moved.
+    /// Returns whether there were any.
+    pub fn rename_path(&mut self, from: &Path, to: &Path) -> bool {
+        let mut renamed = false;
+        for bookmark in self.list.iter_mut().filter(|b| b.path == from) {
+            bookmark.path = to.to_path_buf();
+            renamed = true;
+        }
+        renamed
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.list.len() {
+            self.list.remove(index);
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&Bookmark> {
+        self.list.get(index)
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.list.iter().map(Bookmark::label).collect()
+    }
+}
@@ -0,0 +1,46 @@
+const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Line of the delimiter matching the first bracket on `line`. Opening
+/// brackets are matched forwards and closing ones backwards. Only brackets of
+/// the same kind are counted, strings and comments aren't treated specially.
+pub fn matching_bracket(lines: &[String], line: usize) -> Option<usize> {
+    let text = lines.get(line)?;
+    let (col, bracket) = text
+        .char_indices()
+        .find(|(_, c)| PAIRS.iter().any(|&(o, e)| *c == o || *c == e))?;
+    let &(open, close) = PAIRS.iter().find(|&&(o, e)| bracket == o || bracket == e)?;
+
+    let mut depth = 0usize;
+    if bracket == open {
+        let rest =
+            std::iter::once(&text[col..]).chain(lines[line + 1..].iter().map(String::as_str));
+        for (i, part) in rest.enumerate() {
+            for c in part.chars() {
+                if c == open {
+                    depth += 1;
+                } else if c == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(line + i);
+                    }
+                }
+            }
+        }
+    } else {
+        let before = std::iter::once(&text[..col + close.len_utf8()])
+            .chain(lines[..line].iter().rev().map(String::as_str));
+        for (i, part) in before.enumerate() {
+            for c in part.chars().rev() {
+                if c == close {
+                    depth += 1;
+                } else if c == open {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(line - i);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,105 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use ratatui::style::{Color, Style};
+
+/// Control characters told apart on the status line at most, for the
+/// cursor line.
+const MAX_LISTED: usize = 3;
+
+/// Whether `c` would move the cursor or change the terminal if it was
+/// written out as it is. Tabs are expanded instead.
+pub fn is_control(c: char) -> bool {
+    c.is_control() && c != '\t'
+}
+
+/// How the control character `c` is shown: `^A` through `^_` for the C0
+/// ones, `^?` for delete and `<9b>` for the C1 ones.
+pub fn notation(c: char) -> String {
+    match c as u32 {
+        n @ 0..=0x1f => format!("^{}", char::from(n as u8 + 0x40)),
+        0x7f => "^?".to_string(),
+        n => format!("<{n:02x}>"),
+    }
+}
+
+/// The line with its control characters in their notations, in `fg` over
+/// the background of the matches over them or else of `style`, and the
+/// ranges of characters of the matches moved along.
+pub fn notate<'a>(
+    line: &'a str,
+    matches: Vec<(Range<usize>, Style)>,
+    style: Style,
+    fg: Color,
+) -> (Cow<'a, str>, Vec<(Range<usize>, Style)>) {
+    if !line.chars().any(is_control) {
+        return (Cow::Borrowed(line), matches);
+    }
+    let mut notated = String::with_capacity(line.len() + 8);
+    // The character of the notated line each character ends up at, and
+    // where the line ends.
+    let mut starts = Vec::with_capacity(line.len() + 1);
+    let mut controls = Vec::new();
+    let mut index = 0;
+    for (i, c) in line.chars().enumerate() {
+        starts.push(index);
+        match is_control(c) {
+            true => {
+                let notation = notation(c);
+                let len = notation.chars().count();
+                notated.push_str(&notation);
+                controls.push((i, index..index + len));
+                index += len;
+            }
+            false => {
+                notated.push(c);
+                index += 1;
+            }
+        }
+    }
+    starts.push(index);
+    let at = |i: usize| starts.get(i).copied().unwrap_or(index);
+    let glyphs = controls.into_iter().map(|(i, range)| {
+        let under = matches
+            .iter()
+            .find(|(m, _)| m.contains(&i))
+            .map_or(style, |&(_, s)| s);
+        (range, under.fg(fg))
+    });
+    let mut notated_matches = glyphs.collect::<Vec<_>>();
+    notated_matches.extend(
+        matches
+            .iter()
+            .map(|(range, s)| (at(range.start)..at(range.end), *s)),
+    );
+    (Cow::Owned(notated), notated_matches)
+}
+
+/// The line with its control characters in their notations, as for
+/// `notate`.
+pub fn notate_line(line: &str) -> Cow<'_, str> {
+    notate(line, Vec::new(), Style::default(), Color::Reset).0
+}
+
+/// The control characters of `line` with their codes, such as `^[ 0x1b`,
+/// each told once.
+pub fn describe(line: &str) -> Option<String> {
+    let mut listed = Vec::new();
+    for c in line.chars().filter(|&c| is_control(c)) {
+        if !listed.contains(&c) {
+            listed.push(c);
+        }
+    }
+    if listed.is_empty() {
+        return None;
+    }
+    let mut described = listed
+        .iter()
+        .take(MAX_LISTED)
+        .map(|&c| format!("{} 0x{:02x}", notation(c), c as u32))
+        .collect::<Vec<_>>();
+    if listed.len() > MAX_LISTED {
+        described.push("…".to_string());
+    }
+    Some(described.join(", "))
+}
@@ -0,0 +1,68 @@
+use std::env;
+use std::io::{stdout, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::anyhow;
+
+/// Clipboard tools tried in order, the first one that works wins.
+const TOOLS: [(&str, &[&str]); 4] = [
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("pbcopy", &[]),
+];
+
+/// Put `text` into the system clipboard. Over SSH, or without a clipboard
+/// tool, the terminal is asked to do it through an OSC 52 sequence.
+pub fn copy(text: &str) -> anyhow::Result<()> {
+    let remote = env::var_os("SSH_TTY").is_some() || env::var_os("SSH_CONNECTION").is_some();
+    if !remote
+        && TOOLS
+            .iter()
+            .any(|(tool, args)| run(tool, args, text).is_ok())
+    {
+        return Ok(());
+    }
+    osc52(text)
+}
+
+fn run(tool: &str, args: &[&str], text: &str) -> anyhow::Result<()> {
+    let mut child = Command::new(tool)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    match child.wait()?.success() {
+        true => Ok(()),
+        false => Err(anyhow!("{tool} failed")),
+    }
+}
+
+fn osc52(text: &str) -> anyhow::Result<()> {
+    let mut out = stdout();
+    write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))
+        .and_then(|_| out.flush())
+        .map_err(|err| anyhow!("Couldn't write to the terminal due to: {err}"))
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,311 @@
+use std::ops::Range;
+
+use anyhow::anyhow;
+
+/// Edits between the two files looked for at most, somewhat beyond which
+/// going through every file line by line takes too long.
+const MAX_EDITS: usize = 4_000;
+/// Number of columns a single horizontal scroll moves both sides by.
+const HSCROLL_STEP: usize = 4;
+
+/// How a row of the comparison differs between the two sides.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Same,
+    /// The line on the left was changed into the one on the right.
+    Changed,
+    /// Only on the left.
+    Removed,
+    /// Only on the right.
+    Added,
+}
+
+/// A row of the comparison, the 0-based lines it shows on either side.
+#[derive(Clone, Copy)]
+pub struct Row {
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+    pub kind: Kind,
+}
+
+// A step of the edit script turning the left lines into the right ones.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Two files shown side by side in the View pane, lined up row by row so
+/// that what's the same on both sides stays level while scrolling.
+pub struct Compare {
+    names: (String, String),
+    left: Vec<String>,
+    right: Vec<String>,
+    rows: Vec<Row>,
+    // The row at the top of the View pane.
+    top: usize,
+    hscroll: usize,
+    height: usize,
+}
+
+impl Compare {
+    pub fn new(
+        (left_name, left): (&str, &[String]),
+        (right_name, right): (&str, &[String]),
+    ) -> anyhow::Result<Self> {
+        let edits = edit_script(left, right)
+            .ok_or_else(|| anyhow!("{left_name} and {right_name} differ too much to compare"))?;
+        Ok(Compare {
+            names: (left_name.to_string(), right_name.to_string()),
+            left: left.to_vec(),
+            right: right.to_vec(),
+            rows: rows(&edits),
+            top: 0,
+            hscroll: 0,
+            height: 0,
+        })
+    }
+
+    #[inline]
+    pub fn names(&self) -> (&str, &str) {
+        (&self.names.0, &self.names.1)
+    }
+
+    /// The rows from the top of the View pane onwards.
+    pub fn rows(&self) -> &[Row] {
+        &self.rows[self.top.min(self.rows.len())..]
+    }
+
+    #[inline]
+    pub fn hscroll(&self) -> usize {
+        self.hscroll
+    }
+
+    /// Number of lines on the left and on the right.
+    pub fn line_counts(&self) -> (usize, usize) {
+        (self.left.len(), self.right.len())
+    }
+
+    pub fn left_line(&self, index: usize) -> &str {
+        self.left.get(index).map_or("", String::as_str)
+    }
+
+    pub fn right_line(&self, index: usize) -> &str {
+        self.right.get(index).map_or("", String::as_str)
+    }
+
+    /// Number of places the files differ, each a run of rows that aren't
+    /// the same.
+    pub fn changes(&self) -> usize {
+        self.starts().count()
+    }
+
+    /// The rows showing on the screen, as the View pane was last drawn.
+    pub fn set_height(&mut self, height: usize) {
+        self.height = height;
+        self.top = self.top.min(self.last_top());
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.top = self.top.saturating_add(rows).min(self.last_top());
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.top = self.top.saturating_sub(rows);
+    }
+
+    #[inline]
+    pub fn page(&self) -> usize {
+        self.height.max(1)
+    }
+
+    pub fn to_top(&mut self) {
+        self.top = 0;
+    }
+
+    pub fn to_bottom(&mut self) {
+        self.top = self.last_top();
+    }
+
+    pub fn scroll_left(&mut self) {
+        self.hscroll = self.hscroll.saturating_sub(HSCROLL_STEP);
+    }
+
+    /// Scrolling stops once the longest line on either side is about to
+    /// disappear.
+    pub fn scroll_right(&mut self) {
+        let longest = self
+            .left
+            .iter()
+            .chain(&self.right)
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or_default();
+        if self.hscroll + HSCROLL_STEP < longest {
+            self.hscroll += HSCROLL_STEP;
+        }
+    }
+
+    /// Scroll the next place the files differ below the top row to the top,
+    /// or the previous one when not going `forward`. Returns false if there
+    /// is none.
+    pub fn next_change(&mut self, forward: bool) -> bool {
+        let top = self.top;
+        let start = match forward {
+            true => self.starts().find(|&start| start > top),
+            false => self.starts().filter(|&start| start < top).last(),
+        };
+        match start {
+            Some(start) => {
+                self.top = start.min(self.last_top());
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The rows starting a run of rows that aren't the same.
+    fn starts(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.rows.len()).filter(|&i| {
+            self.rows[i].kind != Kind::Same && (i == 0 || self.rows[i - 1].kind == Kind::Same)
+        })
+    }
+
+    fn last_top(&self) -> usize {
+        self.rows.len().saturating_sub(self.height)
+    }
+}
+
+/// The parts of two lines that differ, in characters, once what they start
+/// and end with alike is left out.
+pub fn changed_ranges(left: &str, right: &str) -> (Range<usize>, Range<usize>) {
+    let left = left.chars().collect::<Vec<_>>();
+    let right = right.chars().collect::<Vec<_>>();
+    let prefix = left.iter().zip(&right).take_while(|(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    (prefix..left.len() - suffix, prefix..right.len() - suffix)
+}
+
+// Line up the lines kept, and pair the lines deleted with those inserted in
+// their place as changed ones.
+fn rows(edits: &[Edit]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    let (mut left, mut right) = (0, 0);
+    let mut i = 0;
+    while i < edits.len() {
+        if edits[i] == Edit::Keep {
+            rows.push(Row {
+                left: Some(left),
+                right: Some(right),
+                kind: Kind::Same,
+            });
+            (left, right, i) = (left + 1, right + 1, i + 1);
+            continue;
+        }
+        let run = edits[i..].iter().take_while(|&&e| e != Edit::Keep).count();
+        let deleted = edits[i..i + run]
+            .iter()
+            .filter(|&&e| e == Edit::Delete)
+            .count();
+        let inserted = run - deleted;
+        for k in 0..deleted.max(inserted) {
+            let (l, r) = (
+                (k < deleted).then_some(left + k),
+                (k < inserted).then_some(right + k),
+            );
+            let kind = match (l, r) {
+                (Some(_), Some(_)) => Kind::Changed,
+                (Some(_), None) => Kind::Removed,
+                _ => Kind::Added,
+            };
+            rows.push(Row {
+                left: l,
+                right: r,
+                kind,
+            });
+        }
+        (left, right, i) = (left + deleted, right + inserted, i + run);
+    }
+    rows
+}
+
+// The shortest edit script from `a` to `b` as found by Myers' algorithm,
+// none if it takes more than `MAX_EDITS` edits.
+fn edit_script(a: &[String], b: &[String]) -> Option<Vec<Edit>> {
+    // What both start and end with is kept as it is.
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    // The furthest points reached on each diagonal before every round, to
+    // walk back through.
+    let mut trace = Vec::new();
+    let mut found = false;
+    'search: for d in 0..=max.min(MAX_EDITS) as isize {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = |k: isize| (offset + k) as usize;
+            let mut x = match k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                true => v[at(k + 1)],
+                false => v[at(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                (x, y) = (x + 1, y + 1);
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let get = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let prev_k = match k == -d || (k != d && get(k - 1) < get(k + 1)) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let prev_x = if d == 0 { 0 } else { get(prev_k) };
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Keep);
+            (x, y) = (x - 1, y - 1);
+        }
+        if d > 0 {
+            edits.push(if x == prev_x {
+                Edit::Insert
+            } else {
+                Edit::Delete
+            });
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    edits.extend(std::iter::repeat_n(Edit::Keep, prefix));
+    edits.reverse();
+    edits.extend(std::iter::repeat_n(Edit::Keep, suffix));
+    Some(edits)
+}
@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+use crate::{inflate, xz, zstd};
+
+/// Ways a file can be compressed and still be read, decompressed as it's
+/// loaded.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// The compression of the file at `path`, told by its extension.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gz" | "tgz" => Some(Compression::Gzip),
+            "zst" | "tzst" => Some(Compression::Zstd),
+            "xz" | "txz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// The extension it's known by.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+            Compression::Xz => "xz",
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => inflate::gzip(data),
+            Compression::Zstd => zstd::decompress(data),
+            Compression::Xz => xz::decompress(data),
+        }
+    }
+}
+
+/// The path of what's compressed at `path`, without the extension of its
+/// compression, by which its kind is told. `app.log.gz` holds `app.log`, and
+/// `src.tgz` holds `src.tar`.
+pub fn inner_path(path: &Path) -> PathBuf {
+    let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+        return path.to_path_buf();
+    };
+    match extension.to_ascii_lowercase().as_str() {
+        "gz" | "zst" | "xz" => path.with_extension(""),
+        "tgz" | "tzst" | "txz" => path.with_extension("tar"),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::icon::*;
+use crate::logs::*;
+use crate::search::*;
+use crate::store::*;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// How line numbers are shown in the gutter of the View pane.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbers {
+    Off,
+    Absolute,
+    /// Distance from the current line, which itself keeps its absolute number.
+    Relative,
+}
+
+impl LineNumbers {
+    fn next(self) -> Self {
+        match self {
+            LineNumbers::Off => LineNumbers::Absolute,
+            LineNumbers::Absolute => LineNumbers::Relative,
+            LineNumbers::Relative => LineNumbers::Off,
+        }
+    }
+}
+
+/// Settings shared by every file buffer in the View pane. They are read from
+/// `config.toml` in the configuration directory, missing keys keeping their
+/// defaults.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    line_numbers: LineNumbers,
+    minimap: bool,
+    /// Draw spaces, tabs and line endings as glyphs.
+    invisibles: bool,
+    smooth_scroll: bool,
+    /// Lines per second scrolled by the reading mode.
+    autoscroll_rate: f64,
+    sticky_header: bool,
+    /// Highlight the other occurrences of the word under the cursor.
+    highlight_word: bool,
+    /// Lines shown around every match of a search across files.
+    search_context: usize,
+    /// Bytes a file may have at most to be added along with a directory.
+    max_file_size: u64,
+    /// Color code files by their language.
+    syntax: bool,
+    /// Columns between the tab stops tabs are expanded to.
+    tab_width: usize,
+    /// Tab widths of their own for files with these extensions, or names.
+    tab_widths: HashMap<String, usize>,
+    /// Column a ruler is drawn past in the View pane, none if 0.
+    ruler: usize,
+    /// Rulers of their own for files with these extensions, or names.
+    rulers: HashMap<String, usize>,
+    /// The file type shown in front of the names in the Files pane.
+    icons: Icons,
+    /// Styles for what the lines of log files match, over the built-in ones.
+    highlights: Vec<HighlightRule>,
+    searches: Vec<SavedSearch>,
+}
+
+// Wraps a saved search into a `[[searches]]` table.
+#[derive(Serialize)]
+struct SavedSearches<'a> {
+    searches: [&'a SavedSearch; 1],
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            line_numbers: LineNumbers::Absolute,
+            minimap: false,
+            invisibles: false,
+            smooth_scroll: false,
+            autoscroll_rate: 2.0,
+            sticky_header: true,
+            highlight_word: false,
+            search_context: 0,
+            max_file_size: 1024 * 1024,
+            syntax: true,
+            tab_width: 8,
+            tab_widths: HashMap::new(),
+            ruler: 0,
+            rulers: HashMap::new(),
+            icons: Icons::Off,
+            highlights: Vec::new(),
+            searches: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    #[inline]
+    pub fn new() -> Self {
+        Config::default()
+    }
+
+    /// Read the configuration file. Not having one isn't an error.
+    pub fn load() -> anyhow::Result<Self> {
+        let file = config_dir()?.join(CONFIG_FILE);
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+            Err(err) => return Err(anyhow!("Couldn't read {} due to: {err}", file.display())),
+        };
+        let mut config: Config = toml::from_str(&content)
+            .map_err(|err| anyhow!("Couldn't parse {} due to: {err}", file.display()))?;
+        // A search saved again under the same name replaces the earlier one.
+        let searches = std::mem::take(&mut config.searches);
+        for search in searches {
+            config.add_search(search);
+        }
+        Ok(config)
+    }
+
+    #[inline]
+    pub fn line_numbers(&self) -> LineNumbers {
+        self.line_numbers
+    }
+
+    pub fn cycle_line_numbers(&mut self) {
+        self.line_numbers = self.line_numbers.next();
+    }
+
+    #[inline]
+    pub fn minimap(&self) -> bool {
+        self.minimap
+    }
+
+    pub fn toggle_minimap(&mut self) {
+        self.minimap = !self.minimap;
+    }
+
+    #[inline]
+    pub fn invisibles(&self) -> bool {
+        self.invisibles
+    }
+
+    pub fn toggle_invisibles(&mut self) {
+        self.invisibles = !self.invisibles;
+    }
+
+    #[inline]
+    pub fn smooth_scroll(&self) -> bool {
+        self.smooth_scroll
+    }
+
+    #[inline]
+    pub fn autoscroll_rate(&self) -> f64 {
+        self.autoscroll_rate
+    }
+
+    #[inline]
+    pub fn sticky_header(&self) -> bool {
+        self.sticky_header
+    }
+
+    #[inline]
+    pub fn highlight_word(&self) -> bool {
+        self.highlight_word
+    }
+
+    pub fn toggle_highlight_word(&mut self) {
+        self.highlight_word = !self.highlight_word;
+    }
+
+    #[inline]
+    pub fn search_context(&self) -> usize {
+        self.search_context
+    }
+
+    #[inline]
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    #[inline]
+    pub fn syntax(&self) -> bool {
+        self.syntax
+    }
+
+    /// The tab width for the file at `path`, its extension or else its name
+    /// looked up among the tab widths of their own.
+    pub fn tab_width(&self, path: &Path) -> usize {
+        of_file(&self.tab_widths, path).unwrap_or(self.tab_width)
+    }
+
+    /// The column a ruler is drawn past for the file at `path`, looked up
+    /// the way its tab width is.
+    pub fn ruler(&self, path: &Path) -> Option<usize> {
+        Some(of_file(&self.rulers, path).unwrap_or(self.ruler)).filter(|&column| column > 0)
+    }
+
+    #[inline]
+    pub fn icons(&self) -> Icons {
+        self.icons
+    }
+
+    #[inline]
+    pub fn highlights(&self) -> &[HighlightRule] {
+        &self.highlights
+    }
+
+    #[inline]
+    pub fn searches(&self) -> &[SavedSearch] {
+        &self.searches
+    }
+
+    /// Keep `search` for later sessions. It's appended to the configuration
+    /// file so that everything else in there stays as it was written.
+    pub fn save_search(&mut self, search: SavedSearch) -> anyhow::Result<()> {
+        let dir = config_dir()?;
+        fs::create_dir_all(&dir)
+            .map_err(|err| anyhow!("Couldn't create {} due to: {err}", dir.display()))?;
+        let file = dir.join(CONFIG_FILE);
+        let table = toml::to_string(&SavedSearches {
+            searches: [&search],
+        })
+        .map_err(|err| anyhow!("Couldn't write the search due to: {err}"))?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file)
+            .and_then(|mut f| write!(f, "\n{table}"))
+            .map_err(|err| anyhow!("Couldn't save to {} due to: {err}", file.display()))?;
+        self.add_search(search);
+        Ok(())
+    }
+
+    fn add_search(&mut self, search: SavedSearch) {
+        self.searches.retain(|s| s.name != search.name);
+        self.searches.push(search);
+    }
+}
+
+// The setting in `settings` for the file at `path`, by its extension or else
+// its name.
+fn of_file(settings: &HashMap<String, usize>, path: &Path) -> Option<usize> {
+    let key = |name: Option<&std::ffi::OsStr>| {
+        name.and_then(|name| name.to_str())
+            .and_then(|name| settings.get(name))
+    };
+    key(path.extension())
+        .or_else(|| key(path.file_name()))
+        .copied()
+}
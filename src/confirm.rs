@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+/// What's done once a confirmation is answered with yes.
+pub enum Action {
+    /// Create the empty file named and open it.
+    CreateFile(String),
+    /// Remove the file from disk and close it.
+    DeleteFile(PathBuf),
+    /// Add the files, too many of them to do so without asking.
+    AddFiles(Vec<PathBuf>),
+}
+
+/// A yes or no question popping up in front of the panes, before doing
+/// something to the files on disk.
+pub struct Confirm {
+    question: String,
+    action: Action,
+}
+
+impl Confirm {
+    pub fn new(question: impl Into<String>, action: Action) -> Self {
+        Confirm {
+            question: question.into(),
+            action,
+        }
+    }
+
+    #[inline]
+    pub fn question(&self) -> &str {
+        &self.question
+    }
+
+    #[inline]
+    pub fn into_action(self) -> Action {
+        self.action
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes looked at to tell whether a file is a diff.
+const DIFF_SNIFF: u64 = 8 * 1024;
+
+/// What a line of a unified diff is, to color it by.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// The `@@ -1,4 +1,5 @@` line starting a hunk.
+    Hunk,
+    /// The lines naming the files changed, ahead of their hunks.
+    Header,
+}
+
+/// Whether the file at `path` is a diff, told by its extension or else by
+/// the lines it starts with.
+pub fn is_diff(path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str());
+    if extension.is_some_and(|e| ["diff", "patch"].contains(&e.to_ascii_lowercase().as_str())) {
+        return true;
+    }
+    let mut head = Vec::new();
+    if File::open(path)
+        .and_then(|file| file.take(DIFF_SNIFF).read_to_end(&mut head))
+        .is_err()
+    {
+        return false;
+    }
+    head.starts_with(b"diff ")
+        || head.starts_with(b"--- ") && head.windows(5).any(|w| w == b"\n+++ ")
+}
+
+/// Lines of the old and of the new file a hunk has left to go, as told by
+/// its `@@` line. Both are none between hunks.
+#[derive(Clone, Copy, Default)]
+pub struct HunkLeft {
+    old: usize,
+    new: usize,
+}
+
+impl HunkLeft {
+    #[inline]
+    pub fn is_open(self) -> bool {
+        self.old > 0 || self.new > 0
+    }
+
+    /// What's left after `line`, a hunk opening at its `@@` line.
+    pub fn after(self, line: &str) -> HunkLeft {
+        if !self.is_open() {
+            return hunk_header(line).unwrap_or_default();
+        }
+        let old = self.old.saturating_sub(1);
+        let new = self.new.saturating_sub(1);
+        match line.chars().next() {
+            Some('-') => HunkLeft { old, ..self },
+            Some('+') => HunkLeft { new, ..self },
+            Some('\\') => self,
+            // An empty line is one of context whose space was trimmed.
+            Some(' ') | None => HunkLeft { old, new },
+            // The hunk was cut short.
+            Some(_) => HunkLeft::default(),
+        }
+    }
+}
+
+// The lines a hunk starting at `line` spans, such as `@@ -1,4 +1,5 @@`. A
+// range without a count spans a single line.
+fn hunk_header(line: &str) -> Option<HunkLeft> {
+    let mut ranges = line.strip_prefix("@@ ")?.split_whitespace();
+    let old = ranges.next()?.strip_prefix('-')?;
+    let new = ranges.next()?.strip_prefix('+')?;
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    Some(HunkLeft {
+        old: count(old)?,
+        new: count(new)?,
+    })
+}
+
+/// What the line is in a diff, none for a line of context. Within a hunk a
+/// line such as `--- a` is a removed one rather than the name of a file.
+pub fn change(line: &str, in_hunk: bool) -> Option<Change> {
+    const HEADERS: &[&str] = &[
+        "diff ",
+        "index ",
+        "--- ",
+        "+++ ",
+        "new file mode",
+        "deleted file mode",
+        "old mode",
+        "new mode",
+        "similarity index",
+        "rename from",
+        "rename to",
+    ];
+    if !in_hunk && HEADERS.iter().any(|h| line.starts_with(h)) {
+        return Some(Change::Header);
+    }
+    match line.chars().next()? {
+        '+' => Some(Change::Added),
+        '-' => Some(Change::Removed),
+        '@' if line.starts_with("@@") => Some(Change::Hunk),
+        _ => None,
+    }
+}
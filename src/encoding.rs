@@ -0,0 +1,61 @@
+/// How the bytes of a file encode its text, told from its first bytes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    Utf8,
+    /// UTF-8 starting with a byte order mark.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Not text at all.
+    Binary,
+    /// Some 8-bit encoding such as Latin-1, not valid UTF-8.
+    Other,
+}
+
+impl Encoding {
+    /// The encoding of a file starting with `head`, which may end in the
+    /// middle of a character.
+    pub fn detect(head: &[u8]) -> Self {
+        match head {
+            [0xEF, 0xBB, 0xBF, ..] => return Encoding::Utf8Bom,
+            [0xFF, 0xFE, ..] => return Encoding::Utf16Le,
+            [0xFE, 0xFF, ..] => return Encoding::Utf16Be,
+            _ => {}
+        }
+        if head.contains(&0) {
+            return Encoding::Binary;
+        }
+        if head.is_ascii() {
+            return Encoding::Ascii;
+        }
+        match std::str::from_utf8(head) {
+            Ok(_) => Encoding::Utf8,
+            // Only cut short by the end of `head`.
+            Err(err) if err.error_len().is_none() => Encoding::Utf8,
+            // Text in an 8-bit encoding hardly has any control bytes.
+            Err(_) if head.iter().filter(|&&b| is_control(b)).count() * 20 > head.len() => {
+                Encoding::Binary
+            }
+            Err(_) => Encoding::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Ascii => "ascii",
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf8Bom => "utf-8 bom",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Binary => "binary",
+            Encoding::Other => "8-bit",
+        }
+    }
+}
+
+// Whether `byte` is a control character not found in text, unlike tabs,
+// line endings, form feeds and the escapes starting terminal colors.
+fn is_control(byte: u8) -> bool {
+    (byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b)) || byte == 0x7f
+}
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+/// How the lines of a file nest into the blocks a fold hides.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Nesting {
+    /// The lines indented deeper than a line belong to its block.
+    #[default]
+    Indent,
+    /// The lines below a `[section]` belong to it as well, as in TOML or INI
+    /// files, a `[section.sub]` nesting into `[section]`.
+    Sections,
+}
+
+/// Closed folds of a file buffer. A fold hides the lines below its header
+/// line that are indented deeper than the header itself. A filter can hide
+/// every line but the ones it keeps instead, the folds are left alone then.
+#[derive(Clone, Default)]
+pub struct Folds {
+    // Header line of each closed fold mapped to the line right after it.
+    closed: BTreeMap<usize, usize>,
+    // Lines kept by the filter in ascending order.
+    filter: Option<Vec<usize>>,
+}
+
+impl Folds {
+    #[inline]
+    pub fn new() -> Self {
+        Folds::default()
+    }
+
+    /// Open the fold at `line`, or close the block `line` belongs to. Returns
+    /// the header of a fold that was closed.
+    pub fn toggle(&mut self, line: usize, lines: &[String], nesting: Nesting) -> Option<usize> {
+        if self.closed.remove(&line).is_some() {
+            return None;
+        }
+        let depths = depths(lines, nesting);
+        let depth = |i: usize| depths.get(i).copied().flatten();
+        let header = match block_end(depth, depths.len(), line) {
+            Some(_) => line,
+            None => enclosing_header(depth, line)?,
+        };
+        let end = block_end(depth, depths.len(), header)?;
+        self.closed.insert(header, end);
+        Some(header)
+    }
+
+    pub fn open_all(&mut self) {
+        self.closed.clear();
+    }
+
+    /// Open every fold that hides `line`.
+    pub fn reveal(&mut self, line: usize) {
+        self.closed
+            .retain(|&header, &mut end| !(header < line && line < end));
+    }
+
+    /// Hide every line but `lines`, which have to be in ascending order.
+    pub fn set_filter(&mut self, lines: Vec<usize>) {
+        self.filter = Some(lines);
+    }
+
+    /// Keep `lines` as well, which have to come after the ones kept so far.
+    pub fn extend_filter(&mut self, lines: Vec<usize>) {
+        if let Some(filter) = &mut self.filter {
+            filter.extend(lines);
+        }
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+    }
+
+    #[inline]
+    pub fn is_filtered(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    /// Whether the filter hides `line`.
+    pub fn is_filtered_out(&self, line: usize) -> bool {
+        self.filter
+            .as_ref()
+            .is_some_and(|filter| filter.binary_search(&line).is_err())
+    }
+
+    /// Number of lines hidden below `line` if it's the header of a fold.
+    pub fn hidden(&self, line: usize) -> Option<usize> {
+        if self.filter.is_some() || self.header_of(line).is_some() {
+            return None;
+        }
+        self.closed.get(&line).map(|end| end - line - 1)
+    }
+
+    /// The outermost closed fold hiding `line`. While filtering, it's the
+    /// closest line kept above `line`, or below it if there is none.
+    pub fn header_of(&self, line: usize) -> Option<usize> {
+        if let Some(filter) = &self.filter {
+            let index = filter.binary_search(&line).err()?;
+            let above = index.checked_sub(1).map(|i| filter[i]);
+            return above.or_else(|| filter.get(index).copied());
+        }
+        self.closed
+            .range(..line)
+            .find(|(_, &end)| line < end)
+            .map(|(&header, _)| header)
+    }
+
+    /// The first line shown after `line`. While filtering, it's `usize::MAX`
+    /// past the last line kept.
+    pub fn next_visible(&self, line: usize) -> usize {
+        if let Some(filter) = &self.filter {
+            let index = filter.partition_point(|&l| l <= line);
+            return filter.get(index).copied().unwrap_or(usize::MAX);
+        }
+        match self.closed.get(&line) {
+            Some(&end) if self.header_of(line).is_none() => end,
+            _ => line.saturating_add(1),
+        }
+    }
+
+    /// The last line shown before `line`. While filtering, it's `line` itself
+    /// before the first line kept.
+    pub fn prev_visible(&self, line: usize) -> usize {
+        if let Some(filter) = &self.filter {
+            let index = filter.partition_point(|&l| l < line);
+            return index.checked_sub(1).map_or(line, |i| filter[i]);
+        }
+        let prev = line.saturating_sub(1);
+        self.header_of(prev).unwrap_or(prev)
+    }
+}
+
+/// The header of the innermost block `line` belongs to, blank lines counting
+/// towards the block of the line after them.
+pub fn context_of(lines: &[String], line: usize) -> Option<usize> {
+    let line = line
+        + lines
+            .get(line..)?
+            .iter()
+            .position(|l| indent(l).is_some())?;
+    enclosing_header(|i| indent(&lines[i]), line)
+}
+
+// How deep every line is nested, blank lines not being so at all.
+fn depths(lines: &[String], nesting: Nesting) -> Vec<Option<usize>> {
+    let mut section = None;
+    lines
+        .iter()
+        .map(|line| {
+            let depth = indent(line)?;
+            if nesting == Nesting::Indent {
+                return Some(depth);
+            }
+            let header = line.strip_prefix('[').filter(|_| depth == 0);
+            match (header, section) {
+                (Some(header), _) => {
+                    // Nested by the dots in its name.
+                    let level = header
+                        .split(']')
+                        .next()
+                        .unwrap_or_default()
+                        .matches('.')
+                        .count();
+                    section = Some(level);
+                    Some(level)
+                }
+                (None, Some(level)) => Some(level + 1 + depth),
+                (None, None) => Some(depth),
+            }
+        })
+        .collect()
+}
+
+// Width of the leading whitespace, blank lines having none.
+fn indent(line: &str) -> Option<usize> {
+    let content = line.trim_start();
+    (!content.is_empty()).then(|| line.chars().count() - content.chars().count())
+}
+
+// The line right after the last one nested deeper than `header`. Blank lines
+// trailing the block aren't part of it.
+fn block_end(
+    depth_of: impl Fn(usize) -> Option<usize>,
+    len: usize,
+    header: usize,
+) -> Option<usize> {
+    let depth = depth_of(header)?;
+    let mut last = None;
+    for i in header + 1..len {
+        match depth_of(i) {
+            None => continue,
+            Some(i_depth) if i_depth > depth => last = Some(i),
+            Some(_) => break,
+        }
+    }
+    last.map(|last| last + 1)
+}
+
+// The closest line above `line` that is nested less deep than it.
+fn enclosing_header(depth_of: impl Fn(usize) -> Option<usize>, line: usize) -> Option<usize> {
+    let depth = depth_of(line)?;
+    (0..line)
+        .rev()
+        .find(|&i| depth_of(i).is_some_and(|d| d < depth))
+}
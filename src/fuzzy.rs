@@ -0,0 +1,63 @@
+/// Points for every character of the pattern found in the candidate.
+const MATCH: i64 = 16;
+/// Extra points for a character right after the previous match.
+const CONSECUTIVE: i64 = 8;
+/// Extra points for a character starting a word or a path component.
+const BOUNDARY: i64 = 12;
+/// Points taken for every skipped character, up to `MAX_GAP` per gap.
+const GAP: i64 = 1;
+const MAX_GAP: i64 = 8;
+
+/// Score `candidate` against the fuzzy `pattern`, whose characters have to
+/// appear in it in the same order. Higher scores are better matches, `None`
+/// means no match at all. The case only matters for uppercase patterns.
+pub fn score(pattern: &str, candidate: &str) -> Option<i64> {
+    let ignore_case = !pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| match ignore_case {
+        true => c.to_ascii_lowercase(),
+        false => c,
+    };
+    let mut pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .peekable();
+
+    let mut score = 0;
+    let mut gap = 0;
+    let mut prev = None;
+    for c in candidate.chars() {
+        let Some(&wanted) = pattern.peek() else {
+            break;
+        };
+        if fold(c) != wanted {
+            gap += 1;
+            prev = Some(c);
+            continue;
+        }
+        score += MATCH;
+        if gap == 0 && prev.is_some() {
+            score += CONSECUTIVE;
+        }
+        if prev.is_none_or(|p| !p.is_alphanumeric()) {
+            score += BOUNDARY;
+        }
+        score -= (gap * GAP).min(MAX_GAP);
+        gap = 0;
+        prev = Some(c);
+        pattern.next();
+    }
+    pattern.peek().is_none().then_some(score)
+}
+
+/// Indices of the `candidates` matching `pattern`, best match first. Ties go
+/// to the shorter candidate, then to the one listed first.
+pub fn rank(pattern: &str, candidates: &[String]) -> Vec<usize> {
+    let mut ranked = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| score(pattern, c).map(|s| (s, c.len(), i)))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    ranked.into_iter().map(|(_, _, i)| i).collect()
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::glob::*;
+
+/// Files listing what to leave out, looked for in every directory walked.
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+struct Rule {
+    regex: Regex,
+    // A `!` pattern, keeping what an earlier one left out.
+    negated: bool,
+    // A pattern ending in `/`, for directories alone.
+    dir_only: bool,
+    // A pattern with a `/` in it, matched from the directory of the file
+    // rather than against names.
+    anchored: bool,
+}
+
+/// The patterns of the ignore files of a directory, in the `.gitignore`
+/// format: wildcards as for `Glob`, `!` in front to keep a file after all,
+/// `/` at the end for directories only. Unlike the `ignore` crate, it
+/// doesn't read `.git/info/exclude` or the global excludes file of git.
+pub struct Ignore {
+    dir: PathBuf,
+    // Where `dir` is as seen from the directory of the ignore files, for
+    // those of a directory above the one walked.
+    prefix: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl Ignore {
+    /// The patterns of the ignore files in `dir`, none if it has no such file.
+    pub fn load(dir: &Path) -> Self {
+        let mut rules = Vec::new();
+        for file in IGNORE_FILES {
+            let Ok(content) = fs::read_to_string(dir.join(file)) else {
+                continue;
+            };
+            rules.extend(content.lines().filter_map(parse));
+        }
+        // The paths walked below the working directory have no leading `.`.
+        let dir = dir.strip_prefix(".").unwrap_or(dir);
+        Ignore {
+            dir: dir.to_path_buf(),
+            prefix: PathBuf::new(),
+            rules,
+        }
+    }
+
+    /// The patterns of the ignore files in `above`, a directory above `dir`
+    /// whose full path is `full`, matched against the paths below `dir`.
+    pub fn above(above: &Path, dir: &Path, full: &Path) -> Self {
+        let mut ignore = Ignore::load(above);
+        ignore.dir = dir.strip_prefix(".").unwrap_or(dir).to_path_buf();
+        ignore.prefix = full.strip_prefix(above).unwrap_or(full).to_path_buf();
+        ignore
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether the last pattern matching `path` leaves it out, `None` if no
+    /// pattern matches it.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = self.prefix.join(path.strip_prefix(&self.dir).ok()?);
+        let relative = relative.to_string_lossy();
+        let name = path.file_name()?.to_string_lossy();
+        let rule = self.rules.iter().rev().find(|rule| {
+            let text = if rule.anchored { &relative } else { &name };
+            (is_dir || !rule.dir_only) && rule.regex.is_match(text)
+        })?;
+        Some(!rule.negated)
+    }
+}
+
+fn parse(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(pattern) => (true, pattern),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern),
+    };
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    let regex = compile(pattern).map_err(|err| log::trace!("{err}")).ok()?;
+    Some(Rule {
+        regex,
+        negated,
+        dir_only,
+        anchored,
+    })
+}
@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use regex::Regex;
+
+use crate::walk::*;
+
+/// Files a glob expands to at most.
+const MAX_EXPANDED: usize = 1_000;
+
+/// A shell-style wildcard pattern. `*` stands for any characters but `/`,
+/// `**` for any characters at all and `?` for a single one but `/`. A
+/// pattern without `/` is matched against the file name alone.
+pub struct Glob {
+    regex: Regex,
+    name_only: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        Ok(Glob {
+            regex: compile(pattern)?,
+            name_only: !pattern.contains('/'),
+        })
+    }
+
+    /// Whether `text` holds any wildcard, rather than naming a single file.
+    #[inline]
+    pub fn is_glob(text: &str) -> bool {
+        text.contains(['*', '?'])
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        let path = path.strip_prefix("./").unwrap_or(path);
+        match self.name_only {
+            true => self.regex.is_match(path.rsplit('/').next().unwrap_or(path)),
+            false => self.regex.is_match(path),
+        }
+    }
+}
+
+/// The files matching `pattern` as a whole path, such as `src/**/*.rs`, in
+/// name order. Hidden files and those the ignore files list are left out
+/// as when walking the directories.
+pub fn expand(pattern: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    let regex = compile(pattern)?;
+    // Only the directory the wildcards start in needs walking.
+    let literal = pattern
+        .split('/')
+        .take_while(|part| !Glob::is_glob(part))
+        .collect::<Vec<_>>();
+    let base = match literal.len() < pattern.split('/').count() {
+        true => literal.join("/"),
+        false => return Ok(vec![PathBuf::from(pattern)]),
+    };
+    let base = if base.is_empty() {
+        ".".to_string()
+    } else {
+        base
+    };
+    let mut files = Vec::new();
+    walk_unignored(Path::new(&base), &mut |path| {
+        if regex.is_match(&path.to_string_lossy()) {
+            files.push(path.to_path_buf());
+        }
+        files.len() < MAX_EXPANDED
+    });
+    Ok(files)
+}
+
+/// The regex matching the whole of what `pattern` does.
+pub fn compile(pattern: &str) -> anyhow::Result<Regex> {
+    let mut expr = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no directory at all.
+                match chars.peek() == Some(&'/') {
+                    true => {
+                        chars.next();
+                        expr.push_str("(?:.*/)?");
+                    }
+                    false => expr.push_str(".*"),
+                }
+            }
+            '*' => expr.push_str("[^/]*"),
+            '?' => expr.push_str("[^/]"),
+            c => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expr.push('$');
+    Regex::new(&expr).map_err(|err| anyhow!("Couldn't compile the glob {pattern} due to: {err}"))
+}
+
+/// Globs keeping to the files they match, such as `*.rs *.toml !build.rs`.
+/// A file is kept if it matches any of the plain globs, or there's none of
+/// them, and none of the ones behind a `!`.
+pub struct GlobFilter {
+    text: String,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+}
+
+impl GlobFilter {
+    /// The filter for the globs in `text` separated by spaces, `None` if it
+    /// has none.
+    pub fn parse(text: &str) -> anyhow::Result<Option<Self>> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for pattern in text.split_whitespace() {
+            match pattern.strip_prefix('!') {
+                Some(pattern) => exclude.push(Glob::new(pattern)?),
+                None => include.push(Glob::new(pattern)?),
+            }
+        }
+        if include.is_empty() && exclude.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(GlobFilter {
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+            include,
+            exclude,
+        }))
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|g| g.is_match(path)))
+            && !self.exclude.iter().any(|g| g.is_match(path))
+    }
+}
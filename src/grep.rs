@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use crate::search::*;
+use crate::walk::*;
+
+/// Hits kept at most, so that a too general pattern can't eat up memory.
+const MAX_HITS: usize = 10_000;
+/// Bytes looked at to tell whether a file is binary.
+const BINARY_SNIFF: usize = 8 * 1024;
+
+/// A search of every file below the working directory. It runs on its own
+/// thread and sends hits over as they're found.
+#[derive(Default)]
+pub struct Grep {
+    receiver: Option<Receiver<Hit>>,
+    hits: Vec<Hit>,
+}
+
+impl Grep {
+    #[inline]
+    pub fn new() -> Self {
+        Grep::default()
+    }
+
+    /// Start searching with `matcher`, dropping the hits of a previous run.
+    /// Hits come with `context` lines on either side.
+    pub fn start(&mut self, matcher: Matcher, context: usize) {
+        let (sender, receiver) = mpsc::channel();
+        self.receiver = Some(receiver);
+        self.hits.clear();
+        thread::spawn(move || {
+            let mut sent = 0;
+            walk(Path::new("."), &mut |path| {
+                grep_file(path, &matcher, context, &sender, &mut sent)
+            });
+        });
+    }
+
+    /// Stop a running search.
+    pub fn stop(&mut self) {
+        self.receiver = None;
+    }
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Collect the hits found since the last call, returning their labels.
+    pub fn poll(&mut self) -> Vec<String> {
+        let Some(receiver) = &self.receiver else {
+            return Vec::new();
+        };
+        let mut labels = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(hit) => {
+                    labels.push(hit.label.clone());
+                    self.hits.push(hit);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+        labels
+    }
+
+    #[inline]
+    pub fn hits(&self) -> &[Hit] {
+        &self.hits
+    }
+
+    #[inline]
+    pub fn hit(&self, index: usize) -> Option<&Hit> {
+        self.hits.get(index)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.hits.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+}
+
+// Returns false once the receiving end is gone or enough hits were sent.
+fn grep_file(
+    path: &Path,
+    matcher: &Matcher,
+    context: usize,
+    sender: &Sender<Hit>,
+    sent: &mut usize,
+) -> bool {
+    let Ok(mut file) = File::open(path) else {
+        return true;
+    };
+    let mut head = Vec::new();
+    if (&mut file)
+        .take(BINARY_SNIFF as u64)
+        .read_to_end(&mut head)
+        .is_err()
+        || head.contains(&0)
+    {
+        return true;
+    }
+
+    let name = path.display().to_string();
+    let reader = BufReader::new(head.as_slice().chain(file));
+    // The lines before the current one, and the hits still waiting for the
+    // lines after them.
+    let mut before = VecDeque::with_capacity(context + 1);
+    let mut pending = VecDeque::<Hit>::new();
+    let mut send = |hit: Hit| {
+        *sent += 1;
+        sender.send(hit).is_ok() && *sent < MAX_HITS
+    };
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let Ok(line) = line else {
+            break;
+        };
+        let line = String::from_utf8_lossy(&line);
+        let label = context_label(&name, i + 1, &line);
+        for hit in pending.iter_mut() {
+            hit.after.push(label.clone());
+        }
+        if matcher.is_match(&line) {
+            pending.push_back(Hit {
+                path: path.into(),
+                line: i + 1,
+                label: format!("{name}:{}: {}", i + 1, line.trim()),
+                before: before.iter().cloned().collect(),
+                after: Vec::new(),
+            });
+        }
+        while pending
+            .front()
+            .is_some_and(|hit| hit.after.len() >= context)
+        {
+            if !pending.pop_front().is_some_and(&mut send) {
+                return false;
+            }
+        }
+        before.push_back(label);
+        if before.len() > context {
+            before.pop_front();
+        }
+    }
+    pending.into_iter().all(send)
+}
@@ -0,0 +1,51 @@
+use std::path::{Path, PathBuf};
+
+use crate::io::*;
+
+/// Named groups of files, such as "kernel docs", persisted in the session
+/// file so a file is back in its group whenever it's opened.
+#[derive(Clone, Default)]
+pub struct Groups {
+    // The group of every file in one, by absolute path.
+    members: Vec<(String, PathBuf)>,
+}
+
+impl Groups {
+    /// The groups the session was left with.
+    #[inline]
+    pub fn new(members: Vec<(String, PathBuf)>) -> Self {
+        Groups { members }
+    }
+
+    #[inline]
+    pub fn members(&self) -> &[(String, PathBuf)] {
+        &self.members
+    }
+
+    pub fn group_of(&self, path: &Path) -> Option<&str> {
+        self.members
+            .iter()
+            .find(|(_, p)| p == path)
+            .map(|(group, _)| group.as_str())
+    }
+
+    /// The group of every file in `files`, in their order.
+    pub fn of_files(&self, files: &FileList) -> Vec<Option<&str>> {
+        files.iter().map(|f| self.group_of(f.path())).collect()
+    }
+
+    /// Put the file at `path` into `group`, or take it out of its group.
+    pub fn set(&mut self, path: &Path, group: Option<&str>) {
+        self.members.retain(|(_, p)| p != path);
+        if let Some(group) = group.filter(|g| !g.is_empty() && !g.contains(['\t', '\n'])) {
+            self.members.push((group.to_string(), path.to_path_buf()));
+        }
+    }
+
+    /// Keep the file at `from` in its group after it moved to `to`.
+    pub fn rename_path(&mut self, from: &Path, to: &Path) {
+        for (_, path) in self.members.iter_mut().filter(|(_, p)| p == from) {
+            *path = to.to_path_buf();
+        }
+    }
+}
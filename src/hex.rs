@@ -0,0 +1,130 @@
+use std::io::{self, Read};
+
+use anyhow::anyhow;
+
+/// Bytes shown on each row of the hex dump.
+pub const ROW_BYTES: usize = 16;
+/// Characters the offset takes up at the start of a row.
+pub const OFFSET_WIDTH: usize = 8;
+
+/// A row of the hex dump: the offset of its first byte, the bytes in hex,
+/// split in two halves, and those of them that are printable as text.
+pub fn row(offset: u64, bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(ROW_BYTES * 3 + 1);
+    for i in 0..ROW_BYTES {
+        if i == ROW_BYTES / 2 {
+            hex.push(' ');
+        }
+        match bytes.get(i) {
+            Some(b) => hex.push_str(&format!("{b:02x} ")),
+            None => hex.push_str("   "),
+        }
+    }
+    let text = bytes
+        .iter()
+        .map(|&b| match b.is_ascii_graphic() || b == b' ' {
+            true => b as char,
+            false => '.',
+        })
+        .collect::<String>();
+    format!("{offset:0w$x}  {hex} {text}", w = OFFSET_WIDTH)
+}
+
+/// Where the printable column of a row starts.
+#[inline]
+pub fn text_start() -> usize {
+    OFFSET_WIDTH + 2 + ROW_BYTES * 3 + 2
+}
+
+/// The byte offset in `text`, in decimal or in hex behind `0x`.
+pub fn parse_offset(text: &str) -> anyhow::Result<u64> {
+    let text = text.trim();
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| anyhow!("Not an offset: {text}"))
+}
+
+/// The bytes to search a hex dump for: pairs of hex digits such as
+/// `DE AD BE EF`, or text as it's typed, between double quotes if it could
+/// be taken for hex digits.
+pub fn parse_pattern(text: &str) -> anyhow::Result<Vec<u8>> {
+    let pattern = match text.strip_prefix('"') {
+        Some(quoted) => quoted
+            .strip_suffix('"')
+            .unwrap_or(quoted)
+            .as_bytes()
+            .to_vec(),
+        None => parse_bytes(text).unwrap_or_else(|| text.as_bytes().to_vec()),
+    };
+    match pattern.is_empty() {
+        true => Err(anyhow!("Nothing to search for")),
+        false => Ok(pattern),
+    }
+}
+
+// The bytes spelled out in hex by `text`, spaces between them or not.
+fn parse_bytes(text: &str) -> Option<Vec<u8>> {
+    let digits = text.split_whitespace().collect::<String>();
+    if digits.is_empty() || digits.len() % 2 != 0 || !digits.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// `bytes` in hex, as typed to search for them.
+pub fn spell(bytes: &[u8]) -> String {
+    let hex = bytes.iter().map(|b| format!("{b:02X}"));
+    hex.collect::<Vec<_>>().join(" ")
+}
+
+/// Offsets of the matches of a byte pattern that a search through a file
+/// can land on, seen from the offset it's started at.
+#[derive(Default)]
+pub struct Found {
+    pub first: Option<u64>,
+    pub last: Option<u64>,
+    // The first match from the offset started at on.
+    pub after: Option<u64>,
+    // The last match before it.
+    pub before: Option<u64>,
+}
+
+/// The matches of `pattern` in the bytes `reader` reads, seen from `from`
+/// on, found in a single read through them however the rows break.
+pub fn find(mut reader: impl Read, pattern: &[u8], from: u64) -> io::Result<Found> {
+    let mut found = Found::default();
+    let mut chunk = vec![0; 64 * 1024];
+    // What's read so far past the last bytes a match could start at, and
+    // the offset of its first byte.
+    let mut window = Vec::with_capacity(chunk.len() + pattern.len());
+    let mut start = 0;
+    loop {
+        let read = reader.read(&mut chunk)?;
+        if read == 0 {
+            return Ok(found);
+        }
+        window.extend_from_slice(&chunk[..read]);
+        for (i, bytes) in window.windows(pattern.len()).enumerate() {
+            if bytes != pattern {
+                continue;
+            }
+            let offset = start + i as u64;
+            found.first.get_or_insert(offset);
+            found.last = Some(offset);
+            if offset < from {
+                found.before = Some(offset);
+            } else {
+                found.after.get_or_insert(offset);
+            }
+        }
+        let keep = window.len().min(pattern.len() - 1);
+        start += (window.len() - keep) as u64;
+        window.drain(..window.len() - keep);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::io::ErrorKind;
+
+use anyhow::anyhow;
+
+use crate::store::*;
+
+pub const SEARCH_HISTORY_FILE: &str = "search_history";
+pub const FILE_HISTORY_FILE: &str = "file_history";
+pub const RECENT_FILES_FILE: &str = "recent_files";
+
+/// Entries kept at most, the oldest ones are dropped first.
+const MAX_ENTRIES: usize = 100;
+
+/// Text submitted to a prompt earlier, persisted in the data directory so it
+/// can be recalled in later sessions too.
+pub struct History {
+    file: &'static str,
+    // Oldest entry first.
+    entries: Vec<String>,
+    // The entry recalled into the prompt, if any.
+    browsing: Option<usize>,
+    // What was typed before recalling, given back when going past the newest
+    // entry.
+    draft: String,
+}
+
+impl History {
+    #[inline]
+    pub fn new(file: &'static str) -> Self {
+        History {
+            file,
+            entries: Vec::new(),
+            browsing: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Read the history file. Having none yet isn't an error.
+    pub fn load(file: &'static str) -> anyhow::Result<Self> {
+        let path = data_file(file)?;
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(anyhow!("Couldn't read {file} due to: {err}")),
+        };
+        let mut history = History::new(file);
+        history.entries = content.lines().map(str::to_string).collect();
+        Ok(history)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = data_file(self.file)?;
+        let content = self
+            .entries
+            .iter()
+            .map(|e| e.clone() + "\n")
+            .collect::<String>();
+        fs::write(path, content).map_err(|err| anyhow!("Couldn't save {} due to: {err}", self.file))
+    }
+
+    /// Add an entry as the newest one, moving it there if it's known already.
+    pub fn add(&mut self, entry: &str) {
+        self.reset();
+        if entry.is_empty() || entry.contains('\n') {
+            return;
+        }
+        self.entries.retain(|e| e != entry);
+        self.entries.push(entry.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Every entry, oldest first.
+    #[inline]
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// The entry before the recalled one, remembering `typed` when starting
+    /// to recall.
+    pub fn older(&mut self, typed: &str) -> Option<&str> {
+        let index = match self.browsing {
+            Some(index) => index.checked_sub(1)?,
+            None => {
+                self.draft = typed.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+        };
+        self.browsing = Some(index);
+        Some(&self.entries[index])
+    }
+
+    /// The entry after the recalled one, or what was typed before recalling
+    /// once past the newest entry.
+    pub fn newer(&mut self) -> Option<&str> {
+        let index = self.browsing? + 1;
+        if index < self.entries.len() {
+            self.browsing = Some(index);
+            return Some(&self.entries[index]);
+        }
+        self.browsing = None;
+        Some(&self.draft)
+    }
+
+    /// Stop recalling, the next recall starts from the newest entry.
+    pub fn reset(&mut self) {
+        self.browsing = None;
+        self.draft.clear();
+    }
+}
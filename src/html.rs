@@ -0,0 +1,579 @@
+use std::ops::Range;
+use std::path::Path;
+
+use crate::markdown::{Mark, Rendered};
+
+/// Width of the rule an `<hr>` is drawn as.
+const RULE_WIDTH: usize = 40;
+/// What the cells of a table row are set apart by.
+const CELL_SEPARATOR: &str = " │ ";
+
+// Named character references beyond those of XML, the ones documentation
+// pages tend to use.
+const ENTITIES: [(&str, char); 33] = [
+    ("nbsp", ' '),
+    ("ensp", ' '),
+    ("emsp", ' '),
+    ("thinsp", ' '),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("mdash", '—'),
+    ("ndash", '–'),
+    ("hellip", '…'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("middot", '·'),
+    ("bull", '•'),
+    ("times", '×'),
+    ("divide", '÷'),
+    ("plusmn", '±'),
+    ("deg", '°'),
+    ("para", '¶'),
+    ("sect", '§'),
+    ("larr", '←'),
+    ("rarr", '→'),
+    ("uarr", '↑'),
+    ("darr", '↓'),
+    ("harr", '↔'),
+    ("le", '≤'),
+    ("ge", '≥'),
+    ("ne", '≠'),
+    ("shy", '\u{AD}'),
+];
+
+/// Whether the file at `path` is an HTML page, told by its extension.
+pub fn is_html(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["html", "htm", "xhtml"].contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// An HTML page laid out as text: its tags taken out, its headings, lists,
+/// quotes, code and tables kept as the lines they'd be read as, and its
+/// links numbered, their targets listed at the end.
+pub struct Page {
+    pub lines: Vec<String>,
+    /// How the parts of each line are shown, in characters.
+    pub marks: Vec<Vec<(Range<usize>, Mark)>>,
+}
+
+impl Page {
+    /// The page whose source is `source`.
+    pub fn render(source: &str) -> Self {
+        let mut writer = Writer::default();
+        let mut rest = source;
+        while !rest.is_empty() {
+            let Some(start) = rest.find('<') else {
+                writer.text(&decode(rest));
+                break;
+            };
+            writer.text(&decode(&rest[..start]));
+            rest = &rest[start..];
+            rest = match tag(rest) {
+                Some((tag, after)) => writer.tag(tag, after),
+                // A `<` starting no tag is text.
+                None => {
+                    writer.text("<");
+                    &rest[1..]
+                }
+            };
+        }
+        writer.finish()
+    }
+}
+
+// A tag of the source: its name in lowercase, its attributes, and whether
+// it closes an element.
+struct Tag<'a> {
+    name: String,
+    attributes: &'a str,
+    closing: bool,
+}
+
+// The tag at the start of `source`, and what follows it. Comments and
+// declarations come out as a tag without a name.
+fn tag(source: &str) -> Option<(Tag<'_>, &str)> {
+    if let Some(comment) = source.strip_prefix("<!--") {
+        let end = comment.find("-->").map_or(comment.len(), |end| end + 3);
+        return Some((Tag::none(), &comment[end..]));
+    }
+    let closing = source.starts_with("</");
+    let body = &source[if closing { 2 } else { 1 }..];
+    let first = body.chars().next()?;
+    if !closing && (first == '!' || first == '?') {
+        let end = body.find('>').map_or(body.len(), |end| end + 1);
+        return Some((Tag::none(), &body[end..]));
+    }
+    if !first.is_ascii_alphabetic() {
+        return None;
+    }
+    let name_end = body
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != ':')
+        .unwrap_or(body.len());
+    // The tag ends at the first `>` outside of a quoted attribute.
+    let mut quote = None;
+    let mut end = body.len();
+    for (i, c) in body.char_indices().skip(name_end) {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let tag = Tag {
+        name: body[..name_end].to_ascii_lowercase(),
+        attributes: &body[name_end..end],
+        closing,
+    };
+    Some((tag, body.get(end + 1..).unwrap_or_default()))
+}
+
+impl<'a> Tag<'a> {
+    fn none() -> Self {
+        Tag {
+            name: String::new(),
+            attributes: "",
+            closing: false,
+        }
+    }
+
+    // The value of the attribute `name`, its references decoded.
+    fn attribute(&self, name: &str) -> Option<String> {
+        let mut rest = self.attributes;
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            if rest.is_empty() {
+                return None;
+            }
+            let key_end = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+                .unwrap_or(rest.len());
+            let key = &rest[..key_end];
+            rest = rest[key_end..].trim_start();
+            let mut value = "";
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                (value, rest) = match after.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let end = after[1..].find(q).map_or(after.len(), |end| end + 1);
+                        (&after[1..end], after.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+            }
+            if key.eq_ignore_ascii_case(name) {
+                return Some(decode(value));
+            }
+        }
+    }
+}
+
+// `text` with its character references, such as `&amp;` and `&#8212;`,
+// replaced by what they stand for.
+fn decode(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest[1..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '#')
+            .map_or(rest.len(), |end| end + 1);
+        let name = &rest[1..end];
+        let number = match name.strip_prefix('#') {
+            Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok(),
+            Some(decimal) => decimal.parse().ok(),
+            None => None,
+        };
+        let decoded = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if number.is_some() => number.and_then(char::from_u32),
+            _ => ENTITIES.iter().find(|(n, _)| *n == name).map(|&(_, c)| c),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = rest[end..].strip_prefix(';').unwrap_or(&rest[end..]);
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Lays the text of the page out line by line as its tags go by.
+#[derive(Default)]
+struct Writer {
+    page: Vec<Rendered>,
+    line: String,
+    len: usize,
+    marks: Vec<(Range<usize>, Mark)>,
+    // Whether the line holds any text yet, past its indentation.
+    started: bool,
+    // Whitespace went by since the last word.
+    space: bool,
+    // A blank line is due before the next text, as between paragraphs, and
+    // the quotes it's within.
+    blank: Option<usize>,
+    // How each part of the text is shown, the innermost last.
+    styles: Vec<Mark>,
+    // The lists the text is in, numbered ones with their next number, and
+    // the bullet of an item not yet written.
+    lists: Vec<Option<usize>>,
+    bullet: Option<String>,
+    quotes: usize,
+    // Description details and other indented blocks.
+    indent: usize,
+    // Preformatted elements the text is in, kept as it is.
+    pre: usize,
+    // Elements whose text isn't shown, such as scripts and the head.
+    hidden: usize,
+    // Cells gone by in the table row.
+    cells: usize,
+    // The targets of the links, numbered in order, and that of the link the
+    // text is in.
+    links: Vec<String>,
+    link: Option<String>,
+}
+
+impl Writer {
+    // Handle the tag `tag`, returning where the source goes on after it.
+    fn tag<'a>(&mut self, tag: Tag, after: &'a str) -> &'a str {
+        let name = tag.name.as_str();
+        if tag.closing {
+            self.close(name);
+            return after;
+        }
+        match name {
+            // Their text isn't markup, and isn't shown either.
+            "script" | "style" | "template" | "title" | "textarea" => {
+                let close = format!("</{name}");
+                let end = find_ignoring_case(after, &close).unwrap_or(after.len());
+                let rest = &after[end..];
+                return rest.find('>').map_or("", |end| &rest[end + 1..]);
+            }
+            "head" => self.hidden += 1,
+            "br" => self.line_break(),
+            "hr" => {
+                self.finish_line();
+                let rule = "─".repeat(RULE_WIDTH);
+                self.push(&rule, Some(Mark::Decoration));
+                self.finish_line();
+            }
+            "p" | "table" | "dl" | "figure" | "details" | "address" => self.paragraph(),
+            "div" | "section" | "article" | "header" | "footer" | "nav" | "main" | "aside"
+            | "figcaption" | "form" | "fieldset" | "summary" | "center" => self.finish_line(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.paragraph();
+                let level = usize::from(name.as_bytes()[1] - b'0');
+                self.styles.push(Mark::Heading(level));
+            }
+            "pre" => {
+                self.paragraph();
+                self.pre += 1;
+                self.styles.push(Mark::CodeBlock);
+                // A line break right after the tag isn't part of the text.
+                return after
+                    .strip_prefix("\r\n")
+                    .or_else(|| after.strip_prefix('\n'))
+                    .unwrap_or(after);
+            }
+            "blockquote" => {
+                self.paragraph();
+                self.quotes += 1;
+            }
+            "ul" | "ol" | "menu" => {
+                match self.lists.is_empty() {
+                    true => self.paragraph(),
+                    false => self.finish_line(),
+                }
+                let start = tag.attribute("start").and_then(|n| n.trim().parse().ok());
+                self.lists
+                    .push((name == "ol").then_some(start.unwrap_or(1)));
+            }
+            "li" => {
+                self.finish_line();
+                self.bullet = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                });
+            }
+            "dt" => {
+                self.finish_line();
+                self.styles.push(Mark::Bold);
+            }
+            "dd" => {
+                self.finish_line();
+                self.indent += 1;
+            }
+            "tr" => {
+                self.finish_line();
+                self.cells = 0;
+            }
+            "td" | "th" => {
+                if self.cells > 0 {
+                    self.push(CELL_SEPARATOR, Some(Mark::Decoration));
+                    self.space = false;
+                }
+                self.cells += 1;
+                if name == "th" {
+                    self.styles.push(Mark::Bold);
+                }
+            }
+            "b" | "strong" => self.styles.push(Mark::Bold),
+            "i" | "em" | "cite" | "dfn" | "var" => self.styles.push(Mark::Italic),
+            "code" | "kbd" | "samp" | "tt" => self.styles.push(Mark::Code),
+            "a" => {
+                // Links within the page and scripts lead nowhere to read.
+                let href = tag
+                    .attribute("href")
+                    .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"));
+                if href.is_some() {
+                    self.styles.push(Mark::Link);
+                }
+                self.link = href;
+            }
+            "img" => {
+                if let Some(alt) = tag.attribute("alt").filter(|alt| !alt.trim().is_empty()) {
+                    self.word(&format!("[{}]", alt.trim()), Some(Mark::Decoration));
+                }
+            }
+            _ => {}
+        }
+        after
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "head" => self.hidden = self.hidden.saturating_sub(1),
+            "p" | "table" | "dl" | "figure" | "details" | "address" => self.paragraph(),
+            "div" | "section" | "article" | "header" | "footer" | "nav" | "main" | "aside"
+            | "figcaption" | "form" | "fieldset" | "summary" | "center" | "tr" | "li" => {
+                self.finish_line()
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.pop(|mark| matches!(mark, Mark::Heading(_)));
+                self.paragraph();
+            }
+            "pre" => {
+                self.finish_line();
+                self.pop(|mark| mark == Mark::CodeBlock);
+                self.pre = self.pre.saturating_sub(1);
+                self.paragraph();
+            }
+            "blockquote" => {
+                self.paragraph();
+                self.quotes = self.quotes.saturating_sub(1);
+            }
+            "ul" | "ol" | "menu" => {
+                self.finish_line();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.paragraph();
+                }
+            }
+            "dt" => {
+                self.pop(|mark| mark == Mark::Bold);
+                self.finish_line();
+            }
+            "dd" => {
+                self.finish_line();
+                self.indent = self.indent.saturating_sub(1);
+            }
+            "th" | "b" | "strong" => self.pop(|mark| mark == Mark::Bold),
+            "i" | "em" | "cite" | "dfn" | "var" => self.pop(|mark| mark == Mark::Italic),
+            "code" | "kbd" | "samp" | "tt" => self.pop(|mark| mark == Mark::Code),
+            "a" => {
+                if let Some(href) = self.link.take() {
+                    self.pop(|mark| mark == Mark::Link);
+                    self.links.push(href);
+                    let number = format!("[{}]", self.links.len());
+                    self.push(&number, Some(Mark::Decoration));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Drop the innermost style `is` tells, if the text is in one.
+    fn pop(&mut self, is: impl Fn(Mark) -> bool) {
+        if let Some(i) = self.styles.iter().rposition(|&mark| is(mark)) {
+            self.styles.remove(i);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.hidden > 0 {
+            return;
+        }
+        if self.pre > 0 {
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 {
+                    self.line_break();
+                }
+                let line = line.trim_end_matches('\r');
+                if !line.is_empty() {
+                    self.push(line, self.styles.last().copied());
+                }
+            }
+            return;
+        }
+        for (i, word) in text.split(char::is_whitespace).enumerate() {
+            if i > 0 {
+                self.space = true;
+            }
+            if !word.is_empty() {
+                self.word(word, self.styles.last().copied());
+            }
+        }
+    }
+
+    // A word of the text, after a space if whitespace went before it.
+    fn word(&mut self, word: &str, mark: Option<Mark>) {
+        if self.space && self.started {
+            self.push(" ", mark.filter(|&m| m != Mark::Decoration));
+        }
+        self.space = false;
+        self.push(word, mark);
+    }
+
+    // Add `text` to the line, shown as `mark`, starting the line first if it
+    // hasn't been.
+    fn push(&mut self, text: &str, mark: Option<Mark>) {
+        self.start();
+        let len = text.chars().count();
+        let range = self.len..self.len + len;
+        match (self.marks.last_mut(), mark) {
+            (Some((last, m)), Some(mark)) if *m == mark && last.end == range.start => {
+                last.end = range.end;
+            }
+            (_, Some(mark)) => self.marks.push((range, mark)),
+            _ => {}
+        }
+        self.line.push_str(text);
+        self.len += len;
+    }
+
+    // Begin the line with the blank line due before it, and the bars of the
+    // quotes, the indentation of the lists and the bullet of an item.
+    fn start(&mut self) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        self.space = false;
+        let blank = self.blank.take();
+        if let Some(quotes) = blank.filter(|_| self.page.last().is_some_and(|(l, _)| !l.is_empty()))
+        {
+            // Within a quote, its bars go on along the blank line.
+            let bars = "▌ ".repeat(quotes.min(self.quotes)).trim_end().to_string();
+            let marks = match bars.is_empty() {
+                true => Vec::new(),
+                false => vec![(0..bars.chars().count(), Mark::Decoration)],
+            };
+            self.page.push((bars, marks));
+        }
+        let mut prefix = "▌ ".repeat(self.quotes);
+        let depth = self.lists.len().saturating_sub(1) + self.indent;
+        prefix.push_str(&"  ".repeat(depth));
+        match self.bullet.take() {
+            Some(bullet) => prefix.push_str(&bullet),
+            // The lines of an item after its first line up with its text.
+            None if !self.lists.is_empty() => prefix.push_str("  "),
+            None => {}
+        }
+        if self.pre > 0 {
+            prefix.push_str("│ ");
+        }
+        let len = prefix.chars().count();
+        if len > 0 {
+            let mark = match self.pre > 0 {
+                true => Mark::CodeBlock,
+                false => Mark::Decoration,
+            };
+            self.marks.push((0..len, mark));
+        }
+        self.line = prefix;
+        self.len = len;
+    }
+
+    // End the line, if any text was put on it.
+    fn finish_line(&mut self) {
+        if !self.started {
+            return;
+        }
+        let line = std::mem::take(&mut self.line);
+        let marks = std::mem::take(&mut self.marks);
+        self.page.push((line.trim_end().to_string(), marks));
+        (self.len, self.started, self.space) = (0, false, false);
+    }
+
+    // End the line at a `<br>`, an empty one even.
+    fn line_break(&mut self) {
+        self.start();
+        self.finish_line();
+    }
+
+    // End the line, and leave a blank line before the next text.
+    fn paragraph(&mut self) {
+        self.finish_line();
+        self.blank = Some(
+            self.blank
+                .map_or(self.quotes, |quotes| quotes.min(self.quotes)),
+        );
+    }
+
+    fn finish(mut self) -> Page {
+        self.finish_line();
+        if !self.links.is_empty() {
+            self.paragraph();
+            self.push("Links", Some(Mark::Heading(2)));
+            self.paragraph();
+            for (i, href) in std::mem::take(&mut self.links).iter().enumerate() {
+                let number = format!("[{}] ", i + 1);
+                self.push(&number, Some(Mark::Decoration));
+                self.push(href, Some(Mark::Link));
+                self.finish_line();
+            }
+        }
+        while self.page.last().is_some_and(|(line, _)| line.is_empty()) {
+            self.page.pop();
+        }
+        let (lines, marks) = self.page.into_iter().unzip();
+        Page { lines, marks }
+    }
+}
+
+// Where `pattern`, in lowercase, is first found in `text`, whatever the case.
+fn find_ignoring_case(text: &str, pattern: &str) -> Option<usize> {
+    text.as_bytes()
+        .windows(pattern.len())
+        .position(|window| window.eq_ignore_ascii_case(pattern.as_bytes()))
+}
@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use ratatui::style::Color;
+use serde::Deserialize;
+
+/// How the type of a file is shown in front of its name in the Files pane.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Icons {
+    Off,
+    /// Letters telling the type, for any font.
+    Ascii,
+    /// Glyphs of a Nerd Font, which the terminal needs to be using.
+    Nerd,
+}
+
+/// A file type: its extensions, Nerd Font glyph, ASCII tag and color.
+struct Kind {
+    extensions: &'static [&'static str],
+    nerd: &'static str,
+    ascii: &'static str,
+    color: Color,
+}
+
+const KINDS: &[Kind] = &[
+    kind(&["rs"], "\u{e7a8}", "rs", Color::Rgb(222, 165, 132)),
+    kind(&["py", "pyi"], "\u{e73c}", "py", Color::Rgb(255, 212, 59)),
+    kind(
+        &["js", "mjs", "cjs", "jsx"],
+        "\u{e74e}",
+        "js",
+        Color::Rgb(240, 219, 79),
+    ),
+    kind(&["ts", "tsx"], "\u{e628}", "ts", Color::Rgb(49, 120, 198)),
+    kind(&["go"], "\u{e626}", "go", Color::Rgb(0, 173, 216)),
+    kind(&["c", "h"], "\u{e61e}", "c ", Color::Rgb(85, 154, 211)),
+    kind(
+        &["cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        "\u{e61d}",
+        "c+",
+        Color::Rgb(85, 154, 211),
+    ),
+    kind(
+        &["java", "kt", "scala"],
+        "\u{e738}",
+        "jv",
+        Color::Rgb(204, 62, 68),
+    ),
+    kind(&["rb"], "\u{e739}", "rb", Color::Rgb(112, 21, 22)),
+    kind(&["lua"], "\u{e620}", "lu", Color::Rgb(81, 160, 207)),
+    kind(
+        &["sh", "bash", "zsh", "fish"],
+        "\u{f489}",
+        "sh",
+        Color::Rgb(77, 90, 94),
+    ),
+    kind(
+        &["md", "markdown", "rst"],
+        "\u{e609}",
+        "md",
+        Color::Rgb(81, 154, 186),
+    ),
+    kind(
+        &["json", "jsonc"],
+        "\u{e60b}",
+        "{}",
+        Color::Rgb(203, 203, 65),
+    ),
+    kind(
+        &["toml", "yaml", "yml", "ini", "cfg", "conf"],
+        "\u{e615}",
+        "cf",
+        Color::Rgb(109, 128, 134),
+    ),
+    kind(
+        &["html", "htm", "xml"],
+        "\u{e736}",
+        "<>",
+        Color::Rgb(228, 79, 57),
+    ),
+    kind(
+        &["css", "scss", "sass"],
+        "\u{e749}",
+        "cs",
+        Color::Rgb(66, 165, 245),
+    ),
+    kind(&["csv", "tsv"], "\u{f1c3}", "cv", Color::Rgb(137, 224, 81)),
+    kind(&["log"], "\u{f18d}", "lg", Color::Rgb(128, 128, 128)),
+    kind(&["txt"], "\u{f15c}", "tx", Color::Rgb(180, 180, 180)),
+];
+
+const fn kind(
+    extensions: &'static [&'static str],
+    nerd: &'static str,
+    ascii: &'static str,
+    color: Color,
+) -> Kind {
+    Kind {
+        extensions,
+        nerd,
+        ascii,
+        color,
+    }
+}
+
+/// The glyph, or tag, and color for the type of the file at `path`, told by
+/// its extension. `None` when icons are off.
+pub fn icon(path: &Path, icons: Icons) -> Option<(&'static str, Color)> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let kind = extension.and_then(|e| KINDS.iter().find(|k| k.extensions.contains(&e.as_str())));
+    let color = kind.map_or(Color::Gray, |k| k.color);
+    match icons {
+        Icons::Off => None,
+        Icons::Ascii => Some((kind.map_or("--", |k| k.ascii), color)),
+        Icons::Nerd => Some((kind.map_or("\u{f15b}", |k| k.nerd), color)),
+    }
+}
@@ -0,0 +1,214 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crossterm::{cursor, queue};
+use ratatui::layout::Rect;
+
+use crate::clipboard::base64;
+
+/// Bytes looked at to find the size of an image.
+const HEADER_SNIFF: u64 = 64 * 1024;
+/// Pixels a cell of the terminal is taken to be wide and high, to keep the
+/// proportions of an image.
+const CELL_PIXELS: (u32, u32) = (8, 16);
+/// Bytes of an image sent to the terminal in each escape sequence.
+const CHUNK: usize = 4096;
+
+/// What kind of image a file holds and its size in pixels, read from its
+/// header.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub format: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImageInfo {
+    /// The image at `path`, told by its extension and its header.
+    pub fn probe(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        if !["png", "jpg", "jpeg", "gif", "bmp", "webp"].contains(&extension.as_str()) {
+            return None;
+        }
+        let mut head = Vec::new();
+        File::open(path)
+            .ok()?
+            .take(HEADER_SNIFF)
+            .read_to_end(&mut head)
+            .ok()?;
+        let (format, (width, height)) = match head.as_slice() {
+            [0x89, b'P', b'N', b'G', ..] => ("PNG", png_size(&head)?),
+            [b'G', b'I', b'F', b'8', ..] => ("GIF", (le16(&head, 6)?, le16(&head, 8)?)),
+            [0xFF, 0xD8, ..] => ("JPEG", jpeg_size(&head)?),
+            [b'B', b'M', ..] => ("BMP", bmp_size(&head)?),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => {
+                ("WebP", webp_size(&head)?)
+            }
+            _ => return None,
+        };
+        Some(ImageInfo {
+            format,
+            width,
+            height,
+        })
+    }
+
+    /// The lines telling about the image at `path`, shown in place of its
+    /// bytes.
+    pub fn describe(&self, path: &Path) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} image, {} × {} pixels",
+            self.format, self.width, self.height
+        )];
+        if let Ok(metadata) = fs::metadata(path) {
+            lines.push(format!("{} bytes", metadata.len()));
+        }
+        lines
+    }
+
+    // Cells the image takes up at most within `cols` by `rows`, keeping its
+    // proportions and never growing it.
+    fn cells(&self, cols: u16, rows: u16) -> (u16, u16) {
+        let width = self.width.div_ceil(CELL_PIXELS.0).max(1) as f64;
+        let height = self.height.div_ceil(CELL_PIXELS.1).max(1) as f64;
+        let scale = (cols as f64 / width).min(rows as f64 / height).min(1.0);
+        let cells = |n: f64| (n * scale).round().max(1.0) as u16;
+        (cells(width), cells(height))
+    }
+}
+
+fn png_size(head: &[u8]) -> Option<(u32, u32)> {
+    Some((be32(head, 16)?, be32(head, 20)?))
+}
+
+fn jpeg_size(head: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    while i + 9 < head.len() {
+        if head[i] != 0xFF {
+            return None;
+        }
+        let marker = head[i + 1];
+        // Start of frame markers, short of those for other things.
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            return Some((be16(head, i + 7)?, be16(head, i + 5)?));
+        }
+        i += 2 + be16(head, i + 2)? as usize;
+    }
+    None
+}
+
+fn bmp_size(head: &[u8]) -> Option<(u32, u32)> {
+    let width = i32::from_le_bytes(head.get(18..22)?.try_into().ok()?);
+    let height = i32::from_le_bytes(head.get(22..26)?.try_into().ok()?);
+    Some((width.unsigned_abs(), height.unsigned_abs()))
+}
+
+fn webp_size(head: &[u8]) -> Option<(u32, u32)> {
+    match head.get(12..16)? {
+        b"VP8X" => Some((le24(head, 24)? + 1, le24(head, 27)? + 1)),
+        b"VP8 " => Some((le16(head, 26)? & 0x3FFF, le16(head, 28)? & 0x3FFF)),
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(head.get(21..25)?.try_into().ok()?);
+            Some(((bits & 0x3FFF) + 1, (bits >> 14 & 0x3FFF) + 1))
+        }
+        _ => None,
+    }
+}
+
+fn be16(head: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(head.get(at..at + 2)?.try_into().ok()?).into())
+}
+
+fn be32(head: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(head.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le16(head: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(head.get(at..at + 2)?.try_into().ok()?).into())
+}
+
+fn le24(head: &[u8], at: usize) -> Option<u32> {
+    let bytes = head.get(at..at + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+/// The image drawn over the View pane through the kitty graphics protocol,
+/// in terminals speaking it. The terminal decodes the image itself, which
+/// it only does for PNG ones, so others are only described.
+pub struct Preview {
+    supported: bool,
+    // The image asked for by the last frame drawn, and the one on screen.
+    wanted: Option<(PathBuf, Rect)>,
+    shown: Option<(PathBuf, Rect)>,
+}
+
+impl Preview {
+    /// The preview for the terminal refer runs in, as told by its
+    /// environment.
+    pub fn detect() -> Self {
+        let term = env::var("TERM").unwrap_or_default();
+        let program = env::var("TERM_PROGRAM").unwrap_or_default();
+        let supported = env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || ["WezTerm", "ghostty"].contains(&program.as_str());
+        Preview {
+            supported,
+            wanted: None,
+            shown: None,
+        }
+    }
+
+    /// Whether `image` can be drawn in this terminal.
+    pub fn can_show(&self, image: &ImageInfo) -> bool {
+        self.supported && image.format == "PNG"
+    }
+
+    /// Draw the image at `path` within `area` once the frame is drawn. Left
+    /// uncalled while drawing a frame, the image is taken off the screen.
+    pub fn want(&mut self, path: &Path, image: &ImageInfo, area: Rect) {
+        if !self.can_show(image) || area.width == 0 || area.height == 0 {
+            return;
+        }
+        let (cols, rows) = image.cells(area.width, area.height);
+        let area = Rect::new(area.x, area.y, cols, rows);
+        self.wanted = Some((path.to_path_buf(), area));
+    }
+
+    /// Bring the screen in line with the image the frame asked for,
+    /// redrawing it only when it changed.
+    pub fn flush(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let wanted = self.wanted.take();
+        if wanted == self.shown {
+            return Ok(());
+        }
+        if self.shown.take().is_some() {
+            write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        }
+        if let Some((path, area)) = &wanted {
+            let bytes = fs::read(path)?;
+            queue!(out, cursor::SavePosition, cursor::MoveTo(area.x, area.y))?;
+            let encoded = base64(&bytes);
+            let chunks = encoded.as_bytes().chunks(CHUNK).collect::<Vec<_>>();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                match i {
+                    0 => write!(
+                        out,
+                        "\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={more};",
+                        area.width, area.height
+                    )?,
+                    _ => write!(out, "\x1b_Gm={more};")?,
+                }
+                out.write_all(chunk)?;
+                write!(out, "\x1b\\")?;
+            }
+            queue!(out, cursor::RestorePosition)?;
+        }
+        out.flush()?;
+        self.shown = wanted;
+        Ok(())
+    }
+}
@@ -0,0 +1,364 @@
+use anyhow::anyhow;
+
+/// Bytes a stream is decompressed to at most, past which it's taken to be
+/// something other than text to read.
+pub const MAX_INFLATED: usize = 256 * 1024 * 1024;
+
+// The lengths and distances of the length codes and the distance codes, and
+// the extra bits following each.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// The order the code lengths of the code length code come in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// The data of a zlib stream, as PDF files keep their streams.
+pub fn zlib(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    match data {
+        [cmf, flg, rest @ ..]
+            if cmf & 0x0F == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0 =>
+        {
+            inflate(rest)
+        }
+        _ => Err(anyhow!("Not a zlib stream")),
+    }
+}
+
+/// The data of a gzip file, its members one after the other.
+pub fn gzip(mut data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let [0x1f, 0x8b, 8, flags, ..] = *data else {
+            return Err(anyhow!("Not a gzip file"));
+        };
+        let mut at = 10;
+        // An extra field, a name, a comment and a check of the header may
+        // come before the deflate stream, as the flags tell.
+        if flags & 4 != 0 {
+            let len = data
+                .get(at..at + 2)
+                .ok_or_else(|| anyhow!("Gzip header runs past the end"))?;
+            at += 2 + (usize::from(len[1]) << 8 | usize::from(len[0]));
+        }
+        for flag in [8, 16] {
+            if flags & flag != 0 {
+                let end = data
+                    .get(at..)
+                    .and_then(|rest| rest.iter().position(|&b| b == 0))
+                    .ok_or_else(|| anyhow!("Gzip header runs past the end"))?;
+                at += end + 1;
+            }
+        }
+        if flags & 2 != 0 {
+            at += 2;
+        }
+        let stream = data
+            .get(at..)
+            .ok_or_else(|| anyhow!("Gzip header runs past the end"))?;
+        let (inflated, read) = inflate_stream(stream)?;
+        out.extend_from_slice(&inflated);
+        if out.len() > MAX_INFLATED {
+            return Err(anyhow!("Inflates to more than {MAX_INFLATED} bytes"));
+        }
+        // The CRC and the size of the data follow it.
+        data = data.get(at + read + 8..).unwrap_or_default();
+        // Files are sometimes padded with zeros after the last member.
+        if data.iter().all(|&b| b == 0) {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// The data of a raw deflate stream, as zip and gzip files hold them.
+pub fn inflate(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    inflate_stream(data).map(|(out, _)| out)
+}
+
+// The data of a deflate stream, and the bytes it took up.
+fn inflate_stream(data: &[u8]) -> anyhow::Result<(Vec<u8>, usize)> {
+    let mut bits = Bits {
+        data,
+        at: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::with_capacity(data.len() * 4);
+    loop {
+        let last = bits.take(1)? == 1;
+        match bits.take(2)? {
+            0 => {
+                bits.align();
+                let len = bits.bytes(2)? as usize;
+                let _complement = bits.bytes(2)?;
+                let stored = data
+                    .get(bits.at..bits.at + len)
+                    .ok_or_else(|| anyhow!("Stored block runs past the end"))?;
+                out.extend_from_slice(stored);
+                bits.at += len;
+            }
+            1 => {
+                let (lengths, distances) = fixed_codes();
+                block(&mut bits, &mut out, &lengths, &distances)?;
+            }
+            2 => {
+                let (lengths, distances) = dynamic_codes(&mut bits)?;
+                block(&mut bits, &mut out, &lengths, &distances)?;
+            }
+            _ => return Err(anyhow!("Unknown block type")),
+        }
+        if out.len() > MAX_INFLATED {
+            return Err(anyhow!("Inflates to more than {MAX_INFLATED} bytes"));
+        }
+        if last {
+            bits.align();
+            return Ok((out, bits.at));
+        }
+    }
+}
+
+// Reads the bits of a deflate stream, the least significant first, a few
+// bytes ahead of them.
+struct Bits<'a> {
+    data: &'a [u8],
+    // The next byte to be read ahead, and the bits read ahead of it.
+    at: usize,
+    buffer: u64,
+    count: u8,
+}
+
+impl Bits<'_> {
+    fn take(&mut self, count: u8) -> anyhow::Result<u32> {
+        while self.count < count {
+            let byte = *self
+                .data
+                .get(self.at)
+                .ok_or_else(|| anyhow!("Unexpected end of the stream"))?;
+            self.buffer |= u64::from(byte) << self.count;
+            self.count += 8;
+            self.at += 1;
+        }
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value as u32)
+    }
+
+    // Skip to the next whole byte, handing back the ones read ahead.
+    fn align(&mut self) {
+        self.at -= usize::from(self.count / 8);
+        (self.buffer, self.count) = (0, 0);
+    }
+
+    // A little endian number of `count` whole bytes.
+    fn bytes(&mut self, count: usize) -> anyhow::Result<u32> {
+        let bytes = self
+            .data
+            .get(self.at..self.at + count)
+            .ok_or_else(|| anyhow!("Unexpected end of the stream"))?;
+        self.at += count;
+        Ok(bytes.iter().rev().fold(0, |n, &b| n << 8 | u32::from(b)))
+    }
+}
+
+// A canonical Huffman code: the number of codes of each length, and the
+// symbols ordered by their codes.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0; 16];
+        for len in 1..16 {
+            offsets[len] = offsets[len - 1] + counts[len - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len > 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> anyhow::Result<u16> {
+        // The first code of each length, and where its symbols start.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.take(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(anyhow!("Bad Huffman code"))
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(bits: &mut Bits) -> anyhow::Result<(Huffman, Huffman)> {
+    let literals = bits.take(5)? as usize + 257;
+    let distances = bits.take(5)? as usize + 1;
+    let code_lengths = bits.take(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[i] = bits.take(3)? as u8;
+    }
+    let code = Huffman::new(&lengths);
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (len, repeat) = match code.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| anyhow!("Repeat with no length before"))?;
+                (previous, 3 + bits.take(2)?)
+            }
+            17 => (0, 3 + bits.take(3)?),
+            _ => (0, 11 + bits.take(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(len, repeat as usize));
+    }
+    if lengths.len() > literals + distances {
+        return Err(anyhow!("Code lengths run past their count"));
+    }
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+fn block(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    lengths: &Huffman,
+    distances: &Huffman,
+) -> anyhow::Result<()> {
+    loop {
+        let symbol = lengths.decode(bits)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                let base = *LENGTH_BASES.get(i).ok_or_else(|| anyhow!("Bad length"))?;
+                let len = base as usize + bits.take(LENGTH_EXTRA[i])? as usize;
+                let i = distances.decode(bits)? as usize;
+                let base = *DISTANCE_BASES
+                    .get(i)
+                    .ok_or_else(|| anyhow!("Bad distance"))?;
+                let distance = base as usize + bits.take(DISTANCE_EXTRA[i])? as usize;
+                if distance > out.len() {
+                    return Err(anyhow!("Distance reaches before the start"));
+                }
+                // Copied a byte at a time, as the copy may overlap itself.
+                let start = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+                if out.len() > MAX_INFLATED {
+                    return Err(anyhow!("Inflates to more than {MAX_INFLATED} bytes"));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `hello hello hello hello zlib\n` as zlib writes it at level 9, in a
+    // block with the fixed codes.
+    const ZLIB: &[u8] = &[
+        0x78, 0xda, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0xc0, 0x20, 0xab, 0x72, 0x32, 0x93,
+        0xb8, 0x00, 0xa3, 0x04, 0x0a, 0x8c,
+    ];
+    // `hello hello hello hello gzip\n` as gzip writes it, with no name.
+    const GZIP: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+        0x57, 0xc8, 0xc0, 0x20, 0xd3, 0xab, 0x32, 0x0b, 0xb8, 0x00, 0x8f, 0x08, 0x1d, 0x6f, 0x1d,
+        0x00, 0x00, 0x00,
+    ];
+    const PANGRAM: &[u8] =
+        b"the quick brown fox jumps over the lazy dog while zebras quietly vex jumpy wizards\n";
+    // `PANGRAM` twice as a raw deflate stream, in a block with codes of its
+    // own.
+    const DYNAMIC: &[u8] = &[
+        0xad, 0xcc, 0xc1, 0x11, 0x80, 0x20, 0x0c, 0x05, 0xd1, 0xbb, 0x55, 0xfc, 0xd6, 0x40, 0xa2,
+        0xa0, 0x68, 0x34, 0x04, 0x10, 0xaa, 0x57, 0xc7, 0x16, 0x3c, 0xef, 0xbc, 0x55, 0x4f, 0x38,
+        0x73, 0x18, 0x57, 0x58, 0xe1, 0xba, 0x63, 0xe2, 0x0b, 0x4b, 0xde, 0x8e, 0x04, 0x2e, 0x24,
+        0xd0, 0x27, 0x47, 0xd3, 0x1b, 0x1c, 0xcf, 0xa8, 0x3e, 0x44, 0x42, 0x27, 0x2b, 0x26, 0xbd,
+        0x88, 0x34, 0x36, 0x14, 0xfa, 0x40, 0x43, 0x0d, 0xdd, 0x88, 0x4b, 0x83, 0xfe, 0xbf, 0xbc,
+        0x01,
+    ];
+
+    #[test]
+    fn inflates_streams() {
+        assert_eq!(zlib(ZLIB).unwrap(), b"hello hello hello hello zlib\n");
+        assert_eq!(gzip(GZIP).unwrap(), b"hello hello hello hello gzip\n");
+        assert_eq!(inflate(DYNAMIC).unwrap(), PANGRAM.repeat(2));
+    }
+
+    #[test]
+    fn rejects_truncated_streams() {
+        for len in [1, 5, ZLIB.len() - 6] {
+            assert!(zlib(&ZLIB[..len]).is_err(), "zlib cut at {len}");
+        }
+        for len in [2, 8, 15] {
+            assert!(gzip(&GZIP[..len]).is_err(), "gzip cut at {len}");
+        }
+        for len in [1, 10, 40, DYNAMIC.len() - 2] {
+            assert!(inflate(&DYNAMIC[..len]).is_err(), "deflate cut at {len}");
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_streams() {
+        let mut data = ZLIB.to_vec();
+        // A check of the header that doesn't add up.
+        data[1] ^= 0x01;
+        assert!(zlib(&data).is_err());
+        let mut data = GZIP.to_vec();
+        data[1] ^= 0xFF;
+        assert!(gzip(&data).is_err());
+        let mut data = DYNAMIC.to_vec();
+        // The block type left reserved.
+        data[0] |= 0x06;
+        assert!(inflate(&data).is_err());
+    }
+}
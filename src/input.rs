@@ -1,19 +1,98 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
 use anyhow::anyhow;
 use crossterm::event::*;
 use io::FileBuf;
+use ratatui::layout::Direction;
 use ratatui::widgets::*;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
+use crate::bookmark::*;
+use crate::clipboard;
+use crate::compare::*;
+use crate::confirm::*;
 use crate::cursor::*;
+use crate::fuzzy::*;
+use crate::glob::*;
+use crate::hex;
+use crate::history::*;
+use crate::io::*;
+use crate::jump::*;
+use crate::mouse::*;
+use crate::picker::*;
 use crate::resource::*;
+use crate::search::*;
+use crate::split::*;
+use crate::substitute::*;
+use crate::view::*;
+use crate::walk::*;
 use crate::*;
 
 pub const DELTA: u64 = 16;
+/// Files listed at most by the file finder.
+const MAX_FOUND_FILES: usize = 50_000;
+/// Files added at once without asking first.
+const MANY_FILES: usize = 100;
+/// The key leading the commands on the splits of the View area, `ctrl + w`
+/// as the control character it stands for.
+const SPLIT_KEY: char = '\u{17}';
+
+/// What the text typed into the `EntryBox` is used for.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    #[default]
+    File,
+    Goto,
+    GotoOffset,
+    Bookmark,
+    Search,
+    SearchBytes,
+    SearchAll,
+    Grep,
+    FilterFiles,
+    GlobFiles,
+    SaveSearch,
+    Rename,
+    Group,
+}
+
+impl Prompt {
+    /// Whether the text typed is a search query.
+    pub fn is_search(&self) -> bool {
+        matches!(self, Prompt::Search | Prompt::SearchAll | Prompt::Grep)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Prompt::File => " Filename ",
+            Prompt::Goto => " Goto line ",
+            Prompt::GotoOffset => " Goto offset ",
+            Prompt::Bookmark => " Bookmark name ",
+            Prompt::Search => " Search ",
+            Prompt::SearchBytes => " Search bytes ",
+            Prompt::SearchAll => " Search all ",
+            Prompt::Grep => " Grep ",
+            Prompt::FilterFiles => " Filter files ",
+            Prompt::GlobFiles => " Show files matching ",
+            Prompt::SaveSearch => " Save search as ",
+            Prompt::Rename => " Rename to ",
+            Prompt::Group => " Add to group ",
+        }
+    }
+}
 
 #[derive(Default)]
 pub struct EntryBox {
     is_active: bool,
     is_err: bool,
+    prompt: Prompt,
     input_buff: String,
+    // Position of the text cursor in the input, in characters.
+    cursor: usize,
 }
 
 impl EntryBox {
@@ -26,17 +105,131 @@ impl EntryBox {
         self.is_active = !self.is_active;
     }
 
+    pub fn open(&mut self, prompt: Prompt) {
+        self.prompt = prompt;
+        self.is_active = true;
+    }
+
+    #[inline]
+    pub fn prompt(&self) -> Prompt {
+        self.prompt
+    }
+
     #[inline]
     pub fn is_visible(&self) -> bool {
         self.is_active
     }
 
+    /// Insert a character at the text cursor.
     pub fn push(&mut self, ch: char) {
-        self.input_buff.push(ch);
+        let at = self.byte(self.cursor);
+        self.input_buff.insert(at, ch);
+        self.cursor += 1;
     }
 
+    /// Remove the character before the text cursor, along with the marks
+    /// combined with it.
     pub fn pop(&mut self) {
-        self.input_buff.pop();
+        let start = self.cursor - self.grapheme_before();
+        let (from, to) = (self.byte(start), self.byte(self.cursor));
+        self.input_buff.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    /// Remove the character under the text cursor, along with the marks
+    /// combined with it.
+    pub fn delete(&mut self) {
+        let end = self.cursor + self.grapheme_after();
+        let (from, to) = (self.byte(self.cursor), self.byte(end));
+        self.input_buff.replace_range(from..to, "");
+    }
+
+    /// Remove the word before the text cursor, as well as the separators
+    /// between them.
+    pub fn delete_word(&mut self) {
+        let start = self.word_start();
+        let (from, to) = (self.byte(start), self.byte(self.cursor));
+        self.input_buff.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    pub fn left(&mut self) {
+        self.cursor -= self.grapheme_before();
+    }
+
+    pub fn right(&mut self) {
+        self.cursor += self.grapheme_after();
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.char_len();
+    }
+
+    pub fn word_left(&mut self) {
+        self.cursor = self.word_start();
+    }
+
+    pub fn word_right(&mut self) {
+        let chars = self.input_buff.chars().skip(self.cursor);
+        let skipped = chars.clone().take_while(|&c| !is_word(c)).count();
+        let word = chars.skip(skipped).take_while(|&c| is_word(c)).count();
+        self.cursor += skipped + word;
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // Where the word before the text cursor starts.
+    fn word_start(&self) -> usize {
+        let before = self
+            .input_buff
+            .chars()
+            .take(self.cursor)
+            .collect::<Vec<_>>();
+        let skipped = before.iter().rev().take_while(|&&c| !is_word(c)).count();
+        let word = before[..before.len() - skipped]
+            .iter()
+            .rev()
+            .take_while(|&&c| is_word(c))
+            .count();
+        self.cursor - skipped - word
+    }
+
+    // Characters in the grapheme before the text cursor, and in the one
+    // under it, such as a letter and the accent combined with it.
+    fn grapheme_before(&self) -> usize {
+        let before = &self.input_buff[..self.byte(self.cursor)];
+        before
+            .graphemes(true)
+            .next_back()
+            .map_or(0, |g| g.chars().count())
+    }
+
+    fn grapheme_after(&self) -> usize {
+        let after = &self.input_buff[self.byte(self.cursor)..];
+        after
+            .graphemes(true)
+            .next()
+            .map_or(0, |g| g.chars().count())
+    }
+
+    // Byte offset of the character at `index`.
+    fn byte(&self, index: usize) -> usize {
+        self.input_buff
+            .char_indices()
+            .nth(index)
+            .map_or(self.input_buff.len(), |(i, _)| i)
+    }
+
+    #[inline]
+    fn char_len(&self) -> usize {
+        self.input_buff.chars().count()
     }
 
     #[inline]
@@ -51,10 +244,18 @@ impl EntryBox {
 
     pub fn clear(&mut self) {
         self.input_buff.clear();
+        self.cursor = 0;
+    }
+
+    /// Replace the input, putting the text cursor at its end.
+    pub fn set(&mut self, text: &str) {
+        self.input_buff = text.to_string();
+        self.cursor = self.char_len();
     }
 
     #[inline]
     pub fn take(&mut self) -> String {
+        self.cursor = 0;
         std::mem::take(&mut self.input_buff)
     }
 
@@ -81,17 +282,113 @@ impl EntryBox {
         self.input_buff.clone().into_boxed_str()
     }
 
-    pub fn get_span(&self, width: usize) -> &str {
-        let len = self.input_buff.len();
-        let offset = len.saturating_sub(width);
-        &self.input_buff[offset..len]
+    /// The part of the input that fits into `width` columns around the text
+    /// cursor, and the column of the cursor within it. Wide characters take
+    /// up two columns.
+    pub fn get_span(&self, width: usize) -> (&str, usize) {
+        let cursor = self.byte(self.cursor);
+        // Graphemes before the cursor scroll out of view from the left.
+        let mut start = 0;
+        let mut column = self.input_buff[..cursor].width();
+        for grapheme in self.input_buff[..cursor].graphemes(true) {
+            if column <= width {
+                break;
+            }
+            start += grapheme.len();
+            column -= grapheme.width();
+        }
+        let mut end = start;
+        let mut used = 0;
+        for grapheme in self.input_buff[start..].graphemes(true) {
+            used += grapheme.width();
+            if used > width {
+                break;
+            }
+            end += grapheme.len();
+        }
+        (&self.input_buff[start..end], column)
+    }
+}
+
+// Characters making up the words that Ctrl-Left, Ctrl-Right and Ctrl-W move
+// over. Everything else, such as `/` in paths, separates them.
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Keys typed in front of a command: a numeric count as in `12j` and a
+/// leading key that waits for its argument as in `ma`.
+#[derive(Default)]
+pub struct Pending {
+    count: Option<usize>,
+    key: Option<char>,
+}
+
+impl Pending {
+    #[inline]
+    pub fn new() -> Self {
+        Pending::default()
+    }
+
+    /// Append a digit to the count. A leading zero doesn't start a count.
+    pub fn push_digit(&mut self, digit: u32) -> bool {
+        if self.count.is_none() && digit == 0 {
+            return false;
+        }
+        let count = self.count.unwrap_or_default();
+        self.count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+        true
+    }
+
+    #[inline]
+    pub fn take_count(&mut self) -> Option<usize> {
+        self.count.take()
+    }
+
+    pub fn set_key(&mut self, key: char) {
+        self.key = Some(key);
     }
+
+    #[inline]
+    pub fn take_key(&mut self) -> Option<char> {
+        self.key.take()
+    }
+}
+
+/// A line of the Files pane, either a file or the header of the group of
+/// files following it.
+pub enum FileRow {
+    /// A group, holding the files it lists, or hides while collapsed.
+    Group {
+        name: String,
+        files: Vec<usize>,
+        collapsed: bool,
+    },
+    File {
+        index: usize,
+        grouped: bool,
+    },
 }
 
 pub struct FileListState {
     size: usize,
+    // The file shown in the View pane.
     pub index: usize,
+    // The file highlighted on the list, shown once it's opened.
+    selected: usize,
     pub state: ListState,
+    // Pattern the file names are fuzzily matched against. The files it
+    // keeps are laid out in `rows` by `refilter`.
+    filter: String,
+    // Globs the files are kept to on top of the filter, until they're
+    // dropped.
+    globs: Option<GlobFilter>,
+    // The files that can be highlighted, the first of a collapsed group
+    // standing for its header.
+    shown: Vec<usize>,
+    // Groups whose files are folded away under their header.
+    collapsed: HashSet<String>,
+    rows: Vec<FileRow>,
 }
 impl FileListState {
     pub fn new(size: usize) -> Self {
@@ -100,7 +397,18 @@ impl FileListState {
         FileListState {
             size,
             index: 0,
+            selected: 0,
             state,
+            filter: String::new(),
+            globs: None,
+            shown: (0..size).collect(),
+            collapsed: HashSet::new(),
+            rows: (0..size)
+                .map(|index| FileRow::File {
+                    index,
+                    grouped: false,
+                })
+                .collect(),
         }
     }
 
@@ -109,23 +417,230 @@ impl FileListState {
     }
 
     pub fn next(&mut self) {
-        self.index = bounded_add(self.index, 1, self.size.saturating_sub(1));
-        self.state.select(Some(self.index));
+        let next = self.shown.partition_point(|&i| i <= self.selected);
+        self.selected = self.shown.get(next).copied().unwrap_or(self.selected);
+        self.sync();
     }
 
     pub fn bottom(&mut self) {
-        self.index = self.size.saturating_sub(1);
-        self.state.select(Some(self.index));
+        self.selected = self.shown.last().copied().unwrap_or(self.selected);
+        self.sync();
     }
 
     pub fn prev(&mut self) {
-        self.index = self.index.saturating_sub(1);
-        self.state.select(Some(self.index));
+        let prev = self.shown.partition_point(|&i| i < self.selected);
+        self.selected = prev.checked_sub(1).map_or(self.selected, |p| self.shown[p]);
+        self.sync();
     }
 
     pub fn top(&mut self) {
-        self.index = 0;
-        self.state.select(Some(self.index));
+        self.selected = self.shown.first().copied().unwrap_or(self.selected);
+        self.sync();
+    }
+
+    /// Show the file at `index` in the View pane, highlighting it as well.
+    pub fn select(&mut self, index: usize) {
+        self.index = index.min(self.size.saturating_sub(1));
+        self.selected = self.index;
+        self.sync();
+    }
+
+    /// Show the file at `index` and highlight the one at `selected`, as
+    /// after the files got reordered.
+    pub fn place(&mut self, index: usize, selected: usize) {
+        self.index = index.min(self.size.saturating_sub(1));
+        self.selected = selected.min(self.size.saturating_sub(1));
+        self.sync();
+    }
+
+    /// The file highlighted on the list.
+    #[inline]
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Show the highlighted file in the View pane.
+    pub fn open_selected(&mut self) {
+        self.select(self.selected);
+    }
+
+    /// Move the highlighted file one place down the list, or up, returning
+    /// the two places swapped. The View pane keeps showing the same file.
+    pub fn move_selected(&mut self, forward: bool) -> Option<(usize, usize)> {
+        let from = self.selected;
+        let to = match forward {
+            true => Some(from + 1).filter(|&to| to < self.size)?,
+            false => from.checked_sub(1)?,
+        };
+        self.selected = to;
+        if self.index == from {
+            self.index = to;
+        } else if self.index == to {
+            self.index = from;
+        }
+        self.sync();
+        Some((from, to))
+    }
+
+    #[inline]
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Show only the files whose name fuzzily matches `pattern`, or every
+    /// file for an empty one. Takes effect with the next `refilter`.
+    pub fn set_filter(&mut self, pattern: &str) {
+        self.filter = pattern.to_string();
+    }
+
+    #[inline]
+    pub fn globs(&self) -> Option<&GlobFilter> {
+        self.globs.as_ref()
+    }
+
+    /// Hide the files `globs` don't match, without closing them, or show
+    /// them again for `None`. Takes effect with the next `refilter`.
+    pub fn set_globs(&mut self, globs: Option<GlobFilter>) {
+        self.globs = globs;
+    }
+
+    /// Fold the files of `group` away under its header, or unfold them.
+    /// Takes effect with the next `refilter`.
+    pub fn toggle_group(&mut self, group: &str) {
+        if !self.collapsed.remove(group) {
+            self.collapsed.insert(group.to_string());
+        }
+    }
+
+    /// Unfold the files of `group`. Takes effect with the next `refilter`.
+    pub fn expand_group(&mut self, group: &str) {
+        self.collapsed.remove(group);
+    }
+
+    /// The group whose header is highlighted, if it's collapsed.
+    pub fn collapsed_group(&self) -> Option<&str> {
+        match self.rows.get(self.state.selected()?)? {
+            FileRow::Group {
+                name,
+                collapsed: true,
+                ..
+            } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Highlight the first file of the next group down the list, or up,
+    /// unfolding it. Returns false if there's no group that way.
+    pub fn jump_group(&mut self, forward: bool) -> bool {
+        let row = self.state.selected().unwrap_or_default();
+        let is_target = |r: &&FileRow| match r {
+            FileRow::Group { files, .. } => files.first().is_some_and(|&f| f != self.selected),
+            FileRow::File { .. } => false,
+        };
+        let target = match forward {
+            true => self.rows.iter().skip(row + 1).find(is_target),
+            false => self.rows[..row.min(self.rows.len())]
+                .iter()
+                .rev()
+                .find(is_target),
+        };
+        let Some(FileRow::Group { name, files, .. }) = target else {
+            return false;
+        };
+        let (name, first) = (name.clone(), files[0]);
+        self.collapsed.remove(&name);
+        self.selected = first;
+        self.sync();
+        true
+    }
+
+    /// Match the file `names` against the filter again, as files come and
+    /// go, laying them out under the `groups` they're in.
+    pub fn refilter(&mut self, names: &[&str], groups: &[Option<&str>]) {
+        let is_shown = |name: &str| {
+            self.globs.as_ref().is_none_or(|g| g.is_match(name))
+                && (self.filter.is_empty() || score(&self.filter, name).is_some())
+        };
+        let mut rows = Vec::new();
+        let mut shown = Vec::new();
+        // The group of the file before, along with the row of its header.
+        let mut group = None;
+        let mut header = None;
+        for (i, name) in names.iter().enumerate() {
+            if !is_shown(name) {
+                continue;
+            }
+            let of = groups.get(i).copied().flatten();
+            if of != group {
+                group = of;
+                header = of.map(|name| {
+                    rows.push(FileRow::Group {
+                        name: name.to_string(),
+                        files: Vec::new(),
+                        collapsed: self.collapsed.contains(name),
+                    });
+                    rows.len() - 1
+                });
+            }
+            if let Some(FileRow::Group {
+                files, collapsed, ..
+            }) = header.and_then(|h| rows.get_mut(h))
+            {
+                files.push(i);
+                if *collapsed {
+                    if files.len() == 1 {
+                        shown.push(i);
+                    }
+                    continue;
+                }
+            }
+            rows.push(FileRow::File {
+                index: i,
+                grouped: of.is_some(),
+            });
+            shown.push(i);
+        }
+        self.rows = rows;
+        self.shown = shown;
+        self.sync();
+    }
+
+    #[inline]
+    pub fn rows(&self) -> &[FileRow] {
+        &self.rows
+    }
+
+    /// The file on the given row of the list, counted from the top of the
+    /// pane, if it's a file and not a group header.
+    pub fn file_at_row(&self, row: usize) -> Option<usize> {
+        match self.rows.get(self.state.offset() + row)? {
+            FileRow::File { index, .. } => Some(*index),
+            FileRow::Group { .. } => None,
+        }
+    }
+
+    /// Whether the file at `index` is on the list, if only under the header
+    /// of its collapsed group.
+    pub fn is_listed(&self, index: usize) -> bool {
+        self.row_of(index).is_some()
+    }
+
+    fn row_of(&self, index: usize) -> Option<usize> {
+        self.rows.iter().position(|row| match row {
+            FileRow::Group {
+                files,
+                collapsed: true,
+                ..
+            } => files.contains(&index),
+            FileRow::Group { .. } => false,
+            FileRow::File { index: i, .. } => *i == index,
+        })
+    }
+
+    // Highlight the row of the selected file, or the header of its group
+    // while collapsed, if it's on the list.
+    fn sync(&mut self) {
+        self.state.select(self.row_of(self.selected));
     }
 
     #[inline]
@@ -138,14 +653,32 @@ impl FileListState {
     }
 
     pub fn close(&mut self) -> anyhow::Result<usize> {
+        self.close_at(self.index)
+    }
+
+    /// Take the highlighted file off the list, whether it's shown or not.
+    pub fn close_selected(&mut self) -> anyhow::Result<usize> {
+        self.close_at(self.selected)
+    }
+
+    /// Take the file at `index` off the list. The files after it move up,
+    /// the shown and highlighted ones staying the same unless it was one of
+    /// them.
+    pub fn close_at(&mut self, index: usize) -> anyhow::Result<usize> {
         if self.size == 0 {
             return Err(anyhow!("ListState empty"));
         }
-        let res = self.index;
         self.size = self.size.saturating_sub(1);
+        if self.selected > index {
+            self.selected -= 1;
+        }
+        if self.index > index {
+            self.index -= 1;
+        }
+        self.selected = self.selected.min(self.size.saturating_sub(1));
         self.index = self.index.min(self.size.saturating_sub(1));
-        self.state.select(Some(self.index));
-        Ok(res)
+        self.sync();
+        Ok(index)
     }
 }
 
@@ -155,9 +688,18 @@ pub fn key_listener(res: &mut Resource) -> anyhow::Result<bool> {
         if quit_listener(&event) {
             return Ok(true);
         }
-        match res.entry_box().is_visible() {
-            true => write_key_event(event, res)?,
-            false => normal_key_event(event, res),
+        if res.confirm().is_some() {
+            confirm_key_event(event, res);
+        } else if res.picker().is_visible() {
+            picker_key_event(event, res);
+        } else if res.entry_box().is_visible() {
+            write_key_event(event, res)?;
+        } else if res.compare().is_some() {
+            compare_key_event(event, res);
+        } else if let Event::Mouse(mouse) = event {
+            mouse_event(mouse, res);
+        } else {
+            normal_key_event(event, res);
         }
     }
 
@@ -176,6 +718,27 @@ pub fn detrigger_view_update(res: &mut Resource) {
         .for_each(FileBuf::detrigger_view_update);
 }
 
+fn yank(res: &mut Resource, count: usize) {
+    let Some((text, lines)) = current_file_buff_mut(res).and_then(|f| f.yank(count)) else {
+        return;
+    };
+    let message = match clipboard::copy(&text) {
+        Ok(()) if lines == 1 => "1 line yanked".to_string(),
+        Ok(()) => format!("{lines} lines yanked"),
+        Err(err) => {
+            log::trace!("Couldn't yank due to: {err}");
+            format!("Couldn't yank: {err}")
+        }
+    };
+    res.toast_mut().show(message);
+}
+
+// The file buffer that is currently shown in the View pane.
+fn current_file_buff_mut(res: &mut Resource) -> Option<&mut FileBuf> {
+    let curr_index = res.file_list_state().index();
+    res.files_mut().get_file_buff_mut(curr_index)
+}
+
 fn quit_listener(event: &Event) -> bool {
     if let Event::Key(KeyEvent {
         code: KeyCode::Char('q'),
@@ -188,7 +751,79 @@ fn quit_listener(event: &Event) -> bool {
     false
 }
 
+fn mouse_event(event: MouseEvent, res: &mut Resource) {
+    let MouseEvent {
+        kind, column, row, ..
+    } = event;
+    let (step, steps) = match kind {
+        MouseEventKind::Down(MouseButton::Left) if !res.tree().is_visible() => {
+            let files_row = res.mouse().files_row(column, row);
+            let index = files_row.and_then(|r| res.file_list_state().file_at_row(r));
+            if let Some(index) = index {
+                let shown = res.file_list_state().index();
+                res.pointer_mut().set_cursor::<Files>();
+                res.file_list_state_mut().place(shown, index);
+                res.mouse_mut().set_grab(Some(Grab::File));
+                return;
+            }
+            let Some(at) = res.mouse().scrollbar_row(column, row) else {
+                return;
+            };
+            res.mouse_mut().set_grab(Some(Grab::Scrollbar));
+            at
+        }
+        MouseEventKind::Drag(MouseButton::Left) if res.mouse().grab() == Some(Grab::Scrollbar) => {
+            res.mouse().scrollbar_drag_row(row)
+        }
+        MouseEventKind::Drag(MouseButton::Left) if res.mouse().grab() == Some(Grab::File) => {
+            let files_row = res.mouse().files_drag_row(row);
+            let to = res.file_list_state().file_at_row(files_row);
+            if let Some(to) = to {
+                drag_file(res, to);
+            }
+            return;
+        }
+        MouseEventKind::Up(_) => {
+            res.mouse_mut().set_grab(None);
+            return;
+        }
+        _ => return,
+    };
+    if let Some(curr_buff) = current_file_buff_mut(res) {
+        curr_buff.scroll_to(step, steps);
+    }
+}
+
 fn normal_key_event(event: Event, res: &mut Resource) {
+    if let Event::Key(KeyEvent {
+        code: KeyCode::Char(c @ '0'..='9'),
+        modifiers: KeyModifiers::NONE,
+        ..
+    }) = event
+    {
+        let digit = c.to_digit(10).unwrap_or_default();
+        if res.pointer().cursor_at::<View>() && res.pending_mut().push_digit(digit) {
+            return;
+        }
+    }
+    let count = res.pending_mut().take_count();
+
+    if let Some(key) = res.pending_mut().take_key() {
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char(arg),
+            ..
+        }) = event
+        {
+            pending_key_event(key, arg, res);
+        }
+        return;
+    }
+
+    if res.pointer().cursor_at::<Files>() && res.tree().is_visible() && tree_key_event(&event, res)
+    {
+        return;
+    }
+
     match event {
         Event::Key(KeyEvent {
             code: KeyCode::Char('n'),
@@ -196,159 +831,2274 @@ fn normal_key_event(event: Event, res: &mut Resource) {
             ..
         }) => {
             res.pointer_mut().toggle();
-            res.entry_box_mut().toggle();
+            res.entry_box_mut().open(Prompt::File);
         }
         Event::Key(KeyEvent {
-            code: KeyCode::Char('d'),
-            modifiers: KeyModifiers::CONTROL,
+            code: KeyCode::Char(':'),
             ..
-        }) => {
-            let id = match res.file_list_state_mut().close() {
-                Ok(id) => id,
-                Err(_) => return,
+        }) if res.pointer().cursor_at::<View>() => {
+            // A hex dump is gone through by the offsets of its bytes.
+            let prompt = match current_file_buff_mut(res).is_some_and(|b| b.is_hex()) {
+                true => Prompt::GotoOffset,
+                false => Prompt::Goto,
             };
-            res.files_mut().close(id);
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(prompt);
         }
         Event::Key(KeyEvent {
-            code: KeyCode::Left,
-            ..
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('h'),
+            code: KeyCode::Char('t'),
+            modifiers: KeyModifiers::CONTROL,
             ..
-        }) => res.pointer_mut().set_cursor::<Files>(),
+        }) => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.toggle_tail();
+            }
+        }
         Event::Key(KeyEvent {
-            code: KeyCode::Right,
-            ..
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('l'),
+            code: KeyCode::Char('/'),
             ..
-        }) => res.pointer_mut().set_cursor::<View>(),
+        }) if res.pointer().cursor_at::<Files>() => {
+            let filter = res.file_list_state().filter().to_string();
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::FilterFiles);
+            res.entry_box_mut().set(&filter);
+        }
         Event::Key(KeyEvent {
-            code: KeyCode::Down,
+            code: KeyCode::Char('f'),
             modifiers: KeyModifiers::NONE,
             ..
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('j'),
-            modifiers: KeyModifiers::NONE,
+        }) if res.pointer().cursor_at::<Files>() => {
+            let globs = res.file_list_state().globs().map(|g| g.text().to_string());
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::GlobFiles);
+            res.entry_box_mut().set(&globs.unwrap_or_default());
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
             ..
-        }) => {
-            if res.pointer().cursor_at::<Files>() {
-                res.file_list_state_mut().next();
-            }
-            if res.pointer().cursor_at::<View>() {
-                let curr_index = res.file_list_state().index();
-                if let Some(curr_buff) = res.files_mut().get_file_buff_mut(curr_index) {
-                    curr_buff.next();
+        }) if res.pointer().cursor_at::<Files>() => {
+            let collapsed = res.file_list_state().collapsed_group().map(str::to_string);
+            match collapsed {
+                Some(group) => {
+                    res.file_list_state_mut().toggle_group(&group);
+                    refilter_files(res);
                 }
+                None => open_selected_file(res),
             }
         }
         Event::Key(KeyEvent {
-            code: KeyCode::Up,
+            code: code @ (KeyCode::Down | KeyCode::Up),
+            modifiers: KeyModifiers::SHIFT,
+            ..
+        }) if res.pointer().cursor_at::<Files>() => move_file(res, code == KeyCode::Down),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('s'),
             modifiers: KeyModifiers::NONE,
             ..
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('k'),
+        }) if res.pointer().cursor_at::<Files>() && !res.tree().is_visible() => sort_files(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('p'),
             modifiers: KeyModifiers::NONE,
             ..
-        }) => {
-            if res.pointer().cursor_at::<Files>() {
-                res.file_list_state_mut().prev();
-            }
-            if res.pointer().cursor_at::<View>() {
-                let curr_index = res.file_list_state().index();
-                if let Some(curr_buff) = res.files_mut().get_file_buff_mut(curr_index) {
-                    curr_buff.prev();
-                }
-            }
+        }) if res.pointer().cursor_at::<Files>() && !res.tree().is_visible() => {
+            let index = res.file_list_state().selected();
+            reorder_files(res, |files| files.toggle_pin(index));
         }
         Event::Key(KeyEvent {
-            code: KeyCode::Down,
-            modifiers: KeyModifiers::CONTROL,
-            ..
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('j'),
-            modifiers: KeyModifiers::CONTROL,
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::NONE,
             ..
-        }) => {
-            if res.pointer().cursor_at::<Files>() {
-                res.file_list_state_mut().bottom();
-            }
-        }
+        }) => reload_file(res),
         Event::Key(KeyEvent {
-            code: KeyCode::Up,
+            code: KeyCode::Char('r'),
             modifiers: KeyModifiers::CONTROL,
             ..
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('k'),
-            modifiers: KeyModifiers::CONTROL,
+        }) => reload_all_files(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('m'),
+            modifiers: KeyModifiers::NONE,
             ..
-        }) => {
-            if res.pointer().cursor_at::<Files>() {
-                res.file_list_state_mut().top();
-            }
+        }) if res.pointer().cursor_at::<Files>() && !res.tree().is_visible() => {
+            let index = res.file_list_state().selected();
+            let Some(name) = res
+                .files()
+                .get_file_buff(index)
+                .map(|f| f.name().to_string())
+            else {
+                return;
+            };
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::Rename);
+            res.entry_box_mut().set(&name);
         }
-        _ => {}
-    }
-}
-
-fn write_key_event(event: Event, res: &mut Resource) -> anyhow::Result<()> {
-    match event {
         Event::Key(KeyEvent {
-            code: KeyCode::Char('n'),
-            modifiers: KeyModifiers::CONTROL,
-            ..
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Esc,
+            code: KeyCode::Char('a'),
             modifiers: KeyModifiers::NONE,
             ..
-        }) => {
+        }) if res.pointer().cursor_at::<Files>() => {
+            let index = res.file_list_state().selected();
+            let Some(file) = res.files().get_file_buff(index) else {
+                return;
+            };
+            let group = res.groups().group_of(file.path()).unwrap_or_default();
+            let group = group.to_string();
             res.pointer_mut().toggle();
-            res.entry_box_mut().clear();
-            res.entry_box_mut().toggle();
+            res.entry_box_mut().open(Prompt::Group);
+            res.entry_box_mut().set(&group);
         }
         Event::Key(KeyEvent {
-            code: KeyCode::Enter,
+            code: KeyCode::Char('z'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<Files>() => {
+            let index = res.file_list_state().selected();
+            let Some(file) = res.files().get_file_buff(index) else {
+                return;
+            };
+            match res.groups().group_of(file.path()).map(str::to_string) {
+                Some(group) => {
+                    res.file_list_state_mut().toggle_group(&group);
+                    refilter_files(res);
+                }
+                None => {
+                    let message = format!("{} isn't in a group", file.name());
+                    res.toast_mut().show(message);
+                }
+            }
+        }
+        Event::Key(KeyEvent {
+            code: code @ KeyCode::Char('}' | '{'),
+            ..
+        }) if res.pointer().cursor_at::<Files>() => {
+            let forward = code == KeyCode::Char('}');
+            if res.file_list_state_mut().jump_group(forward) {
+                refilter_files(res);
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('='),
+            ..
+        }) if res.pointer().cursor_at::<Files>() => compare_files(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('D'),
+            ..
+        }) if res.pointer().cursor_at::<Files>() && !res.tree().is_visible() => {
+            let index = res.file_list_state().selected();
+            let Some(file) = res.files().get_file_buff(index) else {
+                return;
+            };
+            let question = format!("Delete {} from disk?", file.name());
+            let action = Action::DeleteFile(file.path().to_path_buf());
+            res.set_confirm(Some(Confirm::new(question, action)));
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('d' | 'x'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<Files>() && !res.tree().is_visible() => {
+            let id = match res.file_list_state_mut().close_selected() {
+                Ok(id) => id,
+                Err(_) => return,
+            };
+            res.files_mut().close(id);
+            refilter_files(res);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc, ..
+        }) if res.pointer().cursor_at::<Files>() => {
+            res.file_list_state_mut().set_filter("");
+            refilter_files(res);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('t'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<Files>() && !res.tree().is_empty() => {
+            res.tree_mut().toggle()
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('/'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            let Some(curr_buff) = current_file_buff_mut(res) else {
+                return;
+            };
+            // A hex dump is searched for bytes rather than lines.
+            if curr_buff.is_hex() {
+                res.pointer_mut().toggle();
+                res.entry_box_mut().open(Prompt::SearchBytes);
+                return;
+            }
+            let cursor = curr_buff.view().cursor();
+            res.search_mut().start(cursor);
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::Search);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(key @ ('n' | 'N')),
+            modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            match current_file_buff_mut(res).is_some_and(|b| b.is_hex()) {
+                true => search_bytes_next(res, key == 'n', count.unwrap_or(1)),
+                false => search_next(res, key == 'n', count.unwrap_or(1)),
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('f'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => {
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::SearchAll);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('g'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => {
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::Grep);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('p'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => find_file(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('l'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.pointer().cursor_at::<View>() => find_line(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('O'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => list_definitions(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(key @ ('}' | '{')),
+            ..
+        }) if res.pointer().cursor_at::<View>() => next_definition(res, key == '}'),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('Q'),
+            ..
+        }) => res.quickfix_mut().toggle(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(key @ (']' | '[')),
+            ..
+        }) => open_quickfix_entry(res, key == ']'),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('o'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => walk_jumps(res, true),
+        // Terminals send a tab for `ctrl + i`.
+        Event::Key(KeyEvent {
+            code: KeyCode::Tab, ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('i'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => walk_jumps(res, false),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('e'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.scroll_down(1);
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('y'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.scroll_up();
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('d'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.half_page_down();
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('u'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.half_page_up();
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('d'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => {
+            let id = match res.file_list_state_mut().close() {
+                Ok(id) => id,
+                Err(_) => return,
+            };
+            res.files_mut().close(id);
+            refilter_files(res);
+        }
+        Event::Key(KeyEvent {
+            code: code @ (KeyCode::Left | KeyCode::Right),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => {
+            res.resize_files(code == KeyCode::Right);
+            save_session(res);
+        }
+        Event::Key(KeyEvent {
+            code: code @ (KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down),
+            modifiers: KeyModifiers::ALT,
+            ..
+        }) => {
+            let (direction, grow) = match code {
+                KeyCode::Left | KeyCode::Right => (Direction::Horizontal, code == KeyCode::Right),
+                _ => (Direction::Vertical, code == KeyCode::Down),
+            };
+            match res.splits_mut().resize(direction, grow) {
+                true => save_session(res),
+                false => res.toast_mut().show("No split to resize that way"),
+            }
+        }
+        // Scroll the View pane left until its edge, then move onto the Files pane.
+        Event::Key(KeyEvent {
+            code: KeyCode::Left,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('h'),
+            ..
+        }) => {
+            let at_view = res.pointer().cursor_at::<View>();
+            match current_file_buff_mut(res) {
+                Some(curr_buff) if at_view && curr_buff.view().hscroll() > 0 => {
+                    curr_buff.scroll_left()
+                }
+                _ => res.pointer_mut().set_cursor::<Files>(),
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Right,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('l'),
+            ..
+        }) => {
+            if res.pointer().cursor_at::<View>() {
+                if let Some(curr_buff) = current_file_buff_mut(res) {
+                    curr_buff.scroll_right();
+                }
+            }
+            res.pointer_mut().set_cursor::<View>();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            modifiers: KeyModifiers::NONE,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('j'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) => {
+            if res.pointer().cursor_at::<Files>() {
+                res.file_list_state_mut().next();
+            }
+            if res.pointer().cursor_at::<View>() {
+                if let Some(curr_buff) = current_file_buff_mut(res) {
+                    curr_buff.next(count.unwrap_or(1));
+                }
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('k'),
+            modifiers: KeyModifiers::NONE,
             ..
         }) => {
-            let name = res.entry_box().input_buff();
-            if let Err(err) = res.files_mut().insert(&name) {
-                log::trace!("Cannot open file due to: {err}");
-                res.entry_box_mut().set_err();
-                return Ok(());
+            if res.pointer().cursor_at::<Files>() {
+                res.file_list_state_mut().prev();
+            }
+            if res.pointer().cursor_at::<View>() {
+                if let Some(curr_buff) = current_file_buff_mut(res) {
+                    curr_buff.prev(count.unwrap_or(1));
+                }
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('j'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::End, ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('G'),
+            ..
+        }) => {
+            if res.pointer().cursor_at::<Files>() {
+                res.file_list_state_mut().bottom();
+            }
+            if res.pointer().cursor_at::<View>() {
+                record_jump(res);
+                if let Some(curr_buff) = current_file_buff_mut(res) {
+                    match count {
+                        Some(line) => curr_buff
+                            .goto(line)
+                            .unwrap_or_else(|err| log::trace!("Cannot go to line due to: {err}")),
+                        None => curr_buff.bottom(),
+                    }
+                }
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('k'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Home,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('g'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) => {
+            if res.pointer().cursor_at::<Files>() {
+                res.file_list_state_mut().top();
+            }
+            if res.pointer().cursor_at::<View>() {
+                record_jump(res);
+                if let Some(curr_buff) = current_file_buff_mut(res) {
+                    curr_buff.top();
+                }
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('w'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.toggle_wrap();
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('v' | 'V'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.toggle_selection();
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc, ..
+        }) if res.pointer().cursor_at::<View>() => {
+            let selected = current_file_buff_mut(res).is_some_and(|f| f.clear_selection());
+            if selected {
+                return;
+            }
+            match res.substitution().is_some() {
+                true => res.set_substitution(None),
+                false => res.search_mut().clear(),
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('a'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<View>() => res.autoscroll_mut().toggle(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('+'),
+            ..
+        }) if res.autoscroll().is_running() => res.autoscroll_mut().faster(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('-'),
+            ..
+        }) if res.autoscroll().is_running() => res.autoscroll_mut().slower(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(key @ ('m' | '\'' | 'z')),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<View>() => res.pending_mut().set_key(key),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('w'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => res.pending_mut().set_key(SPLIT_KEY),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('b'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::Bookmark);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if !res.search().is_active() {
+                res.toast_mut().show("Search for something to save first");
+                return;
+            }
+            res.pointer_mut().toggle();
+            res.entry_box_mut().open(Prompt::SaveSearch);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('S'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if res.config().searches().is_empty() {
+                res.toast_mut()
+                    .show("No saved searches, save one with ctrl + s");
+                return;
+            }
+            let labels = res.config().searches().iter().map(SavedSearch::label);
+            let labels = labels.collect();
+            res.pointer_mut().toggle();
+            res.picker_mut()
+                .open_with_query(PickerKind::SavedSearches, labels);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('R'),
+            ..
+        }) => {
+            let labels = res.recent_files().entries().iter().rev().cloned();
+            let labels = labels.collect();
+            res.pointer_mut().toggle();
+            res.picker_mut()
+                .open_with_query(PickerKind::RecentFiles, labels);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('B'),
+            ..
+        }) => {
+            let labels = res.bookmarks().labels();
+            res.pointer_mut().toggle();
+            res.picker_mut().open(PickerKind::Bookmarks, labels);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('%'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            record_jump(res);
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                let res_jump = match count {
+                    Some(percent) => curr_buff.goto_percent(percent),
+                    None => curr_buff.jump_to_match(),
+                };
+                if let Err(err) = res_jump {
+                    log::trace!("Cannot jump due to: {err}");
+                }
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('y'),
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if res.pointer().cursor_at::<View>() => yank(res, count.unwrap_or(1)),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('&'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            let curr_index = res.file_list_state().index();
+            if let Some(curr_buff) = res.files.get_file_buff_mut(curr_index) {
+                if let Err(err) = curr_buff.toggle_filter(&res.search) {
+                    res.toast.show(err.to_string());
+                }
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('T'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            let Some(curr_buff) = current_file_buff_mut(res) else {
+                return;
+            };
+            let message = match curr_buff.toggle_rendered() {
+                Ok(true) => return,
+                Ok(false) => format!("{} has no rendered view", curr_buff.name()),
+                Err(err) => {
+                    log::trace!("{err}");
+                    err.to_string()
+                }
+            };
+            res.toast_mut().show(message);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        }) if res.pointer().cursor_at::<View>() => open_member(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('X'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            let Some(curr_buff) = current_file_buff_mut(res) else {
+                return;
+            };
+            if let Err(err) = curr_buff.toggle_hex() {
+                log::trace!("{err}");
+                res.toast_mut().show(err.to_string());
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('M'),
+            ..
+        }) => res.config_mut().toggle_minimap(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('I'),
+            ..
+        }) => res.config_mut().toggle_invisibles(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('*'),
+            ..
+        }) if res.pointer().cursor_at::<View>() => res.config_mut().toggle_highlight_word(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(key @ ('>' | '<')),
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.next_word(key == '>');
+            }
+            if !res.config().highlight_word() {
+                res.config_mut().toggle_highlight_word();
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('#'),
+            ..
+        }) => res.config_mut().cycle_line_numbers(),
+        Event::Key(KeyEvent {
+            code: code @ (KeyCode::PageDown | KeyCode::PageUp),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => next_tab(res, code == KeyCode::PageDown),
+        Event::Key(KeyEvent {
+            code: KeyCode::PageDown,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.page_down();
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::PageUp,
+            ..
+        }) if res.pointer().cursor_at::<View>() => {
+            if let Some(curr_buff) = current_file_buff_mut(res) {
+                curr_buff.page_up();
+            }
+        }
+        _ => {}
+    }
+}
+
+// Commands made of a leading key and a character argument.
+fn pending_key_event(key: char, arg: char, res: &mut Resource) {
+    if key == SPLIT_KEY {
+        split_key_event(arg, res);
+        return;
+    }
+    if key == '\'' {
+        record_jump(res);
+    }
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        return;
+    };
+    match key {
+        'm' => curr_buff.set_mark(arg),
+        '\'' => {
+            if let Err(err) = curr_buff.jump_to_mark(arg) {
+                log::trace!("Cannot jump to mark due to: {err}");
+            }
+        }
+        'z' => match arg {
+            'a' => curr_buff.toggle_fold(),
+            't' => curr_buff.anchor_cursor(Anchor::Top),
+            'z' => curr_buff.anchor_cursor(Anchor::Center),
+            'b' => curr_buff.anchor_cursor(Anchor::Bottom),
+            'R' => curr_buff.open_folds(),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+// Divide the View area, move the focus to another split or close one.
+fn split_key_event(arg: char, res: &mut Resource) {
+    let (count, focused) = (res.splits().count(), res.splits().focused());
+    match arg {
+        's' => split_view(res, Direction::Vertical),
+        'v' => split_view(res, Direction::Horizontal),
+        'w' => focus_split(res, (focused + 1) % count),
+        'W' => focus_split(res, (focused + count - 1) % count),
+        'q' | 'c' => {
+            if let Some(pane) = res.splits_mut().close() {
+                show_pane(res, pane);
+            }
+        }
+        'o' => res.splits_mut().only(),
+        '=' => res.splits_mut().even(),
+        't' => open_tab(res),
+        'n' | 'p' => next_tab(res, arg == 'n'),
+        'x' => {
+            if let Some((splits, pane)) = res.tabs_mut().close() {
+                res.splits = splits;
+                show_pane(res, pane);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Open a tab showing the file of the focused split where it's scrolled to,
+// in a split of its own.
+fn open_tab(res: &mut Resource) {
+    let Some(pane) = current_pane(res) else {
+        res.toast_mut().show("No file to open a tab for");
+        return;
+    };
+    let splits = std::mem::take(&mut res.splits);
+    res.tabs_mut().open(splits, pane);
+}
+
+fn next_tab(res: &mut Resource, forward: bool) {
+    let (count, current) = (res.tabs().count(), res.tabs().current());
+    if count == 1 {
+        return;
+    }
+    let index = match forward {
+        true => (current + 1) % count,
+        false => (current + count - 1) % count,
+    };
+    let pane = current_pane(res).unwrap_or_default();
+    let splits = std::mem::take(&mut res.splits);
+    let (splits, pane) = res.tabs_mut().switch(index, splits, pane);
+    res.splits = splits;
+    show_pane(res, pane);
+}
+
+// Divide the focused split in two, both showing its file where it's
+// scrolled to.
+fn split_view(res: &mut Resource, direction: Direction) {
+    match current_pane(res) {
+        Some(pane) => res.splits_mut().split(direction, pane),
+        None => res.toast_mut().show("No file to split"),
+    }
+}
+
+fn focus_split(res: &mut Resource, split: usize) {
+    if res.splits().count() == 1 {
+        return;
+    }
+    let pane = current_pane(res).unwrap_or_default();
+    let pane = res.splits_mut().focus(split, pane);
+    show_pane(res, pane);
+}
+
+// What the focused split shows, kept by it while another split has the focus.
+fn current_pane(res: &Resource) -> Option<Pane> {
+    let curr_index = res.file_list_state().index();
+    let curr_buff = res.files().get_file_buff(curr_index)?;
+    Some(Pane {
+        path: curr_buff.path().to_path_buf(),
+        view: curr_buff.view().clone(),
+    })
+}
+
+// Show the file a split was left on in the View pane, where it was left.
+fn show_pane(res: &mut Resource, mut pane: Pane) {
+    let Some(index) = res.files().iter().position(|f| f.path() == pane.path) else {
+        let message = format!("{} was closed", pane.path.display());
+        res.toast_mut().show(message);
+        return;
+    };
+    res.files()[index].swap_view(&mut pane.view);
+    res.file_list_state_mut().select(index);
+    res.pointer_mut().set_cursor::<View>();
+}
+
+fn picker_key_event(event: Event, res: &mut Resource) {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc, ..
+        }) => close_picker(res),
+        Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            ..
+        }) => res.picker_mut().next(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Up, ..
+        }) => res.picker_mut().prev(),
+        // With a query line, letters are typed into it.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('j'),
+            modifiers,
+            ..
+        }) if res.picker().query().is_none() || modifiers == KeyModifiers::CONTROL => {
+            res.picker_mut().next()
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('k'),
+            modifiers,
+            ..
+        }) if res.picker().query().is_none() || modifiers == KeyModifiers::CONTROL => {
+            res.picker_mut().prev()
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        }) => {
+            let (Some(kind), Some(index)) = (res.picker().kind(), res.picker().selected()) else {
+                return;
+            };
+            // The entries are dropped along with the picker.
+            let item = res.picker().item(index).unwrap_or_default().to_string();
+            close_picker(res);
+            match kind {
+                PickerKind::Bookmarks => open_bookmark(res, index),
+                PickerKind::SearchHits => open_hit(res, index),
+                PickerKind::GrepHits => open_grep_hit(res, index),
+                PickerKind::FindFile => open_found_file(res, &item),
+                PickerKind::FindLine => open_found_line(res, index),
+                PickerKind::Definitions => open_definition(res, index),
+                PickerKind::SavedSearches => open_saved_search(res, index),
+                PickerKind::RecentFiles => open_found_file(res, &item),
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('d'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => {
+            let (Some(PickerKind::Bookmarks), Some(index)) =
+                (res.picker().kind(), res.picker().selected())
+            else {
+                return;
+            };
+            res.bookmarks_mut().remove(index);
+            if let Err(err) = res.bookmarks().save() {
+                log::trace!("{err}");
+            }
+            let labels = res.bookmarks().labels();
+            res.picker_mut().set_items(labels);
+        }
+        event => {
+            let edited = res
+                .picker_mut()
+                .query_mut()
+                .is_some_and(|query| edit_key_event(event, query));
+            if edited {
+                res.picker_mut().filter();
+            }
+        }
+    }
+}
+
+// Show the file highlighted in the Files pane and move onto the View pane.
+// Where the cursor was goes onto the jump list.
+fn open_selected_file(res: &mut Resource) {
+    if res.files().is_empty() {
+        return;
+    }
+    let jump = current_jump(res);
+    let shown = res.file_list_state().index();
+    res.file_list_state_mut().open_selected();
+    res.pointer_mut().set_cursor::<View>();
+    if let Some(jump) = jump.filter(|_| res.file_list_state().index() != shown) {
+        res.jumps_mut().record(jump);
+    }
+}
+
+// Read the file highlighted in the Files pane, or the one in the View pane,
+// from disk again.
+fn reload_file(res: &mut Resource) {
+    let index = match res.pointer().cursor_at::<Files>() {
+        true => res.file_list_state().selected(),
+        false => res.file_list_state().index(),
+    };
+    let Some(file) = res.files_mut().get_file_buff_mut(index) else {
+        return;
+    };
+    let name = file.name().to_string();
+    let message = match file.reload() {
+        Ok(true) => format!("Reloaded {name}"),
+        Ok(false) => format!("{name} didn't change"),
+        Err(err) => {
+            log::trace!("{err}");
+            match file.path().exists() {
+                true => format!("Couldn't reload {name}"),
+                false => format!("{name} is gone from disk"),
+            }
+        }
+    };
+    res.toast_mut().show(message);
+}
+
+// Read every open file from disk again, telling how many changed and which
+// couldn't be read.
+fn reload_all_files(res: &mut Resource) {
+    let mut changed = 0;
+    let mut failed = Vec::new();
+    for file in res.files_mut().iter_mut() {
+        match file.reload() {
+            Ok(true) => changed += 1,
+            Ok(false) => {}
+            Err(err) => {
+                log::trace!("{err}");
+                failed.push(file.name().to_string());
+            }
+        }
+    }
+    let mut message = match changed {
+        1 => "1 file changed".to_string(),
+        n => format!("{n} files changed"),
+    };
+    if !failed.is_empty() {
+        message.push_str(&format!(", couldn't reload {}", failed.join(", ")));
+    }
+    res.toast_mut().show(message);
+}
+
+// Sort the files in the order after the current one.
+fn sort_files(res: &mut Resource) {
+    let sort = res.files().sort().map_or(FileSort::Name, FileSort::cycle);
+    reorder_files(res, |files| files.sort_by(sort));
+}
+
+// Move the file highlighted in the Files pane one place down the list, or
+// up. Returns false if it can't go any further that way.
+fn move_selected_file(res: &mut Resource, forward: bool) -> bool {
+    // Pinned files stay above the others.
+    let from = res.file_list_state().selected();
+    let to = match forward {
+        true => from + 1,
+        false => from.wrapping_sub(1),
+    };
+    let is_pinned = |i| res.files().get_file_buff(i).map(FileBuf::is_pinned);
+    if is_pinned(to).is_some_and(|pinned| Some(pinned) != is_pinned(from)) {
+        return false;
+    }
+    // And files stay among the others of their group.
+    let group_of = |i| {
+        res.files()
+            .get_file_buff(i)
+            .map(|f| res.groups().group_of(f.path()))
+    };
+    if group_of(to).is_some_and(|group| Some(group) != group_of(from)) {
+        return false;
+    }
+    let moved = res.file_list_state_mut().move_selected(forward);
+    if let Some((from, to)) = moved {
+        res.files_mut().swap(from, to);
+    }
+    moved.is_some()
+}
+
+// Move the file highlighted in the Files pane down or up a place, the order
+// kept for the next session.
+fn move_file(res: &mut Resource, forward: bool) {
+    if move_selected_file(res, forward) {
+        save_session(res);
+    }
+}
+
+// Move the file being dragged in the Files pane to the place of the one at
+// `to`, as far as it can go.
+fn drag_file(res: &mut Resource, to: usize) {
+    let mut moved = false;
+    loop {
+        let from = res.file_list_state().selected();
+        if from == to || !move_selected_file(res, to > from) {
+            break;
+        }
+        moved = true;
+    }
+    if moved {
+        save_session(res);
+    }
+}
+
+// Reorder the files with `reorder`, the shown and the highlighted files
+// staying the same.
+fn reorder_files(res: &mut Resource, reorder: impl FnOnce(&mut FileList)) {
+    let path_at = |res: &Resource, index: usize| {
+        res.files()
+            .get_file_buff(index)
+            .map(|f| f.path().to_path_buf())
+    };
+    let shown = path_at(res, res.file_list_state().index());
+    let selected = path_at(res, res.file_list_state().selected());
+    reorder(res.files_mut());
+    // The files of a group stay together whatever the order.
+    let groups = res.groups.of_files(&res.files);
+    res.files.gather(&groups);
+
+    let index_of = |res: &Resource, path: Option<PathBuf>| {
+        path.and_then(|path| res.files().iter().position(|f| f.path() == path))
+            .unwrap_or_default()
+    };
+    let index = index_of(res, shown);
+    let selected = index_of(res, selected);
+    res.file_list_state_mut().place(index, selected);
+    refilter_files(res);
+    save_session(res);
+}
+
+// Browse the directory tree in the Files pane. Returns false for the keys
+// that aren't about the tree.
+fn tree_key_event(event: &Event, res: &mut Resource) -> bool {
+    let Event::Key(KeyEvent {
+        code, modifiers, ..
+    }) = *event
+    else {
+        return false;
+    };
+    match (code, modifiers) {
+        (KeyCode::Down | KeyCode::Char('j'), KeyModifiers::NONE) => res.tree_mut().next(),
+        (KeyCode::Up | KeyCode::Char('k'), KeyModifiers::NONE) => res.tree_mut().prev(),
+        (KeyCode::Home | KeyCode::Char('g'), _) => res.tree_mut().top(),
+        (KeyCode::End | KeyCode::Char('G'), _) => res.tree_mut().bottom(),
+        (KeyCode::Left | KeyCode::Char('h'), KeyModifiers::NONE) => {
+            res.tree_mut().collapse_selected()
+        }
+        (KeyCode::Enter | KeyCode::Right | KeyCode::Char('l'), KeyModifiers::NONE) => {
+            let Some(entry) = res.tree().selected() else {
+                return true;
+            };
+            if entry.is_dir {
+                res.tree_mut().toggle_selected();
+                return true;
+            }
+            let path = entry.path.clone();
+            if let Err(err) = open_file(res, &path) {
+                log::trace!("Cannot open the file due to: {err}");
+                res.toast_mut()
+                    .show(format!("Couldn't open {}", path.display()));
+            }
+        }
+        (KeyCode::Char('a'), KeyModifiers::NONE) => {
+            let Some(entry) = res.tree().selected().filter(|e| e.is_dir) else {
+                return true;
+            };
+            let dir = entry.path.clone();
+            let mut files = Vec::new();
+            let walk = if res.add_ignored() {
+                walk
+            } else {
+                walk_unignored
+            };
+            walk(&dir, &mut |path| {
+                files.push(path.to_path_buf());
+                true
+            });
+            add_files(res, files, &dir.display().to_string());
+        }
+        (KeyCode::Char('t'), KeyModifiers::NONE) => res.tree_mut().toggle(),
+        _ => return false,
+    }
+    true
+}
+
+// Answer the confirmation with `y` or `n`, `esc` is a no as well.
+// Show the file in the View pane on the left and the one highlighted in the
+// Files pane on the right, lined up by what they have alike.
+fn compare_files(res: &mut Resource) {
+    let (shown, selected) = {
+        let state = res.file_list_state();
+        (state.index(), state.selected())
+    };
+    if shown == selected {
+        res.toast_mut()
+            .show("Highlight another file to compare this one with");
+        return;
+    }
+    let mut read = |index: usize| {
+        let file = res
+            .files_mut()
+            .get_file_buff_mut(index)
+            .ok_or_else(|| anyhow!("No file to compare"))?;
+        file.read_all()?;
+        anyhow::Ok((file.name().to_string(), file.lines().to_vec()))
+    };
+    let compare = read(shown).and_then(|(left_name, left)| {
+        let (right_name, right) = read(selected)?;
+        Compare::new((&left_name, &left), (&right_name, &right))
+    });
+    match compare {
+        Ok(compare) => {
+            if compare.changes() == 0 {
+                res.toast_mut().show("The files are the same");
+            }
+            res.set_compare(Some(compare));
+        }
+        Err(err) => {
+            log::trace!("Couldn't compare the files due to: {err}");
+            res.toast_mut().show(err.to_string());
+        }
+    }
+}
+
+fn compare_key_event(event: Event, res: &mut Resource) {
+    let Some(compare) = res.compare.as_mut() else {
+        return;
+    };
+    let half = compare.page().div_ceil(2);
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(key @ ('d' | 'u')),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => match key {
+            'd' => compare.scroll_down(half),
+            _ => compare.scroll_up(half),
+        },
+        Event::Key(KeyEvent { code, .. }) => match code {
+            KeyCode::Esc | KeyCode::Char('q' | '=') => res.set_compare(None),
+            KeyCode::Char('j') | KeyCode::Down => compare.scroll_down(1),
+            KeyCode::Char('k') | KeyCode::Up => compare.scroll_up(1),
+            KeyCode::PageDown => compare.scroll_down(compare.page()),
+            KeyCode::PageUp => compare.scroll_up(compare.page()),
+            KeyCode::Char('g') | KeyCode::Home => compare.to_top(),
+            KeyCode::Char('G') | KeyCode::End => compare.to_bottom(),
+            KeyCode::Char('h') | KeyCode::Left => compare.scroll_left(),
+            KeyCode::Char('l') | KeyCode::Right => compare.scroll_right(),
+            KeyCode::Char(key @ ('}' | '{')) => {
+                let forward = key == '}';
+                if !compare.next_change(forward) {
+                    let message = match forward {
+                        true => "No change below",
+                        false => "No change above",
+                    };
+                    res.toast_mut().show(message);
+                }
+            }
+            _ => {}
+        },
+        Event::Mouse(MouseEvent { kind, .. }) => match kind {
+            MouseEventKind::ScrollDown => compare.scroll_down(3),
+            MouseEventKind::ScrollUp => compare.scroll_up(3),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+fn confirm_key_event(event: Event, res: &mut Resource) {
+    let Event::Key(KeyEvent { code, .. }) = event else {
+        return;
+    };
+    match code {
+        KeyCode::Char('y' | 'Y') => {
+            if let Some(confirm) = res.confirm.take() {
+                confirmed(res, confirm.into_action());
+            }
+        }
+        KeyCode::Char('n' | 'N') | KeyCode::Esc => res.set_confirm(None),
+        _ => {}
+    }
+}
+
+fn confirmed(res: &mut Resource, action: Action) {
+    match action {
+        Action::CreateFile(name) => {
+            let created = File::create_new(&name)
+                .map_err(|err| anyhow!("Couldn't create {name} due to: {err}"))
+                .and_then(|_| add_file(res, &name));
+            match created {
+                Ok(()) => res.toast_mut().show(format!("Created {name}")),
+                Err(err) => {
+                    log::trace!("{err}");
+                    res.toast_mut().show(format!("Couldn't create {name}"));
+                }
+            }
+        }
+        Action::DeleteFile(path) => {
+            let name = path.display().to_string();
+            if let Err(err) = fs::remove_file(&path) {
+                log::trace!("Couldn't delete {name} due to: {err}");
+                res.toast_mut().show(format!("Couldn't delete {name}"));
+                return;
+            }
+            let index = res.files().iter().position(|f| f.path() == path);
+            if let Some(index) = index {
+                if res.file_list_state_mut().close_at(index).is_ok() {
+                    res.files_mut().close(index);
+                    refilter_files(res);
+                }
+            }
+            res.toast_mut().show(format!("Deleted {name}"));
+        }
+        Action::AddFiles(files) => add_many_files(res, &files),
+    }
+}
+
+fn close_picker(res: &mut Resource) {
+    if res.picker().kind() == Some(PickerKind::GrepHits) {
+        res.grep_mut().stop();
+    }
+    res.picker_mut().close();
+    res.pointer_mut().toggle();
+}
+
+// Show the file in the View pane, opening it first if needed. Where the
+// cursor was goes onto the jump list.
+fn open_file(res: &mut Resource, path: &Path) -> anyhow::Result<()> {
+    let jump = current_jump(res);
+    show_file(res, path)?;
+    if let Some(jump) = jump {
+        res.jumps_mut().record(jump);
+    }
+    Ok(())
+}
+
+fn show_file(res: &mut Resource, path: &Path) -> anyhow::Result<()> {
+    let index = res.files_mut().open(&path.to_string_lossy())?;
+    remember_files(&mut res.recent_files, [path]);
+    show_opened(res, index);
+    Ok(())
+}
+
+// Show the open file at `index` in the View pane.
+fn show_opened(res: &mut Resource, index: usize) {
+    let len = res.files().len();
+    res.file_list_state_mut().set_size(len);
+    res.file_list_state_mut().select(index);
+    group_files(res);
+    res.pointer_mut().set_cursor::<View>();
+}
+
+// Open the member of the archive listed on the cursor line, read out of the
+// archive.
+fn open_member(res: &mut Resource) {
+    let index = res.file_list_state().index();
+    let Some(archive) = res.files().get_file_buff(index) else {
+        return;
+    };
+    let Some(member) = archive.member_at(archive.view().cursor()) else {
+        return;
+    };
+    if member.is_dir {
+        return;
+    }
+    let name = member.name.clone();
+    let jump = current_jump(res);
+    match res.files_mut().open_member(index, &name) {
+        Ok(index) => {
+            show_opened(res, index);
+            if let Some(jump) = jump {
+                res.jumps_mut().record(jump);
             }
+        }
+        Err(err) => {
+            log::trace!("Cannot open the member due to: {err}");
+            res.toast_mut().show(format!("Couldn't open {name}"));
+        }
+    }
+}
 
-            // Clean the entry box
-            res.entry_box_mut().clear();
+// Show the file in the View pane at the given 1-based line, opening it first
+// if needed.
+fn open_file_at(res: &mut Resource, path: &Path, line: usize) -> anyhow::Result<()> {
+    open_file(res, path)?;
+    if let Some(curr_buff) = current_file_buff_mut(res) {
+        curr_buff.goto(line)?;
+    }
+    Ok(())
+}
+
+// List the open files and the ones below the working directory to pick from
+// by typing a part of their name.
+fn find_file(res: &mut Resource) {
+    let cwd = env::current_dir().unwrap_or_default();
+    let open = res
+        .files()
+        .iter()
+        .map(|f| f.path().to_path_buf())
+        .collect::<HashSet<_>>();
+    let mut names = res
+        .files()
+        .names()
+        .into_iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    walk_unignored(Path::new("."), &mut |path| {
+        if !open.contains(&cwd.join(path)) {
+            names.push(path.display().to_string());
+        }
+        names.len() < MAX_FOUND_FILES
+    });
+    res.pointer_mut().toggle();
+    res.picker_mut()
+        .open_with_query(PickerKind::FindFile, names);
+}
+
+fn open_found_file(res: &mut Resource, name: &str) {
+    if let Err(err) = open_file(res, Path::new(name)) {
+        log::trace!("Cannot open the file due to: {err}");
+        res.toast_mut().show(format!("Couldn't open {name}"));
+    }
+}
+
+// List the lines of the current file to pick from by typing a part of them.
+fn find_line(res: &mut Resource) {
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        return;
+    };
+    let labels = match curr_buff.line_labels() {
+        Ok(labels) => labels,
+        Err(err) => {
+            log::trace!("Cannot list the lines due to: {err}");
+            return;
+        }
+    };
+    res.pointer_mut().toggle();
+    res.picker_mut()
+        .open_with_query(PickerKind::FindLine, labels);
+}
+
+// Go to the 0-based line `index` of the current file.
+fn open_found_line(res: &mut Resource, index: usize) {
+    record_jump(res);
+    if let Some(curr_buff) = current_file_buff_mut(res) {
+        curr_buff
+            .goto(index + 1)
+            .unwrap_or_else(|err| log::trace!("Cannot go to line due to: {err}"));
+    }
+}
+
+// Run a saved search from the cursor line on.
+fn open_saved_search(res: &mut Resource, index: usize) {
+    let Some(saved) = res.config().searches().get(index).cloned() else {
+        return;
+    };
+    let curr_index = res.file_list_state().index();
+    let Some(curr_buff) = res.files.get_file_buff_mut(curr_index) else {
+        return;
+    };
+    let jump = Jump {
+        path: curr_buff.path().into(),
+        line: curr_buff.view().cursor() + 1,
+    };
+    res.search.start(jump.line - 1);
+    res.search.restore(&saved);
+    match curr_buff.search(&res.search, jump.line - 1) {
+        Ok(_) => res.jumps.record(jump),
+        Err(err) => {
+            log::trace!("{err}");
+            res.toast
+                .show(format!("Pattern not found: {}", saved.query));
+        }
+    }
+}
+
+// The definitions of the current file, telling why there are none.
+fn definitions(res: &mut Resource) -> Option<Vec<usize>> {
+    let curr_buff = current_file_buff_mut(res)?;
+    let lines = match curr_buff.definitions() {
+        Ok(lines) if lines.is_empty() => Err(anyhow!("No definitions in this file")),
+        lines => lines,
+    };
+    match lines {
+        Ok(lines) => Some(lines),
+        Err(err) => {
+            res.toast_mut().show(err.to_string());
+            None
+        }
+    }
+}
+
+// List the definitions of the current file to pick one to go to.
+fn list_definitions(res: &mut Resource) {
+    let Some(lines) = definitions(res) else {
+        return;
+    };
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        return;
+    };
+    let labels = lines
+        .iter()
+        .map(|&i| format!("{}: {}", i + 1, curr_buff.lines()[i].trim()))
+        .collect();
+    res.pointer_mut().toggle();
+    res.picker_mut()
+        .open_with_query(PickerKind::Definitions, labels);
+}
+
+fn open_definition(res: &mut Resource, index: usize) {
+    if let Some(line) = definitions(res).and_then(|lines| lines.get(index).copied()) {
+        open_found_line(res, line);
+    }
+}
+
+// Go to the definition below the cursor line, or above it when not going
+// `forward`.
+fn next_definition(res: &mut Resource, forward: bool) {
+    let Some(lines) = definitions(res) else {
+        return;
+    };
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        return;
+    };
+    let cursor = curr_buff.view().cursor();
+    let line = match forward {
+        true => lines.into_iter().find(|&l| l > cursor),
+        false => lines.into_iter().rfind(|&l| l < cursor),
+    };
+    let Some(line) = line else {
+        let message = match forward {
+            true => "No definition below",
+            false => "No definition above",
+        };
+        res.toast_mut().show(message);
+        return;
+    };
+    curr_buff
+        .goto(line + 1)
+        .unwrap_or_else(|err| log::trace!("Cannot go to line due to: {err}"));
+}
+
+fn open_bookmark(res: &mut Resource, index: usize) {
+    let Some(bookmark) = res.bookmarks().get(index).cloned() else {
+        return;
+    };
+    if let Err(err) = open_file_at(res, &bookmark.path, bookmark.line) {
+        log::trace!("Cannot open bookmark due to: {err}");
+    }
+}
+
+fn open_hit(res: &mut Resource, index: usize) {
+    let Some(hit) = res.search().hit(index).cloned() else {
+        return;
+    };
+    if let Err(err) = open_file_at(res, &hit.path, hit.line) {
+        log::trace!("Cannot open the match due to: {err}");
+    }
+}
+
+// Go to the next or previous entry of the quickfix pane.
+fn open_quickfix_entry(res: &mut Resource, forward: bool) {
+    let hit = match forward {
+        true => res.quickfix_mut().forward(),
+        false => res.quickfix_mut().back(),
+    };
+    let Some(hit) = hit.cloned() else {
+        let message = match (res.quickfix().hits().is_empty(), forward) {
+            (true, _) => "No search results, search all files or grep first",
+            (false, true) => "At the last search result",
+            (false, false) => "At the first search result",
+        };
+        res.toast_mut().show(message);
+        return;
+    };
+    if !res.quickfix().is_visible() {
+        res.quickfix_mut().toggle();
+    }
+    if let Err(err) = open_file_at(res, &hit.path, hit.line) {
+        log::trace!("Cannot open the match due to: {err}");
+    }
+}
+
+fn open_grep_hit(res: &mut Resource, index: usize) {
+    let Some(hit) = res.grep().hit(index).cloned() else {
+        return;
+    };
+    if let Err(err) = open_file_at(res, &hit.path, hit.line) {
+        log::trace!("Cannot open the match due to: {err}");
+    }
+}
 
-            if name.is_empty() {
-                return Ok(());
+fn write_key_event(event: Event, res: &mut Resource) -> anyhow::Result<()> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('n'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) => {
+            match res.entry_box().prompt() {
+                Prompt::Search => cancel_search(res),
+                Prompt::FilterFiles => {
+                    res.file_list_state_mut().set_filter("");
+                    refilter_files(res);
+                }
+                Prompt::Goto => res.set_substitution(None),
+                _ => {}
+            }
+            if let Some(history) = prompt_history(res) {
+                history.reset();
             }
-            let len = res.files().len();
-            res.file_list_state_mut().set_size(len);
-            res.entry_box_mut().set_ok();
             res.pointer_mut().toggle();
+            res.entry_box_mut().clear();
+            res.entry_box_mut().set_ok();
             res.entry_box_mut().toggle();
         }
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        }) => {
+            add_to_history(res);
+            match res.entry_box().prompt() {
+                Prompt::File => submit_file(res),
+                Prompt::Goto => submit_goto(res),
+                Prompt::GotoOffset => submit_goto_offset(res),
+                Prompt::Bookmark => submit_bookmark(res),
+                Prompt::Search => submit_search(res),
+                Prompt::SearchBytes => submit_search_bytes(res),
+                Prompt::SearchAll => submit_search_all(res),
+                Prompt::Grep => submit_grep(res),
+                Prompt::FilterFiles => close_entry_box(res),
+                Prompt::GlobFiles => submit_file_globs(res),
+                Prompt::SaveSearch => submit_save_search(res),
+                Prompt::Rename => submit_rename(res),
+                Prompt::Group => submit_group(res),
+            }
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up, ..
+        }) => recall_history(res, true),
+        Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            ..
+        }) => recall_history(res, false),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.entry_box().prompt().is_search() => res.search_mut().cycle_mode(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.entry_box().prompt().is_search() => res.search_mut().cycle_case(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('b'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) if res.entry_box().prompt().is_search() => res.search_mut().toggle_whole_word(),
+        event => {
+            edit_key_event(event, res.entry_box_mut());
+        }
+    }
+
+    if res.entry_box().is_visible() {
+        match res.entry_box().prompt() {
+            Prompt::Search => update_search(res),
+            Prompt::FilterFiles => update_file_filter(res),
+            Prompt::Goto => update_substitution(res),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// The history of the prompt being typed into, if it keeps one.
+fn prompt_history(res: &mut Resource) -> Option<&mut History> {
+    match res.entry_box().prompt() {
+        prompt if prompt.is_search() => Some(&mut res.search_history),
+        Prompt::File => Some(&mut res.file_history),
+        _ => None,
+    }
+}
+
+fn add_to_history(res: &mut Resource) {
+    let entry = res.entry_box().get().trim().to_string();
+    let Some(history) = prompt_history(res) else {
+        return;
+    };
+    history.add(&entry);
+    if let Err(err) = history.save() {
+        log::trace!("{err}");
+    }
+}
+
+// Put an older or newer entry of the history into the prompt.
+fn recall_history(res: &mut Resource, older: bool) {
+    let typed = res.entry_box().get().to_string();
+    let Some(history) = prompt_history(res) else {
+        return;
+    };
+    let entry = match older {
+        true => history.older(&typed),
+        false => history.newer(),
+    };
+    if let Some(entry) = entry.map(str::to_string) {
+        res.entry_box_mut().set(&entry);
+        res.entry_box_mut().set_ok();
+    }
+}
+
+// Edit the text typed into `entry_box`, returning whether the key did.
+fn edit_key_event(event: Event, entry_box: &mut EntryBox) -> bool {
+    match event {
         Event::Key(KeyEvent {
             code: KeyCode::Backspace,
             ..
         }) => {
-            res.entry_box_mut().set_ok();
-            res.entry_box_mut().pop();
+            entry_box.set_ok();
+            entry_box.pop();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Delete,
+            ..
+        }) => {
+            entry_box.set_ok();
+            entry_box.delete();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('w'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => {
+            entry_box.set_ok();
+            entry_box.delete_word();
         }
+        Event::Key(KeyEvent {
+            code: KeyCode::Left,
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => entry_box.word_left(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Right,
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }) => entry_box.word_right(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Left,
+            ..
+        }) => entry_box.left(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Right,
+            ..
+        }) => entry_box.right(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Home,
+            ..
+        }) => entry_box.home(),
+        Event::Key(KeyEvent {
+            code: KeyCode::End, ..
+        }) => entry_box.end(),
         Event::Key(KeyEvent {
             code: KeyCode::Char(c),
             ..
-        }) => res.entry_box_mut().push(c),
-        _ => {}
+        }) => entry_box.push(c),
+        _ => return false,
+    }
+    true
+}
+
+// Preview the substitution typed so far, once it can be parsed.
+fn update_substitution(res: &mut Resource) {
+    let command = res.entry_box().get().to_string();
+    if !Substitution::is_command(&command) {
+        res.set_substitution(None);
+        return;
+    }
+    match Substitution::parse(&command) {
+        Ok(substitution) => {
+            res.set_substitution(Some(substitution));
+            res.entry_box_mut().set_ok();
+        }
+        Err(err) => {
+            log::trace!("{err}");
+            res.entry_box_mut().set_err();
+        }
+    }
+}
+
+// Keep the preview of the substitution, telling how many lines it changes.
+fn submit_substitution(res: &mut Resource) {
+    let command = res.entry_box().get().to_string();
+    let substitution = match Substitution::parse(&command) {
+        Ok(substitution) => substitution,
+        Err(err) => {
+            res.entry_box_mut().set_err();
+            res.toast_mut().show(err.to_string());
+            return;
+        }
+    };
+    let changed = current_file_buff_mut(res).map_or(0, |curr_buff| {
+        curr_buff
+            .lines()
+            .iter()
+            .filter(|l| substitution.changes(l))
+            .count()
+    });
+    res.set_substitution(Some(substitution));
+    close_entry_box(res);
+    let message = match changed {
+        1 => "1 line would change, esc drops the preview".to_string(),
+        n => format!("{n} lines would change, esc drops the preview"),
+    };
+    res.toast_mut().show(message);
+}
+
+// Narrow the Files pane down to the names matching what's typed so far,
+// selecting the first of them.
+fn update_file_filter(res: &mut Resource) {
+    let pattern = res.entry_box().get().to_string();
+    res.file_list_state_mut().set_filter(&pattern);
+    refilter_files(res);
+    res.file_list_state_mut().top();
+}
+
+// The cursor line of the file in the View pane.
+fn current_jump(res: &Resource) -> Option<Jump> {
+    let curr_index = res.file_list_state().index();
+    let curr_buff = res.files().get_file_buff(curr_index)?;
+    Some(Jump {
+        path: curr_buff.path().into(),
+        line: curr_buff.view().cursor() + 1,
+    })
+}
+
+// Remember the cursor line before jumping away from it.
+fn record_jump(res: &mut Resource) {
+    if let Some(jump) = current_jump(res) {
+        res.jumps_mut().record(jump);
+    }
+}
+
+// Go back to, or forth again from, a position jumped away from.
+fn walk_jumps(res: &mut Resource, back: bool) {
+    let jump = match back {
+        true => current_jump(res).and_then(|current| res.jumps_mut().back(current)),
+        false => res.jumps_mut().forward(),
+    };
+    let Some(jump) = jump else {
+        return;
+    };
+    let res_jump = show_file(res, &jump.path).and_then(|_| {
+        current_file_buff_mut(res).map_or(Ok(()), |curr_buff| curr_buff.goto(jump.line))
+    });
+    if let Err(err) = res_jump {
+        log::trace!("Cannot jump due to: {err}");
+        res.toast_mut()
+            .show(format!("Couldn't go back to {}", jump.path.display()));
+    }
+}
+
+// Keep the search, remembering where it was started on the jump list.
+fn submit_search(res: &mut Resource) {
+    let origin = res.search().origin();
+    if let Some(mut jump) = current_jump(res) {
+        if res.search().is_active() {
+            jump.line = origin + 1;
+            res.jumps_mut().record(jump);
+        }
+    }
+    close_entry_box(res);
+}
+
+// Jump to the first match of the query typed so far, as seen from where the
+// search was started.
+fn update_search(res: &mut Resource) {
+    let query = res.entry_box().get().to_string();
+    res.search_mut().set_query(&query);
+    if let Some(err) = res.search().error() {
+        let message = format!("Invalid regex, searching literally: {err}");
+        res.toast_mut().show(message);
+    }
+    let origin = res.search().origin();
+    let curr_index = res.file_list_state().index();
+    let Some(curr_buff) = res.files.get_file_buff_mut(curr_index) else {
+        return;
+    };
+    if query.is_empty() {
+        curr_buff.move_cursor(origin);
+        res.entry_box_mut().set_ok();
+        return;
+    }
+    match curr_buff.search(&res.search, origin) {
+        Ok(_) => res.entry_box_mut().set_ok(),
+        Err(err) => {
+            log::trace!("{err}");
+            curr_buff.move_cursor(origin);
+            res.entry_box_mut().set_err();
+        }
+    }
+}
+
+// Move `count` matches on, telling when the search went past an end of the
+// file and started over from the other.
+fn search_next(res: &mut Resource, forward: bool, count: usize) {
+    if !res.search().is_active() {
+        return;
+    }
+    record_jump(res);
+    let curr_index = res.file_list_state().index();
+    let Some(curr_buff) = res.files.get_file_buff_mut(curr_index) else {
+        return;
+    };
+    let mut wrapped = false;
+    for _ in 0..count {
+        match curr_buff.search_next(&res.search, forward) {
+            Ok(w) => wrapped |= w,
+            Err(err) => {
+                res.toast.show(err.to_string());
+                return;
+            }
+        }
+    }
+    if wrapped {
+        let message = match forward {
+            true => "Search wrapped to the top",
+            false => "Search wrapped to the bottom",
+        };
+        res.toast_mut().show(message);
+    }
+}
+
+// Jump to the first row of the hex dump from the cursor on holding the bytes
+// typed, remembering where it was searched from on the jump list.
+fn submit_search_bytes(res: &mut Resource) {
+    let pattern = hex::parse_pattern(res.entry_box().get());
+    let jump = current_jump(res);
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        close_entry_box(res);
+        return;
+    };
+    let cursor = curr_buff.view().cursor();
+    let res_search = pattern.and_then(|pattern| {
+        curr_buff.search_bytes(&pattern, cursor, true)?;
+        Ok(pattern)
+    });
+    match res_search {
+        Ok(pattern) => res.set_byte_pattern(pattern),
+        Err(err) => {
+            log::trace!("Cannot search the bytes due to: {err}");
+            res.entry_box_mut().set_err();
+            return;
+        }
+    }
+    if let Some(jump) = jump {
+        res.jumps_mut().record(jump);
+    }
+    close_entry_box(res);
+}
+
+// Move `count` rows holding the bytes searched for on, telling when the
+// search went past an end of the file and started over from the other.
+fn search_bytes_next(res: &mut Resource, forward: bool, count: usize) {
+    if res.byte_pattern().is_empty() {
+        return;
+    }
+    record_jump(res);
+    let curr_index = res.file_list_state().index();
+    let Some(curr_buff) = res.files.get_file_buff_mut(curr_index) else {
+        return;
+    };
+    let mut wrapped = false;
+    for _ in 0..count {
+        let cursor = curr_buff.view().cursor();
+        let row = if forward { cursor + 1 } else { cursor };
+        match curr_buff.search_bytes(&res.byte_pattern, row, forward) {
+            Ok(w) => wrapped |= w,
+            Err(err) => {
+                res.toast.show(err.to_string());
+                return;
+            }
+        }
+    }
+    if wrapped {
+        let message = match forward {
+            true => "Search wrapped to the top",
+            false => "Search wrapped to the bottom",
+        };
+        res.toast_mut().show(message);
+    }
+}
+
+// Search every open file and list the matching lines to pick from.
+fn submit_search_all(res: &mut Resource) {
+    let query = res.entry_box().get().to_string();
+    if query.is_empty() {
+        close_entry_box(res);
+        return;
+    }
+    res.search_mut().set_query(&query);
+    let context = res.config().search_context();
+    let mut hits = Vec::new();
+    for file in res.files.iter_mut() {
+        match file.find_all(&res.search, context) {
+            Ok(mut found) => hits.append(&mut found),
+            Err(err) => log::trace!("Couldn't search {} due to: {err}", file.name()),
+        }
     }
+    if hits.is_empty() {
+        res.entry_box_mut().set_err();
+        res.toast_mut().show(format!("Pattern not found: {query}"));
+        return;
+    }
+    let labels = hits.iter().map(|hit| hit.label.clone()).collect();
+    res.quickfix_mut().set(hits.clone());
+    res.search_mut().set_hits(hits);
+    close_entry_box(res);
+    res.pointer_mut().toggle();
+    res.picker_mut().open(PickerKind::SearchHits, labels);
+}
+
+// Search the files below the working directory, the matches showing up in
+// the picker while they're found.
+fn submit_grep(res: &mut Resource) {
+    let query = res.entry_box().get().to_string();
+    res.search_mut().set_query(&query);
+    let Some(matcher) = res.search().matcher().cloned() else {
+        close_entry_box(res);
+        return;
+    };
+    let context = res.config().search_context();
+    res.grep_mut().start(matcher, context);
+    res.quickfix_mut().set(Vec::new());
+    close_entry_box(res);
+    res.pointer_mut().toggle();
+    res.picker_mut().open(PickerKind::GrepHits, Vec::new());
+}
+
+// Go back to where the search was started.
+fn cancel_search(res: &mut Resource) {
+    res.search_mut().clear();
+    let origin = res.search().origin();
+    if let Some(curr_buff) = current_file_buff_mut(res) {
+        curr_buff.move_cursor(origin);
+    }
+}
+
+fn close_entry_box(res: &mut Resource) {
+    res.entry_box_mut().clear();
+    res.entry_box_mut().set_ok();
+    res.pointer_mut().toggle();
+    res.entry_box_mut().toggle();
+}
+
+fn submit_file(res: &mut Resource) {
+    let name = res.entry_box().input_buff();
+    if !name.is_empty() && Path::new(name.as_ref()).is_dir() {
+        res.tree_mut().add_root(Path::new(name.as_ref()));
+        res.entry_box_mut().clear();
+        close_entry_box(res);
+        res.pointer_mut().set_cursor::<Files>();
+        return;
+    }
+    if Glob::is_glob(&name) {
+        submit_file_glob(res, &name);
+        return;
+    }
+    // A file that isn't there yet may be created, as for a new note.
+    if !name.is_empty() && !Path::new(name.as_ref()).exists() {
+        let question = format!("Create {name}?");
+        res.set_confirm(Some(Confirm::new(
+            question,
+            Action::CreateFile(name.into()),
+        )));
+        close_entry_box(res);
+        return;
+    }
+    // The same file under another name, or through a link, is shown rather
+    // than added twice.
+    let open = res.files().len();
+    match res.files_mut().open(&name) {
+        Ok(index) if index < open => {
+            res.file_list_state_mut().select(index);
+            let shown = res
+                .files()
+                .get_file_buff(index)
+                .map(|f| f.name().to_string());
+            res.toast_mut()
+                .show(format!("{} is open already", shown.unwrap_or_default()));
+        }
+        Ok(_) => {
+            remember_files(&mut res.recent_files, [Path::new(name.as_ref())]);
+            let len = res.files().len();
+            res.file_list_state_mut().set_size(len);
+            group_files(res);
+        }
+        Err(err) => {
+            log::trace!("Cannot open file due to: {err}");
+            res.entry_box_mut().set_err();
+            return;
+        }
+    }
+    close_entry_box(res);
+}
+
+// Add every file matching the glob, telling how many there were.
+fn submit_file_glob(res: &mut Resource, pattern: &str) {
+    let files = match expand(pattern) {
+        Ok(files) => files,
+        Err(err) => {
+            log::trace!("{err}");
+            res.entry_box_mut().set_err();
+            return;
+        }
+    };
+    if files.is_empty() {
+        res.entry_box_mut().set_err();
+        res.toast_mut().show(format!("No files match {pattern}"));
+        return;
+    }
+    close_entry_box(res);
+    add_files(res, files, pattern);
+}
+
+/// Add the files found in `origin`, leaving out the ones larger than the
+/// configuration allows. It asks first if there are many of them.
+pub fn add_files(res: &mut Resource, mut files: Vec<PathBuf>, origin: &str) {
+    let max_size = res.config().max_file_size();
+    files.retain(|path| fs::metadata(path).is_ok_and(|m| m.len() <= max_size));
+    if files.len() > MANY_FILES {
+        let question = format!("Add {} files from {origin}?", files.len());
+        res.set_confirm(Some(Confirm::new(question, Action::AddFiles(files))));
+        return;
+    }
+    add_many_files(res, &files);
+}
+
+// Add the files, telling how many were.
+fn add_many_files(res: &mut Resource, files: &[PathBuf]) {
+    let added = files
+        .iter()
+        .filter(|path| insert_file(res, &path.to_string_lossy()).is_ok())
+        .map(PathBuf::as_path)
+        .collect::<Vec<_>>();
+    remember_files(&mut res.recent_files, added.iter().copied());
+    res.toast_mut().show(match added.len() {
+        1 => "Added 1 file".to_string(),
+        n => format!("Added {n} files"),
+    });
+}
+
+// Add the file to the Files pane.
+fn add_file(res: &mut Resource, name: &str) -> anyhow::Result<()> {
+    insert_file(res, name)?;
+    remember_files(&mut res.recent_files, [Path::new(name)]);
+    Ok(())
+}
 
+// Add the file to the Files pane, leaving it to the caller to remember it
+// among the recent files.
+fn insert_file(res: &mut Resource, name: &str) -> anyhow::Result<()> {
+    res.files_mut().insert(name)?;
+    let len = res.files().len();
+    res.file_list_state_mut().set_size(len);
+    group_files(res);
     Ok(())
 }
+
+// Bring a file opened into a group back together with the others in it.
+fn group_files(res: &mut Resource) {
+    reorder_files(res, |_| {});
+}
+
+fn submit_goto(res: &mut Resource) {
+    if Substitution::is_command(res.entry_box().get()) {
+        submit_substitution(res);
+        return;
+    }
+    let line = res.entry_box().get().trim().parse::<usize>();
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        close_entry_box(res);
+        return;
+    };
+    let jump = Jump {
+        path: curr_buff.path().into(),
+        line: curr_buff.view().cursor() + 1,
+    };
+    let res_goto = line
+        .map_err(anyhow::Error::from)
+        .and_then(|line| curr_buff.goto(line));
+    if res_goto.is_ok() {
+        res.jumps_mut().record(jump);
+    }
+    if let Err(err) = res_goto {
+        log::trace!("Cannot go to line due to: {err}");
+        res.entry_box_mut().set_err();
+        return;
+    }
+    close_entry_box(res);
+}
+
+fn submit_goto_offset(res: &mut Resource) {
+    let offset = hex::parse_offset(res.entry_box().get());
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        close_entry_box(res);
+        return;
+    };
+    let jump = Jump {
+        path: curr_buff.path().into(),
+        line: curr_buff.view().cursor() + 1,
+    };
+    let res_goto = offset.and_then(|offset| curr_buff.goto_offset(offset));
+    if let Err(err) = res_goto {
+        log::trace!("Cannot go to offset due to: {err}");
+        res.entry_box_mut().set_err();
+        return;
+    }
+    res.jumps_mut().record(jump);
+    close_entry_box(res);
+}
+
+// Keep the Files pane to the files matching the globs typed, every file
+// showing again if there's none.
+fn submit_file_globs(res: &mut Resource) {
+    let globs = match GlobFilter::parse(res.entry_box().get()) {
+        Ok(globs) => globs,
+        Err(err) => {
+            log::trace!("{err}");
+            res.entry_box_mut().set_err();
+            return;
+        }
+    };
+    res.file_list_state_mut().set_globs(globs);
+    refilter_files(res);
+    let mut file_list_state = res.file_list_state_mut();
+    if !file_list_state.is_listed(file_list_state.selected()) {
+        file_list_state.top();
+    }
+    drop(file_list_state);
+    close_entry_box(res);
+}
+
+// Rename the file highlighted in the Files pane on disk, moving its
+// bookmarks along.
+fn submit_rename(res: &mut Resource) {
+    let name = res.entry_box().get().trim().to_string();
+    let index = res.file_list_state().selected();
+    let Some(file) = res.files_mut().get_file_buff_mut(index) else {
+        close_entry_box(res);
+        return;
+    };
+    if name.is_empty() || name == file.name() {
+        close_entry_box(res);
+        return;
+    }
+    let from = file.path().to_path_buf();
+    if let Err(err) = file.rename(&name) {
+        log::trace!("{err}");
+        res.entry_box_mut().set_err();
+        res.toast_mut().show(format!("Couldn't rename to {name}"));
+        return;
+    }
+    let to = file.path().to_path_buf();
+    remember_files(&mut res.recent_files, [to.as_path()]);
+    res.splits_mut().rename_path(&from, &to);
+    res.tabs_mut().rename_path(&from, &to);
+    if res.bookmarks_mut().rename_path(&from, &to) {
+        if let Err(err) = res.bookmarks().save() {
+            log::trace!("{err}");
+        }
+    }
+    if res.groups().group_of(&from).is_some() {
+        res.groups_mut().rename_path(&from, &to);
+        save_session(res);
+    }
+    // The new name may no longer match the filter.
+    refilter_files(res);
+    close_entry_box(res);
+    res.toast_mut().show(format!("Renamed to {name}"));
+}
+
+// Put the file highlighted in the Files pane into the group named, or take
+// it out of its group for no name, moving it along with the others.
+fn submit_group(res: &mut Resource) {
+    let group = res.entry_box().get().trim().to_string();
+    let index = res.file_list_state().selected();
+    let Some(path) = res
+        .files()
+        .get_file_buff(index)
+        .map(|f| f.path().to_path_buf())
+    else {
+        close_entry_box(res);
+        return;
+    };
+    if group.contains('\t') {
+        res.entry_box_mut().set_err();
+        return;
+    }
+    res.groups_mut()
+        .set(&path, Some(group.as_str()).filter(|g| !g.is_empty()));
+    save_session(res);
+    // Its group is shown open so the file can still be seen.
+    res.file_list_state_mut().expand_group(&group);
+    group_files(res);
+    close_entry_box(res);
+    let name = res.files().get_file_buff(res.file_list_state().selected());
+    let name = name.map(|f| f.name().to_string()).unwrap_or_default();
+    res.toast_mut().show(match group.is_empty() {
+        true => format!("{name} is out of its group"),
+        false => format!("{name} is in {group}"),
+    });
+}
+
+fn submit_save_search(res: &mut Resource) {
+    let name = res.entry_box().get().trim().to_string();
+    if name.is_empty() {
+        res.entry_box_mut().set_err();
+        return;
+    }
+    let saved = res.search().save(&name);
+    if let Err(err) = res.config_mut().save_search(saved) {
+        log::trace!("{err}");
+        res.entry_box_mut().set_err();
+        return;
+    }
+    close_entry_box(res);
+    res.toast_mut().show(format!("Saved the search as {name}"));
+}
+
+fn submit_bookmark(res: &mut Resource) {
+    let name = res.entry_box().get().trim().replace('\t', " ");
+    let Some(curr_buff) = current_file_buff_mut(res) else {
+        close_entry_box(res);
+        return;
+    };
+    if name.is_empty() {
+        res.entry_box_mut().set_err();
+        return;
+    }
+    let bookmark = Bookmark {
+        name,
+        line: curr_buff.view().cursor() + 1,
+        path: curr_buff.path().to_path_buf(),
+    };
+    res.bookmarks_mut().add(bookmark);
+    if let Err(err) = res.bookmarks().save() {
+        log::trace!("{err}");
+        res.entry_box_mut().set_err();
+        return;
+    }
+    close_entry_box(res);
+}
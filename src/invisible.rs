@@ -0,0 +1,81 @@
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+
+use ratatui::style::{Color, Style};
+
+use crate::tab;
+
+/// Bytes looked at to tell how the lines of a file end.
+const LINE_ENDING_SNIFF: u64 = 8 * 1024;
+const SPACE: char = '·';
+const TAB: char = '→';
+
+/// Whether the lines of the file at `path` end with a carriage return and a
+/// line feed, as told by the first line ending in it.
+pub fn uses_crlf(path: &Path) -> bool {
+    let mut head = Vec::new();
+    let read =
+        File::open(path).and_then(|file| file.take(LINE_ENDING_SNIFF).read_to_end(&mut head));
+    read.is_ok()
+        && head
+            .iter()
+            .position(|&b| b == b'\n')
+            .is_some_and(|i| i > 0 && head[i - 1] == b'\r')
+}
+
+/// The line as `tab::expand` lays it out, its spaces and tabs drawn as
+/// glyphs in `fg` and its line ending after it. The glyphs keep the
+/// background of the matches over them, or else of `style`, whitespace the
+/// line ends with being set apart by `trailing_bg`.
+pub fn reveal(
+    line: &str,
+    matches: Vec<(Range<usize>, Style)>,
+    tab_width: usize,
+    crlf: bool,
+    style: Style,
+    (fg, trailing_bg): (Color, Color),
+) -> (String, Vec<(Range<usize>, Style)>) {
+    let trailing = line.trim_end_matches([' ', '\t']).chars().count();
+    // The whitespace is laid out along with the matches, to learn the
+    // characters it ends up at.
+    let spaces = line
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| matches!(c, ' ' | '\t'))
+        .map(|(i, c)| (i..i + 1, (c, i >= trailing)))
+        .collect::<Vec<_>>();
+    let count = spaces.len();
+    let (expanded, spaces) = tab::expand(line, spaces, tab_width);
+    let (_, matches) = tab::expand(line, matches, tab_width);
+
+    let mut text = expanded.chars().collect::<Vec<_>>();
+    let mut glyphs = Vec::with_capacity(count + 1);
+    for (chars, (c, is_trailing)) in spaces {
+        text[chars.start] = match c {
+            '\t' => TAB,
+            _ => SPACE,
+        };
+        let under = matches
+            .iter()
+            .find(|(m, _)| m.contains(&chars.start))
+            .map_or(style, |&(_, s)| s);
+        let glyph = match is_trailing {
+            true => under.fg(fg).bg(trailing_bg),
+            false => under.fg(fg),
+        };
+        glyphs.push((chars, glyph));
+    }
+    let ending = match crlf {
+        true => "␍↵",
+        false => "↵",
+    };
+    let end = text.len();
+    glyphs.push((end..end + ending.chars().count(), style.fg(fg)));
+
+    let mut text = text.into_iter().collect::<String>();
+    text.push_str(ending);
+    glyphs.extend(matches);
+    (text, glyphs)
+}
@@ -1,17 +1,149 @@
-use std::cell::RefCell;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::ops::{Deref, DerefMut};
-use std::path::Path;
+use std::cell::{Cell, Ref, RefCell};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::mem;
+use std::ops::{Deref, DerefMut, Range};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::anyhow;
 use ratatui::layout::*;
+use ratatui::style::{Color, Style};
+use unicode_width::UnicodeWidthStr;
+
+use crate::ansi;
+use crate::archive::{self, Kind, Member};
+use crate::bracket::*;
+use crate::compression::{self, Compression};
+use crate::diff::{self, Change, HunkLeft};
+use crate::encoding::*;
+use crate::fold::*;
+use crate::hex;
+use crate::html::{self, Page};
+use crate::image::*;
+use crate::invisible;
+use crate::json;
+use crate::logs;
+use crate::man;
+use crate::markdown::*;
+use crate::notebook::{self, Notebook, Part};
+use crate::outline::*;
+#[cfg(feature = "pdf")]
+use crate::pdf;
+use crate::search::*;
+#[cfg(feature = "syntax")]
+use crate::syntax::*;
+use crate::table::*;
+use crate::view::*;
+use crate::word::*;
+
+/// Number of columns a single horizontal scroll moves the View pane by.
+const HSCROLL_STEP: usize = 4;
+
+/// How often a file is looked at on disk for changes at most.
+const STALE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Size and modification time of the file at `path`, to tell whether it
+// changed.
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// Bytes looked at to tell the encoding of a file.
+const ENCODING_SNIFF: usize = 8 * 1024;
+
+// What the lines of a file are read from, along with its encoding and the
+// count of its lines, sent over once it's done.
+type Source = (Box<dyn BufRead>, Option<Encoding>, Receiver<usize>);
+
+// The encoding of a file, told from its first bytes.
+fn sniff(file: impl Read) -> Option<Encoding> {
+    let mut head = Vec::with_capacity(ENCODING_SNIFF);
+    file.take(ENCODING_SNIFF as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(Encoding::detect(&head))
+}
+
+// Number of lines in a file, read through once.
+fn count_lines(mut file: impl Read) -> Option<usize> {
+    let mut chunk = vec![0; 64 * 1024];
+    let (mut lines, mut last) = (0, b'\n');
+    loop {
+        let read = file.read(&mut chunk).ok()?;
+        if read == 0 {
+            break;
+        }
+        lines += chunk[..read].iter().filter(|&&b| b == b'\n').count();
+        last = chunk[read - 1];
+    }
+    // An unterminated last line counts too.
+    if last != b'\n' {
+        lines += 1;
+    }
+    Some(lines)
+}
+
+// The Markdown renderer for a `.md` file.
+fn markdown_for(path: &Path) -> Option<Markdown> {
+    has_extension(path, &["md", "markdown"]).then(Markdown::new)
+}
+
+// Whether the file is a PDF one whose text is read, with the `pdf` feature.
+fn is_pdf(path: &Path) -> bool {
+    cfg!(feature = "pdf") && has_extension(path, &["pdf"])
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Orders the Files pane can be sorted in, the latest modified and the
+/// largest files coming first.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FileSort {
+    Name,
+    Modified,
+    Size,
+}
+
+impl FileSort {
+    /// The order after this one, back to the first after the last.
+    pub fn cycle(self) -> Self {
+        match self {
+            FileSort::Name => FileSort::Modified,
+            FileSort::Modified => FileSort::Size,
+            FileSort::Size => FileSort::Name,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FileSort::Name => "name",
+            FileSort::Modified => "modified",
+            FileSort::Size => "size",
+        }
+    }
+}
 
 /// A list to maintain names of the file. The actual file content will be saved
 /// into another object this type only to provide an ordered list of file names.
 #[derive(Default)]
 pub struct FileList {
     pub table: Vec<FileBuf>,
+    // Views of closed files, so reopening one resumes where it was left.
+    closed: HashMap<Box<Path>, ViewState>,
+    // The order the files were last sorted in, until it's given up by
+    // adding or moving one.
+    sort: Option<FileSort>,
 }
 
 impl Deref for FileList {
@@ -29,11 +161,21 @@ impl DerefMut for FileList {
 
 impl FileList {
     pub fn with_files(files: Vec<String>) -> anyhow::Result<Self> {
-        let mut table = Vec::new();
+        let mut table: Vec<FileBuf> = Vec::new();
         for file in files.into_iter() {
-            table.push(FileBuf::new(&file, false)?);
+            // A file that can't be opened stays on the list, telling why.
+            let file =
+                FileBuf::new(&file, false).unwrap_or_else(|err| FileBuf::failed(&file, &err));
+            // A file given twice, under any name, is opened once.
+            if !table.iter().any(|f| f.path() == file.path()) {
+                table.push(file);
+            }
         }
-        Ok(FileList { table })
+        Ok(FileList {
+            table,
+            closed: HashMap::new(),
+            sort: None,
+        })
     }
 
     pub fn insert(&mut self, name: &str) -> anyhow::Result<()> {
@@ -46,12 +188,122 @@ impl FileList {
             ));
         }
 
+        let file = self.restore(file);
         self.table.push(file);
+        self.sort = None;
         Ok(())
     }
 
+    /// Index of the file, opening it first unless it's already open.
+    pub fn open(&mut self, name: &str) -> anyhow::Result<usize> {
+        let file = FileBuf::new(name, false)?;
+        if let Some(index) = self.table.iter().position(|f| f.path() == file.path()) {
+            return Ok(index);
+        }
+        let file = self.restore(file);
+        self.table.push(file);
+        self.sort = None;
+        Ok(self.table.len() - 1)
+    }
+
+    /// Index of the member `member` of the archive at `index`, opening it
+    /// first unless it's already open.
+    pub fn open_member(&mut self, index: usize, member: &str) -> anyhow::Result<usize> {
+        let archive = self
+            .table
+            .get(index)
+            .ok_or_else(|| anyhow!("No file at {index}"))?;
+        let file = FileBuf::member(archive, member)?;
+        if let Some(index) = self.table.iter().position(|f| f.path() == file.path()) {
+            return Ok(index);
+        }
+        let file = self.restore(file);
+        self.table.push(file);
+        self.sort = None;
+        Ok(self.table.len() - 1)
+    }
+
+    /// Swap the files at `a` and `b`, giving up the sort order.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.table.swap(a, b);
+        self.sort = None;
+    }
+
+    #[inline]
+    pub fn sort(&self) -> Option<FileSort> {
+        self.sort
+    }
+
+    /// Sort the files in `sort` order. Files whose metadata can't be read go
+    /// last when sorting by modification time or size.
+    pub fn sort_by(&mut self, sort: FileSort) {
+        match sort {
+            FileSort::Name => self.table.sort_by(|a, b| a.name().cmp(b.name())),
+            FileSort::Modified => self.table.sort_by_cached_key(|f| {
+                Reverse(fs::metadata(f.path()).and_then(|m| m.modified()).ok())
+            }),
+            FileSort::Size => self
+                .table
+                .sort_by_cached_key(|f| Reverse(fs::metadata(f.path()).map(|m| m.len()).ok())),
+        }
+        self.table.sort_by_key(|f| !f.pinned);
+        self.sort = Some(sort);
+    }
+
+    /// Put the files in the order of `order`, as a session left them. Those
+    /// it doesn't hold go after the others, in the order they're in.
+    pub fn arrange(&mut self, order: &[PathBuf]) {
+        self.table.sort_by_cached_key(|f| {
+            order
+                .iter()
+                .position(|path| path == f.path())
+                .unwrap_or(order.len())
+        });
+    }
+
+    /// Pin the file at `index` below the other pinned ones, or unpin it
+    /// putting it right after them.
+    pub fn toggle_pin(&mut self, index: usize) {
+        if index >= self.table.len() {
+            return;
+        }
+        let mut file = self.table.remove(index);
+        file.pinned = !file.pinned;
+        let pinned = self.table.iter().take_while(|f| f.pinned).count();
+        self.table.insert(pinned, file);
+    }
+
+    /// Bring the files of each group together after the first of them, the
+    /// pinned ones staying first. `groups` holds the group of every file.
+    pub fn gather(&mut self, groups: &[Option<&str>]) {
+        let mut first = HashMap::new();
+        let mut order = (0..self.table.len())
+            .map(|i| match groups.get(i).copied().flatten() {
+                Some(group) => (!self.table[i].pinned, *first.entry(group).or_insert(i), i),
+                None => (!self.table[i].pinned, i, i),
+            })
+            .collect::<Vec<_>>();
+        order.sort();
+        let mut table = mem::take(&mut self.table)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.table = order
+            .into_iter()
+            .filter_map(|(_, _, i)| table[i].take())
+            .collect();
+    }
+
     pub fn close(&mut self, id: usize) {
-        self.table.remove(id);
+        let file = self.table.remove(id);
+        self.closed.insert(file.path, file.view.into_inner());
+    }
+
+    fn restore(&mut self, mut file: FileBuf) -> FileBuf {
+        if let Some(view) = self.closed.remove(file.path()) {
+            file.view = RefCell::new(view);
+        }
+        file
     }
 
     #[inline]
@@ -73,75 +325,742 @@ impl FileList {
 pub struct FileBuf {
     nulled: bool,
     is_tail: bool,
+    // Kept at the top of the Files pane whatever the order.
+    pinned: bool,
     name: Box<str>,
     path: Box<Path>,
-    reader: Option<BufReader<File>>,
-    view: RefCell<[usize; 2]>,
+    reader: Option<Box<dyn BufRead>>,
+    // The archive it's a member of and its name in it, if it's read out of
+    // an archive rather than from a file of its own. It can't be renamed or
+    // followed then.
+    member: Option<(Box<Path>, Box<str>)>,
+    // How it's compressed, if it's decompressed as it's read. It can't be
+    // followed then.
+    compression: Option<Compression>,
+    // Bytes read so far, so tailing resumes where reading stopped.
+    pos: u64,
+    // Unterminated last line while tailing, it may still be written to.
+    partial: Vec<u8>,
+    view: RefCell<ViewState>,
     view_update: bool,
-    lines: usize,
+    // Matches of the search in the buffer, along with the generation of the
+    // search and the length of the buffer they were counted for.
+    match_count: Cell<(usize, usize, usize)>,
+    // Matches of the search before the cursor line, along with the generation
+    // of the search, the cursor line and the length of the buffer they were
+    // counted for.
+    match_index: Cell<(usize, usize, usize, usize)>,
+    // Generation of the search and length of the buffer the filter was built
+    // for.
+    filtered: (usize, usize),
+    // Size and modification time of the file when it was read.
+    stamp: Option<(u64, SystemTime)>,
+    // What a compressed file or the member of an archive decompresses to,
+    // kept so that it's gone through again without decompressing it anew.
+    decompressed: Option<Rc<[u8]>>,
+    // Whether the file changed on disk since.
+    stale: bool,
+    // When it was last looked at for that.
+    checked: Option<Instant>,
+    // Encoding of the file, as of when it was read.
+    encoding: Option<Encoding>,
+    // Lines in the whole file, once counted.
+    lines: Option<usize>,
+    // Counts them on a thread of its own, so that a large file doesn't keep
+    // the first frame waiting.
+    counting: Option<Receiver<usize>>,
+    // Colors the lines by the language of the file, if it's a known one.
+    #[cfg(feature = "syntax")]
+    highlighter: RefCell<Option<Highlighter>>,
+    // Renders a Markdown file, shown so unless switched to its raw text.
+    markdown: RefCell<Option<Markdown>>,
+    // Lays a CSV or TSV file out as a table, likewise.
+    table: RefCell<Option<Table>>,
+    // Whether it's a JSON file, shown pretty-printed unless switched to its
+    // raw text.
+    json: bool,
+    // Whether it's a Jupyter notebook, shown as its cells unless switched
+    // to its raw JSON, and what each line of the cells is part of.
+    notebook: bool,
+    parts: Vec<Part>,
+    // Whether it's an HTML page, or the roff source of a manual page, shown
+    // laid out as text unless switched to its source, and how the parts of
+    // each line of the text are shown.
+    html: bool,
+    man: bool,
+    text_marks: Vec<Vec<(Range<usize>, Mark)>>,
+    // Whether it's a PDF file, shown as the text of its pages unless
+    // switched to its raw bytes, and the lines each page starts at.
+    pdf: bool,
+    pages: Vec<usize>,
+    // What kind of archive it is, shown as the list of its members unless
+    // switched to its raw bytes, and the members once listed.
+    archive: Option<Kind>,
+    members: Vec<Member>,
+    // Whether it holds terminal color escapes, shown as the colors they set
+    // unless switched to the raw text.
+    ansi: bool,
+    // Whether it's a diff, its lines colored by what they change.
+    diff: bool,
+    // How far into a hunk each line of a diff read so far is, worked out
+    // as they're shown.
+    hunks: RefCell<Vec<HunkLeft>>,
+    // Whether it's a log, its lines colored by the log rules.
+    log: bool,
+    // Whether its lines end with a carriage return and a line feed.
+    crlf: bool,
+    // What's in it if it's an image, described unless switched to its raw
+    // bytes.
+    image: Option<ImageInfo>,
+    rendered: bool,
+    // Whether it's shown as a hex dump, as a binary file is at first. The
+    // other view keeps where the dump or the text was scrolled to meanwhile.
+    hex: bool,
+    other_view: Option<ViewState>,
     buffer: Vec<String>,
 }
 
 impl FileBuf {
     pub fn new(path: &str, is_tail: bool) -> anyhow::Result<Self> {
+        let name = path.to_string().into_boxed_str();
+        // A manual page asked for by its topic, as `man:printf`, is looked up.
+        let path = match man::topic(path) {
+            Some(topic) => man::find(topic)?,
+            None => PathBuf::from(path),
+        };
+        let path = path.canonicalize()?.into_boxed_path();
+
+        log::trace!("Opening a file with path {}", path.display());
+
+        Self::open(name, path, is_tail, None)
+    }
+
+    /// The member `member` of the archive `archive`, read out of it rather
+    /// than extracted to disk. Its path is the one it would have below the
+    /// archive.
+    pub fn member(archive: &FileBuf, member: &str) -> anyhow::Result<Self> {
+        let name = format!("{}/{member}", archive.name).into_boxed_str();
+        let file = match archive.archive {
+            Some(kind) => kind.member_file(member),
+            None => member.to_string(),
+        };
+        let path = archive
+            .path
+            .join(file.trim_start_matches('/'))
+            .into_boxed_path();
+        let source = Some((archive.path.clone(), member.into()));
+        Self::open(name, path, false, source)
+    }
+
+    fn open(
+        name: Box<str>,
+        path: Box<Path>,
+        is_tail: bool,
+        member: Option<(Box<Path>, Box<str>)>,
+    ) -> anyhow::Result<Self> {
         let nulled = false;
 
-        let name = path.to_string().into_boxed_str();
-        let path = Path::new(path).canonicalize()?.into_boxed_path();
-        let file = File::open(path.as_ref())?;
-        let reader = Some(BufReader::new(file));
         let buffer = Vec::new();
-        let view = RefCell::new(Default::default());
+        let view = RefCell::new(ViewState::new());
         let view_update = true;
-        let lines = 1;
-
-        log::trace!("Opening a file with path {}", path.display());
 
-        Ok(FileBuf {
+        let mut file = FileBuf {
             nulled,
             is_tail,
+            pinned: false,
             name,
             path,
-            reader,
+            reader: None,
+            member,
+            compression: None,
+            pos: 0,
+            partial: Vec::new(),
             view,
-            lines,
             view_update,
+            match_count: Cell::new((0, 0, 0)),
+            match_index: Cell::new((0, 0, 0, 0)),
+            filtered: (0, 0),
+            stamp: None,
+            decompressed: None,
+            stale: false,
+            checked: None,
+            encoding: None,
+            lines: None,
+            counting: None,
+            #[cfg(feature = "syntax")]
+            highlighter: RefCell::new(None),
+            markdown: RefCell::new(None),
+            table: RefCell::new(None),
+            json: false,
+            notebook: false,
+            parts: Vec::new(),
+            html: false,
+            man: false,
+            text_marks: Vec::new(),
+            pdf: false,
+            pages: Vec::new(),
+            archive: None,
+            members: Vec::new(),
+            ansi: false,
+            diff: false,
+            hunks: RefCell::new(Vec::new()),
+            log: false,
+            crlf: false,
+            image: None,
+            rendered: true,
+            hex: false,
+            other_view: None,
             buffer,
+        };
+        file.probe();
+        file.is_tail = is_tail && file.compression.is_none();
+        let (reader, encoding, counting) = file.source()?;
+        file.reader = Some(reader);
+        file.encoding = encoding;
+        file.counting = Some(counting);
+        file.stamp = stamp(file.origin());
+        file.hex = file.image.is_none()
+            && !file.pdf
+            && file.archive.is_none()
+            && encoding == Some(Encoding::Binary);
+        Ok(file)
+    }
+
+    /// A file that couldn't be opened, kept with the reason in place of its
+    /// lines. Reloading it opens it once it can be.
+    pub fn failed(path: &str, err: &anyhow::Error) -> Self {
+        log::trace!("Couldn't open {path} due to: {err}");
+        let name = path.to_string().into_boxed_str();
+        let path = std::path::absolute(path)
+            .unwrap_or_else(|_| Path::new(path).to_path_buf())
+            .into_boxed_path();
+        FileBuf {
+            nulled: true,
+            is_tail: false,
+            pinned: false,
+            name,
+            path,
+            reader: None,
+            member: None,
+            compression: None,
+            pos: 0,
+            partial: Vec::new(),
+            view: RefCell::new(ViewState::new()),
+            view_update: true,
+            match_count: Cell::new((0, 0, 0)),
+            match_index: Cell::new((0, 0, 0, 0)),
+            filtered: (0, 0),
+            stamp: None,
+            decompressed: None,
+            stale: false,
+            checked: None,
+            encoding: None,
+            lines: None,
+            counting: None,
+            #[cfg(feature = "syntax")]
+            highlighter: RefCell::new(None),
+            markdown: RefCell::new(None),
+            table: RefCell::new(None),
+            json: false,
+            notebook: false,
+            parts: Vec::new(),
+            html: false,
+            man: false,
+            text_marks: Vec::new(),
+            pdf: false,
+            pages: Vec::new(),
+            archive: None,
+            members: Vec::new(),
+            ansi: false,
+            diff: false,
+            hunks: RefCell::new(Vec::new()),
+            log: false,
+            crlf: false,
+            image: None,
+            rendered: true,
+            hex: false,
+            other_view: None,
+            buffer: vec![format!("{err}")],
+        }
+    }
+
+    /// Rename or move the file on disk to `name`, a path as for `new`.
+    pub fn rename(&mut self, name: &str) -> anyhow::Result<()> {
+        if self.member.is_some() {
+            return Err(anyhow!("{} is read out of an archive", self.name));
+        }
+        if Path::new(name).exists() {
+            return Err(anyhow!("{name} already exists"));
+        }
+        fs::rename(&self.path, name)
+            .map_err(|err| anyhow!("Couldn't rename {} due to: {err}", self.name))?;
+        self.name = name.into();
+        self.path = Path::new(name).canonicalize()?.into_boxed_path();
+        self.stamp = stamp(&self.path);
+        self.probe();
+        Ok(())
+    }
+
+    // Tell what kind of file it is by its name and, for some kinds, by what
+    // it starts with. Whatever was worked out for the kind before is
+    // dropped.
+    fn probe(&mut self) {
+        self.compression = Compression::for_path(&self.path);
+        // What the file is told by what it holds once decompressed.
+        let inner = self.inner_path();
+        #[cfg(feature = "syntax")]
+        {
+            *self.highlighter.get_mut() = Highlighter::for_path(&inner);
+        }
+        *self.markdown.get_mut() = markdown_for(&inner);
+        *self.table.get_mut() = Table::for_path(&inner);
+        self.json = has_extension(&inner, &["json"]);
+        self.notebook = notebook::is_notebook(&inner);
+        self.html = html::is_html(&inner);
+        self.man = man::is_man(&inner);
+        self.pdf = is_pdf(&inner);
+        self.ansi = ansi::has_escapes(&self.path);
+        self.diff = diff::is_diff(&inner);
+        self.log = logs::is_log(&inner);
+        self.crlf = invisible::uses_crlf(&self.path);
+        self.image = ImageInfo::probe(&self.path);
+        // Archives held in archives aren't looked into.
+        self.archive = Kind::for_path(&self.path).filter(|_| self.member.is_none());
+    }
+
+    /// Read the file anew, keeping the View pane where it was as far as the
+    /// file still reaches. Returns whether it changed since it was last read.
+    pub fn reload(&mut self) -> anyhow::Result<bool> {
+        let (reader, encoding, counting) = self
+            .source()
+            .map_err(|err| anyhow!("Couldn't reload {} due to: {err}", self.name))?;
+        if let Ok(path) = self.path.canonicalize() {
+            self.path = path.into_boxed_path();
+        }
+        let stamp = stamp(self.origin());
+        let changed = self.nulled || stamp.is_none() || stamp != self.stamp;
+        self.stamp = stamp;
+        self.stale = false;
+        self.nulled = false;
+        self.encoding = encoding;
+        self.lines = None;
+        self.counting = Some(counting);
+        self.probe();
+        self.parts.clear();
+        self.text_marks.clear();
+        self.hunks.get_mut().clear();
+        self.pages.clear();
+        self.members.clear();
+        self.reader = Some(reader);
+        self.pos = 0;
+        self.partial.clear();
+        self.buffer.clear();
+        // The counts and the filter are worked out again as for a new search.
+        self.match_count.set((usize::MAX, 0, 0));
+        self.match_index.set((usize::MAX, 0, 0, 0));
+        self.filtered = (usize::MAX, 0);
+        self.view_update = true;
+
+        let end = self.view.get_mut().end();
+        let cursor = self.view.get_mut().cursor();
+        self.read_lines(end.max(cursor + 1))?;
+        let len = self.buffer.len();
+        if cursor >= len {
+            self.view.get_mut().move_cursor(cursor, len);
+        }
+        Ok(changed)
+    }
+
+    // Where the lines are read from: the file itself, or the member of an
+    // archive read out of it, decompressed if it's compressed. A compressed
+    // archive is decompressed only as its members are listed.
+    fn source(&mut self) -> anyhow::Result<Source> {
+        let bytes = match &self.member {
+            Some((archive, member)) => {
+                let kind = Kind::for_path(archive)
+                    .ok_or_else(|| anyhow!("{} isn't an archive", archive.display()))?;
+                archive::read(kind, archive, member)?
+            }
+            None if self.compression.is_some() && self.archive.is_none() => fs::read(&self.path)?,
+            None => {
+                self.decompressed = None;
+                let file = File::open(&self.path)?;
+                let encoding = File::open(&self.path).ok().and_then(sniff);
+                let (sender, receiver) = mpsc::channel();
+                if let Ok(file) = File::open(&self.path) {
+                    thread::spawn(move || {
+                        if let Some(lines) = count_lines(file) {
+                            let _ = sender.send(lines);
+                        }
+                    });
+                }
+                return Ok((Box::new(BufReader::new(file)), encoding, receiver));
+            }
+        };
+        let bytes = match self.compression {
+            Some(compression) => compression.decompress(&bytes)?,
+            None => bytes,
+        };
+        let bytes = Rc::<[u8]>::from(bytes);
+        self.decompressed = Some(bytes.clone());
+        let encoding = sniff(&bytes[..]);
+        let (sender, receiver) = mpsc::channel();
+        if let Some(lines) = count_lines(&bytes[..]) {
+            let _ = sender.send(lines);
+        }
+        Ok((Box::new(Cursor::new(bytes)), encoding, receiver))
+    }
+
+    // What `source` reads the lines from, read again from its start.
+    fn bytes(&self) -> anyhow::Result<Box<dyn BufRead>> {
+        Ok(match &self.decompressed {
+            Some(bytes) => Box::new(Cursor::new(bytes.clone())),
+            None => Box::new(BufReader::new(File::open(&self.path)?)),
         })
     }
 
+    // Number of bytes a hex dump of it goes through, decompressed.
+    fn dump_size(&self) -> Option<u64> {
+        match &self.decompressed {
+            Some(bytes) => Some(bytes.len() as u64),
+            None => self.stamp.map(|(size, _)| size),
+        }
+    }
+
+    // The path of what it holds, as told by its extension once decompressed.
+    fn inner_path(&self) -> PathBuf {
+        compression::inner_path(&self.path)
+    }
+
+    // The file on disk the lines come from, the archive for a member of one.
+    fn origin(&self) -> &Path {
+        self.member
+            .as_ref()
+            .map_or(&self.path, |(archive, _)| archive)
+    }
+
     pub fn update(&mut self) -> anyhow::Result<()> {
-        if self.is_tail && self.reader.is_none() {
-            let file = File::open(self.path())?;
-            self.reader = Some(BufReader::new(file));
+        if self.is_tail {
+            return self.follow();
+        }
+
+        let lines_to_read = self.view.borrow().end();
+        self.read_lines(lines_to_read)
+    }
+
+    // Read whatever was appended to the file and keep the View pane pinned to
+    // its end. A file that shrank was truncated or rotated and is read anew.
+    fn follow(&mut self) -> anyhow::Result<()> {
+        let size = std::fs::metadata(self.path())?.len();
+        if size < self.pos {
+            log::trace!("File {} was truncated", self.path.display());
+            self.buffer.clear();
+            self.hunks.get_mut().clear();
+            self.partial.clear();
+            self.pos = 0;
+            self.reader = None;
         }
+        if self.reader.is_none() {
+            let mut file = File::open(self.path())?;
+            file.seek(SeekFrom::Start(self.pos))?;
+            self.reader = Some(Box::new(BufReader::new(file)));
+        }
+
+        let len = self.buffer.len();
+        self.read_lines(usize::MAX)?;
+        if self.buffer.len() != len || len == 0 {
+            self.bottom();
+        }
+        Ok(())
+    }
 
+    pub fn toggle_tail(&mut self) {
+        if self.nulled || self.member.is_some() || self.compression.is_some() {
+            return;
+        }
+        self.is_tail = !self.is_tail;
+        if !self.is_tail && !self.partial.is_empty() {
+            self.buffer
+                .push(clean_line(&String::from_utf8_lossy(&self.partial)));
+            self.partial.clear();
+        }
+    }
+
+    #[inline]
+    pub fn is_tail(&self) -> bool {
+        self.is_tail
+    }
+
+    #[inline]
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    /// Whether the file changed on disk since it was read, as of the last
+    /// `check_stale`. A followed file is read as it changes, so it never is.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Take in the number of lines in the whole file once it's counted.
+    pub fn update_count(&mut self) {
+        let Some(counting) = &self.counting else {
+            return;
+        };
+        match counting.try_recv() {
+            Ok(lines) => {
+                self.lines = Some(lines);
+                self.counting = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.counting = None,
+        }
+    }
+
+    pub fn check_stale(&mut self) {
+        if self
+            .checked
+            .is_some_and(|checked| checked.elapsed() < STALE_INTERVAL)
+        {
+            return;
+        }
+        self.checked = Some(Instant::now());
+        self.stale = !self.is_tail && !self.nulled && stamp(self.origin()) != self.stamp;
+    }
+
+    fn read_lines(&mut self, mut lines_to_read: usize) -> anyhow::Result<()> {
+        if self.hex && !self.is_tail {
+            return self.read_hex(lines_to_read);
+        }
+        if let Some(image) = self.image.filter(|_| self.rendered) {
+            self.buffer = image.describe(&self.path);
+            self.reader = None;
+            return Ok(());
+        }
+        if let Some(kind) = self.archive.filter(|_| self.rendered && !self.is_tail) {
+            return self.read_archive(kind);
+        }
+        #[cfg(feature = "pdf")]
+        if self.pdf && self.rendered && !self.is_tail {
+            return self.read_pdf();
+        }
+        // The bytes of a binary file aren't shown as text.
+        if self.is_binary() {
+            if !self.is_tail {
+                self.reader = None;
+            }
+            return Ok(());
+        }
+        if self.json && self.rendered && !self.is_tail {
+            return self.read_pretty();
+        }
+        if self.notebook && self.rendered && !self.is_tail {
+            return self.read_notebook();
+        }
+        if self.html && self.rendered && !self.is_tail {
+            return self.read_html();
+        }
+        if self.man && self.rendered && !self.is_tail {
+            return self.read_man();
+        }
         let Some(reader) = self.reader.as_mut() else {
             return Ok(());
         };
 
-        let mut lines_to_read = self.view.borrow()[1];
-        let mut buffer = String::new();
+        let mut buffer = std::mem::take(&mut self.partial);
 
         while lines_to_read > 0 {
-            if reader.read_line(&mut buffer)? == 0 && !self.is_tail {
-                self.reader = None;
+            let read = reader.read_until(b'\n', &mut buffer)?;
+            self.pos += read as u64;
+            if read == 0 {
+                if !self.is_tail {
+                    self.reader = None;
+                }
+                break;
+            }
+            // The rest of the line might not be written yet.
+            if self.is_tail && !buffer.ends_with(b"\n") {
                 break;
             }
 
-            self.buffer.push(format!(
-                "{:>6}|  {}",
-                self.lines,
-                buffer
-                    .replace('\t', &"\u{000A0}".repeat(4))
-                    .replace('\r', "")
-            ));
-
-            self.lines += 1;
+            // Bytes that aren't UTF-8 show as replacement characters.
+            self.buffer
+                .push(clean_line(&String::from_utf8_lossy(&buffer)));
             lines_to_read -= 1;
             buffer.clear();
         }
 
+        if self.is_tail {
+            self.partial = buffer;
+        }
+        Ok(())
+    }
+
+    // Read the whole JSON file and pretty-print it, or show it as it is if
+    // it isn't valid.
+    fn read_pretty(&mut self) -> anyhow::Result<()> {
+        let Some(mut reader) = self.reader.take() else {
+            return Ok(());
+        };
+        let mut bytes = Vec::new();
+        self.pos += reader.read_to_end(&mut bytes)? as u64;
+        let text = String::from_utf8_lossy(&bytes);
+        self.buffer = match json::pretty(&text) {
+            Ok(lines) => lines,
+            Err(err) => {
+                log::trace!("Couldn't pretty-print {} due to: {err}", self.name);
+                self.rendered = false;
+                text.lines().map(clean_line).collect()
+            }
+        };
+        Ok(())
+    }
+
+    // Read the whole notebook and lay its cells out, or show its JSON as it
+    // is if it isn't a notebook after all.
+    fn read_notebook(&mut self) -> anyhow::Result<()> {
+        let Some(mut reader) = self.reader.take() else {
+            return Ok(());
+        };
+        let mut bytes = Vec::new();
+        self.pos += reader.read_to_end(&mut bytes)? as u64;
+        let text = String::from_utf8_lossy(&bytes);
+        match Notebook::parse(&text) {
+            Ok(notebook) => {
+                self.buffer = notebook.lines;
+                self.parts = notebook.parts;
+                #[cfg(feature = "syntax")]
+                {
+                    *self.highlighter.get_mut() = notebook
+                        .language
+                        .and_then(|path| Highlighter::for_path(&path));
+                }
+                *self.markdown.get_mut() = Some(Markdown::new());
+            }
+            Err(err) => {
+                log::trace!("Couldn't read the cells of {} due to: {err}", self.name);
+                self.rendered = false;
+                self.buffer = text.lines().map(clean_line).collect();
+            }
+        }
+        Ok(())
+    }
+
+    // Read the whole HTML page and lay its text out.
+    fn read_html(&mut self) -> anyhow::Result<()> {
+        let Some(mut reader) = self.reader.take() else {
+            return Ok(());
+        };
+        let mut bytes = Vec::new();
+        self.pos += reader.read_to_end(&mut bytes)? as u64;
+        let page = Page::render(&String::from_utf8_lossy(&bytes));
+        self.buffer = page.lines;
+        self.text_marks = page.marks;
+        Ok(())
+    }
+
+    // Read the whole manual page and lay it out, or show its source as it is
+    // if it isn't roff after all.
+    fn read_man(&mut self) -> anyhow::Result<()> {
+        let Some(mut reader) = self.reader.take() else {
+            return Ok(());
+        };
+        let mut bytes = Vec::new();
+        self.pos += reader.read_to_end(&mut bytes)? as u64;
+        let text = String::from_utf8_lossy(&bytes);
+        match man::render(&text) {
+            Ok(page) => {
+                self.buffer = page.lines;
+                self.text_marks = page.marks;
+            }
+            Err(err) => {
+                log::trace!("Couldn't lay the page {} out due to: {err}", self.name);
+                self.rendered = false;
+                self.buffer = text.lines().map(clean_line).collect();
+            }
+        }
+        Ok(())
+    }
+
+    // List the members of the archive, or show it as a binary file if they
+    // can't be read.
+    fn read_archive(&mut self, kind: Kind) -> anyhow::Result<()> {
+        if self.reader.take().is_none() {
+            return Ok(());
+        }
+        match archive::members(kind, &self.path) {
+            Ok(members) => {
+                self.buffer = members.iter().map(archive::listing).collect();
+                self.members = members;
+            }
+            Err(err) => {
+                log::trace!("Couldn't list the members of {} due to: {err}", self.name);
+                self.rendered = false;
+                return Ok(());
+            }
+        }
+        // The schema of a database follows its tables.
+        #[cfg(feature = "sqlite")]
+        if kind == Kind::Sqlite {
+            match archive::schema(&self.path) {
+                Ok(schema) => {
+                    self.buffer.push(String::new());
+                    self.buffer.extend(schema.iter().map(|l| clean_line(l)));
+                }
+                Err(err) => log::trace!("Couldn't read the schema of {} due to: {err}", self.name),
+            }
+        }
+        Ok(())
+    }
+
+    // Read the whole PDF file and lay the text of its pages out one after
+    // the other, or show it as a binary file if its text can't be had.
+    #[cfg(feature = "pdf")]
+    fn read_pdf(&mut self) -> anyhow::Result<()> {
+        let Some(mut reader) = self.reader.take() else {
+            return Ok(());
+        };
+        let mut bytes = Vec::new();
+        self.pos += reader.read_to_end(&mut bytes)? as u64;
+        match pdf::pages(&bytes) {
+            Ok(pages) => {
+                let count = pages.len();
+                for (i, page) in pages.into_iter().enumerate() {
+                    if i > 0 {
+                        self.buffer.push(String::new());
+                    }
+                    self.pages.push(self.buffer.len());
+                    self.buffer.push(pdf::header(i + 1, count));
+                    self.buffer.extend(page.iter().map(|l| clean_line(l)));
+                }
+            }
+            Err(err) => {
+                log::trace!("Couldn't read the text of {} due to: {err}", self.name);
+                self.rendered = false;
+            }
+        }
+        Ok(())
+    }
+
+    // Read `rows` more rows of the hex dump.
+    fn read_hex(&mut self, rows: usize) -> anyhow::Result<()> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(());
+        };
+        let mut bytes = Vec::with_capacity(hex::ROW_BYTES);
+        for _ in 0..rows {
+            bytes.clear();
+            let read = reader.take(hex::ROW_BYTES as u64).read_to_end(&mut bytes)?;
+            if read > 0 {
+                self.buffer.push(hex::row(self.pos, &bytes));
+                self.pos += read as u64;
+            }
+            if read < hex::ROW_BYTES {
+                self.reader = None;
+                break;
+            }
+        }
         Ok(())
     }
 
@@ -158,58 +1077,794 @@ impl FileBuf {
         self.view_update = true;
     }
 
-    // Only return lines that are visible on the screen.
-    pub fn buffer<'a>(&'a self, rect: Rect) -> (Vec<&'a str>, bool) {
+    // Return the lines from the top of the View pane onwards. Which of them
+    // fit onto the screen is up to the renderer.
+    pub fn buffer(&self, rect: Rect) -> (&[String], bool) {
         if self.view_update {
+            let height = rect.as_size().height.saturating_sub(2); // 2 (borders)
             let mut view = self.view.borrow_mut();
-            view[1] = view[0]
-                .saturating_add(rect.as_size().height.into())
-                .saturating_sub(2);
+            view.resize(height.into());
+            if self.is_loaded() && !view.is_wrap() {
+                view.fit(self.buffer.len());
+            }
+        }
+
+        let start = self.view.borrow().shown().min(self.buffer.len());
+        (&self.buffer[start..], self.nulled)
+    }
+
+    // Replace the buffer with the error message and close the file reader.
+    pub fn nullify(&mut self, message: String) {
+        self.nulled = true;
+        self.buffer = vec![message];
+        let _ = self.reader.take();
+        self.view = RefCell::new(ViewState::new());
+        self.is_tail = false;
+    }
+
+    pub fn next(&mut self, lines: usize) {
+        if lines > 1 {
+            self.read_lines(lines)
+                .unwrap_or_else(|err| log::trace!("Couldn't read ahead: {err}"));
         }
+        let len = self.buffer.len();
+        self.view.get_mut().cursor_down(lines, len);
+    }
 
+    pub fn prev(&mut self, lines: usize) {
+        self.view.get_mut().cursor_up(lines);
+    }
+
+    /// Scroll the View pane by `lines` leaving the cursor where it is.
+    pub fn scroll_down(&mut self, lines: usize) {
+        let len = self.buffer.len();
+        self.view.get_mut().scroll_down(lines, len);
+    }
+
+    /// Scroll to `step` of `steps` even steps through the file, which is
+    /// read to its end first.
+    pub fn scroll_to(&mut self, step: usize, steps: usize) {
+        self.read_lines(usize::MAX)
+            .unwrap_or_else(|err| log::trace!("Couldn't read the whole file: {err}"));
         let len = self.buffer.len();
-        let (start, end) = (self.view.borrow()[0], self.view.borrow()[1]);
-        let lines = self
+        self.view.get_mut().scroll_to(step, steps, len);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.view.get_mut().scroll_up(1);
+    }
+
+    pub fn scroll_left(&mut self) {
+        let view = self.view.get_mut();
+        let step = match self
+            .table
+            .get_mut()
+            .as_ref()
+            .filter(|_| self.rendered && !self.hex)
+        {
+            Some(table) => table
+                .column_starts()
+                .into_iter()
+                .rfind(|&start| start < view.hscroll())
+                .map_or(HSCROLL_STEP, |start| view.hscroll() - start),
+            None => HSCROLL_STEP,
+        };
+        view.scroll_left(step);
+    }
+
+    pub fn toggle_wrap(&mut self) {
+        self.view.get_mut().toggle_wrap();
+    }
+
+    /// Put the cursor on the next word of the cursor line, or the previous
+    /// one when not going `forward`.
+    pub fn next_word(&mut self, forward: bool) {
+        let view = self.view.get_mut();
+        let words = self
             .buffer
+            .get(view.cursor())
+            .map_or(0, |line| words(line).len());
+        match forward {
+            true => view.next_word(words),
+            false => view.prev_word(words),
+        }
+    }
+
+    /// Keep the first `rows` wrapped rows of the cursor line in sight, see
+    /// `ViewState::follow_wrapped`.
+    pub fn follow_wrapped(&self, line_rows: impl Fn(usize) -> usize, rows: usize) {
+        self.view.borrow_mut().follow_wrapped(line_rows, rows);
+    }
+
+    // Scrolling stops once the longest visible line is about to disappear.
+    pub fn scroll_right(&mut self) {
+        let len = self.buffer.len();
+        let view = self.view.get_mut();
+        if view.is_wrap() {
+            return;
+        }
+        // A table scrolls by its columns, up to the last one.
+        if let Some(table) = self
+            .table
+            .get_mut()
+            .as_ref()
+            .filter(|_| self.rendered && !self.hex)
+        {
+            let starts = table.column_starts();
+            if let Some(&start) = starts.iter().find(|&&start| start > view.hscroll()) {
+                view.scroll_right(start - view.hscroll(), start);
+            }
+            return;
+        }
+        let (start, end) = (view.start().min(len), view.end().min(len));
+        let longest = self.buffer[start..end]
             .iter()
-            .map(String::as_str)
-            .collect::<Vec<&'a str>>();
+            .map(|l| l.width())
+            .max()
+            .unwrap_or_default();
+        view.scroll_right(HSCROLL_STEP, longest);
+    }
+
+    pub fn page_down(&mut self) {
+        let height = self.view.get_mut().height();
+        // Make sure the next page is loaded before moving onto it.
+        self.read_lines(height.saturating_mul(2))
+            .unwrap_or_else(|err| log::trace!("Couldn't read ahead: {err}"));
+        let len = self.buffer.len();
+        self.view.get_mut().page_down(len);
+    }
+
+    pub fn page_up(&mut self) {
+        self.view.get_mut().page_up();
+    }
+
+    pub fn half_page_down(&mut self) {
+        let height = self.view.get_mut().height();
+        self.read_lines(height)
+            .unwrap_or_else(|err| log::trace!("Couldn't read ahead: {err}"));
+        let len = self.buffer.len();
+        self.view.get_mut().half_page_down(len);
+    }
+
+    pub fn half_page_up(&mut self) {
+        self.view.get_mut().half_page_up();
+    }
+
+    pub fn top(&mut self) {
+        self.view.get_mut().top();
+    }
+
+    // Reads the rest of the file so the bottom is the real end of it.
+    pub fn bottom(&mut self) {
+        self.read_lines(usize::MAX)
+            .unwrap_or_else(|err| log::trace!("Couldn't read the whole file: {err}"));
+        let len = self.buffer.len();
+        self.view.get_mut().bottom(len);
+    }
+
+    /// Move the cursor to the first line matching `search` from `from` on,
+    /// wrapping around the end of the file.
+    pub fn search(&mut self, search: &Search, from: usize) -> anyhow::Result<usize> {
+        self.read_lines(usize::MAX)?;
+        let len = self.buffer.len();
+        let from = from.min(len);
+        let line = (from..len)
+            .chain(0..from)
+            .find(|&i| search.is_match(&self.buffer[i]))
+            .ok_or_else(|| anyhow!("Pattern not found: {}", search.query()))?;
+        self.view.get_mut().move_cursor(line, len);
+        Ok(line)
+    }
+
+    /// Move the cursor to the next line matching `search` below the cursor
+    /// line, or above it when not going `forward`. Tells whether the search
+    /// wrapped around an end of the file.
+    pub fn search_next(&mut self, search: &Search, forward: bool) -> anyhow::Result<bool> {
+        self.read_lines(usize::MAX)?;
+        let len = self.buffer.len();
+        let cursor = self.view.get_mut().cursor();
+        let line = (1..=len)
+            .map(|step| match forward {
+                true => (cursor + step) % len,
+                false => (cursor + len - step) % len,
+            })
+            .find(|&i| search.is_match(&self.buffer[i]))
+            .ok_or_else(|| anyhow!("Pattern not found: {}", search.query()))?;
+        self.view.get_mut().move_cursor(line, len);
+        Ok(match forward {
+            true => line <= cursor,
+            false => line >= cursor,
+        })
+    }
+
+    /// Show only the lines matching `search`, or every line again.
+    pub fn toggle_filter(&mut self, search: &Search) -> anyhow::Result<()> {
+        if self.view.get_mut().is_filtered() {
+            self.view.get_mut().clear_filter();
+            return Ok(());
+        }
+        if !search.is_active() {
+            return Err(anyhow!("Search for the lines to show first"));
+        }
+        self.read_lines(usize::MAX)?;
+        let lines = self.matching(search, 0);
+        if lines.is_empty() {
+            return Err(anyhow!("Pattern not found: {}", search.query()));
+        }
+        self.filtered = (search.generation(), self.buffer.len());
+        self.view.get_mut().set_filter(lines);
+        Ok(())
+    }
 
-        let slice = if len >= end && start < end {
-            &lines[start..end]
+    /// Keep the filter in line with `search` and the lines read since it was
+    /// built. Clearing the search drops the filter.
+    pub fn update_filter(&mut self, search: &Search) {
+        if !self.view.get_mut().is_filtered() {
+            return;
+        }
+        if !search.is_active() {
+            self.view.get_mut().clear_filter();
+            return;
+        }
+        let len = self.buffer.len();
+        let (generation, filtered_len) = self.filtered;
+        if generation != search.generation() || filtered_len > len {
+            let lines = self.matching(search, 0);
+            self.view.get_mut().set_filter(lines);
+        } else if filtered_len < len {
+            let lines = self.matching(search, filtered_len);
+            self.view.get_mut().extend_filter(lines);
         } else {
-            &lines[..]
+            // The cursor may have been put onto a hidden line.
+            self.view.get_mut().snap();
+        }
+        self.filtered = (search.generation(), len);
+    }
+
+    /// The 0-based lines starting a definition, for the languages `Outline`
+    /// knows, or the hunks of a diff. The file is read to its end first.
+    pub fn definitions(&mut self) -> anyhow::Result<Vec<usize>> {
+        if self.notebook && self.rendered {
+            self.read_lines(usize::MAX)?;
+            let cells = (0..self.buffer.len())
+                .filter(|&i| self.parts.get(i) == Some(&Part::Header) && !self.buffer[i].is_empty())
+                .collect();
+            return Ok(cells);
+        }
+        if self.pdf && self.rendered {
+            self.read_lines(usize::MAX)?;
+            return Ok(self.pages.clone());
+        }
+        if (self.html || self.man) && self.rendered {
+            self.read_lines(usize::MAX)?;
+            let headings = (0..self.buffer.len())
+                .filter(|&i| {
+                    // Lines appended while tailed have no marks.
+                    self.text_marks.get(i).is_some_and(|marks| {
+                        marks
+                            .iter()
+                            .any(|(_, mark)| matches!(mark, Mark::Heading(_)))
+                    })
+                })
+                .collect();
+            return Ok(headings);
+        }
+        if self.diff {
+            self.read_lines(usize::MAX)?;
+            let hunks = (0..self.buffer.len())
+                .filter(|&i| self.change(i) == Some(Change::Hunk))
+                .collect();
+            return Ok(hunks);
+        }
+        let outline = Outline::for_path(&self.path)
+            .ok_or_else(|| anyhow!("No outline for this kind of file"))?;
+        self.read_lines(usize::MAX)?;
+        Ok(outline.definitions(&self.buffer))
+    }
+
+    /// Read the rest of the file.
+    pub fn read_all(&mut self) -> anyhow::Result<()> {
+        self.read_lines(usize::MAX)
+    }
+
+    /// Every line of the file prefixed with its number, to be picked from.
+    /// The file is read to its end first.
+    pub fn line_labels(&mut self) -> anyhow::Result<Vec<String>> {
+        self.read_lines(usize::MAX)?;
+        let labels = self
+            .buffer
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("{}: {}", i + 1, line.trim()))
+            .collect();
+        Ok(labels)
+    }
+
+    // Lines matching `search` from `from` on.
+    fn matching(&self, search: &Search, from: usize) -> Vec<usize> {
+        (from..self.buffer.len())
+            .filter(|&i| search.is_match(&self.buffer[i]))
+            .collect()
+    }
+
+    /// Every line matching `search` along with `context` lines on either
+    /// side, reading the whole file.
+    pub fn find_all(&mut self, search: &Search, context: usize) -> anyhow::Result<Vec<Hit>> {
+        self.read_lines(usize::MAX)?;
+        let label = |i: usize| context_label(&self.name, i + 1, &self.buffer[i]);
+        let hits = self
+            .buffer
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| search.is_match(l))
+            .map(|(i, l)| Hit {
+                path: self.path.clone(),
+                line: i + 1,
+                label: format!("{}:{}: {}", self.name, i + 1, l.trim()),
+                before: (i.saturating_sub(context)..i).map(label).collect(),
+                after: (i + 1..(i + 1 + context).min(self.buffer.len()))
+                    .map(label)
+                    .collect(),
+            })
+            .collect();
+        Ok(hits)
+    }
+
+    /// Number of matches of `search` in the lines read so far.
+    pub fn match_count(&self, search: &Search) -> usize {
+        let (generation, len, count) = self.match_count.get();
+        if generation == search.generation() && len == self.buffer.len() {
+            return count;
+        }
+        let count = self.buffer.iter().map(|l| search.count(l)).sum();
+        self.match_count
+            .set((search.generation(), self.buffer.len(), count));
+        count
+    }
+
+    /// 1-based position of the first match on the cursor line among all the
+    /// matches of `search`, if the cursor line has one.
+    pub fn match_index(&self, search: &Search) -> Option<usize> {
+        let cursor = self.view().cursor();
+        if search.count(self.buffer.get(cursor)?) == 0 {
+            return None;
+        }
+        let (generation, line, len, before) = self.match_index.get();
+        if generation == search.generation() && line == cursor && len == self.buffer.len() {
+            return Some(before + 1);
+        }
+        let before = self.buffer[..cursor].iter().map(|l| search.count(l)).sum();
+        self.match_index
+            .set((search.generation(), cursor, self.buffer.len(), before));
+        Some(before + 1)
+    }
+
+    /// Put the cursor on `line` (0-based), scrolling only as far as needed.
+    pub fn move_cursor(&mut self, line: usize) {
+        let len = self.buffer.len();
+        self.view.get_mut().move_cursor(line, len);
+    }
+
+    /// Jump to the 1-based line number `line`, loading the file up to it.
+    pub fn goto(&mut self, line: usize) -> anyhow::Result<()> {
+        if line == 0 {
+            return Err(anyhow!("Line numbers start at 1"));
+        }
+        let height = self.view.get_mut().height();
+        let missing = line
+            .saturating_add(height)
+            .saturating_sub(self.buffer.len());
+        self.read_lines(missing)?;
+
+        let len = self.buffer.len();
+        if line > len {
+            return Err(anyhow!("File only has {len} lines"));
+        }
+        self.view.get_mut().goto(line - 1, len);
+        Ok(())
+    }
+
+    /// Jump to the row of the hex dump holding the byte at `offset`.
+    pub fn goto_offset(&mut self, offset: u64) -> anyhow::Result<()> {
+        let size = self.dump_size().unwrap_or(0);
+        if offset >= size {
+            return Err(anyhow!("File only has {size} bytes"));
+        }
+        self.goto(offset as usize / hex::ROW_BYTES + 1)
+    }
+
+    /// Move the cursor to the next row of the hex dump holding `pattern`,
+    /// from row `row` on or above it when not going `forward`, wrapping
+    /// around the end of the file. Tells whether the search wrapped.
+    pub fn search_bytes(
+        &mut self,
+        pattern: &[u8],
+        row: usize,
+        forward: bool,
+    ) -> anyhow::Result<bool> {
+        let reader = self.bytes()?;
+        let found = hex::find(reader, pattern, (row * hex::ROW_BYTES) as u64)?;
+        let (offset, wrapped) = match forward {
+            true => found
+                .after
+                .map(|o| (o, false))
+                .or(found.first.map(|o| (o, true))),
+            false => found
+                .before
+                .map(|o| (o, false))
+                .or(found.last.map(|o| (o, true))),
+        }
+        .ok_or_else(|| anyhow!("Bytes not found: {}", hex::spell(pattern)))?;
+        self.goto_offset(offset)?;
+        Ok(wrapped)
+    }
+
+    /// Jump to `percent` percent of the file, which is read completely first.
+    pub fn goto_percent(&mut self, percent: usize) -> anyhow::Result<()> {
+        if percent > 100 {
+            return Err(anyhow!("Percentage can't exceed 100"));
+        }
+        self.read_lines(usize::MAX)?;
+        let len = self.buffer.len();
+        let line = (percent * len).div_ceil(100).max(1);
+        self.goto(line)
+    }
+
+    /// Whether the file has been read up to its end.
+    #[inline]
+    pub fn is_loaded(&self) -> bool {
+        self.reader.is_none()
+    }
+
+    /// Move the cursor onto the line with the bracket matching the first one
+    /// on the cursor line. The rest of the file is read if it's not in sight.
+    pub fn jump_to_match(&mut self) -> anyhow::Result<()> {
+        let cursor = self.view.get_mut().cursor();
+        let line = match matching_bracket(&self.buffer, cursor) {
+            Some(line) => line,
+            None => {
+                self.read_lines(usize::MAX)?;
+                matching_bracket(&self.buffer, cursor)
+                    .ok_or_else(|| anyhow!("No matching bracket"))?
+            }
         };
+        let len = self.buffer.len();
+        self.view.get_mut().move_cursor(line, len);
+        Ok(())
+    }
 
-        (slice.to_vec(), self.nulled)
+    pub fn toggle_selection(&mut self) {
+        if !self.nulled {
+            self.view.get_mut().toggle_selection();
+        }
     }
 
-    // Replace the buffer with the error message and close the file reader.
-    pub fn nullify(&mut self, message: String) {
-        self.nulled = true;
-        self.buffer = vec![message];
-        let _ = self.reader.take();
-        self.view = RefCell::new([0, 1]);
-        self.is_tail = false;
+    pub fn clear_selection(&mut self) -> bool {
+        self.view.get_mut().clear_selection()
+    }
+
+    /// The selected lines, or `count` lines from the cursor line without a
+    /// selection, as they are in the file. The selection is dropped.
+    pub fn yank(&mut self, count: usize) -> Option<(String, usize)> {
+        if self.nulled || self.buffer.is_empty() {
+            return None;
+        }
+        let view = self.view.get_mut();
+        let (start, end) = view.selection().unwrap_or_else(|| {
+            let cursor = view.cursor();
+            (cursor, cursor + count.max(1) - 1)
+        });
+        view.clear_selection();
+        let end = end.min(self.buffer.len() - 1);
+        let text = self.buffer[start..=end]
+            .iter()
+            .map(|l| format!("{l}\n"))
+            .collect();
+        Some((text, end - start + 1))
+    }
+
+    pub fn set_mark(&mut self, name: char) {
+        self.view.get_mut().set_mark(name);
     }
 
-    pub fn next(&mut self) {
+    pub fn jump_to_mark(&mut self, name: char) -> anyhow::Result<()> {
+        let Some(line) = self.view.get_mut().mark(name) else {
+            return Err(anyhow!("Mark '{name}' is not set"));
+        };
+        self.goto(line + 1)
+    }
+
+    pub fn animate(&mut self, smooth: bool) {
+        self.view.get_mut().animate(smooth);
+    }
+
+    pub fn anchor_cursor(&mut self, anchor: Anchor) {
         let len = self.buffer.len();
-        let (start, end) = (self.view.borrow()[0], self.view.borrow()[1]);
-        if end < len {
-            let mut view = self.view.borrow_mut();
-            view[0] = start.saturating_add(1);
-            view[1] = end.saturating_add(1);
+        self.view.get_mut().anchor_cursor(anchor, len);
+    }
+
+    pub fn toggle_fold(&mut self) {
+        // Sections of a configuration file fold as well.
+        let nesting = match has_extension(&self.path, &["toml", "ini", "conf", "cfg"]) {
+            true => Nesting::Sections,
+            false => Nesting::Indent,
+        };
+        self.view.get_mut().toggle_fold(&self.buffer, nesting);
+    }
+
+    pub fn open_folds(&mut self) {
+        self.view.get_mut().open_folds();
+    }
+
+    /// Number of lines read from the file so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Number of lines in the whole file, even those not read yet, or rows
+    /// of its hex dump. A tailed file counts the lines appended since as
+    /// well.
+    pub fn line_count(&self) -> Option<usize> {
+        if self.hex {
+            let size = self.dump_size()? as usize;
+            return Some(size.div_ceil(hex::ROW_BYTES));
         }
+        self.lines.map(|lines| lines.max(self.buffer.len()))
     }
 
-    pub fn prev(&mut self) {
-        let (start, end) = (self.view.borrow()[0], self.view.borrow()[1]);
-        if start > 0 {
-            let mut view = self.view.borrow_mut();
-            view[0] = start.saturating_sub(1);
-            view[1] = end.saturating_sub(1);
+    /// The image the file holds, while it's shown described rather than as
+    /// its bytes.
+    pub fn image(&self) -> Option<&ImageInfo> {
+        self.image.as_ref().filter(|_| self.rendered && !self.hex)
+    }
+
+    /// What the line at `index` changes, if the file is a diff.
+    pub fn change(&self, index: usize) -> Option<Change> {
+        let line = self.buffer.get(index).filter(|_| self.diff && !self.hex)?;
+        let mut hunks = self.hunks.borrow_mut();
+        while hunks.len() <= index {
+            let left = hunks.last().copied().unwrap_or_default();
+            let left = match hunks.len() {
+                0 => left,
+                len => left.after(&self.buffer[len - 1]),
+            };
+            hunks.push(left);
+        }
+        diff::change(line, hunks[index].is_open())
+    }
+
+    /// Whether the file is a log, its lines colored by the log rules.
+    #[inline]
+    pub fn is_log(&self) -> bool {
+        self.log && !self.hex
+    }
+
+    /// Whether the lines of the file end with a carriage return and a line
+    /// feed.
+    #[inline]
+    pub fn is_crlf(&self) -> bool {
+        self.crlf
+    }
+
+    /// Whether the file isn't text, as told by its first bytes.
+    pub fn is_binary(&self) -> bool {
+        self.encoding() == Some(Encoding::Binary)
+    }
+
+    #[inline]
+    pub fn is_hex(&self) -> bool {
+        self.hex
+    }
+
+    /// Switch between the hex dump of the file and its text, each keeping
+    /// where it was scrolled to.
+    pub fn toggle_hex(&mut self) -> anyhow::Result<()> {
+        let view = self.other_view.take().unwrap_or_default();
+        self.other_view = Some(mem::replace(self.view.get_mut(), view));
+        self.hex = !self.hex;
+        self.reload()?;
+        Ok(())
+    }
+
+    /// Whether the file is shown rendered rather than as its raw text.
+    pub fn is_rendered(&self) -> bool {
+        self.rendered
+            && (self.json
+                || self.notebook
+                || self.html
+                || self.man
+                || self.pdf
+                || self.archive.is_some()
+                || self.ansi
+                || self.image.is_some()
+                || self.markdown.borrow().is_some()
+                || self.table.borrow().is_some())
+    }
+
+    /// Whether the file is a PDF one shown as the text of its pages.
+    pub fn is_pdf(&self) -> bool {
+        self.pdf && self.rendered
+    }
+
+    /// Whether the file is an archive shown as the list of its members.
+    pub fn is_archive(&self) -> bool {
+        self.archive.is_some() && self.rendered
+    }
+
+    /// The member of the archive listed at the 0-based line `index`.
+    pub fn member_at(&self, index: usize) -> Option<&Member> {
+        self.members.get(index).filter(|_| self.is_archive())
+    }
+
+    /// Whether the file is shown as a table.
+    pub fn is_table(&self) -> bool {
+        self.rendered && !self.hex && self.table.borrow().is_some()
+    }
+
+    /// Switch between the rendered file and its raw text. Returns false if
+    /// there's no rendered view of the file.
+    pub fn toggle_rendered(&mut self) -> anyhow::Result<bool> {
+        if self.image.is_some() || self.pdf || self.html || self.archive.is_some() {
+            self.rendered = !self.rendered;
+            self.reload()?;
+            return Ok(true);
         }
+        if self.json || self.notebook || self.man {
+            // The lines themselves change, so the file is read anew.
+            let pretty = !self.rendered;
+            self.rendered = pretty;
+            self.reload()?;
+            if pretty && !self.rendered {
+                let what = if self.man {
+                    "a manual page"
+                } else {
+                    "valid JSON"
+                };
+                return Err(anyhow!("{} isn't {what}", self.name));
+            }
+            return Ok(true);
+        }
+        if !self.ansi && self.markdown.get_mut().is_none() && self.table.get_mut().is_none() {
+            return Ok(false);
+        }
+        self.rendered = !self.rendered;
+        self.view_update = true;
+        Ok(true)
+    }
+
+    /// The line at `index` as rendered, along with how its parts are shown,
+    /// if the file is shown rendered.
+    pub fn rendered_line(&self, index: usize) -> Option<Rendered> {
+        // The offsets and the text of a hex dump stand apart from the bytes.
+        if self.hex {
+            let line = self.buffer.get(index)?;
+            let len = line.chars().count();
+            let marks = vec![
+                (0..hex::OFFSET_WIDTH, Mark::Decoration),
+                (hex::text_start().min(len)..len, Mark::Code),
+            ];
+            return Some((line.clone(), marks));
+        }
+        if !self.rendered {
+            return None;
+        }
+        if self.notebook {
+            return self.cell_line(index);
+        }
+        if self.html || self.man {
+            let line = self.buffer.get(index)?;
+            return Some((line.clone(), self.text_marks.get(index)?.clone()));
+        }
+        // The sizes of the members stand apart from their names, not so the
+        // notes after them.
+        if self.archive.is_some() {
+            let line = self.buffer.get(index)?;
+            let len = line.chars().count();
+            let marks = match index < self.members.len() {
+                true => vec![(0..archive::SIZE_WIDTH.min(len), Mark::Decoration)],
+                false => Vec::new(),
+            };
+            return Some((line.clone(), marks));
+        }
+        // The text of the pages stays as it is, their headers set apart.
+        if self.pdf {
+            let line = self.buffer.get(index)?;
+            let len = line.chars().count();
+            return self
+                .pages
+                .binary_search(&index)
+                .ok()
+                .map(|_| (line.clone(), vec![(0..len, Mark::Decoration)]));
+        }
+        if self.ansi {
+            return Some(ansi::render(self.buffer.get(index)?));
+        }
+        if let Some(table) = self.table.borrow_mut().as_mut() {
+            return Some(table.render(&self.buffer, index));
+        }
+        let mut markdown = self.markdown.borrow_mut();
+        Some(markdown.as_mut()?.render(&self.buffer, index))
+    }
+
+    // A line of the cells of a notebook as rendered. Code is left as it is,
+    // to be colored by the language of the notebook.
+    fn cell_line(&self, index: usize) -> Option<Rendered> {
+        let line = self.buffer.get(index)?;
+        let len = line.chars().count();
+        match self.parts.get(index)? {
+            Part::Header | Part::Output => Some((line.clone(), vec![(0..len, Mark::Decoration)])),
+            Part::Error => {
+                let error = Style::default().fg(Color::LightRed);
+                Some((line.clone(), vec![(0..len, Mark::Styled(error))]))
+            }
+            Part::Markdown => Some(
+                self.markdown
+                    .borrow_mut()
+                    .as_mut()?
+                    .render(&self.buffer, index),
+            ),
+            Part::Code => None,
+        }
+    }
+
+    /// The syntax tokens of the line at `index`, none if the language of
+    /// the file isn't known.
+    #[cfg(feature = "syntax")]
+    pub fn tokens(&self, index: usize) -> Vec<(Range<usize>, Token)> {
+        if self.hex {
+            return Vec::new();
+        }
+        match self.highlighter.borrow_mut().as_mut() {
+            Some(highlighter) => highlighter.tokens(&self.buffer, index).to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// How the file is compressed and its size on disk, if it's decompressed
+    /// as it's read.
+    pub fn compression(&self) -> Option<(Compression, u64)> {
+        Some((self.compression?, self.stamp?.0))
+    }
+
+    /// Whether the buffer holds an error message instead of the file.
+    #[inline]
+    pub fn is_nulled(&self) -> bool {
+        self.nulled
+    }
+
+    /// Why the file couldn't be read, if it couldn't.
+    pub fn error(&self) -> Option<&str> {
+        self.buffer
+            .first()
+            .filter(|_| self.nulled)
+            .map(String::as_str)
+    }
+
+    #[inline]
+    pub fn lines(&self) -> &[String] {
+        &self.buffer
+    }
+
+    #[inline]
+    pub fn view(&self) -> Ref<'_, ViewState> {
+        self.view.borrow()
+    }
+
+    /// Trade the view of the file for `view`, as when another split shows
+    /// the file.
+    pub fn swap_view(&self, view: &mut ViewState) {
+        std::mem::swap(&mut *self.view.borrow_mut(), view);
     }
 
     #[inline]
@@ -222,3 +1877,8 @@ impl FileBuf {
         &self.name
     }
 }
+
+// Strip the line ending. Tabs are kept, to be expanded as they're drawn.
+fn clean_line(line: &str) -> String {
+    line.trim_end_matches(['\n', '\r']).replace('\r', "")
+}
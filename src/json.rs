@@ -0,0 +1,350 @@
+use anyhow::anyhow;
+
+/// Spaces every level of a pretty-printed document is indented by.
+const INDENT: usize = 2;
+/// Arrays and objects nested into each other at most, deeper documents
+/// being shown as they're written rather than running out of stack.
+const MAX_DEPTH: usize = 256;
+
+/// The JSON `text` pretty-printed, one value or key per line and nested
+/// ones indented, so that they fold along with their indentation. Fails on
+/// brackets that don't match, a string left open or nesting deeper than
+/// `MAX_DEPTH`.
+pub fn pretty(text: &str) -> anyhow::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    // The brackets open so far, which tell the indentation.
+    let mut open = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    let indent = |depth: usize| " ".repeat(depth * INDENT);
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '"' => {
+                let mut escaped = false;
+                line.push('"');
+                loop {
+                    let Some((_, c)) = chars.next() else {
+                        return Err(anyhow!("Unterminated string starting at byte {at}"));
+                    };
+                    line.push(c);
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            '{' | '[' => {
+                let close = if c == '{' { '}' } else { ']' };
+                // Empty ones stay on a single line.
+                while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+                    chars.next();
+                }
+                if chars.peek().is_some_and(|&(_, next)| next == close) {
+                    chars.next();
+                    line.push(c);
+                    line.push(close);
+                    continue;
+                }
+                if open.len() == MAX_DEPTH {
+                    return Err(anyhow!("Nested deeper than {MAX_DEPTH} levels"));
+                }
+                line.push(c);
+                lines.push(std::mem::take(&mut line));
+                open.push(close);
+                line = indent(open.len());
+            }
+            '}' | ']' => {
+                if open.pop() != Some(c) {
+                    return Err(anyhow!("Unexpected {c} at byte {at}"));
+                }
+                if !line.trim().is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line = indent(open.len());
+                line.push(c);
+            }
+            ',' => {
+                line.push(',');
+                lines.push(std::mem::take(&mut line));
+                line = indent(open.len());
+            }
+            ':' => line.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => line.push(c),
+        }
+    }
+    if let Some(close) = open.last() {
+        return Err(anyhow!("Missing {close} at the end"));
+    }
+    if !line.trim().is_empty() {
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// A JSON value, its numbers kept as they're written.
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    /// The members in the order of the document.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Value] {
+        match self {
+            Value::Array(values) => values,
+            _ => &[],
+        }
+    }
+
+    /// A string, or an array of strings joined, as notebooks keep their
+    /// multiline text.
+    pub fn text(&self) -> String {
+        match self {
+            Value::String(s) => s.clone(),
+            Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// The JSON document `text`.
+pub fn parse(text: &str) -> anyhow::Result<Value> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        at: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(anyhow!("Unexpected {c} after the document")),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+    // Values the one being parsed is nested in.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn next(&mut self) -> anyhow::Result<char> {
+        let c = self
+            .peek()
+            .ok_or_else(|| anyhow!("Unexpected end of the document"))?;
+        self.at += 1;
+        Ok(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> anyhow::Result<()> {
+        for expected in word.chars() {
+            if self.next()? != expected {
+                return Err(anyhow!("Expected {word} at character {}", self.at));
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&mut self) -> anyhow::Result<Value> {
+        if self.depth == MAX_DEPTH {
+            return Err(anyhow!("Nested deeper than {MAX_DEPTH} levels"));
+        }
+        self.depth += 1;
+        let value = self.nested();
+        self.depth -= 1;
+        value
+    }
+
+    // A value at the current depth.
+    fn nested(&mut self) -> anyhow::Result<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.at += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.at += 1;
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    self.expect("\"")?;
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    members.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.next()? {
+                        ',' => continue,
+                        '}' => return Ok(Value::Object(members)),
+                        c => return Err(anyhow!("Unexpected {c} at character {}", self.at)),
+                    }
+                }
+            }
+            Some('[') => {
+                self.at += 1;
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.at += 1;
+                    return Ok(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.next()? {
+                        ',' => continue,
+                        ']' => return Ok(Value::Array(values)),
+                        c => return Err(anyhow!("Unexpected {c} at character {}", self.at)),
+                    }
+                }
+            }
+            Some('"') => {
+                self.at += 1;
+                Ok(Value::String(self.string()?))
+            }
+            Some('t') => self.expect("true").map(|_| Value::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Value::Bool(false)),
+            Some('n') => self.expect("null").map(|_| Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.at;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+                {
+                    self.at += 1;
+                }
+                Ok(Value::Number(self.chars[start..self.at].iter().collect()))
+            }
+            Some(c) => Err(anyhow!("Unexpected {c} at character {}", self.at)),
+            None => Err(anyhow!("Unexpected end of the document")),
+        }
+    }
+
+    // The rest of a string whose opening quote was gone past.
+    fn string(&mut self) -> anyhow::Result<String> {
+        let mut string = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Ok(string),
+                '\\' => match self.next()? {
+                    'n' => string.push('\n'),
+                    't' => string.push('\t'),
+                    'r' => string.push('\r'),
+                    'b' => string.push('\u{8}'),
+                    'f' => string.push('\u{c}'),
+                    'u' => {
+                        let mut code = self.code_unit()?;
+                        // A surrogate pair stands for a single character.
+                        if (0xD800..0xDC00).contains(&code)
+                            && self.chars[self.at..].starts_with(&['\\', 'u'])
+                        {
+                            self.at += 2;
+                            let low = self.code_unit()?;
+                            code = 0x10000
+                                + ((code - 0xD800) << 10)
+                                + (low.wrapping_sub(0xDC00) & 0x3FF);
+                        }
+                        string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    c => string.push(c),
+                },
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn code_unit(&mut self) -> anyhow::Result<u32> {
+        let digits = (0..4)
+            .map(|_| self.next())
+            .collect::<anyhow::Result<String>>()?;
+        u32::from_str_radix(&digits, 16).map_err(|_| anyhow!("Bad escape \\u{digits}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"{"cells": [{"source": ["a\n", "b"]}], "n": -1.5e3, "ok": true}"#;
+
+    #[test]
+    fn parses_a_document() {
+        let doc = parse(DOC).unwrap();
+        let cells = doc.get("cells").unwrap().as_array();
+        assert_eq!(cells.len(), 1);
+        assert_eq!(cells[0].get("source").unwrap().text(), "a\nb");
+        assert!(matches!(doc.get("n"), Some(Value::Number(n)) if n == "-1.5e3"));
+        assert!(matches!(doc.get("ok"), Some(Value::Bool(true))));
+    }
+
+    #[test]
+    fn pretty_prints_a_document() {
+        let lines = pretty(r#"{"a": [1, 2], "b": {}}"#).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "{",
+                "  \"a\": [",
+                "    1,",
+                "    2",
+                "  ],",
+                "  \"b\": {}",
+                "}"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_a_truncated_document() {
+        for len in [1, 10, 30, DOC.len() - 1] {
+            assert!(parse(&DOC[..len]).is_err(), "cut at {len}");
+        }
+        assert!(pretty(r#"{"a": "open"#).is_err());
+        assert!(pretty(r#"{"a": [1"#).is_err());
+    }
+
+    #[test]
+    fn rejects_a_corrupt_document() {
+        assert!(parse(r#"{"a" 1}"#).is_err());
+        assert!(parse(r#"[1, 2] 3"#).is_err());
+        assert!(pretty(r#"{"a": [1}]"#).is_err());
+        let deep = "[".repeat(MAX_DEPTH + 1) + "1" + &"]".repeat(MAX_DEPTH + 1);
+        assert!(parse(&deep).is_err());
+        assert!(pretty(&deep).is_err());
+    }
+}
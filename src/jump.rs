@@ -0,0 +1,61 @@
+use std::path::Path;
+
+/// Positions kept at most, the oldest ones are dropped first.
+const MAX_JUMPS: usize = 100;
+
+/// A position jumped away from.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Jump {
+    pub path: Box<Path>,
+    /// 1-based line number.
+    pub line: usize,
+}
+
+/// Positions visited by goto, search and bookmark jumps, to be walked back
+/// and forth through.
+#[derive(Default)]
+pub struct JumpList {
+    jumps: Vec<Jump>,
+    // Position in `jumps` while walking through them, past their end if not.
+    index: usize,
+}
+
+impl JumpList {
+    #[inline]
+    pub fn new() -> Self {
+        JumpList::default()
+    }
+
+    /// Remember `jump` as the newest position, forgetting the ones walked
+    /// back from.
+    pub fn record(&mut self, jump: Jump) {
+        self.jumps.truncate(self.index);
+        if self.jumps.last() != Some(&jump) {
+            self.jumps.push(jump);
+        }
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.index = self.jumps.len();
+    }
+
+    /// The position before the current one, which is `current` when not
+    /// walking through the jumps yet.
+    pub fn back(&mut self, current: Jump) -> Option<Jump> {
+        if self.index == self.jumps.len() {
+            self.record(current);
+            self.index = self.jumps.len() - 1;
+        }
+        self.index = self.index.checked_sub(1)?;
+        Some(self.jumps[self.index].clone())
+    }
+
+    /// The position after the current one, if walked back from it before.
+    pub fn forward(&mut self) -> Option<Jump> {
+        if self.index + 1 >= self.jumps.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.jumps[self.index].clone())
+    }
+}
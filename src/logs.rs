@@ -0,0 +1,143 @@
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+use serde::Deserialize;
+
+/// The rules coloring every log file, ahead of those of the configuration
+/// file: the levels of the lines, from the worst, timestamps and addresses.
+const BUILT_IN: &[(&str, Color, bool)] = &[
+    (
+        r"(?i)\b(fatal|critical|crit|panic|error|err)\b",
+        Color::LightRed,
+        true,
+    ),
+    (r"(?i)\b(warning|warn)\b", Color::Yellow, true),
+    (r"(?i)\b(info|notice)\b", Color::LightGreen, false),
+    (r"(?i)\b(debug|trace)\b", Color::DarkGray, false),
+    (
+        r"\b\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(:\d{2}([.,]\d+)?)?(Z|[+-]\d{2}:?\d{2})?",
+        Color::Cyan,
+        false,
+    ),
+    (
+        r"\b(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)\s+\d{1,2}\s+\d{2}:\d{2}:\d{2}\b",
+        Color::Cyan,
+        false,
+    ),
+    (r"\b\d{2}:\d{2}:\d{2}([.,]\d+)?\b", Color::Cyan, false),
+    (
+        r"\b(\d{1,3}\.){3}\d{1,3}(:\d+)?\b",
+        Color::LightMagenta,
+        false,
+    ),
+    (
+        r"\b([0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}\b",
+        Color::LightMagenta,
+        false,
+    ),
+];
+
+/// A `[[highlights]]` table of the configuration file, the parts of the
+/// lines of log files matching `pattern` taking its style. Colors are
+/// names such as `red`, indexes such as `208` or hex such as `#ff8800`.
+#[derive(Clone, Deserialize)]
+pub struct HighlightRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub fg: Option<String>,
+    #[serde(default)]
+    pub bg: Option<String>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub underline: bool,
+}
+
+impl HighlightRule {
+    fn style(&self) -> anyhow::Result<Style> {
+        let color = |name: &Option<String>| {
+            name.as_deref()
+                .map(|name| {
+                    Color::from_str(name).map_err(|_| anyhow::anyhow!("{name} isn't a color"))
+                })
+                .transpose()
+        };
+        let mut style = Style::default();
+        if let Some(fg) = color(&self.fg)? {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = color(&self.bg)? {
+            style = style.bg(bg);
+        }
+        let modifiers = [
+            (self.bold, Modifier::BOLD),
+            (self.italic, Modifier::ITALIC),
+            (self.underline, Modifier::UNDERLINED),
+        ];
+        for (on, modifier) in modifiers {
+            if on {
+                style = style.add_modifier(modifier);
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// Colors the lines of log files by the rules of the configuration file,
+/// then by the built-in ones.
+pub struct LogRules {
+    rules: Vec<(Regex, Style)>,
+}
+
+impl LogRules {
+    /// The built-in rules along with `highlights`, those that don't make
+    /// sense being left out.
+    pub fn new(highlights: &[HighlightRule]) -> Self {
+        let configured = highlights.iter().filter_map(|rule| {
+            let compiled = Regex::new(&rule.pattern)
+                .map_err(anyhow::Error::from)
+                .and_then(|regex| Ok((regex, rule.style()?)));
+            compiled
+                .map_err(|err| log::trace!("Skipping the highlight {}: {err}", rule.pattern))
+                .ok()
+        });
+        let built_in = BUILT_IN.iter().filter_map(|&(pattern, color, bold)| {
+            let style = match bold {
+                true => Style::default().fg(color).add_modifier(Modifier::BOLD),
+                false => Style::default().fg(color),
+            };
+            Some((Regex::new(pattern).ok()?, style))
+        });
+        LogRules {
+            rules: configured.chain(built_in).collect(),
+        }
+    }
+
+    /// The parts of `line` the rules match, in characters, in the order of
+    /// the rules. Their styles go over `style`.
+    pub fn matches(&self, line: &str, style: Style) -> Vec<(Range<usize>, Style)> {
+        let chars = |byte: usize| line[..byte].chars().count();
+        self.rules
+            .iter()
+            .flat_map(|(regex, rule)| {
+                regex
+                    .find_iter(line)
+                    .map(move |m| (chars(m.start())..chars(m.end()), style.patch(*rule)))
+            })
+            .collect()
+    }
+}
+
+/// Whether the file at `path` is a log, such as `app.log` or the rotated
+/// `app.log.1`.
+pub fn is_log(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_ascii_lowercase)
+        .is_some_and(|name| name.ends_with(".log") || name.contains(".log."))
+}
@@ -1,8 +1,64 @@
+pub mod ansi;
+pub mod archive;
+pub mod autoscroll;
+pub mod bookmark;
+pub mod bracket;
+pub mod caret;
+pub mod clipboard;
+pub mod compare;
+pub mod compression;
+pub mod config;
+pub mod confirm;
 pub mod cursor;
+pub mod diff;
+pub mod encoding;
+pub mod fold;
+pub mod fuzzy;
+pub mod gitignore;
+pub mod glob;
+pub mod grep;
+pub mod group;
+pub mod hex;
+pub mod history;
+pub mod html;
+pub mod icon;
+pub mod image;
+pub mod inflate;
 pub mod input;
+pub mod invisible;
 pub mod io;
+pub mod json;
+pub mod jump;
+pub mod logs;
+pub mod man;
+pub mod markdown;
+pub mod mouse;
+pub mod notebook;
+pub mod outline;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod picker;
+pub mod quickfix;
 pub mod resource;
+pub mod search;
+pub mod session;
+pub mod split;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
+pub mod substitute;
+#[cfg(feature = "syntax")]
+pub mod syntax;
+pub mod tab;
+pub mod table;
+pub mod toast;
+pub mod tree;
 mod ui;
+pub mod view;
+pub mod walk;
+pub mod word;
+pub mod xz;
+pub mod zstd;
 
 use crossterm::{event::*, execute, terminal::*};
 use ratatui::prelude::*;
@@ -41,8 +97,8 @@ impl App {
             EnableMouseCapture
         )?;
 
-        let mut resource = Resource::new()?;
         create_logger()?;
+        let mut resource = Resource::new()?;
 
         loop {
             if key_listener(&mut resource)? {
@@ -52,8 +108,14 @@ impl App {
 
             trigger_view_update(&mut resource);
             self.terminal.draw(|f| ui::ui(f, &mut resource))?;
+            // Images go over the text drawn, straight to the terminal.
+            if let Err(err) = resource.preview_mut().flush(self.terminal.backend_mut()) {
+                log::trace!("Couldn't draw the image due to: {err}");
+            }
             detrigger_view_update(&mut resource);
         }
+        // Files opened or closed since the order was last saved.
+        save_session(&resource);
 
         Ok(())
     }
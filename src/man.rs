@@ -0,0 +1,2193 @@
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::anyhow;
+use ratatui::style::{Modifier, Style};
+
+use crate::compression::{self, Compression};
+use crate::html::Page;
+use crate::markdown::{Mark, Rendered};
+
+/// Columns the text is filled to, as `man` fills it in a terminal of 80.
+const WIDTH: usize = 78;
+/// How far the text of a section is indented, and tagged paragraphs past
+/// their tags.
+const INDENT: usize = 7;
+/// How far subsection headings are indented.
+const SUBSECTION_INDENT: usize = 3;
+/// Width of a tab in text laid out as it is.
+const TAB_WIDTH: usize = 8;
+/// Sections looked into for a page, in the order `man` looks into them.
+const SECTIONS: [&str; 9] = ["1", "8", "3", "2", "5", "4", "9", "6", "7"];
+/// Where pages are looked for if `MANPATH` doesn't tell.
+const MANPATH: [&str; 4] = [
+    "/usr/local/share/man",
+    "/usr/share/man",
+    "/usr/local/man",
+    "/usr/man",
+];
+/// How many `.so` stubs are followed to the page they stand for.
+const MAX_LINKS: usize = 8;
+/// How deep macros may call each other, and strings hold each other.
+const MAX_DEPTH: usize = 16;
+/// A space text isn't broken at, as `\ ` and `\~` are.
+const NBSP: char = '\u{a0}';
+
+// Special characters, as in `\(em` and `\[bu]`, beyond those given as
+// their code point.
+const CHARACTERS: [(&str, &str); 88] = [
+    ("em", "—"),
+    ("en", "–"),
+    ("hy", "-"),
+    ("mi", "-"),
+    ("aq", "'"),
+    ("dq", "\""),
+    ("lq", "“"),
+    ("rq", "”"),
+    ("oq", "‘"),
+    ("cq", "’"),
+    ("Bq", "„"),
+    ("bq", "‚"),
+    ("Fo", "«"),
+    ("Fc", "»"),
+    ("fo", "‹"),
+    ("fc", "›"),
+    ("bu", "•"),
+    ("ci", "○"),
+    ("sq", "□"),
+    ("co", "©"),
+    ("rg", "®"),
+    ("tm", "™"),
+    ("de", "°"),
+    ("ga", "`"),
+    ("ha", "^"),
+    ("ti", "~"),
+    ("ba", "|"),
+    ("br", "│"),
+    ("rs", "\\"),
+    ("sl", "/"),
+    ("pl", "+"),
+    ("eq", "="),
+    ("mu", "×"),
+    ("di", "÷"),
+    ("+-", "±"),
+    ("<=", "≤"),
+    (">=", "≥"),
+    ("!=", "≠"),
+    ("==", "≡"),
+    ("~=", "≅"),
+    ("ap", "∼"),
+    ("->", "→"),
+    ("<-", "←"),
+    ("<>", "↔"),
+    ("=>", "⇒"),
+    ("ua", "↑"),
+    ("da", "↓"),
+    ("lA", "⇐"),
+    ("rA", "⇒"),
+    ("dg", "†"),
+    ("dd", "‡"),
+    ("ps", "¶"),
+    ("sc", "§"),
+    ("ct", "¢"),
+    ("Po", "£"),
+    ("Ye", "¥"),
+    ("Eu", "€"),
+    ("eu", "€"),
+    ("fm", "′"),
+    ("sd", "″"),
+    ("aa", "´"),
+    ("12", "½"),
+    ("14", "¼"),
+    ("34", "¾"),
+    ("if", "∞"),
+    ("lB", "["),
+    ("rB", "]"),
+    ("lC", "{"),
+    ("rC", "}"),
+    ("la", "⟨"),
+    ("ra", "⟩"),
+    ("OK", "✓"),
+    (":a", "ä"),
+    (":o", "ö"),
+    (":u", "ü"),
+    (":A", "Ä"),
+    (":O", "Ö"),
+    (":U", "Ü"),
+    ("ss", "ß"),
+    ("'e", "é"),
+    ("`e", "è"),
+    ("^e", "ê"),
+    ("'a", "á"),
+    ("`a", "à"),
+    (",c", "ç"),
+    ("~n", "ñ"),
+    ("*m", "μ"),
+    ("*p", "π"),
+];
+
+// Strings pages use without defining them.
+const STRINGS: [(&str, &str); 17] = [
+    ("lq", "“"),
+    ("rq", "”"),
+    ("R", "®"),
+    ("Tm", "™"),
+    ("Lq", "“"),
+    ("Rq", "”"),
+    ("q", "\""),
+    ("Lt", "<"),
+    ("Gt", ">"),
+    ("Le", "≤"),
+    ("Ge", "≥"),
+    ("Ne", "≠"),
+    ("Am", "&"),
+    ("Ba", "|"),
+    ("Pi", "π"),
+    ("Pm", "±"),
+    ("If", "∞"),
+];
+
+// What the sections of mdoc pages are called, in their headers.
+const MANUALS: [&str; 9] = [
+    "General Commands Manual",
+    "System Calls Manual",
+    "Library Functions Manual",
+    "Device Drivers Manual",
+    "File Formats Manual",
+    "Games Manual",
+    "Miscellaneous Information Manual",
+    "System Manager's Manual",
+    "Kernel Developer's Manual",
+];
+
+// The mdoc macros that can be called from the arguments of others, as
+// `Ar` is in `.Op Fl f Ar file`.
+const CALLABLE: [&str; 73] = [
+    "Ac", "Ad", "An", "Ao", "Ap", "Aq", "Ar", "At", "Bc", "Bo", "Bq", "Brc", "Bro", "Brq", "Bsx",
+    "Bx", "Cd", "Cm", "Dc", "Do", "Dq", "Dv", "Dx", "Ec", "Em", "Eo", "Er", "Ev", "Fa", "Fc", "Fl",
+    "Fn", "Fo", "Ft", "Fx", "Ic", "In", "Li", "Lk", "Ms", "Mt", "Nm", "No", "Ns", "Nx", "Oc", "Oo",
+    "Op", "Ox", "Pa", "Pc", "Pf", "Po", "Pq", "Qc", "Ql", "Qo", "Qq", "Sc", "So", "Sq", "St", "Sx",
+    "Sy", "Ta", "Tn", "Ux", "Va", "Vt", "Xc", "Xo", "Xr", "Lb",
+];
+
+// The man macros pages may ask about with `.if d`, defining their own
+// otherwise.
+const MACROS: [&str; 14] = [
+    "UR", "UE", "MT", "ME", "SY", "YS", "OP", "TQ", "EX", "EE", "SB", "SM", "RS", "RE",
+];
+
+/// Whether the file at `path` is the roff source of a manual page, told by
+/// its extension being a section, such as `ls.1` or `Digest::SHA.3pm`.
+pub fn is_man(path: &Path) -> bool {
+    let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    let mut chars = extension.chars();
+    match chars.next() {
+        Some('1'..='9') => extension.len() <= 6 && chars.all(|c| c.is_ascii_alphabetic()),
+        _ => extension.eq_ignore_ascii_case("man"),
+    }
+}
+
+/// The topic of a page asked for by it rather than by its path, as
+/// `man:printf` or `man:printf(3)`.
+pub fn topic(name: &str) -> Option<&str> {
+    name.strip_prefix("man:").filter(|topic| !topic.is_empty())
+}
+
+/// The file of the page `man` would show for `topic`, following the stubs
+/// standing for other pages.
+pub fn find(topic: &str) -> anyhow::Result<PathBuf> {
+    let (name, section) = match topic.strip_suffix(')').and_then(|t| t.split_once('(')) {
+        Some((name, section)) => (name, Some(section)),
+        None => (topic, None),
+    };
+    let mut path = locate(name, section).ok_or_else(|| anyhow!("No manual entry for {topic}"))?;
+    for _ in 0..MAX_LINKS {
+        let Some(target) = stub(&path) else {
+            break;
+        };
+        // What a stub names is below the root the pages are kept in.
+        let root = path
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new("/"));
+        let target = root.join(target);
+        path = ["", ".gz", ".zst", ".xz"]
+            .iter()
+            .map(|suffix| PathBuf::from(format!("{}{suffix}", target.display())))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} stands for {} but it isn't there",
+                    topic,
+                    target.display()
+                )
+            })?;
+    }
+    Ok(path)
+}
+
+// Ask `man` where the page is, looking for it where pages are kept if `man`
+// can't tell.
+fn locate(name: &str, section: Option<&str>) -> Option<PathBuf> {
+    let output = Command::new("man")
+        .arg("-w")
+        .args(section)
+        .arg(name)
+        .stderr(Stdio::null())
+        .output();
+    if let Ok(output) = output {
+        let found = String::from_utf8_lossy(&output.stdout);
+        if let Some(path) = found.lines().next().filter(|_| output.status.success()) {
+            return Some(PathBuf::from(path.trim()));
+        }
+    }
+    let roots: Vec<PathBuf> = match std::env::var("MANPATH") {
+        Ok(paths) if !paths.is_empty() => std::env::split_paths(&paths)
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect(),
+        _ => MANPATH.iter().map(PathBuf::from).collect(),
+    };
+    let sections = match section {
+        Some(section) => vec![section],
+        None => SECTIONS.to_vec(),
+    };
+    for section in sections {
+        let Some(number) = section.get(..1) else {
+            continue;
+        };
+        for root in &roots {
+            let Ok(entries) = fs::read_dir(root.join(format!("man{number}"))) else {
+                continue;
+            };
+            let mut found = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let inner = compression::inner_path(path);
+                    inner.file_stem().is_some_and(|stem| stem == name)
+                        && inner
+                            .extension()
+                            .and_then(|e| e.to_str())
+                            .is_some_and(|e| e.starts_with(section))
+                })
+                .collect::<Vec<_>>();
+            found.sort();
+            if let Some(path) = found.into_iter().next() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+// What the page at `path` stands for if it's a stub of `.so man3/other.3`.
+fn stub(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    let bytes = match Compression::for_path(path) {
+        Some(compression) => compression.decompress(&bytes).ok()?,
+        None => bytes,
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let line = text
+        .lines()
+        .find(|line| !line.trim().is_empty() && !is_comment(line))?;
+    Some(line.strip_prefix(".so")?.trim().to_string())
+}
+
+fn is_comment(line: &str) -> bool {
+    [".\\\"", "'\\\"", ".\\#", "\\\""]
+        .iter()
+        .any(|comment| line.starts_with(comment))
+}
+
+/// Lay the page whose roff source is `source` out as `man` would, in the
+/// man or the mdoc macros, its bold and italic text kept.
+pub fn render(source: &str) -> anyhow::Result<Page> {
+    if !source.lines().any(|line| line.starts_with(['.', '\''])) {
+        return Err(anyhow!("It holds no roff requests"));
+    }
+    let mut roff = Roff::new();
+    let mut pending = String::new();
+    for line in source.lines() {
+        // A line ending in an escaped newline goes on with the next.
+        let backslashes = line.len() - line.trim_end_matches('\\').len();
+        if backslashes % 2 == 1 {
+            pending.push_str(&line[..line.len() - 1]);
+            continue;
+        }
+        pending.push_str(line);
+        roff.line(&mem::take(&mut pending));
+    }
+    if !pending.is_empty() {
+        roff.line(&pending);
+    }
+    Ok(roff.finish())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+}
+
+impl Font {
+    fn named(name: &str) -> Self {
+        match name {
+            "B" | "3" | "4" | "BI" | "CB" | "TB" | "HB" => Font::Bold,
+            "I" | "2" | "CI" | "TI" | "HI" => Font::Italic,
+            _ => Font::Regular,
+        }
+    }
+
+    fn mark(self) -> Option<Mark> {
+        match self {
+            Font::Regular => None,
+            Font::Bold => Some(Mark::Bold),
+            // Italics are underlined, as `man` shows them in a terminal.
+            Font::Italic => Some(Mark::Styled(
+                Style::new().add_modifier(Modifier::UNDERLINED),
+            )),
+        }
+    }
+}
+
+// The characters of text and how each is shown.
+type Pieces = Vec<(char, Option<Mark>)>;
+
+// What the next line of text is taken as.
+enum Next {
+    // A heading, given on the line after `.SH`.
+    Heading,
+    // The tag of a paragraph, its text starting at the column.
+    Tag(usize),
+}
+
+// Lines gathered rather than read as they come.
+enum Gather {
+    // Those of a macro being defined, up to the line ending it.
+    Macro(String, String, Vec<String>),
+    // Those left out up to the line ending them.
+    Ignore(String),
+    // Those of a table, up to `.TE`.
+    Table(Vec<String>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ListKind {
+    Bullet,
+    Dash,
+    Enum,
+    Item,
+    Tag,
+    Ohang,
+    Inset,
+    Column,
+}
+
+// A list of the mdoc macros, `.Bl` to `.El`.
+struct List {
+    kind: ListKind,
+    width: usize,
+    // Where its items start, and where the text was before it.
+    start: usize,
+    indent: usize,
+    items: usize,
+    compact: bool,
+}
+
+// Reads the source line by line, laying the text out as it goes.
+struct Roff {
+    page: Vec<Rendered>,
+    line: String,
+    len: usize,
+    marks: Vec<(Range<usize>, Mark)>,
+    // Whether the line is begun, and where its text starts past the
+    // indentation.
+    started: bool,
+    line_start: usize,
+    // The word being put together, whether whitespace went before it, and
+    // whether the next line of text goes on with it, after a `\c`.
+    word: Pieces,
+    space: bool,
+    join: bool,
+    // Whether the next word goes right after the last, without a space, as
+    // after an mdoc `Ns`, whether words are spaced at all, and whether one
+    // went by since they stopped being.
+    nospace: bool,
+    spacing: bool,
+    unspaced: bool,
+    // How many enclosures keeping their words together the text is in.
+    keep: usize,
+    // A blank line is due before the next text, as between paragraphs.
+    blank: bool,
+    // Whether text is filled into lines, rather than laid out as it is.
+    fill: bool,
+    font: Font,
+    previous: Font,
+    // The font of the next line of text only, after `.B` alone.
+    line_font: Option<Font>,
+    heading: Option<Mark>,
+    // The column lines start at, that of the line after `.ti`, the left
+    // margin and the margins `.RS` moved it from.
+    indent: usize,
+    temporary: Option<usize>,
+    margin: usize,
+    margins: Vec<usize>,
+    previous_margin: usize,
+    tag_width: usize,
+    next: Option<Next>,
+    strings: HashMap<String, String>,
+    macros: HashMap<String, Vec<String>>,
+    gather: Option<Gather>,
+    // How deep the braces of a conditional block left out go.
+    skip: usize,
+    // What the conditions of `.ie` were, for the `.el` after.
+    conditions: Vec<bool>,
+    depth: usize,
+    // The header's title and section, the manual, its source and date.
+    title: Option<String>,
+    manual: String,
+    source: String,
+    date: String,
+    // The target of the link the text is in, after `.UR`.
+    link: Option<String>,
+    // The section the text is in, what the mdoc `.Nm` names, the lists and
+    // displays the text is in, the tag of an item going on over its lines and
+    // the arguments of the function being given.
+    section: String,
+    name: Option<String>,
+    synopsis: usize,
+    lists: Vec<List>,
+    displays: Vec<(bool, usize)>,
+    extended: bool,
+    function: Option<usize>,
+}
+
+impl Roff {
+    fn new() -> Self {
+        Roff {
+            page: Vec::new(),
+            line: String::new(),
+            len: 0,
+            marks: Vec::new(),
+            started: false,
+            line_start: 0,
+            word: Vec::new(),
+            space: false,
+            join: false,
+            nospace: false,
+            spacing: true,
+            unspaced: false,
+            keep: 0,
+            blank: false,
+            fill: true,
+            font: Font::Regular,
+            previous: Font::Regular,
+            line_font: None,
+            heading: None,
+            indent: INDENT,
+            temporary: None,
+            margin: INDENT,
+            margins: Vec::new(),
+            previous_margin: INDENT,
+            tag_width: INDENT,
+            next: None,
+            strings: STRINGS
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            macros: HashMap::new(),
+            gather: None,
+            skip: 0,
+            conditions: Vec::new(),
+            depth: 0,
+            title: None,
+            manual: String::new(),
+            source: String::new(),
+            date: String::new(),
+            link: None,
+            section: String::new(),
+            name: None,
+            synopsis: 0,
+            lists: Vec::new(),
+            displays: Vec::new(),
+            extended: false,
+            function: None,
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        if self.skip > 0 {
+            self.skip = (self.skip + line.matches("\\{").count())
+                .saturating_sub(line.matches("\\}").count());
+            return;
+        }
+        if let Some(gather) = self.gather.take() {
+            self.gather(gather, line);
+            return;
+        }
+        let line = strip_comment(line);
+        match control(line) {
+            Some((name, rest)) => self.request(name, rest),
+            None => self.text(line),
+        }
+    }
+
+    fn gather(&mut self, gather: Gather, line: &str) {
+        let ends = |end: &str| {
+            control(line).is_some_and(|(name, _)| name == end)
+                || (end == "." && line.trim_end() == "..")
+        };
+        match gather {
+            Gather::Macro(name, end, body) if ends(&end) => {
+                self.macros.insert(name, body);
+            }
+            Gather::Macro(name, end, mut body) => {
+                body.push(line.to_string());
+                self.gather = Some(Gather::Macro(name, end, body));
+            }
+            Gather::Ignore(end) if ends(&end) => {}
+            Gather::Ignore(end) => self.gather = Some(Gather::Ignore(end)),
+            Gather::Table(lines) if ends("TE") => self.table(lines),
+            Gather::Table(mut lines) => {
+                lines.push(line.to_string());
+                self.gather = Some(Gather::Table(lines));
+            }
+        }
+    }
+
+    fn request(&mut self, name: &str, rest: &str) {
+        let args = arguments(rest);
+        let arg = |i: usize| args.get(i).map_or("", String::as_str);
+        match name {
+            "" => {}
+            // Definitions and conditions.
+            "ds" | "ds1" | "as" | "as1" => {
+                let rest = rest.trim_start();
+                let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                let value = value.trim_start();
+                let value = value.strip_prefix('"').unwrap_or(value);
+                let string = self.strings.entry(key.to_string()).or_default();
+                if name.starts_with("ds") {
+                    string.clear();
+                }
+                string.push_str(value);
+            }
+            "de" | "de1" | "am" => {
+                let body = match name {
+                    "am" => self.macros.remove(arg(0)).unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+                let end = args.get(1).map_or(".", String::as_str);
+                self.gather = Some(Gather::Macro(arg(0).to_string(), end.to_string(), body));
+            }
+            "ig" => {
+                let end = args.first().map_or(".", String::as_str);
+                self.gather = Some(Gather::Ignore(end.to_string()));
+            }
+            "rm" => {
+                for name in &args {
+                    self.macros.remove(name);
+                    self.strings.remove(name);
+                }
+            }
+            "als" => {
+                if let Some(body) = self.macros.get(arg(1)).cloned() {
+                    self.macros.insert(arg(0).to_string(), body);
+                }
+            }
+            "rn" => {
+                if let Some(body) = self.macros.remove(arg(0)) {
+                    self.macros.insert(arg(1).to_string(), body);
+                }
+            }
+            "if" | "ie" => {
+                let (condition, body) = self.condition(rest);
+                if name == "ie" {
+                    self.conditions.push(!condition);
+                }
+                self.body(condition, body);
+            }
+            "el" => {
+                let condition = self.conditions.pop().unwrap_or(false);
+                self.body(condition, rest);
+            }
+            "nop" => self.text(rest.trim_start()),
+            _ if self.macros.contains_key(name) => self.invoke(name, &args),
+            // Headers and headings.
+            "TH" => {
+                let manual = match args.get(4) {
+                    Some(manual) => manual,
+                    None => manual(arg(1)),
+                };
+                self.header(arg(0), arg(1), manual);
+                self.date = self.plain(arg(2));
+                self.source = self.plain(arg(3));
+            }
+            "Dt" => self.header(arg(0), arg(1), manual(arg(1))),
+            "Dd" => self.date = self.plain(&args.join(" ")),
+            "Os" => self.source = self.plain(&args.join(" ")),
+            "SH" | "Sh" => self.heading(1, &args),
+            "SS" | "Ss" => self.heading(2, &args),
+            // Paragraphs.
+            "PP" | "LP" | "P" => self.paragraph(),
+            "Pp" | "Lp" => {
+                self.finish_line();
+                self.blank = true;
+                if self.lists.is_empty() && self.displays.is_empty() {
+                    self.indent = self.margin;
+                }
+            }
+            "TP" | "TQ" => {
+                if name == "TP" {
+                    self.paragraph();
+                    self.tag_width = args.first().and_then(|w| length(w)).unwrap_or(INDENT);
+                } else {
+                    self.finish_line();
+                    self.indent = self.margin;
+                }
+                self.next = Some(Next::Tag(self.margin + self.tag_width));
+            }
+            "IP" => {
+                self.paragraph();
+                self.tag_width = args.get(1).and_then(|w| length(w)).unwrap_or(INDENT);
+                let tag = self.styled(arg(0));
+                self.put(&tag);
+                self.tab_to(self.margin + self.tag_width);
+            }
+            "HP" => self.paragraph(),
+            "RS" => {
+                self.finish_line();
+                self.margins.push(self.margin);
+                self.margin += args.first().and_then(|n| length(n)).unwrap_or(INDENT);
+                self.indent = self.margin;
+            }
+            "RE" => {
+                self.finish_line();
+                self.margin = self.margins.pop().unwrap_or(INDENT);
+                self.indent = self.margin;
+            }
+            // Fonts.
+            "B" | "I" | "SB" | "SM" => {
+                let font = match name {
+                    "B" | "SB" => Font::Bold,
+                    "I" => Font::Italic,
+                    _ => Font::Regular,
+                };
+                if args.is_empty() {
+                    self.line_font = Some(font);
+                    return;
+                }
+                let next = self.next.take();
+                let pieces = self.styled_as(&args.join(" "), font);
+                self.put(&pieces);
+                self.end_input_line();
+                self.close(next);
+            }
+            "BR" | "RB" | "BI" | "IB" | "IR" | "RI" => {
+                let fonts = name.chars().map(|c| Font::named(&c.to_string()));
+                let fonts = fonts.collect::<Vec<_>>();
+                let next = self.next.take();
+                for (i, arg) in args.iter().enumerate() {
+                    let pieces = self.styled_as(arg, fonts[i % 2]);
+                    self.put(&pieces);
+                }
+                self.end_input_line();
+                self.close(next);
+            }
+            "MR" => {
+                let mut pieces = self.styled_as(arg(0), Font::Italic);
+                pieces.extend(self.styled(&format!("({}){}", arg(1), arg(2))));
+                self.put(&pieces);
+                self.end_input_line();
+            }
+            "ft" => self.set_font(args.first().map_or("P", String::as_str)),
+            "ul" | "cu" => self.line_font = Some(Font::Italic),
+            // Breaks and filling.
+            "br" | "ce" => self.finish_line(),
+            "sp" | "bp" => {
+                self.finish_line();
+                self.blank = true;
+            }
+            "nf" | "EX" => {
+                self.finish_line();
+                self.fill = false;
+            }
+            "fi" | "EE" => {
+                self.finish_line();
+                self.fill = true;
+            }
+            "in" => {
+                self.finish_line();
+                let margin = self.margin;
+                self.margin = match args.first() {
+                    Some(n) => offset(margin, n),
+                    None => self.previous_margin,
+                };
+                self.previous_margin = margin;
+                self.indent = self.margin;
+            }
+            "ti" => {
+                self.finish_line();
+                self.temporary = args.first().map(|n| offset(self.indent, n));
+            }
+            // Links and synopses.
+            "UR" | "MT" => self.link = args.first().cloned(),
+            "UE" | "ME" => {
+                if let Some(link) = self.link.take() {
+                    let mut pieces = self.styled(&format!("⟨{link}⟩"));
+                    pieces
+                        .iter_mut()
+                        .for_each(|(_, mark)| *mark = Some(Mark::Link));
+                    self.gap();
+                    self.put(&pieces);
+                    let trailing = self.styled(arg(0));
+                    self.put(&trailing);
+                    self.end_input_line();
+                }
+            }
+            "SY" => {
+                self.finish_line();
+                self.indent = self.margin;
+                let command = self.styled_as(arg(0), Font::Bold);
+                self.put(&command);
+                self.flush_word();
+                self.space = true;
+                self.indent = self.margin + command.len() + 1;
+            }
+            "YS" => {
+                self.finish_line();
+                self.indent = self.margin;
+            }
+            "OP" => {
+                let mut pieces = self.styled("[");
+                pieces.extend(self.styled_as(arg(0), Font::Bold));
+                if args.len() > 1 {
+                    pieces.push((NBSP, None));
+                    pieces.extend(self.styled_as(arg(1), Font::Italic));
+                }
+                pieces.extend(self.styled("]"));
+                self.gap();
+                self.put(&pieces);
+                self.end_input_line();
+            }
+            "so" | "mso" => {
+                self.finish_line();
+                let see = self.styled_as(&format!("See {}", arg(0)), Font::Italic);
+                self.put(&see);
+                self.finish_line();
+            }
+            "TS" => {
+                self.finish_line();
+                self.gather = Some(Gather::Table(Vec::new()));
+            }
+            "EQ" => self.gather = Some(Gather::Ignore("EN".to_string())),
+            "PS" => self.gather = Some(Gather::Ignore("PE".to_string())),
+            _ => self.mdoc(name, &args),
+        }
+    }
+
+    // Handle the macros of mdoc pages, those of other macros being left out.
+    fn mdoc(&mut self, name: &str, args: &[String]) {
+        let synopsis = self.section == "SYNOPSIS";
+        match name {
+            "Nd" => {
+                self.word("—", Font::Regular);
+                self.call_each(args);
+            }
+            "Nm" if synopsis && !self.extended && self.lists.is_empty() => {
+                self.finish_line();
+                self.synopsis_break();
+                self.indent = self.margin;
+                self.call("Nm", args);
+                let name = args
+                    .first()
+                    .filter(|arg| !is_callable(arg))
+                    .or(self.name.as_ref())
+                    .map_or(0, |name| name.chars().count());
+                self.flush_word();
+                self.space = true;
+                self.indent = self.margin + name + 1;
+            }
+            "Ft" | "In" | "Fd" if synopsis => {
+                self.finish_line();
+                if name == "Ft" {
+                    self.synopsis_break();
+                }
+                self.indent = self.margin;
+                self.call(name, args);
+                self.finish_line();
+            }
+            "Fn" | "Fo" if synopsis => {
+                self.finish_line();
+                self.indent = self.margin;
+                self.call(name, args);
+                if name == "Fn" {
+                    self.attach(";");
+                    self.finish_line();
+                }
+            }
+            "Fc" if synopsis => {
+                self.call(name, args);
+                self.attach(";");
+                self.finish_line();
+            }
+            "Bl" => {
+                self.finish_line();
+                let flag = |flag: &str| args.iter().position(|arg| arg == flag);
+                let value = |flag: &str| flag_value(args, flag);
+                let kind = match args.first().map(String::as_str) {
+                    Some("-bullet") => ListKind::Bullet,
+                    Some("-dash" | "-hyphen") => ListKind::Dash,
+                    Some("-enum") => ListKind::Enum,
+                    Some("-tag" | "-hang") => ListKind::Tag,
+                    Some("-ohang") => ListKind::Ohang,
+                    Some("-inset" | "-diag") => ListKind::Inset,
+                    Some("-column") => ListKind::Column,
+                    _ => ListKind::Item,
+                };
+                let width = match (value("-width"), kind) {
+                    (Some(width), _) => mdoc_width(width),
+                    (None, ListKind::Bullet | ListKind::Dash) => 2,
+                    (None, ListKind::Enum) => 4,
+                    (None, ListKind::Tag) => 8,
+                    _ => 0,
+                };
+                let compact = flag("-compact").is_some();
+                let start = self.indent + value("-offset").map_or(0, mdoc_offset);
+                if !compact {
+                    self.blank = true;
+                }
+                self.lists.push(List {
+                    kind,
+                    width,
+                    start,
+                    indent: self.indent,
+                    items: 0,
+                    compact,
+                });
+            }
+            "It" => {
+                self.finish_line();
+                self.extended = false;
+                let Some(list) = self.lists.last_mut() else {
+                    self.call_each(args);
+                    return;
+                };
+                list.items += 1;
+                if !list.compact && list.items > 1 {
+                    self.blank = true;
+                }
+                let (kind, start, width, items) = (list.kind, list.start, list.width, list.items);
+                self.indent = start;
+                match kind {
+                    ListKind::Bullet | ListKind::Dash | ListKind::Enum => {
+                        let bullet = match kind {
+                            ListKind::Bullet => "•".to_string(),
+                            ListKind::Dash => "-".to_string(),
+                            _ => format!("{items}."),
+                        };
+                        self.word(&bullet, Font::Regular);
+                        self.tab_to(start + width);
+                    }
+                    ListKind::Tag => {
+                        self.call_each(args);
+                        self.extended = args.iter().any(|arg| arg == "Xo");
+                        if !self.extended {
+                            self.tab_to(start + width);
+                        }
+                    }
+                    ListKind::Ohang => {
+                        self.call_each(args);
+                        self.finish_line();
+                    }
+                    ListKind::Inset | ListKind::Column => self.call_each(args),
+                    ListKind::Item => {}
+                }
+            }
+            "El" => {
+                self.finish_line();
+                if let Some(list) = self.lists.pop() {
+                    self.indent = list.indent;
+                    self.blank = !list.compact;
+                }
+            }
+            "Bd" => {
+                self.finish_line();
+                if !args.iter().any(|arg| arg == "-compact") {
+                    self.blank = true;
+                }
+                self.displays.push((self.fill, self.indent));
+                if args
+                    .iter()
+                    .any(|arg| arg == "-literal" || arg == "-unfilled")
+                {
+                    self.fill = false;
+                }
+                self.indent += flag_value(args, "-offset").map_or(0, mdoc_offset);
+            }
+            "Ed" => {
+                self.finish_line();
+                if let Some((fill, indent)) = self.displays.pop() {
+                    (self.fill, self.indent) = (fill, indent);
+                }
+            }
+            "D1" | "Dl" => {
+                self.finish_line();
+                let indent = self.indent;
+                self.indent += INDENT - 1;
+                self.call_each(args);
+                self.finish_line();
+                self.indent = indent;
+            }
+            "Bf" => {
+                self.font = match args.first().map(String::as_str) {
+                    Some("-emphasis" | "Em") => Font::Italic,
+                    Some("-symbolic" | "Sy") => Font::Bold,
+                    _ => Font::Regular,
+                };
+            }
+            "Ef" => self.font = Font::Regular,
+            "Sm" => {
+                self.spacing = args.first().is_none_or(|arg| arg != "off");
+                self.unspaced = false;
+            }
+            "Ex" => {
+                let name = self.name.clone().unwrap_or_default();
+                let text =
+                    format!("The {name} utility exits 0 on success, and >0 if an error occurs.");
+                self.call_each(&arguments(&text));
+            }
+            "Rv" => {
+                let function = args.get(1).cloned().unwrap_or_default();
+                let text = format!(
+                    "The {function}() function returns the value 0 if successful; otherwise \
+                     the value -1 is returned and the global variable errno is set to indicate \
+                     the error."
+                );
+                self.call_each(&arguments(&text));
+            }
+            "%A" | "%B" | "%C" | "%D" | "%I" | "%J" | "%N" | "%O" | "%P" | "%Q" | "%R" | "%T"
+            | "%U" | "%V" => {
+                let font = match name {
+                    "%B" | "%J" | "%T" => Font::Italic,
+                    _ => Font::Regular,
+                };
+                self.words(args, font);
+                self.attach(",");
+            }
+            "Rs" => self.finish_line(),
+            "Re" => {
+                self.attach(".");
+                self.finish_line();
+            }
+            _ if is_callable(name) => self.call(name, args),
+            // Other requests and macros don't show.
+            _ => return,
+        }
+        self.end_input_line();
+    }
+
+    // Call the mdoc macros of `args`, the words before the first shown as
+    // they are.
+    fn call_each(&mut self, args: &[String]) {
+        match args.iter().position(|arg| is_callable(arg)) {
+            Some(i) => {
+                self.words(&args[..i], Font::Regular);
+                self.call(&args[i], &args[i + 1..]);
+            }
+            None => self.words(args, Font::Regular),
+        }
+    }
+
+    // Call the mdoc macro `name` with `args`, and those called from them.
+    fn call(&mut self, name: &str, args: &[String]) {
+        let end = args
+            .iter()
+            .position(|arg| is_callable(arg))
+            .unwrap_or(args.len());
+        let (own, rest) = args.split_at(end);
+        let enclosure = match name {
+            "Op" => Some(("[", "]")),
+            "Aq" => Some(("⟨", "⟩")),
+            "Bq" => Some(("[", "]")),
+            "Brq" => Some(("{", "}")),
+            "Dq" => Some(("“", "”")),
+            "Pq" => Some(("(", ")")),
+            "Qq" => Some(("\"", "\"")),
+            "Sq" | "Ql" => Some(("‘", "’")),
+            _ => None,
+        };
+        // Enclosures hold everything after them, but the punctuation ending
+        // the line.
+        if let Some((open, close)) = enclosure {
+            let end = args.len()
+                - args
+                    .iter()
+                    .rev()
+                    .take_while(|arg| is_punctuation(arg))
+                    .count();
+            // Options of a synopsis aren't broken over lines.
+            let keep = usize::from(self.section == "SYNOPSIS");
+            self.word(open, Font::Regular);
+            self.nospace = true;
+            self.keep += keep;
+            self.call_each(&args[..end]);
+            self.attach(close);
+            self.keep -= keep;
+            self.words(&args[end..], Font::Regular);
+            return;
+        }
+        match name {
+            "Fl" => {
+                if own.iter().all(|arg| is_punctuation(arg)) {
+                    self.word("\\-", Font::Bold);
+                }
+                for arg in own {
+                    match is_punctuation(arg) {
+                        true => self.word(arg, Font::Regular),
+                        false => self.word(&format!("\\-{arg}"), Font::Bold),
+                    }
+                }
+            }
+            "Ar" if own.iter().all(|arg| is_punctuation(arg)) => {
+                self.word("file", Font::Italic);
+                self.word("...", Font::Italic);
+                self.words(own, Font::Regular);
+            }
+            "Nm" => {
+                let first = own.first().filter(|arg| !is_punctuation(arg));
+                if let Some(name) = first.filter(|_| self.name.is_none()) {
+                    self.name = Some(name.clone());
+                }
+                match first {
+                    Some(_) => self.words(own, Font::Bold),
+                    None => {
+                        let name = self.name.clone().unwrap_or_default();
+                        self.word(&name, Font::Bold);
+                        self.words(own, Font::Regular);
+                    }
+                }
+            }
+            "Ar" | "Em" | "Ad" | "Pa" | "Va" | "Vt" | "Ft" => self.words(own, Font::Italic),
+            "Sy" | "Cm" | "Ic" | "Cd" | "Fd" => self.words(own, Font::Bold),
+            "Fa" => {
+                for arg in own {
+                    if self.function.is_some_and(|count| count > 0) {
+                        self.attach(",");
+                    }
+                    self.word(arg, Font::Italic);
+                    if let Some(count) = self.function.as_mut() {
+                        *count += 1;
+                    }
+                }
+            }
+            "Fn" => {
+                let (function, params) = own.split_first().map_or(("", own), |(f, p)| (f, p));
+                let end = params.len()
+                    - params
+                        .iter()
+                        .rev()
+                        .take_while(|arg| is_punctuation(arg))
+                        .count();
+                self.word(function, Font::Bold);
+                self.attach("(");
+                self.nospace = true;
+                for (i, param) in params[..end].iter().enumerate() {
+                    if i > 0 {
+                        self.attach(",");
+                    }
+                    self.word(param, Font::Italic);
+                }
+                self.attach(")");
+                self.words(&params[end..], Font::Regular);
+            }
+            "Fo" => {
+                self.word(own.first().map_or("", String::as_str), Font::Bold);
+                self.attach("(");
+                self.nospace = true;
+                self.function = Some(0);
+            }
+            "Fc" => {
+                self.attach(")");
+                self.function = None;
+                self.words(own, Font::Regular);
+            }
+            "In" => {
+                self.word("#include", Font::Regular);
+                self.word("<", Font::Regular);
+                self.nospace = true;
+                self.word(own.first().map_or("", String::as_str), Font::Bold);
+                self.attach(">");
+                self.words(own.get(1..).unwrap_or_default(), Font::Regular);
+            }
+            "Xr" => {
+                let page = match own.get(1).filter(|arg| !is_punctuation(arg)) {
+                    Some(section) => format!("{}({section})", own[0]),
+                    None => own.first().cloned().unwrap_or_default(),
+                };
+                self.word(&page, Font::Regular);
+                self.words(own.get(shown(own)..).unwrap_or_default(), Font::Regular);
+            }
+            "Lk" | "Mt" => {
+                let target = own.first().map_or("", String::as_str);
+                if let Some(text) = own.get(1).filter(|arg| !is_punctuation(arg)) {
+                    self.word(text, Font::Regular);
+                    self.attach(":");
+                }
+                self.gap();
+                let mut pieces = self.styled(target);
+                pieces
+                    .iter_mut()
+                    .for_each(|(_, mark)| *mark = Some(Mark::Link));
+                self.put(&pieces);
+                self.words(own.get(shown(own)..).unwrap_or_default(), Font::Regular);
+            }
+            "Oo" | "Ao" | "Bo" | "Bro" | "Do" | "Po" | "Qo" | "So" | "Eo" => {
+                let open = match name {
+                    "Oo" | "Bo" => "[",
+                    "Ao" => "⟨",
+                    "Bro" => "{",
+                    "Do" => "“",
+                    "Po" => "(",
+                    "Qo" => "\"",
+                    "So" => "‘",
+                    _ => own.first().map_or("", String::as_str),
+                };
+                self.word(open, Font::Regular);
+                self.nospace = true;
+                let shown = usize::from(name == "Eo").min(own.len());
+                self.words(&own[shown..], Font::Regular);
+            }
+            "Oc" | "Ac" | "Bc" | "Brc" | "Dc" | "Pc" | "Qc" | "Sc" | "Ec" => {
+                let close = match name {
+                    "Oc" | "Bc" => "]",
+                    "Ac" => "⟩",
+                    "Brc" => "}",
+                    "Dc" => "”",
+                    "Pc" => ")",
+                    "Qc" => "\"",
+                    "Sc" => "’",
+                    _ => own.first().map_or("", String::as_str),
+                };
+                self.attach(close);
+                let shown = usize::from(name == "Ec").min(own.len());
+                self.words(&own[shown..], Font::Regular);
+            }
+            "Pf" => {
+                self.word(own.first().map_or("", String::as_str), Font::Regular);
+                self.nospace = true;
+                self.words(own.get(1..).unwrap_or_default(), Font::Regular);
+            }
+            "Ns" => {
+                self.nospace = true;
+                self.words(own, Font::Regular);
+            }
+            "Ap" => {
+                self.attach("'");
+                self.nospace = true;
+                self.words(own, Font::Regular);
+            }
+            "Ta" => {
+                self.gap();
+                self.put(&[(NBSP, None), (NBSP, None)]);
+                self.words(own, Font::Regular);
+            }
+            "Xo" => self.words(own, Font::Regular),
+            // The tag of an item ends with what the line goes on with.
+            "Xc" => {
+                self.words(own, Font::Regular);
+                if let Some((next, args)) = rest.split_first() {
+                    self.call(next, args);
+                }
+                if let Some(list) = self.lists.last().filter(|_| mem::take(&mut self.extended)) {
+                    self.tab_to(list.start + list.width);
+                }
+                return;
+            }
+            "St" => {
+                let standard = own.first().map_or("", String::as_str);
+                let standard = match standard {
+                    "-p1003.1" | "-p1003.1-2008" => "IEEE Std 1003.1 (“POSIX.1”)",
+                    "-p1003.2" => "IEEE Std 1003.2 (“POSIX.2”)",
+                    "-ansiC" | "-isoC" => "ANSI X3.159-1989 (“ANSI C89”)",
+                    "-isoC-99" => "ISO/IEC 9899:1999 (“ISO C99”)",
+                    "-isoC-2011" => "ISO/IEC 9899:2011 (“ISO C11”)",
+                    "-susv2" => "Version 2 of the Single UNIX Specification (“SUSv2”)",
+                    "-susv3" => "Version 3 of the Single UNIX Specification (“SUSv3”)",
+                    "-susv4" => "Version 4 of the Single UNIX Specification (“SUSv4”)",
+                    other => other.trim_start_matches('-'),
+                };
+                self.call_each(&arguments(standard));
+                self.words(own.get(1..).unwrap_or_default(), Font::Regular);
+            }
+            "At" | "Bx" | "Bsx" | "Dx" | "Fx" | "Nx" | "Ox" | "Ux" => {
+                let system = match name {
+                    "At" => "AT&T UNIX",
+                    "Bx" => "BSD",
+                    "Bsx" => "BSD/OS",
+                    "Dx" => "DragonFly",
+                    "Fx" => "FreeBSD",
+                    "Nx" => "NetBSD",
+                    "Ox" => "OpenBSD",
+                    _ => "UNIX",
+                };
+                self.word(system, Font::Regular);
+                self.words(own, Font::Regular);
+            }
+            _ => self.words(own, Font::Regular),
+        }
+        if let Some((next, args)) = rest.split_first() {
+            self.call(next, args);
+        }
+    }
+
+    // A blank line between the commands, or the functions, of a synopsis.
+    fn synopsis_break(&mut self) {
+        if self.synopsis > 0 {
+            self.blank = true;
+        }
+        self.synopsis += 1;
+    }
+
+    fn words(&mut self, words: &[String], font: Font) {
+        for word in words {
+            let font = match is_punctuation(word) {
+                true => Font::Regular,
+                false => font,
+            };
+            self.word(word, font);
+        }
+    }
+
+    // A word given to an mdoc macro, spaced from the last unless it's
+    // punctuation closing something.
+    fn word(&mut self, word: &str, font: Font) {
+        match is_closing(word) {
+            true => {
+                self.nospace = false;
+                if self.word.is_empty() {
+                    self.space = false;
+                }
+            }
+            false => self.gap(),
+        }
+        let pieces = self.styled_as(word, font);
+        self.put(&pieces);
+        if is_opening(word) {
+            self.nospace = true;
+        }
+    }
+
+    // Put `text` right after the last word, without a space.
+    fn attach(&mut self, text: &str) {
+        self.nospace = true;
+        self.gap();
+        let pieces = self.styled(text);
+        self.put(&pieces);
+    }
+
+    // The space between two words, unless they're to go together.
+    fn gap(&mut self) {
+        if mem::take(&mut self.nospace) {
+            if self.word.is_empty() {
+                self.space = false;
+            }
+            return;
+        }
+        // The first word after `.Sm off` is still spaced from those before.
+        if !self.spacing {
+            if mem::replace(&mut self.unspaced, true) && self.word.is_empty() {
+                self.space = false;
+            }
+            return;
+        }
+        if self.keep > 0 && !self.word.is_empty() {
+            self.word.push((NBSP, None));
+        } else if self.fill {
+            self.flush_word();
+            self.space = true;
+        } else if !self.word.is_empty() || self.filled() {
+            self.word.push((' ', None));
+        }
+    }
+
+    fn header(&mut self, title: &str, section: &str, manual: &str) {
+        let side = format!("{}({})", self.plain(title), self.plain(section));
+        let manual = self.plain(manual);
+        let room = WIDTH.saturating_sub(2 * side.chars().count() + manual.chars().count());
+        let line = format!(
+            "{side}{}{manual}{}{side}",
+            " ".repeat((room / 2).max(1)),
+            " ".repeat((room - room / 2).max(1))
+        );
+        let len = line.chars().count();
+        self.finish_line();
+        self.page.push((line, vec![(0..len, Mark::Decoration)]));
+        self.title = Some(side);
+        self.manual = manual;
+        self.blank = true;
+    }
+
+    fn heading(&mut self, level: usize, args: &[String]) {
+        self.finish_line();
+        self.next = None;
+        self.fill = true;
+        self.margin = INDENT;
+        self.margins.clear();
+        self.lists.clear();
+        self.displays.clear();
+        self.font = Font::Regular;
+        self.blank = true;
+        self.indent = match level {
+            1 => 0,
+            _ => SUBSECTION_INDENT,
+        };
+        self.heading = Some(Mark::Heading(level));
+        if level == 1 {
+            self.section = self.plain(&args.join(" "));
+            self.synopsis = 0;
+        }
+        match args.is_empty() {
+            true => self.next = Some(Next::Heading),
+            false => {
+                let heading = self.styled(&args.join(" "));
+                self.put(&heading);
+                self.end_heading();
+            }
+        }
+    }
+
+    fn end_heading(&mut self) {
+        self.finish_line();
+        self.heading = None;
+        self.indent = self.margin;
+    }
+
+    fn paragraph(&mut self) {
+        self.finish_line();
+        self.next = None;
+        self.blank = true;
+        self.font = Font::Regular;
+        self.indent = self.margin;
+    }
+
+    // A line of text, or the heading or the tag the line before asked for.
+    fn text(&mut self, line: &str) {
+        let next = self.next.take();
+        if self.fill && line.trim().is_empty() {
+            self.finish_line();
+            self.blank = true;
+            self.next = next;
+            return;
+        }
+        // Text starting with a space starts a line of its own.
+        if self.fill && line.starts_with([' ', '\t']) {
+            self.finish_line();
+        }
+        let pieces = match self.line_font.take() {
+            Some(font) => self.styled_as(line, font),
+            None => self.styled(line),
+        };
+        // Each line of text laid out as it is makes a line, an empty one even.
+        if !self.fill {
+            self.start();
+        }
+        if let Some(Next::Heading) = next {
+            self.section = pieces.iter().map(|&(c, _)| c).collect();
+        }
+        self.put(&pieces);
+        self.end_input_line();
+        self.close(next);
+    }
+
+    fn close(&mut self, next: Option<Next>) {
+        match next {
+            Some(Next::Heading) => self.end_heading(),
+            Some(Next::Tag(column)) => self.tab_to(column),
+            None => {}
+        }
+    }
+
+    fn end_input_line(&mut self) {
+        if mem::take(&mut self.join) {
+            return;
+        }
+        match self.fill {
+            true => {
+                self.flush_word();
+                self.space = true;
+            }
+            false => self.finish_line(),
+        }
+    }
+
+    // Go past a tag to the column the text after it starts at, on the next
+    // line if the tag reaches it.
+    fn tab_to(&mut self, column: usize) {
+        self.flush_word();
+        if self.started && self.len + 1 > column {
+            self.finish_line();
+        } else if self.started {
+            while self.len < column {
+                self.push(' ', None);
+            }
+        }
+        self.indent = column;
+        self.space = false;
+        self.nospace = false;
+    }
+
+    fn set_font(&mut self, name: &str) {
+        let font = match name {
+            "P" | "" => self.previous,
+            name => Font::named(name),
+        };
+        self.previous = mem::replace(&mut self.font, font);
+    }
+
+    // `text` without how it's shown, as for headers.
+    fn plain(&mut self, text: &str) -> String {
+        let font = self.font;
+        let plain = self.styled(text).iter().map(|&(c, _)| c).collect();
+        self.font = font;
+        plain
+    }
+
+    fn styled_as(&mut self, text: &str, font: Font) -> Pieces {
+        let saved = mem::replace(&mut self.font, font);
+        let pieces = self.styled(text);
+        self.font = saved;
+        pieces
+    }
+
+    // The characters `text` stands for, its escapes taken, each shown in
+    // the font it's in.
+    fn styled(&mut self, text: &str) -> Pieces {
+        let mut pieces = Vec::new();
+        self.escape(text, &mut pieces, 0);
+        pieces
+    }
+
+    fn escape(&mut self, text: &str, pieces: &mut Pieces, depth: usize) {
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+            if c != '\\' {
+                pieces.push((c, self.font.mark()));
+                continue;
+            }
+            let Some(escape) = rest.chars().next() else {
+                break;
+            };
+            rest = &rest[escape.len_utf8()..];
+            let mut put = |text: &str, font: Font| {
+                pieces.extend(text.chars().map(|c| (c, font.mark())));
+            };
+            match escape {
+                'f' => {
+                    let (name, after) = name_argument(rest);
+                    rest = after;
+                    self.set_font(name);
+                }
+                '(' | '[' | 'C' => {
+                    // The name of `\(em` and `\[em]` starts at the bracket.
+                    let (name, after) = match escape {
+                        'C' => quoted_argument(rest),
+                        _ => name_argument(&text[text.len() - rest.len() - 1..]),
+                    };
+                    rest = after;
+                    put(&character(name).unwrap_or_default(), self.font);
+                }
+                '*' => {
+                    let (name, after) = name_argument(rest);
+                    rest = after;
+                    let name = name.split_whitespace().next().unwrap_or_default();
+                    if let Some(value) = self.strings.get(name).cloned() {
+                        if depth < MAX_DEPTH {
+                            self.escape(&value, pieces, depth + 1);
+                        }
+                    }
+                }
+                'N' => {
+                    let (number, after) = quoted_argument(rest);
+                    rest = after;
+                    let c = number.parse().ok().and_then(char::from_u32);
+                    put(&c.map(String::from).unwrap_or_default(), self.font);
+                }
+                's' => rest = size_argument(rest),
+                'n' | 'k' | 'm' | 'M' | 'g' | 'Y' | 'V' | 'F' | '$' => {
+                    rest = name_argument(rest).1;
+                }
+                'h' | 'v' | 'w' | 'o' | 'l' | 'L' | 'D' | 'X' | 'b' | 'x' | 'Z' | 'R' | 'S'
+                | 'H' | 'A' | 'B' => rest = quoted_argument(rest).1,
+                'e' | 'E' | '\\' => put("\\", self.font),
+                ' ' | '~' | '0' => put(&NBSP.to_string(), self.font),
+                'c' => self.join = true,
+                't' => put("\t", self.font),
+                '-' => put("-", self.font),
+                '|' | '^' | '&' | ')' | '%' | ':' | '/' | ',' | '{' | '}' | 'a' | 'd' | 'u'
+                | 'r' | 'z' | 'p' => {}
+                '"' | '#' => break,
+                other => put(&other.to_string(), self.font),
+            }
+        }
+    }
+
+    // Add the characters of text to the words of the line, breaking words
+    // at whitespace when filling.
+    fn put(&mut self, pieces: &[(char, Option<Mark>)]) {
+        for &(c, mark) in pieces {
+            if self.fill && (c == ' ' || c == '\t') {
+                self.flush_word();
+                self.space = true;
+                continue;
+            }
+            self.word.push((c, self.heading.or(mark)));
+        }
+    }
+
+    // Put the word together onto the line, on a line of its own if it
+    // doesn't fit when filling.
+    fn flush_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let word = mem::take(&mut self.word);
+        let space = usize::from(self.space);
+        if self.fill && self.filled() && self.len + space + word.len() > WIDTH {
+            self.finish_line();
+        }
+        self.start();
+        // The text after a tag is already spaced from it.
+        if self.space && self.filled() && !self.line.ends_with(' ') {
+            self.push(' ', None);
+        }
+        self.space = false;
+        for (c, mark) in word {
+            match c {
+                '\t' => {
+                    let column = self.len - self.line_start;
+                    let to = self.line_start + (column / TAB_WIDTH + 1) * TAB_WIDTH;
+                    while self.len < to {
+                        self.push(' ', None);
+                    }
+                }
+                NBSP => self.push(' ', mark),
+                c => self.push(c, mark),
+            }
+        }
+    }
+
+    fn push(&mut self, c: char, mark: Option<Mark>) {
+        let range = self.len..self.len + 1;
+        match (self.marks.last_mut(), mark) {
+            (Some((last, m)), Some(mark)) if *m == mark && last.end == range.start => {
+                last.end = range.end;
+            }
+            (_, Some(mark)) => self.marks.push((range, mark)),
+            _ => {}
+        }
+        self.line.push(c);
+        self.len += 1;
+    }
+
+    fn filled(&self) -> bool {
+        self.started && self.len > self.line_start
+    }
+
+    // Begin the line with the blank line due before it and its indentation.
+    fn start(&mut self) {
+        if self.started {
+            return;
+        }
+        // Text right after a heading isn't set apart from it.
+        let apart = self.page.last().is_some_and(|(line, marks)| {
+            !line.is_empty()
+                && !marks
+                    .iter()
+                    .any(|(_, mark)| matches!(mark, Mark::Heading(_)))
+        });
+        if mem::take(&mut self.blank) && apart {
+            self.page.push((String::new(), Vec::new()));
+        }
+        let indent = self.temporary.take().unwrap_or(self.indent);
+        self.started = true;
+        self.line = " ".repeat(indent);
+        self.len = indent;
+        self.line_start = indent;
+    }
+
+    fn finish_line(&mut self) {
+        self.flush_word();
+        if !self.started {
+            return;
+        }
+        let mut line = mem::take(&mut self.line);
+        let mut marks = mem::take(&mut self.marks);
+        line.truncate(line.trim_end().len());
+        let len = line.chars().count();
+        marks.retain_mut(|(range, _)| {
+            range.end = range.end.min(len);
+            range.start < range.end
+        });
+        self.page.push((line, marks));
+        (self.started, self.len, self.space) = (false, 0, false);
+    }
+
+    // Run the macro `name` defined by the page, its arguments put in.
+    fn invoke(&mut self, name: &str, args: &[String]) {
+        if self.depth >= MAX_DEPTH {
+            return;
+        }
+        let Some(body) = self.macros.get(name).cloned() else {
+            return;
+        };
+        self.depth += 1;
+        for line in body {
+            self.line(&interpolate(&line, args));
+        }
+        self.depth -= 1;
+    }
+
+    // Whether the condition starting `text` holds, as it does in `nroff`,
+    // and the rest of it.
+    fn condition<'a>(&self, text: &'a str) -> (bool, &'a str) {
+        let text = text.trim_start();
+        let (negated, text) = match text.strip_prefix('!') {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+        let Some(first) = text.chars().next() else {
+            return (false, text);
+        };
+        let after = &text[first.len_utf8()..];
+        let (holds, rest) = match first {
+            'n' | 'o' => (true, after),
+            't' | 'e' | 'v' => (false, after),
+            'd' | 'r' | 'c' | 'm' | 'F' | 'S' if after.starts_with(char::is_whitespace) => {
+                let after = after.trim_start();
+                let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                let name = &after[..end];
+                let defined = first == 'd'
+                    && (self.macros.contains_key(name)
+                        || self.strings.contains_key(name)
+                        || MACROS.contains(&name));
+                (defined, &after[end..])
+            }
+            // Two strings set apart by a delimiter, as in `'\*(.T'ascii'`.
+            '\'' | '"' | '|' | '/' => {
+                let mut parts = after.splitn(3, first);
+                let (a, b) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+                (a == b, parts.next().unwrap_or(""))
+            }
+            _ => {
+                let end = text.find(char::is_whitespace).unwrap_or(text.len());
+                let (expression, rest) = text.split_at(end);
+                let (expression, rest) = match expression.find("\\{") {
+                    Some(brace) => (&expression[..brace], &text[brace..]),
+                    None => (expression, rest),
+                };
+                (evaluate(expression), rest)
+            }
+        };
+        (holds != negated, rest)
+    }
+
+    // Read what a condition governs if it holds, leaving out the block it
+    // starts otherwise.
+    fn body(&mut self, holds: bool, body: &str) {
+        let body = body.trim_start();
+        if let Some(block) = body.strip_prefix("\\{") {
+            let block = block.trim_start();
+            match holds {
+                true if !block.is_empty() => self.line(block),
+                true => {}
+                false => {
+                    self.skip = (1 + block.matches("\\{").count())
+                        .saturating_sub(block.matches("\\}").count());
+                }
+            }
+        } else if holds && !body.is_empty() {
+            self.line(body);
+        }
+    }
+
+    // A table of `tbl`, its cells set in columns.
+    fn table(&mut self, lines: Vec<String>) {
+        let mut tab = '\t';
+        let mut lines = lines.as_slice();
+        if let Some(options) = lines.first().filter(|line| line.trim_end().ends_with(';')) {
+            if let Some(at) = options.find("tab(") {
+                tab = options[at + 4..].chars().next().unwrap_or(tab);
+            }
+            lines = &lines[1..];
+        }
+        // The formats of the columns go up to a line ending in a period.
+        let formats = lines
+            .iter()
+            .position(|line| line.trim_end().ends_with('.'))
+            .map_or(0, |i| i + 1);
+        let mut rows = Vec::new();
+        let mut row = String::new();
+        let mut block = false;
+        for line in &lines[formats..] {
+            if block {
+                match line.strip_prefix("T}") {
+                    Some(after) => {
+                        block = false;
+                        row.push_str(after);
+                    }
+                    None => {
+                        let text = match control(line) {
+                            Some((name, rest)) => match font_escapes(name, rest) {
+                                Some(text) => text,
+                                None => continue,
+                            },
+                            None => line.clone(),
+                        };
+                        if !row.is_empty() && !row.ends_with(tab) {
+                            row.push(' ');
+                        }
+                        row.push_str(&text);
+                        continue;
+                    }
+                }
+            } else if control(line).is_some() {
+                continue;
+            } else {
+                row = line.clone();
+            }
+            // A cell of `T{` goes on over the lines up to `T}`.
+            if let Some(start) = row.strip_suffix("T{") {
+                row = start.to_string();
+                block = true;
+                continue;
+            }
+            rows.push(mem::take(&mut row));
+        }
+        let font = self.font;
+        let rows = rows
+            .iter()
+            .map(|row| match row.trim() {
+                "_" | "=" => None,
+                _ => Some(
+                    row.split(tab)
+                        .map(|cell| self.styled(cell))
+                        .collect::<Vec<_>>(),
+                ),
+            })
+            .collect::<Vec<_>>();
+        self.font = font;
+        let mut widths = Vec::new();
+        for cells in rows.iter().flatten() {
+            widths.resize(widths.len().max(cells.len()), 0);
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let total = widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1);
+        self.indent = self.margin;
+        for row in rows {
+            self.start();
+            match row {
+                None => (0..total).for_each(|_| self.push('─', Some(Mark::Decoration))),
+                Some(cells) => {
+                    for (i, cell) in cells.iter().enumerate() {
+                        let column = self.len;
+                        for &(c, mark) in cell {
+                            self.push(if c == NBSP { ' ' } else { c }, mark);
+                        }
+                        if i + 1 < cells.len() {
+                            while self.len < column + widths[i] + 2 {
+                                self.push(' ', None);
+                            }
+                        }
+                    }
+                }
+            }
+            self.finish_line();
+        }
+    }
+
+    fn finish(mut self) -> Page {
+        self.finish_line();
+        if let Some(title) = self.title.take() {
+            let (source, date) = (mem::take(&mut self.source), mem::take(&mut self.date));
+            let room = WIDTH.saturating_sub(
+                source.chars().count() + date.chars().count() + title.chars().count(),
+            );
+            let line = format!(
+                "{source}{}{date}{}{title}",
+                " ".repeat((room / 2).max(1)),
+                " ".repeat((room - room / 2).max(1))
+            );
+            let len = line.chars().count();
+            if self.page.last().is_some_and(|(line, _)| !line.is_empty()) {
+                self.page.push((String::new(), Vec::new()));
+            }
+            self.page.push((line, vec![(0..len, Mark::Decoration)]));
+        }
+        while self.page.last().is_some_and(|(line, _)| line.is_empty()) {
+            self.page.pop();
+        }
+        let (lines, marks) = self.page.into_iter().unzip();
+        Page { lines, marks }
+    }
+}
+
+// The line without the comment ending it.
+fn strip_comment(line: &str) -> &str {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (escaped, c) {
+            (false, '\\') => escaped = true,
+            (true, '"' | '#') => return &line[..i - 1],
+            _ => escaped = false,
+        }
+    }
+    line
+}
+
+// The name of the request or macro a control line calls, and the rest of
+// the line.
+fn control(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(['.', '\''])?.trim_start();
+    let end = rest
+        .find(|c: char| c.is_whitespace() || (c == '\\' && !rest.starts_with('\\')))
+        .unwrap_or(rest.len());
+    Some((&rest[..end], &rest[end..]))
+}
+
+// The arguments of a request, quoted ones kept whole.
+fn arguments(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let mut arg = String::new();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            rest = quoted;
+            loop {
+                match rest.find('"') {
+                    // A doubled quote in a quoted argument stands for one.
+                    Some(i) if rest[i + 1..].starts_with('"') => {
+                        arg.push_str(&rest[..=i]);
+                        rest = &rest[i + 2..];
+                    }
+                    Some(i) => {
+                        arg.push_str(&rest[..i]);
+                        rest = &rest[i + 1..];
+                        break;
+                    }
+                    None => {
+                        arg.push_str(rest);
+                        rest = "";
+                        break;
+                    }
+                }
+            }
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            arg.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+        args.push(arg);
+        rest = rest.trim_start();
+    }
+    args
+}
+
+// The escapes a font macro stands for, as `\fIfile\fR` for `.I file`, in a
+// cell of a table.
+fn font_escapes(name: &str, rest: &str) -> Option<String> {
+    let args = arguments(rest);
+    let text = match name {
+        "B" | "I" => format!("\\f{name}{}", args.join(" ")),
+        "BR" | "RB" | "BI" | "IB" | "IR" | "RI" => args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| format!("\\f{}{arg}", &name[i % 2..i % 2 + 1]))
+            .collect(),
+        _ => return None,
+    };
+    Some(text + "\\fR")
+}
+
+// The name after an escape such as `\f` or `\*`, one character, two after
+// `(` or any up to `]` after `[`, and what follows it.
+fn name_argument(text: &str) -> (&str, &str) {
+    if let Some(rest) = text.strip_prefix('(') {
+        let end = rest.char_indices().nth(2).map_or(rest.len(), |(i, _)| i);
+        return (&rest[..end], &rest[end..]);
+    }
+    if let Some(rest) = text.strip_prefix('[') {
+        let end = rest.find(']').unwrap_or(rest.len());
+        return (&rest[..end], rest.get(end + 1..).unwrap_or_default());
+    }
+    let end = text.chars().next().map_or(0, char::len_utf8);
+    (&text[..end], &text[end..])
+}
+
+// The argument of an escape such as `\h'4n'`, between two delimiters.
+fn quoted_argument(text: &str) -> (&str, &str) {
+    let Some(delimiter) = text.chars().next() else {
+        return ("", text);
+    };
+    let rest = &text[delimiter.len_utf8()..];
+    match rest.find(delimiter) {
+        Some(end) => (&rest[..end], &rest[end + delimiter.len_utf8()..]),
+        None => (rest, ""),
+    }
+}
+
+// What follows the size of a `\s` escape, such as `\s-1` or `\s(12`.
+fn size_argument(text: &str) -> &str {
+    let text = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if text.starts_with(['(', '[']) {
+        return name_argument(text).1;
+    }
+    if text.starts_with('\'') {
+        return quoted_argument(text).1;
+    }
+    let bytes = text.as_bytes();
+    match bytes {
+        [b'1'..=b'3', b'0'..=b'9', ..] => &text[2..],
+        [b'0'..=b'9', ..] => &text[1..],
+        _ => text,
+    }
+}
+
+// The special character `name` stands for, as `em` does for a dash or
+// `u2014` does by its code point.
+fn character(name: &str) -> Option<String> {
+    if let Some((_, c)) = CHARACTERS.iter().find(|(n, _)| *n == name) {
+        return Some(c.to_string());
+    }
+    let code = match name.strip_prefix("char") {
+        Some(decimal) => decimal.parse().ok(),
+        None => name
+            .strip_prefix('u')
+            .and_then(|hex| u32::from_str_radix(hex.split('_').next()?, 16).ok()),
+    };
+    code.and_then(char::from_u32).map(String::from)
+}
+
+// A line of a macro with the arguments of its call put in, as `\\$1` is
+// by the first.
+fn interpolate(line: &str, args: &[String]) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('\\') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        // Backslashes are doubled in the definition of a macro.
+        let backslashes = rest.len() - rest.trim_start_matches('\\').len();
+        let after = &rest[backslashes..];
+        if let Some(reference) = after.strip_prefix('$') {
+            let (name, after) = name_argument(reference);
+            match name {
+                "*" | "@" => out.push_str(&args.join(" ")),
+                _ => {
+                    let arg = name
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| args.get(n.checked_sub(1)?));
+                    out.push_str(arg.map_or("", String::as_str));
+                }
+            }
+            rest = after;
+        } else {
+            out.push_str(&"\\".repeat(backslashes.div_ceil(2)));
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Whether a numeric condition holds, registers taken as they are in
+// `groff`'s `nroff`, where `.g` is set and others aren't.
+fn evaluate(expression: &str) -> bool {
+    let expression = expression.replace("\\n(.g", "1").replace("\\n[.g]", "1");
+    let value = |text: &str| -> Option<i64> {
+        let text = text.trim_matches(|c| c == '(' || c == ')');
+        match text.starts_with("\\n") {
+            true => Some(0),
+            false => text
+                .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                .parse()
+                .ok(),
+        }
+    };
+    for op in [">=", "<=", "==", "=", ">", "<", "&", ":"] {
+        if let Some((a, b)) = expression.split_once(op) {
+            let (Some(a), Some(b)) = (value(a), value(b)) else {
+                return false;
+            };
+            return match op {
+                ">=" => a >= b,
+                "<=" => a <= b,
+                "==" | "=" => a == b,
+                ">" => a > b,
+                "<" => a < b,
+                "&" => a > 0 && b > 0,
+                _ => a > 0 || b > 0,
+            };
+        }
+    }
+    value(&expression).is_some_and(|value| value > 0)
+}
+
+// What the manual of pages in `section` is called, when they don't tell.
+fn manual(section: &str) -> &'static str {
+    let number = section.get(..1).and_then(|n| n.parse::<usize>().ok());
+    number
+        .and_then(|n| MANUALS.get(n.wrapping_sub(1)))
+        .copied()
+        .unwrap_or_default()
+}
+
+// A length such as `4n` or `0.5i`, in columns.
+fn length(text: &str) -> Option<usize> {
+    let text = text.trim();
+    let end = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let number: f64 = text[..end].parse().ok()?;
+    let columns = match &text[end..] {
+        "" | "n" | "m" | "v" => number,
+        "i" => number * 10.0,
+        "c" => number * 4.0,
+        "p" => number / 7.2,
+        "P" => number * 10.0 / 6.0,
+        "u" => number / 24.0,
+        _ => return None,
+    };
+    Some(columns.round() as usize)
+}
+
+// The column `.in` or `.ti` sets, `+` and `-` moving it from `from`.
+fn offset(from: usize, text: &str) -> usize {
+    match (text.strip_prefix('+'), text.strip_prefix('-')) {
+        (Some(n), _) => from + length(n).unwrap_or(0),
+        (_, Some(n)) => from.saturating_sub(length(n).unwrap_or(0)),
+        _ => length(text).unwrap_or(from),
+    }
+}
+
+// The value given after `flag` to an mdoc macro.
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let i = args.iter().position(|arg| arg == flag)?;
+    args.get(i + 1).map(String::as_str)
+}
+
+// The width of the tags of an mdoc list, given as a length or as a string
+// as wide as them.
+fn mdoc_width(width: &str) -> usize {
+    match width {
+        "indent" | "Ds" => INDENT - 1,
+        _ => length(width).unwrap_or(width.chars().count() + 2),
+    }
+}
+
+fn mdoc_offset(offset: &str) -> usize {
+    match offset {
+        "left" => 0,
+        "indent" | "Ds" | "center" | "right" => INDENT - 1,
+        "indent-two" => 2 * (INDENT - 1),
+        _ => length(offset).unwrap_or(offset.chars().count()),
+    }
+}
+
+// How many of the arguments of `Xr`, `Lk` or `Mt` are theirs, the name or
+// the target and what goes with it, before the punctuation after them.
+fn shown(args: &[String]) -> usize {
+    match args.get(1) {
+        Some(arg) if !is_punctuation(arg) => 2,
+        _ => 1,
+    }
+}
+
+fn is_callable(name: &str) -> bool {
+    CALLABLE.contains(&name)
+}
+
+fn is_punctuation(word: &str) -> bool {
+    is_closing(word) || is_opening(word) || word == "|"
+}
+
+fn is_closing(word: &str) -> bool {
+    matches!(word, "." | "," | ";" | ":" | "?" | "!" | ")" | "]")
+}
+
+fn is_opening(word: &str) -> bool {
+    matches!(word, "(" | "[")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#".TH HELLO 1 "2024-01-01" "hello 1.0" "User Commands"
+.SH NAME
+hello \- say hello
+.SH SYNOPSIS
+.B hello
+[\fB\-n\fR \fIname\fR]
+.SH OPTIONS
+.TP
+.BR \-n " " \fIname\fR
+Whom to greet\(em the world by default.
+"#;
+
+    #[test]
+    fn lays_a_page_out() {
+        let page = render(PAGE).unwrap();
+        assert_eq!(
+            page.lines,
+            [
+                "HELLO(1)                        User Commands                         HELLO(1)",
+                "",
+                "NAME",
+                "       hello - say hello",
+                "",
+                "SYNOPSIS",
+                "       hello [-n name]",
+                "",
+                "OPTIONS",
+                "       -n name",
+                "              Whom to greet— the world by default.",
+                "",
+                "hello 1.0                         2024-01-01                          HELLO(1)",
+            ]
+        );
+    }
+
+    #[test]
+    fn lays_a_truncated_page_out() {
+        assert!(render("").is_err());
+        for (at, _) in PAGE.char_indices().skip(1) {
+            assert!(render(&PAGE[..at]).is_ok(), "cut at {at}");
+        }
+    }
+
+    #[test]
+    fn lays_a_corrupt_page_out() {
+        assert!(render("just some text\n").is_err());
+        // Escapes cut off, requests unknown and a macro calling itself.
+        let corrupt = ".TH\n.SH \\f\n\\(\n.XX \\*[\n\\[bu\n.de loop\n.loop\n..\n.loop\n";
+        assert!(render(corrupt).is_ok());
+        for at in (0..PAGE.len()).filter(|&at| PAGE.is_char_boundary(at)) {
+            let mut page = PAGE.to_string();
+            page.insert(at, '\\');
+            assert!(render(&page).is_ok(), "backslash at {at}");
+        }
+    }
+}
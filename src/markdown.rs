@@ -0,0 +1,184 @@
+use std::ops::Range;
+
+use ratatui::style::Style;
+
+/// Width of the rule a `---` line is drawn as.
+const RULE_WIDTH: usize = 40;
+
+/// How a part of a rendered line is shown.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// A heading, of level 1 to 6.
+    Heading(usize),
+    Bold,
+    Italic,
+    Code,
+    Link,
+    /// The box around a fenced code block, along with what's in it.
+    CodeBlock,
+    /// The bar of a quote, bullets and rules.
+    Decoration,
+    /// Colors and styles set by terminal escapes.
+    Styled(Style),
+}
+
+/// A line as rendered, along with how its parts are shown, in characters.
+pub type Rendered = (String, Vec<(Range<usize>, Mark)>);
+
+/// What a line of a Markdown file is part of, which depends on the lines
+/// before it for code blocks.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Block {
+    Text,
+    /// The line opening a fenced code block, or closing it.
+    Fence {
+        open: bool,
+    },
+    Code,
+}
+
+/// Renders the lines of a Markdown file one by one, each into a single row
+/// so that the cursor and the line numbers still match the file. Knowing
+/// whether a line is in a code block takes the lines before it, which are
+/// kept.
+#[derive(Default)]
+pub struct Markdown {
+    blocks: Vec<Block>,
+}
+
+impl Markdown {
+    #[inline]
+    pub fn new() -> Self {
+        Markdown::default()
+    }
+
+    /// Forget the lines gone over, as after the file was read anew.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    /// The line at `index` of `lines` as rendered.
+    pub fn render(&mut self, lines: &[String], index: usize) -> Rendered {
+        if self.blocks.len() > lines.len() {
+            self.clear();
+        }
+        while self.blocks.len() <= index && self.blocks.len() < lines.len() {
+            let in_code = matches!(
+                self.blocks.last(),
+                Some(Block::Fence { open: true } | Block::Code)
+            );
+            let is_fence = lines[self.blocks.len()].trim_start().starts_with("```");
+            self.blocks.push(match (in_code, is_fence) {
+                (false, true) => Block::Fence { open: true },
+                (true, true) => Block::Fence { open: false },
+                (true, false) => Block::Code,
+                (false, false) => Block::Text,
+            });
+        }
+        let line = lines.get(index).map_or("", String::as_str);
+        match self.blocks.get(index).copied().unwrap_or(Block::Text) {
+            Block::Fence { open: true } => {
+                let language = line.trim_start().trim_start_matches('`').trim();
+                whole(format!("┌── {language}"), Mark::CodeBlock)
+            }
+            Block::Fence { open: false } => whole("└──".to_string(), Mark::CodeBlock),
+            Block::Code => whole(format!("│ {line}"), Mark::CodeBlock),
+            Block::Text => render_text(line),
+        }
+    }
+}
+
+fn whole(text: String, mark: Mark) -> Rendered {
+    let len = text.chars().count();
+    (text, vec![(0..len, mark)])
+}
+
+// A line outside of code blocks: headings, rules, quotes and list items,
+// with the inline marks of their text.
+fn render_text(line: &str) -> Rendered {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&level) && trimmed[level..].starts_with([' ', '\t']) {
+        let (text, _) = inline(trimmed[level..].trim());
+        return whole(text, Mark::Heading(level));
+    }
+    let rule = trimmed.replace([' ', '\t'], "");
+    if rule.len() >= 3 && ["-", "*", "_"].iter().any(|c| rule == c.repeat(rule.len())) {
+        return whole("─".repeat(RULE_WIDTH), Mark::Decoration);
+    }
+
+    let (prefix, rest) =
+        if let Some(rest) = trimmed.strip_prefix("> ").or(trimmed.strip_prefix('>')) {
+            ("▌ ".to_string(), rest)
+        } else if let Some(rest) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet))
+        {
+            let (bullet, rest) = match rest {
+                _ if rest.starts_with("[ ] ") => ("☐ ", &rest[4..]),
+                _ if rest.starts_with("[x] ") || rest.starts_with("[X] ") => ("☑ ", &rest[4..]),
+                _ => ("• ", rest),
+            };
+            (format!("{indent}{bullet}"), rest)
+        } else {
+            (indent.to_string(), trimmed)
+        };
+    let (text, marks) = inline(rest);
+    let offset = prefix.chars().count();
+    let mut all = vec![(0..offset, Mark::Decoration)];
+    all.extend(
+        marks
+            .into_iter()
+            .map(|(r, m)| (r.start + offset..r.end + offset, m)),
+    );
+    (prefix + &text, all)
+}
+
+// The inline marks of `text`: `**bold**`, `*italic*`, `` `code` `` and
+// `[links](url)`, of which only the text is kept.
+fn inline(text: &str) -> Rendered {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut len = 0;
+    let mut marks = Vec::new();
+    let mut i = 0;
+    let find = |from: usize, pattern: &[char]| {
+        (from..chars.len()).find(|&j| chars[j..].starts_with(pattern))
+    };
+    while i < chars.len() {
+        let (open, mark, close): (&[char], _, &[char]) = match chars[i..] {
+            ['`', ..] => (&['`'], Mark::Code, &['`']),
+            ['*', '*', ..] => (&['*', '*'], Mark::Bold, &['*', '*']),
+            ['_', '_', ..] => (&['_', '_'], Mark::Bold, &['_', '_']),
+            ['*', c, ..] if !c.is_whitespace() => (&['*'], Mark::Italic, &['*']),
+            ['!', '[', ..] => (&['!', '['], Mark::Link, &[']']),
+            ['[', ..] => (&['['], Mark::Link, &[']']),
+            _ => (&[], Mark::Bold, &[]),
+        };
+        let end = (!open.is_empty())
+            .then(|| find(i + open.len(), close))
+            .flatten()
+            .filter(|&end| end > i + open.len());
+        let Some(end) = end else {
+            out.push(chars[i]);
+            len += 1;
+            i += 1;
+            continue;
+        };
+        let inner = chars[i + open.len()..end].iter().collect::<String>();
+        let start = len;
+        len += inner.chars().count();
+        out.push_str(&inner);
+        marks.push((start..len, mark));
+        i = end + close.len();
+        // The target of a link isn't shown.
+        if mark == Mark::Link && chars.get(i) == Some(&'(') {
+            if let Some(paren) = find(i, &[')']) {
+                i = paren + 1;
+            }
+        }
+    }
+    (out, marks)
+}
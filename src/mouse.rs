@@ -0,0 +1,75 @@
+use ratatui::layout::Rect;
+
+/// What a held down mouse button is dragging.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Grab {
+    Scrollbar,
+    /// A file of the Files pane, moved along the list as it's dragged.
+    File,
+}
+
+/// Screen areas from the last frame, to tell what a mouse event points at.
+#[derive(Default)]
+pub struct Mouse {
+    view: Rect,
+    // The list of the Files pane.
+    files: Rect,
+    grab: Option<Grab>,
+}
+
+impl Mouse {
+    #[inline]
+    pub fn new() -> Self {
+        Mouse::default()
+    }
+
+    pub fn set_view(&mut self, area: Rect) {
+        self.view = area;
+    }
+
+    pub fn set_files(&mut self, area: Rect) {
+        self.files = area;
+    }
+
+    /// The row of the Files pane list at the given position, counted from
+    /// its top, or the nearest one for a drag that has left it.
+    pub fn files_row(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.files;
+        let inside = (area.left()..area.right()).contains(&column);
+        (inside && (area.top()..area.bottom()).contains(&row)).then(|| (row - area.y) as usize)
+    }
+
+    pub fn files_drag_row(&self, row: u16) -> usize {
+        let area = self.files;
+        row.clamp(area.top(), area.bottom().saturating_sub(1).max(area.top()))
+            .saturating_sub(area.y) as usize
+    }
+
+    /// The row of the scrollbar track at the given position, counted from
+    /// its top, and the number of rows in the track.
+    pub fn scrollbar_row(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        let track = self.view.height.saturating_sub(2); // 2 (borders)
+        let top = self.view.y + 1;
+        let at_bar = self.view.width > 0 && column == self.view.right() - 1;
+        (at_bar && (top..top + track).contains(&row))
+            .then(|| ((row - top) as usize, track as usize))
+    }
+
+    /// Like `scrollbar_row`, but clamped to the track for a drag that has
+    /// left it.
+    pub fn scrollbar_drag_row(&self, row: u16) -> (usize, usize) {
+        let track = self.view.height.saturating_sub(2);
+        let top = self.view.y + 1;
+        let row = row.clamp(top, (top + track).saturating_sub(1).max(top));
+        ((row - top) as usize, track as usize)
+    }
+
+    #[inline]
+    pub fn grab(&self) -> Option<Grab> {
+        self.grab
+    }
+
+    pub fn set_grab(&mut self, grab: Option<Grab>) {
+        self.grab = grab;
+    }
+}
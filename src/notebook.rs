@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use crate::ansi;
+use crate::json::{self, Value};
+
+/// Lines of an output shown at most, the rest being counted.
+const MAX_OUTPUT_LINES: usize = 8;
+/// Width of the rule heading a cell.
+const HEADER_WIDTH: usize = 40;
+
+/// What a line of a notebook laid out as text is part of.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    /// The rule heading a cell, and the blank lines between cells.
+    Header,
+    Markdown,
+    Code,
+    /// What running a code cell printed or returned.
+    Output,
+    /// The exception a code cell raised.
+    Error,
+}
+
+/// A Jupyter notebook laid out as text, one line after the other, with what
+/// each line is part of.
+pub struct Notebook {
+    pub lines: Vec<String>,
+    pub parts: Vec<Part>,
+    /// A path with the extension of the language of the code cells, to tell
+    /// how to color them.
+    pub language: Option<PathBuf>,
+}
+
+impl Notebook {
+    /// The notebook whose JSON is `text`.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let document = json::parse(text)?;
+        let mut notebook = Notebook {
+            lines: Vec::new(),
+            parts: Vec::new(),
+            language: language(&document),
+        };
+        let cells = document.get("cells").map_or(&[][..], Value::as_array);
+        for (i, cell) in cells.iter().enumerate() {
+            if i > 0 {
+                notebook.push(String::new(), Part::Header);
+            }
+            let kind = cell
+                .get("cell_type")
+                .and_then(Value::as_str)
+                .unwrap_or("raw");
+            let source = cell.get("source").map(Value::text).unwrap_or_default();
+            let (title, part) = match kind {
+                "markdown" => ("Markdown".to_string(), Part::Markdown),
+                "code" => {
+                    let count = match cell.get("execution_count") {
+                        Some(Value::Number(count)) => count.as_str(),
+                        _ => " ",
+                    };
+                    (format!("In [{count}]"), Part::Code)
+                }
+                kind => (kind.to_string(), Part::Code),
+            };
+            notebook.push(header(&title), Part::Header);
+            for line in source.lines() {
+                notebook.push(line.to_string(), part);
+            }
+            let outputs = cell.get("outputs").map_or(&[][..], Value::as_array);
+            for output in outputs {
+                notebook.output(output);
+            }
+        }
+        Ok(notebook)
+    }
+
+    fn push(&mut self, line: String, part: Part) {
+        self.lines.push(line);
+        self.parts.push(part);
+    }
+
+    // The text an output holds, cut short, or else the kinds of data in it.
+    fn output(&mut self, output: &Value) {
+        let kind = output.get("output_type").and_then(Value::as_str);
+        if kind == Some("error") {
+            let name = output
+                .get("ename")
+                .and_then(Value::as_str)
+                .unwrap_or("Error");
+            let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+            self.push(format!("{name}: {value}"), Part::Error);
+            return;
+        }
+        let prefix = match (kind, output.get("execution_count")) {
+            (Some("execute_result"), Some(Value::Number(count))) => format!("Out [{count}]: "),
+            _ => String::new(),
+        };
+        let data = output.get("data");
+        let text = match kind {
+            Some("stream") => output.get("text").map(Value::text),
+            _ => data
+                .and_then(|data| data.get("text/plain"))
+                .map(Value::text),
+        };
+        let lines = match text {
+            // Escapes coloring the output are taken out.
+            Some(text) => text.lines().map(|l| ansi::render(l).0).collect::<Vec<_>>(),
+            // Images, HTML and the like can't be shown as text.
+            None => match data {
+                Some(Value::Object(members)) => {
+                    let kinds = members.iter().map(|(kind, _)| kind.as_str());
+                    vec![format!("[{}]", kinds.collect::<Vec<_>>().join(", "))]
+                }
+                _ => Vec::new(),
+            },
+        };
+        let part = match output.get("name").and_then(Value::as_str) {
+            Some("stderr") => Part::Error,
+            _ => Part::Output,
+        };
+        for (i, line) in lines.iter().take(MAX_OUTPUT_LINES).enumerate() {
+            let prefix = if i == 0 { prefix.as_str() } else { "" };
+            self.push(format!("{prefix}{line}"), part);
+        }
+        if lines.len() > MAX_OUTPUT_LINES {
+            let more = lines.len() - MAX_OUTPUT_LINES;
+            self.push(format!("… {more} more lines"), Part::Output);
+        }
+    }
+}
+
+/// Whether the file at `path` is a Jupyter notebook.
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("ipynb"))
+}
+
+fn header(title: &str) -> String {
+    let rule = HEADER_WIDTH.saturating_sub(title.chars().count() + 4);
+    format!("── {title} {}", "─".repeat(rule))
+}
+
+// The language of the code cells, from the metadata of the notebook.
+fn language(document: &Value) -> Option<PathBuf> {
+    let metadata = document.get("metadata")?;
+    let info = metadata.get("language_info");
+    if let Some(extension) = info
+        .and_then(|info| info.get("file_extension"))
+        .and_then(Value::as_str)
+    {
+        return Some(PathBuf::from(format!(
+            "cell.{}",
+            extension.trim_start_matches('.')
+        )));
+    }
+    let name = info
+        .and_then(|info| info.get("name"))
+        .or_else(|| metadata.get("kernelspec")?.get("language"))
+        .and_then(Value::as_str)?;
+    let extension = match name.to_ascii_lowercase().as_str() {
+        "python" | "python3" => "py",
+        "javascript" | "typescript" => "js",
+        "rust" => "rs",
+        "go" => "go",
+        "ruby" => "rb",
+        "lua" => "lua",
+        "bash" | "sh" => "sh",
+        "c++" | "cpp" => "cpp",
+        "c" => "c",
+        "java" => "java",
+        "scala" => "scala",
+        _ => return None,
+    };
+    Some(PathBuf::from(format!("cell.{extension}")))
+}
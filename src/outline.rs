@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use regex::Regex;
+
+/// Patterns for the lines starting a definition, by file extension. They
+/// are matched line by line, so a definition is found by its first line.
+const LANGUAGES: &[(&[&str], &str)] = &[
+    (
+        &["rs"],
+        r#"^\s*(pub(\([^)]*\))?\s+)?((const|async|unsafe|extern(\s+"[^"]*")?)\s+)*(fn|struct|enum|union|trait|impl|mod|type|macro_rules!)\b"#,
+    ),
+    (&["py", "pyi"], r"^\s*(async\s+)?(def|class)\s"),
+    (
+        &["js", "jsx", "mjs", "cjs", "ts", "tsx"],
+        r"^\s*(export\s+)?(default\s+)?(async\s+)?(function\*?|class|interface|type|enum)\s|^\s*(export\s+)?(const|let|var)\s+\w+\s*=\s*(async\s+)?(\([^)]*\)|\w+)\s*=>",
+    ),
+    (&["go"], r"^(func|type)\s"),
+    (
+        &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        r"^(struct|union|enum|class|namespace|typedef)\s|^[A-Za-z_][\w\s\*&:<>,]*\b\w+\s*\([^;]*$",
+    ),
+    (
+        &["java", "kt", "cs", "scala"],
+        r"^\s*((public|private|protected|internal|static|final|abstract|sealed|open|data)\s+)*(class|interface|enum|record|object|fun|def)\s|^\s+(public|private|protected)\s[^;=]*\(",
+    ),
+    (&["rb"], r"^\s*(def|class|module)\s"),
+    (&["lua"], r"^\s*(local\s+)?function\s"),
+    (
+        &["sh", "bash", "zsh"],
+        r"^\s*(function\s+[\w-]+|[\w-]+\s*\(\)\s*(\{|$))",
+    ),
+    (&["md", "markdown"], r"^#{1,6}\s"),
+];
+
+/// Finds the definitions of functions, types and the like in a code file,
+/// or the headings of a Markdown one. It goes by how their first line looks
+/// rather than by a syntax tree as tree-sitter queries would, so a
+/// definition written over several lines or inside a string can be missed
+/// or taken for one.
+pub struct Outline {
+    regex: Regex,
+}
+
+impl Outline {
+    /// The outline for the language of `path`, told by its extension.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        let (_, pattern) = LANGUAGES
+            .iter()
+            .find(|(extensions, _)| extensions.contains(&extension.as_str()))?;
+        let regex = Regex::new(pattern).ok()?;
+        Some(Outline { regex })
+    }
+
+    #[inline]
+    pub fn is_definition(&self, line: &str) -> bool {
+        self.regex.is_match(line)
+    }
+
+    /// The 0-based lines starting a definition.
+    pub fn definitions(&self, lines: &[String]) -> Vec<usize> {
+        (0..lines.len())
+            .filter(|&i| self.is_definition(&lines[i]))
+            .collect()
+    }
+}
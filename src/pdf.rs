@@ -0,0 +1,913 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use regex::bytes::Regex;
+
+use crate::inflate;
+
+/// Levels of the page tree gone down at most, against loops in it.
+const MAX_DEPTH: usize = 32;
+/// Width of the rule heading a page.
+const HEADER_WIDTH: usize = 40;
+/// Text spacing in a `TJ` array, in thousandths of a unit of text, taken
+/// to stand for a space between words.
+const WORD_GAP: f64 = 200.0;
+
+/// A PDF object. Names and the keys of dictionaries are kept as text.
+enum Object {
+    Null,
+    /// A boolean, whose value nothing here needs.
+    Bool,
+    Number(f64),
+    Name(String),
+    String(Vec<u8>),
+    Array(Vec<Object>),
+    Dict(Dict),
+    Ref(u32),
+    Stream(Dict, Vec<u8>),
+    /// An operator of a content stream, or a keyword such as `obj`.
+    Keyword(String),
+}
+
+type Dict = Vec<(String, Object)>;
+
+impl Object {
+    fn dict(&self) -> Option<&Dict> {
+        match self {
+            Object::Dict(dict) | Object::Stream(dict, _) => Some(dict),
+            _ => None,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Object> {
+        self.dict()?.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Object::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self {
+            Object::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// The text of each page of the PDF file `data`, line by line, in the order
+/// the pages come in.
+pub fn pages(data: &[u8]) -> anyhow::Result<Vec<Vec<String>>> {
+    if !data.starts_with(b"%PDF") {
+        return Err(anyhow!("Not a PDF file"));
+    }
+    let document = Document::load(data)?;
+    if document
+        .objects
+        .values()
+        .any(|o| o.get("Filter").and_then(Object::name) == Some("Standard"))
+    {
+        return Err(anyhow!("The PDF file is encrypted"));
+    }
+    let pages = document.pages();
+    if pages.is_empty() {
+        return Err(anyhow!("No pages found in the PDF file"));
+    }
+    Ok(pages
+        .into_iter()
+        .map(|(page, resources)| document.page_text(page, resources))
+        .collect())
+}
+
+/// The rule heading page `page` of `count`.
+pub fn header(page: usize, count: usize) -> String {
+    let title = format!("Page {page} of {count}");
+    let rule = HEADER_WIDTH.saturating_sub(title.chars().count() + 4);
+    format!("── {title} {}", "─".repeat(rule))
+}
+
+// The objects of a PDF file by their numbers, found by scanning the file
+// rather than through its cross-reference table, which is often damaged.
+struct Document {
+    objects: HashMap<u32, Object>,
+}
+
+impl Document {
+    fn load(data: &[u8]) -> anyhow::Result<Self> {
+        let header = Regex::new(r"(?-u)(\d+)\s+\d+\s+obj\b")?;
+        let mut objects = HashMap::new();
+        for captures in header.captures_iter(data) {
+            let (Some(whole), Some(number)) = (captures.get(0), captures.get(1)) else {
+                continue;
+            };
+            let Some(number) = std::str::from_utf8(number.as_bytes())
+                .ok()
+                .and_then(|n| n.parse().ok())
+            else {
+                continue;
+            };
+            let mut lexer = Lexer::new(data, whole.end());
+            if let Some(object) = lexer.object() {
+                // Objects updated later in the file replace the earlier ones.
+                objects.insert(number, object);
+            }
+        }
+        let mut document = Document { objects };
+        document.unpack_object_streams();
+        Ok(document)
+    }
+
+    // Objects kept compressed in object streams, since PDF 1.5.
+    fn unpack_object_streams(&mut self) {
+        let streams = self
+            .objects
+            .values()
+            .filter(|o| o.get("Type").and_then(Object::name) == Some("ObjStm"))
+            .filter_map(|o| {
+                let count = o.get("N")?.number()? as usize;
+                let first = o.get("First")?.number()? as usize;
+                Some((decode(o)?, count, first))
+            })
+            .collect::<Vec<_>>();
+        for (data, count, first) in streams {
+            let mut lexer = Lexer::new(&data, 0);
+            let offsets = (0..count)
+                .map_while(|_| {
+                    let number = lexer.object()?.number()? as u32;
+                    let offset = lexer.object()?.number()? as usize;
+                    Some((number, offset))
+                })
+                .collect::<Vec<_>>();
+            for (number, offset) in offsets {
+                if let Some(object) = Lexer::new(&data, first + offset).object() {
+                    self.objects.entry(number).or_insert(object);
+                }
+            }
+        }
+    }
+
+    fn resolve<'a>(&'a self, object: &'a Object) -> &'a Object {
+        let mut object = object;
+        for _ in 0..MAX_DEPTH {
+            match object {
+                Object::Ref(number) => match self.objects.get(number) {
+                    Some(target) => object = target,
+                    None => return &Object::Null,
+                },
+                _ => return object,
+            }
+        }
+        &Object::Null
+    }
+
+    fn get<'a>(&'a self, object: &'a Object, key: &str) -> Option<&'a Object> {
+        self.resolve(object)
+            .get(key)
+            .map(|value| self.resolve(value))
+    }
+
+    // The pages in their order, from the page tree of the catalog, along
+    // with the resources they inherit from their parents.
+    fn pages(&self) -> Vec<(&Object, Option<&Object>)> {
+        let mut pages = Vec::new();
+        let catalog = self
+            .objects
+            .values()
+            .find(|o| o.get("Type").and_then(Object::name) == Some("Catalog"));
+        if let Some(root) = catalog.and_then(|catalog| self.get(catalog, "Pages")) {
+            self.walk(root, None, &mut pages, 0);
+        }
+        if pages.is_empty() {
+            // Without a page tree to go by, the pages in the order of their
+            // numbers.
+            let mut numbers = self
+                .objects
+                .iter()
+                .filter(|(_, o)| o.get("Type").and_then(Object::name) == Some("Page"))
+                .map(|(&n, _)| n)
+                .collect::<Vec<_>>();
+            numbers.sort_unstable();
+            pages = numbers.iter().map(|n| (&self.objects[n], None)).collect();
+        }
+        pages
+    }
+
+    fn walk<'a>(
+        &'a self,
+        node: &'a Object,
+        resources: Option<&'a Object>,
+        pages: &mut Vec<(&'a Object, Option<&'a Object>)>,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let resources = self.get(node, "Resources").or(resources);
+        match self.get(node, "Kids") {
+            Some(Object::Array(kids)) => {
+                for kid in kids {
+                    self.walk(self.resolve(kid), resources, pages, depth + 1);
+                }
+            }
+            // Kids that aren't there, as in files cut short, aren't pages.
+            _ if node.dict().is_some() => pages.push((node, resources)),
+            _ => {}
+        }
+    }
+
+    // The lines of text a page shows.
+    fn page_text(&self, page: &Object, resources: Option<&Object>) -> Vec<String> {
+        let fonts = resources
+            .and_then(|resources| self.get(resources, "Font"))
+            .and_then(Object::dict)
+            .map(|fonts| {
+                fonts
+                    .iter()
+                    .map(|(name, font)| (name.as_str(), self.font(self.resolve(font))))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+        let mut content = Vec::new();
+        match self.get(page, "Contents") {
+            Some(Object::Array(parts)) => {
+                for part in parts {
+                    if let Some(data) = decode(self.resolve(part)) {
+                        content.extend_from_slice(&data);
+                        content.push(b'\n');
+                    }
+                }
+            }
+            Some(stream) => content = decode(stream).unwrap_or_default(),
+            None => {}
+        }
+        text_of(&content, &fonts)
+    }
+
+    // How the codes of the strings a font shows map to text.
+    fn font(&self, font: &Object) -> Font {
+        let mut two_bytes = self.get(font, "Subtype").and_then(Object::name) == Some("Type0");
+        let mut map = HashMap::new();
+        if let Some(cmap) = self.get(font, "ToUnicode").and_then(decode) {
+            let (unicode, width) = to_unicode(&cmap);
+            map = unicode;
+            two_bytes = width == 2;
+        } else if let Some(differences) = self
+            .get(font, "Encoding")
+            .and_then(|encoding| self.get(encoding, "Differences"))
+        {
+            let mut code = 0;
+            for entry in match differences {
+                Object::Array(entries) => entries.as_slice(),
+                _ => &[],
+            } {
+                match entry {
+                    Object::Number(n) => code = *n as u32,
+                    Object::Name(glyph) => {
+                        if let Some(text) = glyph_text(glyph) {
+                            map.insert(code, text);
+                        }
+                        code += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Font { two_bytes, map }
+    }
+}
+
+struct Font {
+    two_bytes: bool,
+    map: HashMap<u32, String>,
+}
+
+impl Font {
+    fn decode(&self, bytes: &[u8], text: &mut String) {
+        if self.two_bytes {
+            for pair in bytes.chunks(2) {
+                let code = pair.iter().fold(0, |n, &b| n << 8 | u32::from(b));
+                if let Some(mapped) = self.map.get(&code) {
+                    text.push_str(mapped);
+                }
+            }
+            return;
+        }
+        for &byte in bytes {
+            match self.map.get(&u32::from(byte)) {
+                Some(mapped) => text.push_str(mapped),
+                None => text.push(win_ansi(byte)),
+            }
+        }
+    }
+}
+
+// The data of a stream, uncompressed. Filters other than Flate aren't
+// undone.
+fn decode(object: &Object) -> Option<Vec<u8>> {
+    let Object::Stream(_, data) = object else {
+        return None;
+    };
+    let filters = match object.get("Filter") {
+        None => Vec::new(),
+        Some(Object::Name(name)) => vec![name.as_str()],
+        Some(Object::Array(names)) => names.iter().filter_map(Object::name).collect(),
+        Some(_) => return None,
+    };
+    let mut data = data.clone();
+    for filter in filters {
+        data = match filter {
+            "FlateDecode" | "Fl" => inflate::zlib(&data)
+                .map_err(|err| log::trace!("Couldn't inflate a PDF stream due to: {err}"))
+                .ok()?,
+            _ => return None,
+        };
+    }
+    Some(data)
+}
+
+// The text a content stream shows, lines told apart by where the text is
+// put on the page.
+fn text_of(content: &[u8], fonts: &HashMap<&str, Font>) -> Vec<String> {
+    let mut lexer = Lexer::new(content, 0);
+    let mut operands = Vec::new();
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut font = None;
+    // Where the text goes down the page, and the line being put together.
+    let (mut y, mut line_y, mut leading) = (0.0, None::<f64>, 0.0);
+    let mut gap = false;
+    while let Some(object) = lexer.object() {
+        let Object::Keyword(operator) = object else {
+            operands.push(object);
+            continue;
+        };
+        let number = |i: usize| operands.get(i).and_then(Object::number).unwrap_or_default();
+        let mut shown = Vec::new();
+        match operator.as_str() {
+            "Tf" => {
+                font = operands
+                    .first()
+                    .and_then(Object::name)
+                    .and_then(|n| fonts.get(n))
+            }
+            "TL" => leading = number(0),
+            "Td" | "TD" => {
+                let (tx, ty) = (number(0), number(1));
+                if operator == "TD" {
+                    leading = -ty;
+                }
+                y += ty;
+                gap |= ty == 0.0 && tx > 0.0;
+            }
+            "Tm" => {
+                gap |= line_y.is_some_and(|line_y| (line_y - number(5)).abs() < 0.5);
+                y = number(5);
+            }
+            "T*" => y -= leading,
+            "Tj" => shown.extend(operands.pop()),
+            "'" | "\"" => {
+                y -= leading;
+                shown.extend(operands.pop());
+            }
+            "TJ" => {
+                if let Some(Object::Array(parts)) = operands.pop() {
+                    shown = parts;
+                }
+            }
+            "BI" => lexer.skip_inline_image(),
+            _ => {}
+        }
+        operands.clear();
+        for part in shown {
+            if line_y.is_some_and(|line_y| (line_y - y).abs() > 0.5) {
+                lines.push(std::mem::take(&mut line).trim_end().to_string());
+                gap = false;
+            }
+            line_y = Some(y);
+            match part {
+                Object::String(bytes) => {
+                    if gap && !line.is_empty() && !line.ends_with(' ') {
+                        line.push(' ');
+                    }
+                    gap = false;
+                    match font {
+                        Some(font) => font.decode(&bytes, &mut line),
+                        None => line.extend(bytes.iter().map(|&b| win_ansi(b))),
+                    }
+                }
+                Object::Number(n) => gap |= -n > WORD_GAP,
+                _ => {}
+            }
+        }
+    }
+    if !line.trim().is_empty() {
+        lines.push(line.trim_end().to_string());
+    }
+    // The text is laid out from the top of the page down, and runs of blank
+    // lines come down to one.
+    lines.dedup_by(|a, b| a.is_empty() && b.is_empty());
+    lines
+}
+
+// The codes of a ToUnicode CMap mapped to their text, and how many bytes
+// the codes take.
+fn to_unicode(cmap: &[u8]) -> (HashMap<u32, String>, usize) {
+    let mut map = HashMap::new();
+    let mut width = 1;
+    let mut lexer = Lexer::new(cmap, 0);
+    let mut section = None;
+    let mut pending = Vec::new();
+    let code = |bytes: &[u8]| bytes.iter().fold(0, |n, &b| n << 8 | u32::from(b));
+    while let Some(object) = lexer.object() {
+        match object {
+            Object::Keyword(keyword) => {
+                section = match keyword.as_str() {
+                    "beginbfchar" | "beginbfrange" | "begincodespacerange" => Some(keyword),
+                    _ => None,
+                };
+                pending.clear();
+            }
+            object if section.is_some() => {
+                pending.push(object);
+                match (section.as_deref(), pending.as_slice()) {
+                    (Some("begincodespacerange"), [Object::String(low), Object::String(_)]) => {
+                        width = low.len().max(1);
+                        pending.clear();
+                    }
+                    (Some("beginbfchar"), [Object::String(src), Object::String(dst)]) => {
+                        width = src.len().max(1);
+                        map.insert(code(src), utf16(dst));
+                        pending.clear();
+                    }
+                    (Some("beginbfrange"), [Object::String(low), Object::String(high), dst]) => {
+                        width = low.len().max(1);
+                        let (low, high) = (code(low), code(high));
+                        for (i, c) in (low..=high.min(low + 0xFFFF)).enumerate() {
+                            let text = match dst {
+                                Object::String(start) => {
+                                    let mut start = start.clone();
+                                    // The last byte counts up along the range.
+                                    if let Some(last) = start.last_mut() {
+                                        *last = last.wrapping_add(i as u8);
+                                    }
+                                    utf16(&start)
+                                }
+                                Object::Array(texts) => match texts.get(i) {
+                                    Some(Object::String(text)) => utf16(text),
+                                    _ => continue,
+                                },
+                                _ => continue,
+                            };
+                            map.insert(c, text);
+                        }
+                        pending.clear();
+                    }
+                    (_, pending_objects) if pending_objects.len() > 3 => pending.clear(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    (map, width)
+}
+
+fn utf16(bytes: &[u8]) -> String {
+    let units = bytes
+        .chunks(2)
+        .map(|pair| pair.iter().fold(0, |n, &b| n << 8 | u16::from(b)));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+// The text of a glyph named by an encoding, for the names standing for
+// more than their own letter.
+fn glyph_text(name: &str) -> Option<String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c.to_string());
+    }
+    if let Some(code) = name
+        .strip_prefix("uni")
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+    {
+        return char::from_u32(code).map(String::from);
+    }
+    const DIGITS: [&str; 10] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    if let Some(digit) = DIGITS.iter().position(|&d| d == name) {
+        return Some(digit.to_string());
+    }
+    let text = match name {
+        "space" => " ",
+        "exclam" => "!",
+        "quotedbl" => "\"",
+        "numbersign" => "#",
+        "dollar" => "$",
+        "percent" => "%",
+        "ampersand" => "&",
+        "quotesingle" => "'",
+        "parenleft" => "(",
+        "parenright" => ")",
+        "asterisk" => "*",
+        "plus" => "+",
+        "comma" => ",",
+        "hyphen" | "minus" => "-",
+        "period" => ".",
+        "slash" => "/",
+        "colon" => ":",
+        "semicolon" => ";",
+        "less" => "<",
+        "equal" => "=",
+        "greater" => ">",
+        "question" => "?",
+        "at" => "@",
+        "bracketleft" => "[",
+        "backslash" => "\\",
+        "bracketright" => "]",
+        "underscore" => "_",
+        "braceleft" => "{",
+        "bar" => "|",
+        "braceright" => "}",
+        "quoteleft" => "‘",
+        "quoteright" => "’",
+        "quotedblleft" => "“",
+        "quotedblright" => "”",
+        "endash" => "–",
+        "emdash" => "—",
+        "bullet" => "•",
+        "ellipsis" => "…",
+        "fi" => "fi",
+        "fl" => "fl",
+        "ff" => "ff",
+        "ffi" => "ffi",
+        "ffl" => "ffl",
+        _ => return None,
+    };
+    Some(text.to_string())
+}
+
+// A byte of a string shown in the standard encoding of simple fonts, taken
+// to be Windows-1252.
+fn win_ansi(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+        '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        byte => char::from(byte),
+    }
+}
+
+// Reads the objects of a PDF file, or the operands and operators of a
+// content stream, from `at` on.
+struct Lexer<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8], at: usize) -> Self {
+        Lexer { data, at }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.at).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.peek() {
+            match b {
+                b'%' => {
+                    while self.peek().is_some_and(|b| b != b'\n' && b != b'\r') {
+                        self.at += 1;
+                    }
+                }
+                b if is_whitespace(b) => self.at += 1,
+                _ => return,
+            }
+        }
+    }
+
+    // The next object, with a reference such as `12 0 R` read as one and a
+    // stream read along with its dictionary.
+    fn object(&mut self) -> Option<Object> {
+        let object = self.token()?;
+        match object {
+            Object::Number(number) if number.fract() == 0.0 && number >= 0.0 => {
+                let start = self.at;
+                if let (Some(Object::Number(_)), Some(Object::Keyword(r))) =
+                    (self.token(), self.token())
+                {
+                    if r == "R" {
+                        return Some(Object::Ref(number as u32));
+                    }
+                }
+                self.at = start;
+                Some(object)
+            }
+            Object::Dict(dict) => {
+                let start = self.at;
+                match self.token() {
+                    Some(Object::Keyword(keyword)) if keyword == "stream" => {
+                        Some(self.stream(dict))
+                    }
+                    _ => {
+                        self.at = start;
+                        Some(Object::Dict(dict))
+                    }
+                }
+            }
+            object => Some(object),
+        }
+    }
+
+    // The data of a stream, after its `stream` keyword.
+    fn stream(&mut self, dict: Dict) -> Object {
+        if self.data[self.at..].starts_with(b"\r\n") {
+            self.at += 2;
+        } else if matches!(self.peek(), Some(b'\n' | b'\r')) {
+            self.at += 1;
+        }
+        let start = self.at;
+        let length = dict
+            .iter()
+            .find(|(k, _)| k == "Length")
+            .and_then(|(_, v)| v.number())
+            .map(|n| n as usize);
+        // The length may be a reference, or wrong, so it's checked against
+        // where the stream ends.
+        let end = match length {
+            Some(len)
+                if self.data.get(start + len..).is_some_and(|rest| {
+                    let rest = &rest[..rest.len().min(16)];
+                    rest.windows(9).any(|w| w == b"endstream")
+                }) =>
+            {
+                start + len
+            }
+            _ => self.data[start..]
+                .windows(9)
+                .position(|w| w == b"endstream")
+                .map_or(self.data.len(), |i| start + i),
+        };
+        self.at = end;
+        Object::Stream(dict, self.data[start..end].to_vec())
+    }
+
+    // Past the data of an inline image, up to its `EI`.
+    fn skip_inline_image(&mut self) {
+        while let Some(object) = self.token() {
+            if matches!(&object, Object::Keyword(k) if k == "ID") {
+                break;
+            }
+        }
+        self.at += 1;
+        while self.at + 2 <= self.data.len() {
+            if self.data[self.at..].starts_with(b"EI")
+                && is_whitespace(self.data[self.at - 1])
+                && self.data.get(self.at + 2).is_none_or(|&b| is_whitespace(b))
+            {
+                self.at += 2;
+                return;
+            }
+            self.at += 1;
+        }
+        self.at = self.data.len();
+    }
+
+    fn token(&mut self) -> Option<Object> {
+        self.skip_whitespace();
+        let b = self.peek()?;
+        match b {
+            b'/' => {
+                self.at += 1;
+                let mut name = Vec::new();
+                while let Some(b) = self
+                    .peek()
+                    .filter(|&b| !is_whitespace(b) && !is_delimiter(b))
+                {
+                    self.at += 1;
+                    // `#` and two hex digits stand for a byte.
+                    let hex = self
+                        .data
+                        .get(self.at..self.at + 2)
+                        .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+                    match (b, hex) {
+                        (b'#', Some(byte)) => {
+                            name.push(byte);
+                            self.at += 2;
+                        }
+                        _ => name.push(b),
+                    }
+                }
+                Some(Object::Name(String::from_utf8_lossy(&name).into_owned()))
+            }
+            b'(' => {
+                self.at += 1;
+                Some(Object::String(self.literal()))
+            }
+            b'<' if self.data.get(self.at + 1) == Some(&b'<') => {
+                self.at += 2;
+                let mut dict = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.data[self.at..].starts_with(b">>") {
+                        self.at += 2;
+                        return Some(Object::Dict(dict));
+                    }
+                    let key = match self.token()? {
+                        Object::Name(key) => key,
+                        _ => continue,
+                    };
+                    let value = self.object()?;
+                    dict.push((key, value));
+                }
+            }
+            b'<' => {
+                self.at += 1;
+                let mut digits = Vec::new();
+                while let Some(b) = self.peek() {
+                    self.at += 1;
+                    match b {
+                        b'>' => break,
+                        b if b.is_ascii_hexdigit() => digits.push(b),
+                        _ => {}
+                    }
+                }
+                if digits.len() % 2 == 1 {
+                    digits.push(b'0');
+                }
+                let bytes = digits
+                    .chunks(2)
+                    .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+                    .collect();
+                Some(Object::String(bytes))
+            }
+            b'[' => {
+                self.at += 1;
+                let mut array = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek()? == b']' {
+                        self.at += 1;
+                        return Some(Object::Array(array));
+                    }
+                    array.push(self.object()?);
+                }
+            }
+            b'+' | b'-' | b'.' | b'0'..=b'9' => {
+                let start = self.at;
+                self.at += 1;
+                while self.peek().is_some_and(|b| b.is_ascii_digit() || b == b'.') {
+                    self.at += 1;
+                }
+                let text = std::str::from_utf8(&self.data[start..self.at]).unwrap_or_default();
+                Some(Object::Number(text.parse().unwrap_or_default()))
+            }
+            // Closing brackets out of place are gone past.
+            b')' | b'>' | b']' | b'{' | b'}' => {
+                self.at += 1;
+                self.token()
+            }
+            _ => {
+                let start = self.at;
+                while self
+                    .peek()
+                    .is_some_and(|b| !is_whitespace(b) && !is_delimiter(b))
+                {
+                    self.at += 1;
+                }
+                let keyword = String::from_utf8_lossy(&self.data[start..self.at]).into_owned();
+                Some(match keyword.as_str() {
+                    "true" | "false" => Object::Bool,
+                    "null" => Object::Null,
+                    _ => Object::Keyword(keyword),
+                })
+            }
+        }
+    }
+
+    // The rest of a literal string, its parentheses nesting.
+    fn literal(&mut self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut depth = 0;
+        while let Some(b) = self.peek() {
+            self.at += 1;
+            match b {
+                b'(' => {
+                    depth += 1;
+                    bytes.push(b);
+                }
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    bytes.push(b);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else {
+                        break;
+                    };
+                    self.at += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'b' => bytes.push(8),
+                        b'f' => bytes.push(12),
+                        b'0'..=b'7' => {
+                            let mut code = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        code = code * 8 + u32::from(d - b'0');
+                                        self.at += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            bytes.push(code as u8);
+                        }
+                        // A backslash at the end of a line joins it to the next.
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.at += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => bytes.push(other),
+                    }
+                }
+                b => bytes.push(b),
+            }
+        }
+        bytes
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0C | 0)
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two pages, the second one's text in a Flate stream, and no
+    // cross-reference table, which isn't gone by.
+    const TWO_PAGES: &[u8] = b"%PDF-1.4
+1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj
+2 0 obj << /Type /Pages /Kids [3 0 R 5 0 R] /Count 2 >> endobj
+3 0 obj << /Type /Page /Parent 2 0 R /Contents 4 0 R >> endobj
+4 0 obj << /Length 54 >> stream
+BT /F1 12 Tf 72 720 Td (Hello) Tj 0 -14 Td (PDF) Tj ET
+endstream endobj
+5 0 obj << /Type /Page /Parent 2 0 R /Contents 6 0 R >> endobj
+6 0 obj << /Length 24 /Filter /FlateDecode >> stream
+x\x9cs\nQ\xd0(\xcf/\xcaI\xd1T\x08\xc9Rp\r\x01\x00)\x06\x04\xc7
+endstream endobj
+%%EOF
+";
+
+    #[test]
+    fn reads_the_text_of_each_page() {
+        let pages = pages(TWO_PAGES).unwrap();
+        assert_eq!(pages, [vec!["Hello", "PDF"], vec!["world"]]);
+    }
+
+    #[test]
+    fn reads_what_is_left_of_a_truncated_file() {
+        // Cut before the first page, there's nothing to show.
+        let first_page = TWO_PAGES.windows(7).position(|w| w == b"3 0 obj").unwrap();
+        assert!(pages(&TWO_PAGES[..first_page]).is_err());
+        for len in 0..TWO_PAGES.len() {
+            let _ = pages(&TWO_PAGES[..len]);
+        }
+    }
+
+    #[test]
+    fn rejects_a_corrupt_file() {
+        assert!(pages(&TWO_PAGES[1..]).is_err());
+        let encrypted = [TWO_PAGES, b"7 0 obj << /Filter /Standard >> endobj\n"].concat();
+        assert!(pages(&encrypted).is_err());
+        for at in 0..TWO_PAGES.len() {
+            let mut data = TWO_PAGES.to_vec();
+            data[at] ^= 0x55;
+            let _ = pages(&data);
+        }
+    }
+}
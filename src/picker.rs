@@ -0,0 +1,170 @@
+use ratatui::widgets::*;
+
+use crate::fuzzy::*;
+use crate::input::*;
+
+/// What the entries of a `Picker` stand for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PickerKind {
+    Bookmarks,
+    SearchHits,
+    GrepHits,
+    FindFile,
+    FindLine,
+    Definitions,
+    SavedSearches,
+    RecentFiles,
+}
+
+impl PickerKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PickerKind::Bookmarks => " Bookmarks ",
+            PickerKind::SearchHits => " Matches in all files ",
+            PickerKind::GrepHits => " Grep ",
+            PickerKind::FindFile => " Find file ",
+            PickerKind::FindLine => " Find line ",
+            PickerKind::Definitions => " Definitions ",
+            PickerKind::SavedSearches => " Saved searches ",
+            PickerKind::RecentFiles => " Recent files ",
+        }
+    }
+}
+
+/// A popup list to choose an entry from. It's hidden while it has no kind.
+/// Some kinds come with a query line narrowing the entries down to the ones
+/// that fuzzily match it.
+#[derive(Default)]
+pub struct Picker {
+    kind: Option<PickerKind>,
+    items: Vec<String>,
+    // Indices of the entries on the list, in the order they are shown.
+    shown: Vec<usize>,
+    query: Option<EntryBox>,
+    state: ListState,
+}
+
+impl Picker {
+    #[inline]
+    pub fn new() -> Self {
+        Picker::default()
+    }
+
+    pub fn open(&mut self, kind: PickerKind, items: Vec<String>) {
+        self.kind = Some(kind);
+        self.query = None;
+        self.state.select(Some(0));
+        self.set_items(items);
+    }
+
+    /// Open the picker with an empty query line in front of the list.
+    pub fn open_with_query(&mut self, kind: PickerKind, items: Vec<String>) {
+        self.open(kind, items);
+        self.query = Some(EntryBox::new());
+    }
+
+    pub fn close(&mut self) {
+        self.kind = None;
+        self.query = None;
+        self.items.clear();
+        self.shown.clear();
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.kind.is_some()
+    }
+
+    #[inline]
+    pub fn kind(&self) -> Option<PickerKind> {
+        self.kind
+    }
+
+    /// The entries on the list, in the order they are shown.
+    pub fn items(&self) -> impl Iterator<Item = &str> {
+        self.shown.iter().map(|&i| self.items[i].as_str())
+    }
+
+    /// Indices of the entries on the list among all of them, in the order
+    /// they are shown.
+    #[inline]
+    pub fn indices(&self) -> &[usize] {
+        &self.shown
+    }
+
+    /// The entry at `index` among all of them, shown or not.
+    #[inline]
+    pub fn item(&self, index: usize) -> Option<&str> {
+        self.items.get(index).map(String::as_str)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shown.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.shown.is_empty()
+    }
+
+    /// Replace the entries keeping the selection within bounds.
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.filter();
+    }
+
+    /// Add entries at the end, as they come in.
+    pub fn extend(&mut self, items: Vec<String>) {
+        self.items.extend(items);
+        self.filter();
+    }
+
+    #[inline]
+    pub fn query(&self) -> Option<&EntryBox> {
+        self.query.as_ref()
+    }
+
+    /// Edit the query line. The entries have to be filtered again afterwards.
+    #[inline]
+    pub fn query_mut(&mut self) -> Option<&mut EntryBox> {
+        self.query.as_mut()
+    }
+
+    /// Show the entries matching the query, best match first.
+    pub fn filter(&mut self) {
+        self.shown = match self.query.as_ref().map(EntryBox::get) {
+            Some(query) if !query.is_empty() => rank(query, &self.items),
+            _ => (0..self.items.len()).collect(),
+        };
+        let index = self
+            .state
+            .selected()
+            .unwrap_or_default()
+            .min(self.shown.len().saturating_sub(1));
+        self.state.select(Some(index));
+    }
+
+    /// Index of the selected entry among all of them, shown or not.
+    #[inline]
+    pub fn selected(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|i| self.shown.get(i).copied())
+    }
+
+    pub fn next(&mut self) {
+        let index = self.state.selected().unwrap_or_default();
+        let last = self.shown.len().saturating_sub(1);
+        self.state.select(Some(index.saturating_add(1).min(last)));
+    }
+
+    pub fn prev(&mut self) {
+        let index = self.state.selected().unwrap_or_default();
+        self.state.select(Some(index.saturating_sub(1)));
+    }
+
+    pub fn get_mut(&mut self) -> &mut ListState {
+        &mut self.state
+    }
+}
@@ -0,0 +1,75 @@
+use ratatui::widgets::*;
+
+use crate::search::*;
+
+/// Results of the last search across files, kept in a pane below the View
+/// pane to be worked through one after the other.
+#[derive(Default)]
+pub struct Quickfix {
+    hits: Vec<Hit>,
+    // The entry jumped to last.
+    index: Option<usize>,
+    visible: bool,
+    state: ListState,
+}
+
+impl Quickfix {
+    #[inline]
+    pub fn new() -> Self {
+        Quickfix::default()
+    }
+
+    /// Replace the entries with `hits` and show the pane.
+    pub fn set(&mut self, hits: Vec<Hit>) {
+        self.hits = hits;
+        self.index = None;
+        self.state.select(None);
+        self.visible = true;
+    }
+
+    /// Add entries at the end, as they come in.
+    pub fn extend(&mut self, hits: &[Hit]) {
+        self.hits.extend_from_slice(hits);
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    #[inline]
+    pub fn hits(&self) -> &[Hit] {
+        &self.hits
+    }
+
+    #[inline]
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Move onto the entry after the current one, the first one to begin with.
+    pub fn forward(&mut self) -> Option<&Hit> {
+        let index = self.index.map_or(0, |i| i + 1);
+        self.select(index)
+    }
+
+    pub fn back(&mut self) -> Option<&Hit> {
+        let index = self.index?.checked_sub(1)?;
+        self.select(index)
+    }
+
+    pub fn get_mut(&mut self) -> &mut ListState {
+        &mut self.state
+    }
+
+    fn select(&mut self, index: usize) -> Option<&Hit> {
+        let hit = self.hits.get(index)?;
+        self.index = Some(index);
+        self.state.select(Some(index));
+        Some(hit)
+    }
+}
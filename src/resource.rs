@@ -1,14 +1,82 @@
 use std::cell::{Ref, RefCell, RefMut};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
+use crate::autoscroll::*;
+use crate::bookmark::*;
+use crate::compare::*;
+use crate::config::*;
+use crate::confirm::*;
 use crate::cursor::*;
+use crate::glob::*;
+use crate::grep::*;
+use crate::group::*;
+use crate::history::*;
+use crate::image::*;
 use crate::input::*;
 use crate::io::*;
+use crate::jump::*;
+use crate::logs::*;
+use crate::mouse::*;
+use crate::picker::*;
+use crate::quickfix::*;
+use crate::search::*;
+use crate::session::*;
+use crate::split::*;
+use crate::substitute::*;
+use crate::toast::*;
+use crate::tree::*;
+use crate::walk::*;
+
+/// Columns the Files pane takes up at first.
+const FILES_WIDTH: u16 = 40;
+/// Columns the Files pane can be narrowed to, or widened to.
+const FILES_WIDTHS: RangeInclusive<u16> = 16..=120;
+/// Columns the Files pane is widened or narrowed by at a time.
+const FILES_STEP: u16 = 4;
 
 pub struct Resource {
+    pub config: Config,
+    pub pending: Pending,
     pub pointer: KeyboardCursor,
     pub entry_box: EntryBox,
+    pub picker: Picker,
+    pub bookmarks: Bookmarks,
+    pub groups: Groups,
+    // The order of the files the last session was left with, for those not
+    // opened in this one to keep their place.
+    pub order: Vec<PathBuf>,
+    pub autoscroll: AutoScroll,
+    pub mouse: Mouse,
+    pub toast: Toast,
+    pub preview: Preview,
+    pub log_rules: LogRules,
+    pub search: Search,
+    // The bytes a hex dump was last searched for.
+    pub byte_pattern: Vec<u8>,
+    pub substitution: Option<Substitution>,
+    // The question asked before touching the files on disk, if any.
+    pub confirm: Option<Confirm>,
+    // Two files shown side by side in place of the file in the View pane.
+    pub compare: Option<Compare>,
+    // The View area divided into splits showing files of their own.
+    pub splits: Splits,
+    // The other layouts of splits, on tabs above the View area.
+    pub tabs: ViewTabs,
+    // Columns the Files pane takes up, as it was resized.
+    pub files_width: u16,
+    // Whether the files `.gitignore` lists are added along with a directory.
+    pub add_ignored: bool,
+    pub search_history: History,
+    pub file_history: History,
+    // Files opened lately, across runs.
+    pub recent_files: History,
+    pub grep: Grep,
+    pub quickfix: Quickfix,
+    pub jumps: JumpList,
+    pub tree: Tree,
     pub file_list_state: RefCell<FileListState>,
     pub files: FileList,
 }
@@ -16,14 +84,119 @@ pub struct Resource {
 impl Resource {
     pub fn new() -> anyhow::Result<Self> {
         let args = Refer::parse();
-        let files = FileList::with_files(args.filename)?;
+        // Directories go into the tree, the files get opened.
+        let mut tree = Tree::new();
+        let mut names = Vec::new();
+        // Files below the directories given, with `--recursive`.
+        let mut below = Vec::new();
+        for name in args.filename {
+            match Path::new(&name).is_dir() {
+                true if args.recursive => {
+                    let walk = if args.all { walk } else { walk_unignored };
+                    walk(Path::new(&name), &mut |path| {
+                        below.push(path.to_path_buf());
+                        true
+                    });
+                }
+                true => tree.add_root(Path::new(&name)),
+                // A glob the shell left alone, as when it's quoted.
+                false if Glob::is_glob(&name) && !Path::new(&name).exists() => {
+                    let files = expand(&name).unwrap_or_else(|err| {
+                        log::trace!("{err}");
+                        Vec::new()
+                    });
+                    names.extend(files.iter().map(|f| f.display().to_string()));
+                }
+                false => names.push(name),
+            }
+        }
+        let mut files = FileList::with_files(names)?;
+        let mut pointer = KeyboardCursor::new();
+        if !tree.is_empty() {
+            pointer.set_cursor::<Files>();
+        }
+        let config = Config::load().unwrap_or_else(|err| {
+            log::trace!("{err}");
+            Config::default()
+        });
+        let bookmarks = Bookmarks::load().unwrap_or_else(|err| {
+            log::trace!("{err}");
+            Bookmarks::default()
+        });
+        let session = Session::load().unwrap_or_else(|err| {
+            log::trace!("{err}");
+            Session::default()
+        });
+        files.arrange(&session.order);
+        let groups = Groups::new(session.groups);
+        files.gather(&groups.of_files(&files));
+
+        let search_history = History::load(SEARCH_HISTORY_FILE).unwrap_or_else(|err| {
+            log::trace!("{err}");
+            History::new(SEARCH_HISTORY_FILE)
+        });
+        let file_history = History::load(FILE_HISTORY_FILE).unwrap_or_else(|err| {
+            log::trace!("{err}");
+            History::new(FILE_HISTORY_FILE)
+        });
+
+        let mut recent_files = History::load(RECENT_FILES_FILE).unwrap_or_else(|err| {
+            log::trace!("{err}");
+            History::new(RECENT_FILES_FILE)
+        });
+        remember_files(&mut recent_files, files.iter().map(|file| file.path()));
+
+        let autoscroll = AutoScroll::new(config.autoscroll_rate());
+        let log_rules = LogRules::new(config.highlights());
 
-        Ok(Resource {
-            pointer: KeyboardCursor::new(),
+        let mut res = Resource {
+            config,
+            pending: Pending::new(),
+            pointer,
             entry_box: EntryBox::new(),
+            picker: Picker::new(),
+            bookmarks,
+            groups,
+            order: session.order,
+            autoscroll,
+            mouse: Mouse::new(),
+            toast: Toast::new(),
+            preview: Preview::detect(),
+            log_rules,
+            search: Search::new(),
+            byte_pattern: Vec::new(),
+            substitution: None,
+            confirm: None,
+            compare: None,
+            splits: session
+                .splits
+                .and_then(|(layout, focused)| Splits::from_layout(&layout, session.panes, focused))
+                .unwrap_or_default(),
+            tabs: ViewTabs::new(),
+            files_width: session.files_width.map_or(FILES_WIDTH, |width| {
+                width.clamp(*FILES_WIDTHS.start(), *FILES_WIDTHS.end())
+            }),
+            add_ignored: args.all,
+            search_history,
+            file_history,
+            recent_files,
+            grep: Grep::new(),
+            quickfix: Quickfix::new(),
+            jumps: JumpList::new(),
+            tree,
             file_list_state: RefCell::new(FileListState::new(files.len())),
             files,
-        })
+        };
+        if !below.is_empty() {
+            add_files(&mut res, below, "the directories given");
+        }
+        refilter_files(&res);
+        Ok(res)
+    }
+
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
     }
 
     #[inline]
@@ -37,7 +210,92 @@ impl Resource {
     }
 
     #[inline]
-    pub fn file_list_state(&self) -> Ref<FileListState> {
+    pub fn picker(&self) -> &Picker {
+        &self.picker
+    }
+
+    #[inline]
+    pub fn bookmarks(&self) -> &Bookmarks {
+        &self.bookmarks
+    }
+
+    #[inline]
+    pub fn groups(&self) -> &Groups {
+        &self.groups
+    }
+
+    #[inline]
+    pub fn autoscroll(&self) -> &AutoScroll {
+        &self.autoscroll
+    }
+
+    #[inline]
+    pub fn mouse(&self) -> &Mouse {
+        &self.mouse
+    }
+
+    #[inline]
+    pub fn toast(&self) -> &Toast {
+        &self.toast
+    }
+
+    #[inline]
+    pub fn preview(&self) -> &Preview {
+        &self.preview
+    }
+
+    #[inline]
+    pub fn log_rules(&self) -> &LogRules {
+        &self.log_rules
+    }
+
+    #[inline]
+    pub fn search(&self) -> &Search {
+        &self.search
+    }
+
+    #[inline]
+    pub fn substitution(&self) -> Option<&Substitution> {
+        self.substitution.as_ref()
+    }
+
+    #[inline]
+    pub fn search_history(&self) -> &History {
+        &self.search_history
+    }
+
+    #[inline]
+    pub fn file_history(&self) -> &History {
+        &self.file_history
+    }
+
+    #[inline]
+    pub fn recent_files(&self) -> &History {
+        &self.recent_files
+    }
+
+    #[inline]
+    pub fn grep(&self) -> &Grep {
+        &self.grep
+    }
+
+    #[inline]
+    pub fn quickfix(&self) -> &Quickfix {
+        &self.quickfix
+    }
+
+    #[inline]
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    #[inline]
+    pub fn jumps(&self) -> &JumpList {
+        &self.jumps
+    }
+
+    #[inline]
+    pub fn file_list_state(&self) -> Ref<'_, FileListState> {
         self.file_list_state.borrow()
     }
 
@@ -46,6 +304,16 @@ impl Resource {
         &self.files
     }
 
+    #[inline]
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    #[inline]
+    pub fn pending_mut(&mut self) -> &mut Pending {
+        &mut self.pending
+    }
+
     #[inline]
     pub fn pointer_mut(&mut self) -> &mut KeyboardCursor {
         &mut self.pointer
@@ -57,7 +325,158 @@ impl Resource {
     }
 
     #[inline]
-    pub fn file_list_state_mut(&self) -> RefMut<FileListState> {
+    pub fn picker_mut(&mut self) -> &mut Picker {
+        &mut self.picker
+    }
+
+    #[inline]
+    pub fn bookmarks_mut(&mut self) -> &mut Bookmarks {
+        &mut self.bookmarks
+    }
+
+    #[inline]
+    pub fn groups_mut(&mut self) -> &mut Groups {
+        &mut self.groups
+    }
+
+    #[inline]
+    pub fn autoscroll_mut(&mut self) -> &mut AutoScroll {
+        &mut self.autoscroll
+    }
+
+    #[inline]
+    pub fn mouse_mut(&mut self) -> &mut Mouse {
+        &mut self.mouse
+    }
+
+    #[inline]
+    pub fn toast_mut(&mut self) -> &mut Toast {
+        &mut self.toast
+    }
+
+    #[inline]
+    pub fn preview_mut(&mut self) -> &mut Preview {
+        &mut self.preview
+    }
+
+    #[inline]
+    pub fn search_mut(&mut self) -> &mut Search {
+        &mut self.search
+    }
+
+    #[inline]
+    pub fn set_substitution(&mut self, substitution: Option<Substitution>) {
+        self.substitution = substitution;
+    }
+
+    #[inline]
+    pub fn byte_pattern(&self) -> &[u8] {
+        &self.byte_pattern
+    }
+
+    pub fn set_byte_pattern(&mut self, pattern: Vec<u8>) {
+        self.byte_pattern = pattern;
+    }
+
+    #[inline]
+    pub fn add_ignored(&self) -> bool {
+        self.add_ignored
+    }
+
+    #[inline]
+    pub fn confirm(&self) -> Option<&Confirm> {
+        self.confirm.as_ref()
+    }
+
+    pub fn set_confirm(&mut self, confirm: Option<Confirm>) {
+        self.confirm = confirm;
+    }
+
+    #[inline]
+    pub fn compare(&self) -> Option<&Compare> {
+        self.compare.as_ref()
+    }
+
+    #[inline]
+    pub fn compare_mut(&mut self) -> Option<&mut Compare> {
+        self.compare.as_mut()
+    }
+
+    pub fn set_compare(&mut self, compare: Option<Compare>) {
+        self.compare = compare;
+    }
+
+    #[inline]
+    pub fn splits(&self) -> &Splits {
+        &self.splits
+    }
+
+    #[inline]
+    pub fn splits_mut(&mut self) -> &mut Splits {
+        &mut self.splits
+    }
+
+    #[inline]
+    pub fn tabs(&self) -> &ViewTabs {
+        &self.tabs
+    }
+
+    #[inline]
+    pub fn tabs_mut(&mut self) -> &mut ViewTabs {
+        &mut self.tabs
+    }
+
+    #[inline]
+    pub fn files_width(&self) -> u16 {
+        self.files_width
+    }
+
+    /// Widen the Files pane, or narrow it.
+    pub fn resize_files(&mut self, wider: bool) {
+        let width = match wider {
+            true => self.files_width.saturating_add(FILES_STEP),
+            false => self.files_width.saturating_sub(FILES_STEP),
+        };
+        self.files_width = width.clamp(*FILES_WIDTHS.start(), *FILES_WIDTHS.end());
+    }
+
+    #[inline]
+    pub fn search_history_mut(&mut self) -> &mut History {
+        &mut self.search_history
+    }
+
+    #[inline]
+    pub fn file_history_mut(&mut self) -> &mut History {
+        &mut self.file_history
+    }
+
+    #[inline]
+    pub fn recent_files_mut(&mut self) -> &mut History {
+        &mut self.recent_files
+    }
+
+    #[inline]
+    pub fn grep_mut(&mut self) -> &mut Grep {
+        &mut self.grep
+    }
+
+    #[inline]
+    pub fn quickfix_mut(&mut self) -> &mut Quickfix {
+        &mut self.quickfix
+    }
+
+    #[inline]
+    pub fn tree_mut(&mut self) -> &mut Tree {
+        &mut self.tree
+    }
+
+    #[inline]
+    pub fn jumps_mut(&mut self) -> &mut JumpList {
+        &mut self.jumps
+    }
+
+    #[inline]
+    pub fn file_list_state_mut(&self) -> RefMut<'_, FileListState> {
         self.file_list_state.borrow_mut()
     }
 
@@ -71,12 +490,92 @@ impl Resource {
 #[command(about, long_about=None)]
 struct Refer {
     filename: Vec<String>,
+    /// Open every file below the directories given, rather than browsing
+    /// them as a tree
+    #[arg(short, long)]
+    recursive: bool,
+    /// Open the files `.gitignore` or `.ignore` list as well, when opening
+    /// the files below a directory
+    #[arg(short, long)]
+    all: bool,
+}
+
+/// Put the files at `paths` first among the recent ones, the last of them
+/// newest, by their absolute paths so they can be reopened from anywhere.
+/// They're saved once for all of them.
+pub fn remember_files<'a>(recent_files: &mut History, paths: impl IntoIterator<Item = &'a Path>) {
+    for path in paths {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        recent_files.add(&path.to_string_lossy());
+    }
+    if let Err(err) = recent_files.save() {
+        log::trace!("{err}");
+    }
+}
+
+/// Save how the Files pane is arranged, so it's arranged so again on the
+/// next run.
+pub fn save_session(res: &Resource) {
+    let open = res.files.iter().map(|f| f.path().to_path_buf());
+    let closed = res
+        .order
+        .iter()
+        .filter(|path| !res.files.iter().any(|f| f.path() == *path))
+        .cloned();
+    // A View area left undivided isn't kept.
+    let (splits, panes) = match res.splits.count() {
+        1 => (None, Vec::new()),
+        _ => {
+            let (layout, panes) = res.splits.layout();
+            (Some((layout, res.splits.focused())), panes)
+        }
+    };
+    let session = Session {
+        groups: res.groups.members().to_vec(),
+        order: open.chain(closed).collect(),
+        files_width: Some(res.files_width),
+        splits,
+        panes,
+    };
+    if let Err(err) = session.save() {
+        log::trace!("{err}");
+    }
+}
+
+/// Match the files against the filter of the Files pane again, laying them
+/// out under their groups.
+pub fn refilter_files(res: &Resource) {
+    let names = res.files.names();
+    let groups = res.groups.of_files(&res.files);
+    res.file_list_state.borrow_mut().refilter(&names, &groups);
 }
 
 pub fn state_update(res: &mut Resource) {
-    res.files_mut().iter_mut().for_each(|f| {
+    let found = res.grep().len();
+    let labels = res.grep_mut().poll();
+    if !labels.is_empty() {
+        res.quickfix.extend(&res.grep.hits()[found..]);
+        if res.picker().kind() == Some(PickerKind::GrepHits) {
+            res.picker_mut().extend(labels);
+        }
+    }
+
+    let lines = res.autoscroll_mut().tick();
+    if lines > 0 {
+        let curr_index = res.file_list_state().index();
+        if let Some(curr_buff) = res.files_mut().get_file_buff_mut(curr_index) {
+            curr_buff.scroll_down(lines);
+        }
+    }
+
+    let smooth_scroll = res.config().smooth_scroll();
+    res.files.iter_mut().for_each(|f| {
         if let Err(err) = f.update() {
             f.nullify(format!("{err}"));
         }
+        f.update_filter(&res.search);
+        f.update_count();
+        f.check_stale();
+        f.animate(smooth_scroll);
     });
 }
@@ -0,0 +1,417 @@
+use std::ops::Range;
+use std::path::Path;
+
+use regex::{Match, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// How the query typed into the search prompt is matched.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    #[default]
+    Regex,
+    Literal,
+    /// Every word of the query has to be found in a line, in any order. A
+    /// word can list alternatives separated by `|`.
+    Terms,
+}
+
+impl SearchMode {
+    fn next(self) -> Self {
+        match self {
+            SearchMode::Regex => SearchMode::Literal,
+            SearchMode::Literal => SearchMode::Terms,
+            SearchMode::Terms => SearchMode::Regex,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SearchMode::Regex => "regex",
+            SearchMode::Literal => "literal",
+            SearchMode::Terms => "all terms",
+        }
+    }
+}
+
+/// Whether letters of the query match letters of the other case.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseMode {
+    /// Ignore case unless the query has an uppercase letter.
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl CaseMode {
+    fn next(self) -> Self {
+        match self {
+            CaseMode::Smart => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Insensitive,
+            CaseMode::Insensitive => CaseMode::Smart,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            CaseMode::Smart => "smart case",
+            CaseMode::Sensitive => "match case",
+            CaseMode::Insensitive => "ignore case",
+        }
+    }
+}
+
+/// A search query kept under a name along with how it's matched, as listed
+/// by `[[searches]]` tables of the configuration file.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    #[serde(default)]
+    pub mode: SearchMode,
+    #[serde(default)]
+    pub case: CaseMode,
+    #[serde(default)]
+    pub whole_word: bool,
+}
+
+impl SavedSearch {
+    pub fn label(&self) -> String {
+        let word = if self.whole_word { ", whole word" } else { "" };
+        let (mode, case) = (self.mode.name(), self.case.name());
+        format!("{}  {} ({mode}, {case}{word})", self.name, self.query)
+    }
+}
+
+/// A line matching the search in some file.
+#[derive(Clone)]
+pub struct Hit {
+    pub path: Box<Path>,
+    /// 1-based line number.
+    pub line: usize,
+    pub label: String,
+    /// Labels of the lines around the match, when asked for context.
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Label of a line around a match, told apart from the match as `grep` does.
+pub fn context_label(name: &str, line: usize, text: &str) -> String {
+    format!("{name}-{line}- {}", text.trim())
+}
+
+/// The compiled query of a search.
+#[derive(Clone)]
+pub struct Matcher {
+    // Finds what's highlighted on a matching line.
+    regex: Regex,
+    // Each of these has to match too, for a search of terms.
+    terms: Vec<Regex>,
+}
+
+impl Matcher {
+    pub fn is_match(&self, line: &str) -> bool {
+        self.terms.iter().all(|t| t.is_match(line)) && self.regex.is_match(line)
+    }
+
+    /// The non-empty capture groups within the matches of a matching
+    /// `line`, along with the number of their group.
+    pub fn groups<'a>(&'a self, line: &'a str) -> impl Iterator<Item = (usize, Match<'a>)> + 'a {
+        let found = self.terms.iter().all(|t| t.is_match(line));
+        let groups = match found {
+            true => self.regex.captures_len(),
+            false => 0,
+        };
+        self.regex
+            .captures_iter(line)
+            .take_while(move |_| groups > 1)
+            .flat_map(move |captures| {
+                (1..groups).filter_map(move |g| captures.get(g).map(|m| (g, m)))
+            })
+            .filter(|(_, m)| !m.is_empty())
+    }
+
+    /// The non-empty matches within a matching `line`.
+    pub fn find_iter<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match<'a>> + 'a {
+        let found = self.terms.iter().all(|t| t.is_match(line));
+        self.regex
+            .find_iter(line)
+            .filter(move |m| found && !m.is_empty())
+    }
+}
+
+/// The query typed into the search prompt, shared by every file buffer.
+#[derive(Default)]
+pub struct Search {
+    query: String,
+    mode: SearchMode,
+    case: CaseMode,
+    // Matches have to start and end at word boundaries.
+    whole_word: bool,
+    // The compiled query. A query that isn't a valid regex is compiled as a
+    // literal one.
+    matcher: Option<Matcher>,
+    error: Option<String>,
+    // Bumped whenever the matches may have changed, so that counts cached
+    // along with it can tell they're stale.
+    generation: usize,
+    // The cursor line the search prompt was opened on.
+    origin: usize,
+    // Matches found by the last search across files.
+    hits: Vec<Hit>,
+}
+
+impl Search {
+    #[inline]
+    pub fn new() -> Self {
+        Search::default()
+    }
+
+    /// Start a new search from the cursor line `origin`.
+    pub fn start(&mut self, origin: usize) {
+        self.set_query("");
+        self.origin = origin;
+    }
+
+    #[inline]
+    pub fn origin(&self) -> usize {
+        self.origin
+    }
+
+    pub fn set_query(&mut self, query: &str) {
+        self.query = query.to_string();
+        self.compile();
+    }
+
+    #[inline]
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn clear(&mut self) {
+        self.set_query("");
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    #[inline]
+    pub fn mode(&self) -> SearchMode {
+        self.mode
+    }
+
+    pub fn cycle_mode(&mut self) {
+        self.mode = self.mode.next();
+        self.compile();
+    }
+
+    #[inline]
+    pub fn case(&self) -> CaseMode {
+        self.case
+    }
+
+    pub fn cycle_case(&mut self) {
+        self.case = self.case.next();
+        self.compile();
+    }
+
+    #[inline]
+    pub fn is_whole_word(&self) -> bool {
+        self.whole_word
+    }
+
+    pub fn toggle_whole_word(&mut self) {
+        self.whole_word = !self.whole_word;
+        self.compile();
+    }
+
+    /// The query and how it's matched, to be kept under `name`.
+    pub fn save(&self, name: &str) -> SavedSearch {
+        SavedSearch {
+            name: name.to_string(),
+            query: self.query.clone(),
+            mode: self.mode,
+            case: self.case,
+            whole_word: self.whole_word,
+        }
+    }
+
+    /// Search for a saved query the way it was matched back then.
+    pub fn restore(&mut self, saved: &SavedSearch) {
+        self.mode = saved.mode;
+        self.case = saved.case;
+        self.whole_word = saved.whole_word;
+        self.set_query(&saved.query);
+    }
+
+    /// Why the query couldn't be used as a regex.
+    #[inline]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn set_hits(&mut self, hits: Vec<Hit>) {
+        self.hits = hits;
+    }
+
+    #[inline]
+    pub fn hit(&self, index: usize) -> Option<&Hit> {
+        self.hits.get(index)
+    }
+
+    /// Title of the prompt `name`, telling how the query is matched.
+    pub fn title(&self, name: &str) -> String {
+        let mode = match (self.mode, self.error.is_some()) {
+            (SearchMode::Regex, true) => "bad regex",
+            (mode, _) => mode.name(),
+        };
+        let word = if self.whole_word { ", whole word" } else { "" };
+        format!(" {name} ({mode}, {}{word}) ", self.case.name())
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        self.matcher.as_ref().is_some_and(|m| m.is_match(line))
+    }
+
+    /// The matches within `line` as ranges of characters. Empty matches of a
+    /// regex are left out.
+    pub fn matches(&self, line: &str) -> Vec<Range<usize>> {
+        let Some(matcher) = &self.matcher else {
+            return Vec::new();
+        };
+        matcher
+            .find_iter(line)
+            .map(|m| {
+                let start = line[..m.start()].chars().count();
+                start..start + m.as_str().chars().count()
+            })
+            .collect()
+    }
+
+    /// The capture groups of the matches within `line` as ranges of
+    /// characters, each with the number of its group. Inner groups come
+    /// before the groups enclosing them.
+    pub fn groups(&self, line: &str) -> Vec<(Range<usize>, usize)> {
+        let Some(matcher) = &self.matcher else {
+            return Vec::new();
+        };
+        let mut groups = matcher
+            .groups(line)
+            .map(|(g, m)| {
+                let start = line[..m.start()].chars().count();
+                (start..start + m.as_str().chars().count(), g)
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by_key(|&(_, g)| std::cmp::Reverse(g));
+        groups
+    }
+
+    /// The compiled query, if there is one.
+    #[inline]
+    pub fn matcher(&self) -> Option<&Matcher> {
+        self.matcher.as_ref()
+    }
+
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// The number of non-empty matches within `line`.
+    pub fn count(&self, line: &str) -> usize {
+        self.matcher
+            .as_ref()
+            .map_or(0, |m| m.find_iter(line).count())
+    }
+
+    fn compile(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        self.matcher = None;
+        self.error = None;
+        if self.query.trim().is_empty() {
+            return;
+        }
+        let ignore_case = match self.case {
+            CaseMode::Smart => !has_uppercase(&self.query, self.mode),
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+        };
+        let whole_word = self.whole_word;
+        let build = |pattern: &str| {
+            // Half boundaries still let a query start or end with punctuation.
+            let pattern = match whole_word {
+                true => format!(r"\b{{start-half}}(?:{pattern})\b{{end-half}}"),
+                false => pattern.to_string(),
+            };
+            RegexBuilder::new(&pattern)
+                .case_insensitive(ignore_case)
+                .build()
+        };
+        let literal = regex::escape(&self.query);
+        let pattern = match self.mode {
+            SearchMode::Regex => self.query.clone(),
+            SearchMode::Literal => literal.clone(),
+            SearchMode::Terms => {
+                let terms = terms(&self.query);
+                let terms = terms
+                    .iter()
+                    .map(|t| build(t))
+                    .collect::<Result<Vec<_>, _>>();
+                let any = alternatives(&self.query).collect::<Vec<_>>().join("|");
+                self.matcher = match (terms, build(&any)) {
+                    (Ok(terms), Ok(regex)) if !terms.is_empty() => Some(Matcher { regex, terms }),
+                    _ => None,
+                };
+                return;
+            }
+        };
+        let matcher = |regex| Matcher {
+            regex,
+            terms: Vec::new(),
+        };
+        match build(&pattern) {
+            Ok(regex) => self.matcher = Some(matcher(regex)),
+            // The message of a syntax error ends with a one-line summary.
+            Err(err) => {
+                let err = err.to_string();
+                let summary = err.lines().rfind(|l| !l.trim().is_empty());
+                let summary = summary.unwrap_or_default().trim();
+                self.error = Some(summary.trim_start_matches("error: ").to_string());
+                self.matcher = build(&literal).ok().map(matcher);
+            }
+        }
+    }
+}
+
+// Whether the query spells out an uppercase letter. Escapes of a regex such
+// as `\S` don't count.
+fn has_uppercase(query: &str, mode: SearchMode) -> bool {
+    let mut escaped = false;
+    query.chars().any(|c| {
+        let upper = c.is_uppercase() && !escaped;
+        escaped = mode == SearchMode::Regex && c == '\\' && !escaped;
+        upper
+    })
+}
+
+// A pattern for each word of the query, matching any of its alternatives.
+fn terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|word| alternatives(word).collect::<Vec<_>>().join("|"))
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+// The escaped `|`-separated alternatives of each word of `query`.
+fn alternatives(query: &str) -> impl Iterator<Item = String> + '_ {
+    query
+        .split_whitespace()
+        .flat_map(|word| word.split('|'))
+        .filter(|alt| !alt.is_empty())
+        .map(regex::escape)
+}
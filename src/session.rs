@@ -0,0 +1,94 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+
+use crate::store::*;
+
+const SESSION_FILE: &str = "session";
+
+/// How the Files pane and the View area were arranged, kept in the session
+/// file of the data directory so they're arranged so again on the next run.
+#[derive(Default)]
+pub struct Session {
+    /// The group of every file in one, by absolute path.
+    pub groups: Vec<(String, PathBuf)>,
+    /// The files in the order they were moved to, those opened in earlier
+    /// runs only after the others.
+    pub order: Vec<PathBuf>,
+    /// Columns the Files pane was resized to.
+    pub files_width: Option<u16>,
+    /// How the View area was divided, as `Splits::layout` writes it, and
+    /// the split the focus was on.
+    pub splits: Option<(String, usize)>,
+    /// The files the splits showed, in the order they're counted.
+    pub panes: Vec<PathBuf>,
+}
+
+impl Session {
+    /// Read the session file. Having none yet isn't an error.
+    pub fn load() -> anyhow::Result<Self> {
+        let file = data_file(SESSION_FILE)?;
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(anyhow!("Couldn't read the session due to: {err}")),
+        };
+        let mut session = Session::default();
+        // One entry per line, told by its first field:
+        // `group<TAB>name<TAB>path`, `file<TAB>path`, `files_width<TAB>columns`,
+        // `splits<TAB>focused<TAB>layout` or `pane<TAB>path`.
+        for line in content.lines() {
+            let mut fields = line.splitn(3, '\t');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some("group"), Some(group), Some(path)) => {
+                    session
+                        .groups
+                        .push((group.to_string(), PathBuf::from(path)));
+                }
+                (Some("file"), Some(path), None) => session.order.push(PathBuf::from(path)),
+                (Some("files_width"), Some(width), None) => {
+                    session.files_width = width.parse().ok()
+                }
+                (Some("splits"), Some(focused), Some(layout)) => {
+                    session.splits = focused.parse().ok().map(|f| (layout.to_string(), f));
+                }
+                (Some("pane"), Some(path), None) => session.panes.push(PathBuf::from(path)),
+                // Entries of a later version are left out.
+                _ => {}
+            }
+        }
+        Ok(session)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let file = data_file(SESSION_FILE)?;
+        let groups = self
+            .groups
+            .iter()
+            .map(|(group, path)| format!("group\t{group}\t{}\n", path.display()));
+        let order = self
+            .order
+            .iter()
+            .map(|path| format!("file\t{}\n", path.display()));
+        let files_width = self
+            .files_width
+            .map(|width| format!("files_width\t{width}\n"));
+        let splits = self
+            .splits
+            .iter()
+            .map(|(layout, focused)| format!("splits\t{focused}\t{layout}\n"));
+        let panes = self
+            .panes
+            .iter()
+            .map(|path| format!("pane\t{}\n", path.display()));
+        let content = groups
+            .chain(order)
+            .chain(files_width)
+            .chain(splits)
+            .chain(panes)
+            .collect::<String>();
+        fs::write(file, content).map_err(|err| anyhow!("Couldn't save the session due to: {err}"))
+    }
+}
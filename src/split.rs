@@ -0,0 +1,451 @@
+use std::path::{Path, PathBuf};
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+use crate::view::*;
+
+/// A file shown in a split of the View area, and where it was scrolled to
+/// while another split had the focus.
+#[derive(Default)]
+pub struct Pane {
+    pub path: PathBuf,
+    pub view: ViewState,
+}
+
+/// The share of its split a split starts out with.
+const SHARE: u32 = 100;
+/// Least share a split can be shrunk to.
+const MIN_SHARE: u32 = 20;
+/// Share a split grows or shrinks by at a time.
+const SHARE_STEP: i32 = 10;
+/// Greatest share a split read from the session file is given.
+const MAX_SHARE: u32 = 100 * SHARE;
+/// How deep splits read from the session file may nest.
+const MAX_DEPTH: usize = 16;
+
+// Splits nest, a split divided the other way holding splits of its own.
+// Every node of a split takes up its share of it.
+enum Node {
+    Pane(Pane),
+    Split(Direction, Vec<(u32, Node)>),
+}
+
+impl Node {
+    fn count(&self) -> usize {
+        match self {
+            Node::Pane(_) => 1,
+            Node::Split(_, nodes) => nodes.iter().map(|(_, node)| node.count()).sum(),
+        }
+    }
+
+    // The panes from the top left to the bottom right, the order they are
+    // counted in.
+    fn panes_mut<'a>(&'a mut self, panes: &mut Vec<&'a mut Pane>) {
+        match self {
+            Node::Pane(pane) => panes.push(pane),
+            Node::Split(_, nodes) => nodes.iter_mut().for_each(|(_, node)| node.panes_mut(panes)),
+        }
+    }
+
+    fn areas(&self, area: Rect, areas: &mut Vec<Rect>) {
+        match self {
+            Node::Pane(_) => areas.push(area),
+            Node::Split(direction, nodes) => {
+                let total = nodes.iter().map(|&(share, _)| share).sum();
+                let parts = Layout::default()
+                    .direction(*direction)
+                    .constraints(
+                        nodes
+                            .iter()
+                            .map(|&(share, _)| Constraint::Ratio(share, total)),
+                    )
+                    .split(area);
+                for ((_, node), &part) in nodes.iter().zip(parts.iter()) {
+                    node.areas(part, areas);
+                }
+            }
+        }
+    }
+
+    // Put `pane` in front of pane `index`, the two sharing its place along
+    // `direction`.
+    fn insert(&mut self, index: usize, direction: Direction, pane: Pane) {
+        let Node::Split(along, nodes) = self else {
+            let node = std::mem::replace(self, Node::Split(direction, Vec::new()));
+            *self = Node::Split(direction, vec![(SHARE, Node::Pane(pane)), (SHARE, node)]);
+            return;
+        };
+        let (i, start) = locate(nodes, index);
+        match nodes[i] {
+            (share, Node::Pane(_)) if *along == direction => {
+                nodes[i].0 = share.div_ceil(2);
+                nodes.insert(i, ((share / 2).max(1), Node::Pane(pane)));
+            }
+            _ => nodes[i].1.insert(index - start, direction, pane),
+        }
+    }
+
+    // Take pane `index` out, a split left with a single node giving its
+    // place to that node.
+    fn remove(&mut self, index: usize) -> Option<Pane> {
+        let Node::Split(along, nodes) = self else {
+            return None;
+        };
+        let (i, start) = locate(nodes, index);
+        let pane = match nodes[i] {
+            (_, Node::Pane(_)) => match nodes.remove(i) {
+                (_, Node::Pane(pane)) => Some(pane),
+                (_, Node::Split(..)) => None,
+            },
+            _ => nodes[i].1.remove(index - start),
+        };
+        // A split divided the same way as the one holding it merges into it,
+        // its nodes taking their part of its share.
+        if let Some((_, Node::Split(direction, _))) = nodes.get(i) {
+            if direction == along {
+                if let (share, Node::Split(_, inner)) = nodes.remove(i) {
+                    let total = inner.iter().map(|&(s, _)| s).sum::<u32>().max(1);
+                    let inner = inner
+                        .into_iter()
+                        .map(|(s, node)| ((share * s / total).max(1), node));
+                    nodes.splice(i..i, inner);
+                }
+            }
+        }
+        if nodes.len() == 1 {
+            if let Some((_, node)) = nodes.pop() {
+                *self = node;
+            }
+        }
+        pane
+    }
+
+    // Grow the split holding pane `index` along `direction` by `step`, or
+    // shrink it. The innermost split divided that way gives way. Returns
+    // false if there is none.
+    fn resize(&mut self, index: usize, direction: Direction, step: i32) -> bool {
+        let Node::Split(along, nodes) = self else {
+            return false;
+        };
+        let (i, start) = locate(nodes, index);
+        if nodes[i].1.resize(index - start, direction, step) {
+            return true;
+        }
+        if *along != direction {
+            return false;
+        }
+        // A split left smaller than that by dividing it isn't grown by
+        // shrinking it.
+        let share = nodes[i].0.saturating_add_signed(step);
+        nodes[i].0 = share.max(MIN_SHARE.min(nodes[i].0));
+        true
+    }
+
+    // Write the node out as `h(100 p,100 v(60 p,140 p))` for a split side by
+    // side whose second half is divided top and bottom, the files of its
+    // panes going to `paths`.
+    fn write(&self, layout: &mut String, paths: &mut Vec<PathBuf>) {
+        match self {
+            Node::Pane(pane) => {
+                layout.push('p');
+                paths.push(pane.path.clone());
+            }
+            Node::Split(direction, nodes) => {
+                layout.push(match direction {
+                    Direction::Horizontal => 'h',
+                    Direction::Vertical => 'v',
+                });
+                layout.push('(');
+                for (i, (share, node)) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        layout.push(',');
+                    }
+                    layout.push_str(&format!("{share} "));
+                    node.write(layout, paths);
+                }
+                layout.push(')');
+            }
+        }
+    }
+
+    // The node `layout` starts with, as `write` wrote it, its panes showing
+    // the files of `paths` in turn. `layout` is left with what follows.
+    fn read(
+        layout: &mut &str,
+        paths: &mut impl Iterator<Item = PathBuf>,
+        depth: usize,
+    ) -> Option<Node> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let (kind, rest) = layout.split_at_checked(1)?;
+        *layout = rest;
+        let direction = match kind {
+            "p" => {
+                let path = paths.next().unwrap_or_default();
+                return Some(Node::Pane(Pane {
+                    path,
+                    ..Pane::default()
+                }));
+            }
+            "h" => Direction::Horizontal,
+            "v" => Direction::Vertical,
+            _ => return None,
+        };
+        *layout = layout.strip_prefix('(')?;
+        let mut nodes = Vec::new();
+        loop {
+            let (share, rest) = layout.split_once(' ')?;
+            *layout = rest;
+            let share = share.parse::<u32>().ok()?.clamp(1, MAX_SHARE);
+            nodes.push((share, Node::read(layout, paths, depth + 1)?));
+            let (next, rest) = layout.split_at_checked(1)?;
+            *layout = rest;
+            match next {
+                "," => {}
+                ")" => break,
+                _ => return None,
+            }
+        }
+        // A split holds two nodes at least.
+        (nodes.len() > 1).then_some(Node::Split(direction, nodes))
+    }
+
+    // Give every split the same share of the split holding it.
+    fn even(&mut self) {
+        if let Node::Split(_, nodes) = self {
+            for (share, node) in nodes {
+                *share = SHARE;
+                node.even();
+            }
+        }
+    }
+}
+
+// The node holding pane `index` among `nodes`, and the number of panes
+// before that node.
+fn locate(nodes: &[(u32, Node)], index: usize) -> (usize, usize) {
+    let mut start = 0;
+    for (i, (_, node)) in nodes.iter().enumerate() {
+        let count = node.count();
+        if index < start + count {
+            return (i, start);
+        }
+        start += count;
+    }
+    (nodes.len().saturating_sub(1), start)
+}
+
+/// The View area divided into splits, each showing a file of its own or
+/// another part of the same file. The focused split shows the file opened
+/// from the Files pane, the others hold on to what they were left showing.
+pub struct Splits {
+    root: Node,
+    focused: usize,
+}
+
+impl Default for Splits {
+    fn default() -> Self {
+        Splits {
+            root: Node::Pane(Pane::default()),
+            focused: 0,
+        }
+    }
+}
+
+impl Splits {
+    #[inline]
+    pub fn new() -> Self {
+        Splits::default()
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.root.count()
+    }
+
+    #[inline]
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// Divide the focused split in two along `direction`. `pane` takes the
+    /// first half and the focus moves on to the second.
+    pub fn split(&mut self, direction: Direction, pane: Pane) {
+        self.root.insert(self.focused, direction, pane);
+        self.focused += 1;
+    }
+
+    /// Move the focus to split `index`, the focused one keeping `pane`.
+    /// Returns the pane the split focused was left showing.
+    pub fn focus(&mut self, index: usize, pane: Pane) -> Pane {
+        if let Some(left) = self.pane_mut(self.focused) {
+            *left = pane;
+        }
+        self.focused = index.min(self.count() - 1);
+        self.pane_mut(self.focused)
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Close the focused split, the one before it taking the focus. Returns
+    /// the pane that one was left showing, none if there is a single split.
+    pub fn close(&mut self) -> Option<Pane> {
+        self.root.remove(self.focused)?;
+        self.focused = self.focused.saturating_sub(1);
+        self.pane_mut(self.focused).map(std::mem::take)
+    }
+
+    /// Close every split but the focused one.
+    pub fn only(&mut self) {
+        *self = Splits::new();
+    }
+
+    /// Grow the focused split along `direction`, or shrink it. Returns false
+    /// if no split is divided that way.
+    pub fn resize(&mut self, direction: Direction, grow: bool) -> bool {
+        let step = if grow { SHARE_STEP } else { -SHARE_STEP };
+        self.root.resize(self.focused, direction, step)
+    }
+
+    /// Give every split the same room.
+    pub fn even(&mut self) {
+        self.root.even();
+    }
+
+    pub fn pane_mut(&mut self, index: usize) -> Option<&mut Pane> {
+        let mut panes = Vec::new();
+        self.root.panes_mut(&mut panes);
+        panes.into_iter().nth(index)
+    }
+
+    /// The part of `area` every split takes up, in the order they're counted.
+    pub fn areas(&self, area: Rect) -> Vec<Rect> {
+        let mut areas = Vec::new();
+        self.root.areas(area, &mut areas);
+        areas
+    }
+
+    /// Keep showing the file at `from` after it was renamed to `to`.
+    pub fn rename_path(&mut self, from: &Path, to: &Path) {
+        let mut panes = Vec::new();
+        self.root.panes_mut(&mut panes);
+        for pane in panes.into_iter().filter(|pane| pane.path == from) {
+            pane.path = to.to_path_buf();
+        }
+    }
+
+    /// The splits written out for the session file: how they nest and share
+    /// their room, and the files they show in the order they're counted.
+    pub fn layout(&self) -> (String, Vec<PathBuf>) {
+        let (mut layout, mut paths) = (String::new(), Vec::new());
+        self.root.write(&mut layout, &mut paths);
+        (layout, paths)
+    }
+
+    /// The splits `layout` wrote, showing the files of `paths` with the
+    /// focus on split `focused`. None if `layout` doesn't read as splits.
+    pub fn from_layout(layout: &str, paths: Vec<PathBuf>, focused: usize) -> Option<Self> {
+        let mut rest = layout;
+        let root = Node::read(&mut rest, &mut paths.into_iter(), 0)?;
+        if !rest.is_empty() {
+            return None;
+        }
+        let focused = focused.min(root.count() - 1);
+        let mut splits = Splits { root, focused };
+        // The focused split shows the file opened from the Files pane.
+        if let Some(pane) = splits.pane_mut(focused) {
+            *pane = Pane::default();
+        }
+        Some(splits)
+    }
+}
+
+// A tab of the View area, the layout of its splits and what its focused
+// split shows while another tab is shown.
+#[derive(Default)]
+struct Tab {
+    splits: Splits,
+    pane: Pane,
+}
+
+/// Tabs above the View area, each holding a layout of splits of its own.
+/// The layout of the current tab is the one in use, what's kept for it
+/// here goes stale until another tab is moved to.
+pub struct ViewTabs {
+    tabs: Vec<Tab>,
+    current: usize,
+}
+
+impl Default for ViewTabs {
+    fn default() -> Self {
+        ViewTabs {
+            tabs: vec![Tab::default()],
+            current: 0,
+        }
+    }
+}
+
+impl ViewTabs {
+    #[inline]
+    pub fn new() -> Self {
+        ViewTabs::default()
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.tabs.len()
+    }
+
+    #[inline]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The file the focused split of tab `index` shows.
+    pub fn path(&self, index: usize) -> Option<&Path> {
+        self.tabs.get(index).map(|tab| tab.pane.path.as_path())
+    }
+
+    /// Open a tab after the current one, the current one keeping `splits`
+    /// and `pane`, what its focused split shows.
+    pub fn open(&mut self, splits: Splits, pane: Pane) {
+        self.tabs[self.current] = Tab { splits, pane };
+        self.current += 1;
+        self.tabs.insert(self.current, Tab::default());
+    }
+
+    /// Move to tab `index`, the current one keeping `splits` and `pane`.
+    /// Returns the layout of the tab moved to and what its focused split
+    /// was left showing.
+    pub fn switch(&mut self, index: usize, splits: Splits, pane: Pane) -> (Splits, Pane) {
+        self.tabs[self.current] = Tab { splits, pane };
+        self.current = index.min(self.tabs.len() - 1);
+        let tab = std::mem::take(&mut self.tabs[self.current]);
+        (tab.splits, tab.pane)
+    }
+
+    /// Close the current tab, the one before it becoming the current one.
+    /// Returns its layout and what its focused split was left showing, none
+    /// if there is a single tab.
+    pub fn close(&mut self) -> Option<(Splits, Pane)> {
+        if self.tabs.len() == 1 {
+            return None;
+        }
+        self.tabs.remove(self.current);
+        self.current = self.current.saturating_sub(1);
+        let tab = std::mem::take(&mut self.tabs[self.current]);
+        Some((tab.splits, tab.pane))
+    }
+
+    /// Keep showing the file at `from` on the other tabs after it was
+    /// renamed to `to`.
+    pub fn rename_path(&mut self, from: &Path, to: &Path) {
+        for tab in &mut self.tabs {
+            tab.splits.rename_path(from, to);
+            if tab.pane.path == from {
+                tab.pane.path = to.to_path_buf();
+            }
+        }
+    }
+}
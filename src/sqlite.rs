@@ -0,0 +1,593 @@
+use std::mem;
+
+use anyhow::anyhow;
+
+const MAGIC: &[u8] = b"SQLite format 3\0";
+const HEADER_SIZE: usize = 100;
+// Kinds of b-tree pages, those of tables keeping rows by their rowid and
+// those of indexes keeping them by their keys.
+const INTERIOR_INDEX: u8 = 2;
+const INTERIOR_TABLE: u8 = 5;
+const LEAF_INDEX: u8 = 10;
+const LEAF_TABLE: u8 = 13;
+/// Bytes of a blob shown in its cell, the rest cut short.
+const BLOB_SHOWN: usize = 16;
+
+/// The tables of the database, with the number of their rows, in the order
+/// the schema lists them. Virtual tables, holding no rows of their own,
+/// are left out.
+pub fn tables(data: &[u8]) -> anyhow::Result<Vec<(String, u64)>> {
+    let database = Database::open(data)?;
+    let mut tables = Vec::new();
+    for entry in database.schema()? {
+        if entry.kind != "table" || entry.root == 0 {
+            continue;
+        }
+        let mut rows = 0;
+        database.walk(entry.root, &mut |_, _| {
+            rows += 1;
+            Ok(())
+        })?;
+        tables.push((entry.name, rows));
+    }
+    Ok(tables)
+}
+
+/// The statements the tables, indexes, views and triggers of the database
+/// were created by, a blank line between them.
+pub fn schema(data: &[u8]) -> anyhow::Result<Vec<String>> {
+    let database = Database::open(data)?;
+    let mut lines = Vec::new();
+    for entry in database.schema()? {
+        let Some(sql) = entry.sql else {
+            continue;
+        };
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(format!("{sql};").lines().map(str::to_string));
+    }
+    Ok(lines)
+}
+
+/// The rows of the table `name` as a TSV file, a header row naming its
+/// columns first.
+pub fn rows(data: &[u8], name: &str) -> anyhow::Result<Vec<u8>> {
+    let database = Database::open(data)?;
+    let entry = database
+        .schema()?
+        .into_iter()
+        .find(|entry| entry.kind == "table" && entry.name == name && entry.root != 0)
+        .ok_or_else(|| anyhow!("No table {name} in the database"))?;
+    let columns = Columns::parse(entry.sql.as_deref().unwrap_or_default());
+    let mut out = columns.names.join("\t");
+    out.push('\n');
+    database.walk(entry.root, &mut |rowid, payload| {
+        let mut values = database.record(&payload)?;
+        // The column aliasing the rowid is kept as a NULL.
+        if let (Some(rowid), Some(alias)) = (rowid, columns.rowid) {
+            if let Some(value @ Value::Null) = values.get_mut(alias) {
+                *value = Value::Integer(rowid);
+            }
+        }
+        let values = columns.declared(values);
+        let cells = values.iter().map(Value::cell).collect::<Vec<_>>();
+        out.push_str(&cells.join("\t"));
+        out.push('\n');
+        Ok(())
+    })?;
+    Ok(out.into_bytes())
+}
+
+// What the schema holds about a table, an index, a view or a trigger.
+struct Entry {
+    kind: String,
+    name: String,
+    root: u32,
+    sql: Option<String>,
+}
+
+enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    // The value as a cell of a TSV file, its tabs and line breaks escaped.
+    fn cell(&self) -> String {
+        match self {
+            Value::Null => "NULL".to_string(),
+            Value::Integer(n) => n.to_string(),
+            Value::Real(n) => format!("{n:?}"),
+            Value::Text(text) => text
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+            Value::Blob(bytes) => {
+                let hex = bytes
+                    .iter()
+                    .take(BLOB_SHOWN)
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<String>();
+                let more = if bytes.len() > BLOB_SHOWN { "…" } else { "" };
+                format!("x'{hex}{more}'")
+            }
+        }
+    }
+
+    fn text(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+// Called with the rowid and the payload of each row of a b-tree.
+type Visit<'v> = dyn FnMut(Option<i64>, Vec<u8>) -> anyhow::Result<()> + 'v;
+
+struct Database<'a> {
+    data: &'a [u8],
+    page_size: usize,
+    // Bytes of each page not reserved for extensions.
+    usable: usize,
+    // How text is stored: 1 for UTF-8, 2 and 3 for UTF-16 little and big
+    // endian.
+    encoding: u8,
+}
+
+impl<'a> Database<'a> {
+    fn open(data: &'a [u8]) -> anyhow::Result<Self> {
+        if !data.starts_with(MAGIC) || data.len() < HEADER_SIZE {
+            return Err(anyhow!("Not an SQLite database"));
+        }
+        let page_size = match u16::from_be_bytes([data[16], data[17]]) {
+            1 => 65536,
+            size => usize::from(size),
+        };
+        if !page_size.is_power_of_two() || page_size < 512 {
+            return Err(anyhow!("Bad SQLite page size {page_size}"));
+        }
+        Ok(Database {
+            data,
+            page_size,
+            usable: page_size - usize::from(data[20]),
+            encoding: data[59],
+        })
+    }
+
+    // The page numbered `number`, counting from 1.
+    fn page(&self, number: u32) -> anyhow::Result<&'a [u8]> {
+        let start = (number as usize)
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("SQLite page 0 referred to"))?
+            * self.page_size;
+        self.data
+            .get(start..start + self.page_size)
+            .ok_or_else(|| anyhow!("SQLite page {number} past the end of the file"))
+    }
+
+    fn page_count(&self) -> usize {
+        self.data.len() / self.page_size
+    }
+
+    // The entries of the schema, kept in the table whose root is page 1.
+    fn schema(&self) -> anyhow::Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        self.walk(1, &mut |_, payload| {
+            let values = self.record(&payload)?;
+            let text = |i: usize| values.get(i).and_then(Value::text).map(str::to_string);
+            let root = match values.get(3) {
+                Some(Value::Integer(root)) => u32::try_from(*root).unwrap_or_default(),
+                _ => 0,
+            };
+            entries.push(Entry {
+                kind: text(0).unwrap_or_default(),
+                name: text(1).unwrap_or_default(),
+                root,
+                sql: text(4),
+            });
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    // Visit the rows of the b-tree whose root is the page `root` in order,
+    // with their rowid if it's the tree of a table.
+    fn walk(&self, root: u32, visit: &mut Visit) -> anyhow::Result<()> {
+        let mut pages = 0;
+        self.visit(root, visit, &mut pages)
+    }
+
+    fn visit(&self, number: u32, visit: &mut Visit, pages: &mut usize) -> anyhow::Result<()> {
+        // A damaged file may have its pages point at each other in a loop.
+        *pages += 1;
+        if *pages > self.page_count() {
+            return Err(anyhow!("SQLite b-tree visits more pages than there are"));
+        }
+        let page = self.page(number)?;
+        let header = if number == 1 { HEADER_SIZE } else { 0 };
+        let kind = page[header];
+        let interior = match kind {
+            INTERIOR_INDEX | INTERIOR_TABLE => true,
+            LEAF_INDEX | LEAF_TABLE => false,
+            _ => return Err(anyhow!("Bad SQLite b-tree page {number}")),
+        };
+        let count = usize::from(u16_at(page, header + 3)?);
+        let pointers = header + if interior { 12 } else { 8 };
+        for i in 0..count {
+            let mut at = usize::from(u16_at(page, pointers + 2 * i)?);
+            if interior {
+                self.visit(u32_at(page, at)?, visit, pages)?;
+                at += 4;
+            }
+            match kind {
+                LEAF_TABLE => {
+                    let size = varint(page, &mut at)? as usize;
+                    let rowid = varint(page, &mut at)? as i64;
+                    visit(Some(rowid), self.payload(page, at, size, true)?)?;
+                }
+                // The keys of an index are its rows, those of the interior
+                // pages coming between those of their children.
+                LEAF_INDEX | INTERIOR_INDEX => {
+                    let size = varint(page, &mut at)? as usize;
+                    visit(None, self.payload(page, at, size, false)?)?;
+                }
+                _ => {}
+            }
+        }
+        if interior {
+            self.visit(u32_at(page, header + 8)?, visit, pages)?;
+        }
+        Ok(())
+    }
+
+    // The payload of `size` bytes at `at` on `page`, the part of it that
+    // doesn't fit there read from the chain of overflow pages after it.
+    fn payload(&self, page: &[u8], at: usize, size: usize, table: bool) -> anyhow::Result<Vec<u8>> {
+        let usable = self.usable;
+        let most = match table {
+            true => usable - 35,
+            false => (usable - 12) * 64 / 255 - 23,
+        };
+        let local = match size <= most {
+            true => size,
+            false => {
+                let least = (usable - 12) * 32 / 255 - 23;
+                let local = least + (size - least) % (usable - 4);
+                if local <= most {
+                    local
+                } else {
+                    least
+                }
+            }
+        };
+        let mut payload = page
+            .get(at..at + local)
+            .ok_or_else(|| anyhow!("SQLite cell runs past its page"))?
+            .to_vec();
+        if local == size {
+            return Ok(payload);
+        }
+        let mut next = u32_at(page, at + local)?;
+        let mut pages = 0;
+        while payload.len() < size {
+            pages += 1;
+            if next == 0 || pages > self.page_count() {
+                return Err(anyhow!("Damaged SQLite overflow chain"));
+            }
+            let overflow = self.page(next)?;
+            next = u32_at(overflow, 0)?;
+            let take = (size - payload.len()).min(usable - 4);
+            payload.extend_from_slice(&overflow[4..4 + take]);
+        }
+        Ok(payload)
+    }
+
+    // The values of a record: a header of the types of its values, and the
+    // values after it.
+    fn record(&self, payload: &[u8]) -> anyhow::Result<Vec<Value>> {
+        let mut at = 0;
+        let header_size = varint(payload, &mut at)? as usize;
+        let mut body = header_size;
+        let mut values = Vec::new();
+        while at < header_size {
+            let serial = varint(payload, &mut at)?;
+            let len = match serial {
+                0 | 8 | 9 | 10 | 11 => 0,
+                1..=4 => serial as usize,
+                5 => 6,
+                6 | 7 => 8,
+                _ => (serial as usize - 12) / 2,
+            };
+            let bytes = payload
+                .get(body..body + len)
+                .ok_or_else(|| anyhow!("SQLite record runs past its payload"))?;
+            body += len;
+            values.push(match serial {
+                0 | 10 | 11 => Value::Null,
+                1..=6 => {
+                    // Big endian, the sign taken from the first byte.
+                    let first = i64::from(bytes[0] as i8);
+                    let n = bytes[1..].iter().fold(first, |n, &b| n << 8 | i64::from(b));
+                    Value::Integer(n)
+                }
+                7 => {
+                    let n = bytes.iter().fold(0u64, |n, &b| n << 8 | u64::from(b));
+                    Value::Real(f64::from_bits(n))
+                }
+                8 => Value::Integer(0),
+                9 => Value::Integer(1),
+                _ if serial % 2 == 0 => Value::Blob(bytes.to_vec()),
+                _ => Value::Text(self.text(bytes)),
+            });
+        }
+        Ok(values)
+    }
+
+    fn text(&self, bytes: &[u8]) -> String {
+        let units = bytes.chunks_exact(2).map(|pair| match self.encoding {
+            2 => u16::from_le_bytes([pair[0], pair[1]]),
+            _ => u16::from_be_bytes([pair[0], pair[1]]),
+        });
+        match self.encoding {
+            2 | 3 => char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect(),
+            _ => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+}
+
+// The columns of a table, read from the statement it was created by.
+struct Columns {
+    names: Vec<String>,
+    // The column that's the rowid under another name, an INTEGER PRIMARY
+    // KEY one.
+    rowid: Option<usize>,
+    // The column each value of a record holds, if they're held in another
+    // order than the columns were declared in. A table without a rowid
+    // keeps those of its primary key first.
+    order: Option<Vec<usize>>,
+}
+
+impl Columns {
+    fn parse(sql: &str) -> Self {
+        let (columns, rest) = definitions(sql);
+        let mut names = Vec::new();
+        let mut key = Vec::new();
+        let mut rowid = None;
+        for definition in &columns {
+            let (first, after) = token(definition);
+            let upper = after.to_ascii_uppercase();
+            match first.to_ascii_uppercase().as_str() {
+                "PRIMARY" | "CONSTRAINT" | "UNIQUE" | "CHECK" | "FOREIGN" => {
+                    // PRIMARY KEY (a, b) names the columns of the key.
+                    let primary = match first.eq_ignore_ascii_case("PRIMARY") {
+                        true => Some(0),
+                        false => upper.find("PRIMARY KEY"),
+                    };
+                    if let Some(at) = primary {
+                        let (columns, _) = definitions(&after[at..]);
+                        key = columns.iter().map(|c| token(c).0).collect();
+                    }
+                }
+                _ => {
+                    if upper.contains("PRIMARY KEY") {
+                        if upper.trim_start().starts_with("INTEGER") {
+                            rowid = Some(names.len());
+                        }
+                        key = vec![first.clone()];
+                    }
+                    names.push(first);
+                }
+            }
+        }
+        let without_rowid = rest.to_ascii_uppercase().contains("WITHOUT ROWID");
+        let order = without_rowid.then(|| {
+            let mut order = key
+                .iter()
+                .filter_map(|k| names.iter().position(|n| n.eq_ignore_ascii_case(k)))
+                .collect::<Vec<_>>();
+            let rest = (0..names.len())
+                .filter(|i| !order.contains(i))
+                .collect::<Vec<_>>();
+            order.extend(rest);
+            order
+        });
+        Columns {
+            names,
+            rowid: rowid.filter(|_| !without_rowid),
+            order,
+        }
+    }
+
+    // The values of a record put in the order the columns were declared in.
+    fn declared(&self, values: Vec<Value>) -> Vec<Value> {
+        let Some(order) = &self.order else {
+            return values;
+        };
+        let mut declared = (0..self.names.len().max(values.len()))
+            .map(|_| Value::Null)
+            .collect::<Vec<_>>();
+        for (i, value) in values.into_iter().enumerate() {
+            let column = order.get(i).copied().unwrap_or(i);
+            declared[column] = value;
+        }
+        declared
+    }
+}
+
+// The definitions between the first pair of parentheses of `sql`, split at
+// their commas, and what follows them.
+fn definitions(sql: &str) -> (Vec<String>, &str) {
+    match sql.find('(') {
+        Some(start) => definitions_in(&sql[start..]),
+        None => (Vec::new(), ""),
+    }
+}
+
+// Likewise, `text` starting at the parenthesis, if it does.
+fn definitions_in(text: &str) -> (Vec<String>, &str) {
+    let Some(text) = text.trim_start().strip_prefix('(') else {
+        return (Vec::new(), text);
+    };
+    let mut definitions = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(close), _) if c == close => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') if depth == 0 => {
+                definitions.push(current.trim().to_string());
+                return (definitions, &text[i + 1..]);
+            }
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                definitions.push(mem::take(&mut current).trim().to_string());
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    definitions.push(current.trim().to_string());
+    (definitions, "")
+}
+
+// The name starting `text`, unquoted, and what follows it.
+fn token(text: &str) -> (String, &str) {
+    let text = text.trim_start();
+    let close = match text.chars().next() {
+        Some(c @ ('"' | '`' | '\'')) => c,
+        Some('[') => ']',
+        _ => {
+            let end = text
+                .find(|c: char| c.is_whitespace() || c == '(' || c == ',')
+                .unwrap_or(text.len());
+            return (text[..end].to_string(), &text[end..]);
+        }
+    };
+    match text[1..].find(close) {
+        Some(end) => (text[1..end + 1].to_string(), &text[end + 2..]),
+        None => (text[1..].to_string(), ""),
+    }
+}
+
+// A number of a b-tree cell or a record, 7 bits to a byte but for a ninth
+// one, most significant first.
+fn varint(data: &[u8], at: &mut usize) -> anyhow::Result<u64> {
+    let mut value = 0;
+    for i in 0..9 {
+        let byte = *data
+            .get(*at)
+            .ok_or_else(|| anyhow!("SQLite number runs past the end"))?;
+        *at += 1;
+        if i == 8 {
+            return Ok(value << 8 | u64::from(byte));
+        }
+        value = value << 7 | u64::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(value)
+}
+
+fn u16_at(data: &[u8], at: usize) -> anyhow::Result<u16> {
+    let bytes = data
+        .get(at..at + 2)
+        .ok_or_else(|| anyhow!("SQLite page runs past the end"))?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], at: usize) -> anyhow::Result<u32> {
+    let bytes = data
+        .get(at..at + 4)
+        .ok_or_else(|| anyhow!("SQLite page runs past the end"))?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A database of 512 byte pages as SQLite writes it, holding the table
+    // `t` with the rows `one` and `two<tab>three`, its bytes other than the
+    // runs of zeros placed by their offsets.
+    fn database() -> Vec<u8> {
+        let mut data = vec![0; 1024];
+        for (at, bytes) in [
+            (
+                0,
+                &b"SQLite format 3\x00\x02\x00\x01\x01\x00@  \x00\x00\x00\x02\x00\x00\x00\x02"[..],
+            ),
+            (43, b"\x01\x00\x00\x00\x04"),
+            (59, b"\x01"),
+            (
+                95,
+                b"\x02\x00.c\x01\x0d\x00\x00\x00\x01\x01\xbe\x00\x01\xbe",
+            ),
+            (
+                446,
+                b"@\x01\x06\x17\x0f\x0f\x01qtablett\x02CREATE TABLE t (id integer primary key, \
+                name text)\x0d\x00\x00\x00\x02\x01\xea\x00\x01\xf8\x01\xea",
+            ),
+            (
+                1002,
+                b"\x0c\x02\x03\x00\x1ftwo\tthree\x06\x01\x03\x00\x13one",
+            ),
+        ] {
+            data[at..at + bytes.len()].copy_from_slice(bytes);
+        }
+        data
+    }
+
+    #[test]
+    fn reads_a_database() {
+        let data = database();
+        assert_eq!(tables(&data).unwrap(), [("t".to_string(), 2)]);
+        assert_eq!(
+            schema(&data).unwrap(),
+            ["CREATE TABLE t (id integer primary key, name text);"]
+        );
+        assert_eq!(
+            String::from_utf8(rows(&data, "t").unwrap()).unwrap(),
+            "id\tname\n1\tone\n2\ttwo\\tthree\n"
+        );
+        assert!(rows(&data, "u").is_err());
+    }
+
+    #[test]
+    fn rejects_a_truncated_database() {
+        let data = database();
+        for len in [10, 90, 300, 600] {
+            assert!(tables(&data[..len]).is_err(), "cut at {len}");
+        }
+    }
+
+    #[test]
+    fn rejects_a_corrupt_database() {
+        let mut data = database();
+        // A page size that isn't a power of two.
+        data[16] = 0x03;
+        assert!(tables(&data).is_err());
+        let mut data = database();
+        // The root page of `t` past the end of the file.
+        data[461] = 0x07;
+        assert!(tables(&data).is_err());
+        let mut data = database();
+        // The first row of `t` pointing past the end of its page.
+        data[520] = 0xFF;
+        assert!(rows(&data, "t").is_err());
+    }
+}
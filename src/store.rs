@@ -0,0 +1,37 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+
+const APP_DIR: &str = "refer";
+
+/// Directory where refer keeps its data between runs. It's
+/// `$XDG_DATA_HOME/refer` or `~/.local/share/refer` when that isn't set.
+pub fn data_dir() -> anyhow::Result<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", &[".local", "share"])
+}
+
+/// Directory the configuration is read from. It's `$XDG_CONFIG_HOME/refer`
+/// or `~/.config/refer` when that isn't set.
+pub fn config_dir() -> anyhow::Result<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+fn xdg_dir(var: &str, fallback: &[&str]) -> anyhow::Result<PathBuf> {
+    let base = match env::var_os(var).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => env::var_os("HOME")
+            .map(|home| fallback.iter().fold(PathBuf::from(home), |p, d| p.join(d)))
+            .ok_or_else(|| anyhow!("Couldn't find the home directory"))?,
+    };
+    Ok(base.join(APP_DIR))
+}
+
+/// Path of a data file, creating the data directory if it's missing.
+pub fn data_file(name: &str) -> anyhow::Result<PathBuf> {
+    let dir = data_dir()?;
+    fs::create_dir_all(&dir)
+        .map_err(|err| anyhow!("Couldn't create {} due to: {err}", dir.display()))?;
+    Ok(dir.join(name))
+}
@@ -0,0 +1,134 @@
+use std::ops::Range;
+
+use anyhow::anyhow;
+use regex::{Regex, RegexBuilder};
+
+/// A `s/pattern/replacement/flags` command whose outcome is only previewed,
+/// the file itself is never touched. Any punctuation may stand in for `/`,
+/// `\N` in the replacement stands for the Nth group as `$N` does. The `g`
+/// flag replaces every match of a line rather than the first one, `i`
+/// ignores case.
+pub struct Substitution {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl Substitution {
+    /// Whether `command` is meant to be a substitution, valid or not.
+    pub fn is_command(command: &str) -> bool {
+        let mut chars = command.chars();
+        chars.next() == Some('s') && chars.next().is_some_and(|c| c.is_ascii_punctuation())
+    }
+
+    pub fn parse(command: &str) -> anyhow::Result<Self> {
+        if !Substitution::is_command(command) {
+            return Err(anyhow!("Not a substitution: {command}"));
+        }
+        let mut chars = command[1..].chars();
+        let delimiter = chars.next().unwrap_or('/');
+        let parts = split(chars.as_str(), delimiter);
+        let (pattern, replacement, flags) = match parts.as_slice() {
+            [pattern] => (pattern.as_str(), "", ""),
+            [pattern, replacement] => (pattern.as_str(), replacement.as_str(), ""),
+            [pattern, replacement, flags] => {
+                (pattern.as_str(), replacement.as_str(), flags.as_str())
+            }
+            _ => return Err(anyhow!("Too many `{delimiter}` in the substitution")),
+        };
+        if pattern.is_empty() {
+            return Err(anyhow!("The substitution has no pattern"));
+        }
+        if let Some(flag) = flags.chars().find(|c| !matches!(c, 'g' | 'i')) {
+            return Err(anyhow!("Unknown substitution flag `{flag}`"));
+        }
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(flags.contains('i'))
+            .build()
+            .map_err(|err| anyhow!("Couldn't compile the pattern due to: {err}"))?;
+        Ok(Substitution {
+            regex,
+            replacement: group_references(replacement),
+            global: flags.contains('g'),
+        })
+    }
+
+    /// Whether the substitution changes `line`.
+    #[inline]
+    pub fn changes(&self, line: &str) -> bool {
+        self.regex.is_match(line)
+    }
+
+    /// `line` after the substitution along with the replaced parts as ranges
+    /// of characters, or `None` if it wouldn't change.
+    pub fn apply(&self, line: &str) -> Option<(String, Vec<Range<usize>>)> {
+        let limit = if self.global { usize::MAX } else { 1 };
+        let mut text = String::new();
+        let mut replaced = Vec::new();
+        let mut last = 0;
+        for captures in self.regex.captures_iter(line).take(limit) {
+            let whole = captures.get(0)?;
+            text.push_str(&line[last..whole.start()]);
+            let start = text.chars().count();
+            captures.expand(&self.replacement, &mut text);
+            replaced.push(start..text.chars().count());
+            last = whole.end();
+        }
+        if replaced.is_empty() {
+            return None;
+        }
+        text.push_str(&line[last..]);
+        Some((text, replaced))
+    }
+}
+
+// Split at every `delimiter` that isn't escaped by a backslash, an escaped
+// one standing for the delimiter itself.
+fn split(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().unwrap();
+        match c {
+            '\\' if chars.peek() == Some(&delimiter) => part.push(chars.next().unwrap()),
+            '\\' => {
+                part.push(c);
+                part.extend(chars.next());
+            }
+            c if c == delimiter => parts.push(String::new()),
+            c => part.push(c),
+        }
+    }
+    // A trailing delimiter closes the last part rather than opening another.
+    if parts.len() > 1 && parts.last().is_some_and(String::is_empty) && text.ends_with(delimiter) {
+        parts.pop();
+    }
+    parts
+}
+
+// Turn the `\N` group references of the replacement into `${N}` and keep a
+// lone `$` from being taken for one.
+fn group_references(replacement: &str) -> String {
+    let mut expanded = String::new();
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&d)) if d.is_ascii_digit() => {
+                expanded.push_str(&format!("${{{d}}}"));
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                expanded.push('\\');
+                chars.next();
+            }
+            ('$', Some(&d)) if d.is_ascii_alphanumeric() || d == '{' || d == '$' => {
+                expanded.push('$');
+                expanded.push(d);
+                chars.next();
+            }
+            ('$', _) => expanded.push_str("$$"),
+            (c, _) => expanded.push(c),
+        }
+    }
+    expanded
+}
@@ -0,0 +1,495 @@
+use std::ops::Range;
+use std::path::Path;
+
+/// What a part of a line of code is, to color it by.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Keyword,
+    /// The key of a setting in a configuration file.
+    Key,
+    /// The header of a `[section]` of a configuration file.
+    Section,
+    /// A capitalized name, most often a type.
+    Type,
+    String,
+    Number,
+    Comment,
+}
+
+/// How the code of a language is told apart, well enough to color it.
+struct Language {
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+    // What follows the key of a setting at the start of a line, for the
+    // configuration files.
+    key_separator: Option<char>,
+    // Whether the lines starting with `[` head a section.
+    sections: bool,
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "namespace",
+    "new",
+    "nullptr",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        extensions: &["rs"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait",
+            "true", "type", "unsafe", "use", "where", "while",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["py", "pyi"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        keywords: &[
+            "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+            "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+            "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+            "return", "try", "while", "with", "yield",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["js", "jsx", "mjs", "cjs", "ts", "tsx"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "enum",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "of",
+            "return",
+            "static",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["go"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '`'],
+        keywords: &[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "fallthrough",
+            "false",
+            "for",
+            "func",
+            "go",
+            "goto",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        keywords: C_KEYWORDS,
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["java", "kt", "cs", "scala"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        keywords: &[
+            "abstract",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "data",
+            "default",
+            "do",
+            "else",
+            "enum",
+            "extends",
+            "false",
+            "final",
+            "finally",
+            "for",
+            "fun",
+            "if",
+            "implements",
+            "import",
+            "interface",
+            "new",
+            "null",
+            "object",
+            "override",
+            "package",
+            "private",
+            "protected",
+            "public",
+            "record",
+            "return",
+            "static",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "val",
+            "var",
+            "void",
+            "when",
+            "while",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["rb"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        keywords: &[
+            "begin", "break", "case", "class", "def", "do", "else", "elsif", "end", "ensure",
+            "false", "for", "if", "in", "module", "next", "nil", "rescue", "return", "self",
+            "then", "true", "unless", "until", "when", "while", "yield",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["lua"],
+        line_comments: &["--"],
+        block_comment: Some(("--[[", "]]")),
+        quotes: &['"', '\''],
+        keywords: &[
+            "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in",
+            "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["sh", "bash", "zsh"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "local", "return", "then", "while",
+        ],
+        key_separator: None,
+        sections: false,
+    },
+    Language {
+        extensions: &["toml", "ini", "conf", "cfg"],
+        line_comments: &["#", ";"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        keywords: &["false", "true"],
+        key_separator: Some('='),
+        sections: true,
+    },
+    Language {
+        extensions: &["yaml", "yml"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        keywords: &["false", "null", "true", "yes", "no", "on", "off"],
+        key_separator: Some(':'),
+        sections: false,
+    },
+    Language {
+        extensions: &["json"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        keywords: &["false", "null", "true"],
+        key_separator: None,
+        sections: false,
+    },
+];
+
+/// Colors the lines of a code file by the language its extension tells,
+/// keeping what it found for every line so that scrolling back and forth
+/// doesn't go over them again.
+pub struct Highlighter {
+    language: &'static Language,
+    // The tokens of the lines gone over so far.
+    lines: Vec<Vec<(Range<usize>, Token)>>,
+    // Whether the last of them ended inside a block comment.
+    in_comment: bool,
+}
+
+impl Highlighter {
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        let language = LANGUAGES
+            .iter()
+            .find(|l| l.extensions.contains(&extension.as_str()))?;
+        Some(Highlighter {
+            language,
+            lines: Vec::new(),
+            in_comment: false,
+        })
+    }
+
+    /// Forget the lines gone over, as after the file was read anew.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.in_comment = false;
+    }
+
+    /// The tokens of the line at `index` of `lines`, in characters, going
+    /// over the lines before it first if they weren't yet.
+    pub fn tokens(&mut self, lines: &[String], index: usize) -> &[(Range<usize>, Token)] {
+        if self.lines.len() > lines.len() {
+            self.clear();
+        }
+        while self.lines.len() <= index && self.lines.len() < lines.len() {
+            let tokens = self.tokenize(&lines[self.lines.len()]);
+            self.lines.push(tokens);
+        }
+        self.lines.get(index).map_or(&[], Vec::as_slice)
+    }
+
+    fn tokenize(&mut self, line: &str) -> Vec<(Range<usize>, Token)> {
+        let language = self.language;
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        if !self.in_comment {
+            match config_key(language, &chars) {
+                Some(Ok(key)) => {
+                    i = key.end;
+                    tokens.push((key, Token::Key));
+                }
+                Some(Err(section)) => return vec![(section, Token::Section)],
+                None => {}
+            }
+        }
+        while i < chars.len() {
+            let start = i;
+            if self.in_comment {
+                let (_, end) = language.block_comment.unwrap_or_default();
+                match (i..chars.len()).find(|&j| starts_with(&chars, j, end)) {
+                    Some(j) => {
+                        i = j + end.chars().count();
+                        self.in_comment = false;
+                    }
+                    None => i = chars.len(),
+                }
+                tokens.push((start..i, Token::Comment));
+                continue;
+            }
+            if let Some((open, _)) = language.block_comment {
+                if starts_with(&chars, i, open) {
+                    i += open.chars().count();
+                    self.in_comment = true;
+                    // The rest of the comment is found as inside one.
+                    tokens.push((start..i, Token::Comment));
+                    continue;
+                }
+            }
+            if language
+                .line_comments
+                .iter()
+                .any(|c| starts_with(&chars, i, c))
+            {
+                tokens.push((i..chars.len(), Token::Comment));
+                break;
+            }
+            let c = chars[i];
+            if language.quotes.contains(&c) {
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(chars.len());
+                tokens.push((start..i, Token::String));
+            } else if is_word(c) {
+                while i < chars.len()
+                    && (is_word(chars[i]) || chars[i] == '.' && c.is_ascii_digit())
+                {
+                    i += 1;
+                }
+                let word = chars[start..i].iter().collect::<String>();
+                if c.is_ascii_digit() {
+                    tokens.push((start..i, Token::Number));
+                } else if language.keywords.contains(&word.as_str()) {
+                    tokens.push((start..i, Token::Keyword));
+                } else if c.is_uppercase() {
+                    tokens.push((start..i, Token::Type));
+                }
+            } else {
+                i += 1;
+            }
+        }
+        // Adjacent comment tokens read better as one.
+        tokens.dedup_by(|next, prev| {
+            let join =
+                prev.1 == Token::Comment && next.1 == Token::Comment && prev.0.end == next.0.start;
+            if join {
+                prev.0.end = next.0.end;
+            }
+            join
+        });
+        tokens
+    }
+}
+
+// The key a line of a configuration file starts with, or else the header of
+// the section it starts, in characters.
+fn config_key(language: &Language, chars: &[char]) -> Option<Result<Range<usize>, Range<usize>>> {
+    let separator = language.key_separator?;
+    let mut start = chars.iter().position(|c| !c.is_whitespace())?;
+    if language.sections && chars[start] == '[' {
+        let end = chars.iter().rposition(|&c| c == ']')? + 1;
+        return Some(Err(start..end));
+    }
+    // The items of a YAML list can hold settings too.
+    if chars[start..].starts_with(&['-', ' ']) {
+        start += 2;
+    }
+    let at = start
+        + chars[start..]
+            .iter()
+            .position(|&c| c == separator || language.quotes.contains(&c) || c == '#')?;
+    let key_end = chars[start..at]
+        .iter()
+        .rposition(|c| !c.is_whitespace())
+        .map(|end| start + end + 1)?;
+    // A colon in YAML only ends a key before a space, unlike in a URL.
+    let follows = chars.get(at + 1).is_none_or(|c| c.is_whitespace());
+    (chars[at] == separator && (separator != ':' || follows)).then_some(Ok(start..key_end))
+}
+
+#[inline]
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn starts_with(chars: &[char], at: usize, pattern: &str) -> bool {
+    !pattern.is_empty()
+        && pattern
+            .chars()
+            .enumerate()
+            .all(|(k, c)| chars.get(at + k) == Some(&c))
+}
@@ -0,0 +1,52 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+/// The line with its tabs turned into the spaces up to the next tab stop,
+/// every `width` columns, and the ranges of characters over it moved to the
+/// characters they end up at. A range covering part of a tab covers all of
+/// it. Wide characters take up two columns on the way to a tab stop.
+pub fn expand<'a, T>(
+    line: &'a str,
+    ranges: Vec<(Range<usize>, T)>,
+    width: usize,
+) -> (Cow<'a, str>, Vec<(Range<usize>, T)>) {
+    if !line.contains('\t') {
+        return (Cow::Borrowed(line), ranges);
+    }
+    let width = width.max(1);
+    let mut expanded = String::with_capacity(line.len());
+    // The character of the expanded line each character ends up at, and
+    // where the line ends.
+    let mut starts = Vec::with_capacity(line.len() + 1);
+    let (mut index, mut column) = (0, 0);
+    for c in line.chars() {
+        starts.push(index);
+        match c {
+            '\t' => {
+                let stop = (column / width + 1) * width;
+                expanded.extend(std::iter::repeat_n(' ', stop - column));
+                index += stop - column;
+                column = stop;
+            }
+            c => {
+                expanded.push(c);
+                index += 1;
+                column += c.width().unwrap_or_default();
+            }
+        }
+    }
+    starts.push(index);
+    let at = |i: usize| starts.get(i).copied().unwrap_or(index);
+    let ranges = ranges
+        .into_iter()
+        .map(|(range, value)| (at(range.start)..at(range.end), value))
+        .collect();
+    (Cow::Owned(expanded), ranges)
+}
+
+/// The line with its tabs turned into spaces, as for `expand`.
+pub fn expand_line(line: &str, width: usize) -> Cow<'_, str> {
+    expand::<()>(line, Vec::new(), width).0
+}
@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::markdown::*;
+
+/// Columns a cell is shown in at most, cut short past them.
+const MAX_CELL: usize = 40;
+/// What the cells of a row are set apart by.
+const SEPARATOR: &str = " │ ";
+
+/// Shows the rows of a CSV or TSV file as an aligned table, each column as
+/// wide as its widest cell among the lines read so far.
+pub struct Table {
+    delimiter: &'static str,
+    widths: Vec<usize>,
+    // Lines the widths were measured over.
+    measured: usize,
+}
+
+impl Table {
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        let delimiter = match extension.as_str() {
+            "csv" => ",",
+            "tsv" | "tab" => "\t",
+            _ => return None,
+        };
+        Some(Table {
+            delimiter,
+            widths: Vec::new(),
+            measured: 0,
+        })
+    }
+
+    /// Forget the widths measured, as after the file was read anew.
+    pub fn clear(&mut self) {
+        self.widths.clear();
+        self.measured = 0;
+    }
+
+    /// The line at `index` of `lines` as a row of the table, the first one
+    /// standing out as the header.
+    pub fn render(&mut self, lines: &[String], index: usize) -> Rendered {
+        if self.measured > lines.len() {
+            self.clear();
+        }
+        for line in &lines[self.measured..] {
+            for (column, cell) in self.cells(line).iter().enumerate() {
+                let width = cell.width().min(MAX_CELL);
+                match self.widths.get_mut(column) {
+                    Some(widest) => *widest = (*widest).max(width),
+                    None => self.widths.push(width),
+                }
+            }
+        }
+        self.measured = lines.len();
+
+        let line = lines.get(index).map_or("", String::as_str);
+        let mut text = String::new();
+        let mut marks = Vec::new();
+        let mut len = 0;
+        for (column, cell) in self.cells(line).iter().enumerate() {
+            if column > 0 {
+                text.push_str(SEPARATOR);
+                let end = len + SEPARATOR.chars().count();
+                marks.push((len..end, Mark::Decoration));
+                len = end;
+            }
+            let width = self.widths.get(column).copied().unwrap_or_default();
+            let cell = match cell.width() > MAX_CELL {
+                true => truncate(cell),
+                false => cell.clone(),
+            };
+            // Padded by the columns the cell takes up, wide characters
+            // taking two.
+            let pad = width.saturating_sub(cell.width());
+            let padded = format!("{cell}{}", " ".repeat(pad));
+            let chars = padded.chars().count();
+            if index == 0 {
+                marks.push((len..len + chars, Mark::Bold));
+            }
+            len += chars;
+            text.push_str(&padded);
+        }
+        (text, marks)
+    }
+
+    /// Where each column starts on a row, in columns of the screen.
+    pub fn column_starts(&self) -> Vec<usize> {
+        let separator = SEPARATOR.chars().count();
+        let mut start = 0;
+        self.widths
+            .iter()
+            .map(|width| {
+                let column = start;
+                start += width + separator;
+                column
+            })
+            .collect()
+    }
+
+    // The cells of a row. In a CSV file a quoted cell can hold the
+    // delimiter, a doubled quote standing for a quote.
+    fn cells(&self, line: &str) -> Vec<String> {
+        if self.delimiter != "," {
+            return line.split(self.delimiter).map(str::to_string).collect();
+        }
+        let mut cells = Vec::new();
+        let mut cell = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cell.push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => cells.push(std::mem::take(&mut cell)),
+                c => cell.push(c),
+            }
+        }
+        cells.push(cell);
+        cells
+    }
+}
+
+// A cell cut down to `MAX_CELL` columns, the last one telling it goes on.
+fn truncate(cell: &str) -> String {
+    let mut used = 0;
+    let mut truncated = cell
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or_default();
+            used < MAX_CELL
+        })
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,29 @@
+use std::time::{Duration, Instant};
+
+/// How long a toast stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// A short-lived message at the bottom of the View pane.
+#[derive(Default)]
+pub struct Toast {
+    message: Option<(String, Instant)>,
+}
+
+impl Toast {
+    #[inline]
+    pub fn new() -> Self {
+        Toast::default()
+    }
+
+    pub fn show(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), Instant::now()));
+    }
+
+    /// The message if it hasn't expired yet.
+    pub fn message(&self) -> Option<&str> {
+        self.message
+            .as_ref()
+            .filter(|(_, since)| since.elapsed() < TOAST_DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+}
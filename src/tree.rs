@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ratatui::widgets::*;
+
+/// A file or directory shown in the tree.
+pub struct Entry {
+    pub path: Box<Path>,
+    pub is_dir: bool,
+    /// Number of directories above it up to its root.
+    depth: usize,
+    expanded: bool,
+}
+
+impl Entry {
+    fn new(path: &Path, is_dir: bool, depth: usize) -> Self {
+        Entry {
+            path: path.into(),
+            is_dir,
+            depth,
+            expanded: false,
+        }
+    }
+
+    /// The name indented by its depth, directories marked as open or closed.
+    pub fn label(&self) -> String {
+        let name = match self.depth {
+            0 => self.path.display().to_string(),
+            _ => self
+                .path
+                .file_name()
+                .map_or_else(String::new, |n| n.to_string_lossy().to_string()),
+        };
+        let marker = match (self.is_dir, self.expanded) {
+            (false, _) => "  ",
+            (true, false) => "▸ ",
+            (true, true) => "▾ ",
+        };
+        let slash = if self.is_dir { "/" } else { "" };
+        format!("{}{marker}{name}{slash}", "  ".repeat(self.depth))
+    }
+}
+
+/// Directories added to refer, browsed as an expandable tree in the Files
+/// pane in place of the open files. A directory is only read once it gets
+/// expanded, and again every time it does.
+#[derive(Default)]
+pub struct Tree {
+    // The entries that can be seen, each directory followed by its entries
+    // while it's expanded.
+    entries: Vec<Entry>,
+    visible: bool,
+    state: ListState,
+}
+
+impl Tree {
+    #[inline]
+    pub fn new() -> Self {
+        Tree::default()
+    }
+
+    /// Add `dir` as another root and show the tree.
+    pub fn add_root(&mut self, dir: &Path) {
+        self.visible = true;
+        if let Some(index) = self.entries.iter().position(|e| *e.path == *dir) {
+            self.state.select(Some(index));
+            return;
+        }
+        self.entries.push(Entry::new(dir, true, 0));
+        let index = self.entries.len() - 1;
+        self.state.select(Some(index));
+        self.expand(index);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.visible && !self.is_empty()
+    }
+
+    /// Switch the Files pane between the tree and the open files.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.entries.iter().map(Entry::label).collect()
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.entries.get(self.state.selected()?)
+    }
+
+    pub fn next(&mut self) {
+        let index = self.state.selected().unwrap_or_default();
+        let last = self.entries.len().saturating_sub(1);
+        self.state.select(Some(index.saturating_add(1).min(last)));
+    }
+
+    pub fn prev(&mut self) {
+        let index = self.state.selected().unwrap_or_default();
+        self.state.select(Some(index.saturating_sub(1)));
+    }
+
+    pub fn top(&mut self) {
+        self.state.select(Some(0));
+    }
+
+    pub fn bottom(&mut self) {
+        self.state
+            .select(Some(self.entries.len().saturating_sub(1)));
+    }
+
+    /// Expand or collapse the selected directory.
+    pub fn toggle_selected(&mut self) {
+        let Some(index) = self.state.selected() else {
+            return;
+        };
+        match self.entries.get(index).map(|e| e.expanded) {
+            Some(true) => self.collapse(index),
+            Some(false) => self.expand(index),
+            None => {}
+        }
+    }
+
+    /// Collapse the selected directory, or else select the directory it's in.
+    pub fn collapse_selected(&mut self) {
+        let Some(index) = self.state.selected() else {
+            return;
+        };
+        if self.entries.get(index).is_some_and(|e| e.expanded) {
+            self.collapse(index);
+            return;
+        }
+        let depth = self.entries.get(index).map_or(0, |e| e.depth);
+        let parent = self.entries[..index].iter().rposition(|e| e.depth < depth);
+        if let Some(parent) = parent {
+            self.state.select(Some(parent));
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut ListState {
+        &mut self.state
+    }
+
+    // Read the directory at `index` and list its entries below it,
+    // directories first.
+    fn expand(&mut self, index: usize) {
+        let Some(entry) = self.entries.get_mut(index).filter(|e| e.is_dir) else {
+            return;
+        };
+        let read = fs::read_dir(&entry.path);
+        let Ok(read) = read else {
+            log::trace!("Couldn't read {}", entry.path.display());
+            return;
+        };
+        entry.expanded = true;
+        let depth = entry.depth + 1;
+        let mut children = read
+            .filter_map(Result::ok)
+            .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .map(|e| {
+                let is_dir = e.file_type().is_ok_and(|t| t.is_dir());
+                (!is_dir, e.file_name(), e.path())
+            })
+            .collect::<Vec<(bool, _, PathBuf)>>();
+        children.sort();
+        let children = children
+            .into_iter()
+            .map(|(is_file, _, path)| Entry::new(&path, !is_file, depth));
+        self.entries.splice(index + 1..index + 1, children);
+    }
+
+    // Take the entries below the directory at `index` off the tree.
+    fn collapse(&mut self, index: usize) {
+        let depth = self.entries[index].depth;
+        self.entries[index].expanded = false;
+        let end = self.entries[index + 1..]
+            .iter()
+            .position(|e| e.depth <= depth)
+            .map_or(self.entries.len(), |p| index + 1 + p);
+        self.entries.drain(index + 1..end);
+    }
+}
@@ -1,12 +1,68 @@
-use ratatui::{border, prelude::*, widgets::*};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::Path;
 
+use ratatui::{
+    border,
+    prelude::*,
+    widgets::{block::*, *},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::caret;
+use crate::compare::*;
+use crate::config::*;
 use crate::cursor::*;
+use crate::diff::*;
+use crate::fold::*;
+use crate::icon::*;
+use crate::input::*;
+use crate::invisible;
+use crate::markdown::*;
+use crate::picker::*;
 use crate::resource::*;
+use crate::search::*;
+use crate::substitute::*;
+#[cfg(feature = "syntax")]
+use crate::syntax::*;
+use crate::tab;
+use crate::word::*;
 use crate::RectVec;
 
 const BORDER: BorderType = BorderType::Thick;
 const RFG: Color = Color::Gray;
 const RBG: Color = Color::Rgb(20, 20, 20);
+const CBG: Color = Color::Rgb(45, 45, 45);
+const RULER_BG: Color = Color::Rgb(32, 32, 32);
+const SBG: Color = Color::Rgb(40, 60, 90);
+const MBG: Color = Color::Rgb(150, 130, 70);
+const CMBG: Color = Color::Rgb(240, 170, 50);
+const SUBBG: Color = Color::Rgb(70, 130, 70);
+const WBG: Color = Color::Rgb(60, 60, 100);
+/// Backgrounds of the capture groups of a regex search, taken in turn.
+const GROUP_BGS: [Color; 4] = [
+    Color::Rgb(90, 160, 200),
+    Color::Rgb(200, 110, 160),
+    Color::Rgb(120, 190, 110),
+    Color::Rgb(190, 140, 230),
+];
+const KEYWORD_FG: Color = Color::Rgb(200, 120, 200);
+const TYPE_FG: Color = Color::Rgb(100, 180, 200);
+#[cfg(feature = "syntax")]
+const KEY_FG: Color = Color::Rgb(130, 160, 230);
+const STRING_FG: Color = Color::Rgb(150, 190, 100);
+#[cfg(feature = "syntax")]
+const NUMBER_FG: Color = Color::Rgb(220, 150, 90);
+const COMMENT_FG: Color = Color::Rgb(110, 110, 110);
+const ADDED_FG: Color = Color::Rgb(120, 200, 120);
+const REMOVED_FG: Color = Color::Rgb(220, 100, 100);
+const ADDED_BG: Color = Color::Rgb(30, 60, 30);
+const ADDED_STRONG_BG: Color = Color::Rgb(45, 110, 45);
+const REMOVED_BG: Color = Color::Rgb(70, 30, 30);
+const REMOVED_STRONG_BG: Color = Color::Rgb(130, 45, 45);
+const INVISIBLE_FG: Color = Color::Rgb(75, 75, 75);
+const CONTROL_FG: Color = Color::Rgb(200, 110, 200);
+const TRAILING_BG: Color = Color::Rgb(90, 40, 40);
 const DFG: Color = Color::Rgb(80, 80, 80);
 const EFG: Color = Color::LightRed;
 const LOG: Color = Color::DarkGray;
@@ -32,6 +88,62 @@ const LOG_MSG: Style = Style {
     add_modifier: Modifier::empty(),
     sub_modifier: Modifier::empty(),
 };
+const HIGHLIGHT: Style = Style {
+    fg: Some(RBG),
+    bg: Some(RFG),
+    underline_color: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+const CURSORLINE: Style = Style {
+    fg: Some(RFG),
+    bg: Some(CBG),
+    underline_color: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+const SELECTION: Style = Style {
+    fg: Some(RFG),
+    bg: Some(SBG),
+    underline_color: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+const MATCH: Style = Style {
+    fg: Some(RBG),
+    bg: Some(MBG),
+    underline_color: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+const CURRENT_MATCH: Style = Style {
+    fg: Some(RBG),
+    bg: Some(CMBG),
+    underline_color: None,
+    add_modifier: Modifier::BOLD,
+    sub_modifier: Modifier::empty(),
+};
+const SUBSTITUTED: Style = Style {
+    fg: Some(RBG),
+    bg: Some(SUBBG),
+    underline_color: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+const WORD: Style = Style {
+    fg: Some(RFG),
+    bg: Some(WBG),
+    underline_color: None,
+    add_modifier: Modifier::empty(),
+    sub_modifier: Modifier::empty(),
+};
+const CURRENT_WORD: Style = Style {
+    fg: Some(RFG),
+    bg: Some(WBG),
+    underline_color: None,
+    add_modifier: Modifier::UNDERLINED,
+    sub_modifier: Modifier::empty(),
+};
 const INVISIBLE: Style = Style {
     fg: None,
     bg: None,
@@ -39,6 +151,13 @@ const INVISIBLE: Style = Style {
     add_modifier: Modifier::empty(),
     sub_modifier: Modifier::empty(),
 };
+const STICKY: Style = Style {
+    fg: Some(RFG),
+    bg: Some(RBG),
+    underline_color: None,
+    add_modifier: Modifier::UNDERLINED,
+    sub_modifier: Modifier::empty(),
+};
 const FADE: Style = Style {
     fg: Some(DFG),
     bg: Some(RBG),
@@ -47,10 +166,23 @@ const FADE: Style = Style {
     sub_modifier: Modifier::empty(),
 };
 
+/// Width of the minimap on the right of the View pane.
+const MINIMAP_WIDTH: u16 = 12;
+/// Number of text columns condensed into a single minimap column.
+const MINIMAP_SCALE: usize = 8;
+/// Lines sampled for each minimap row, so large files stay cheap to draw.
+const MINIMAP_SAMPLES: usize = 8;
+/// Rows taken by the quickfix pane, borders included.
+const QUICKFIX_HEIGHT: u16 = 8;
+/// Columns left to the View pane at least, next to the Files pane.
+const MIN_VIEW_WIDTH: u16 = 20;
+/// Put in front of the query typed into the picker.
+const QUERY_PREFIX: &str = " > ";
+
 const HEADERS: &str = "\
     (ctrl) + (q) quit  │  \
     (ctrl) + (n) new file  │  \
-    (ctrl) + (d) delete file  │  \
+    (ctrl) + (d) delete file / half page down  │  \
     (ctrl) + (j or ↑) up  │  \
     (ctrl) + (k or ↓) down  │  \
     (ctrl) + (t) toggle tailing";
@@ -64,12 +196,18 @@ pub fn ui(frame: &mut Frame, res: &mut Resource) {
         .split(size);
 
     ui_main(frame, vflex, res);
+    ui_picker(frame, size, res);
+    ui_confirm(frame, size, res);
 }
 
 fn ui_main(frame: &mut Frame, vflex: RectVec, res: &mut Resource) {
+    // The View pane keeps some room however wide the Files pane was made.
+    let files_width = res
+        .files_width()
+        .min(vflex[1].width.saturating_sub(MIN_VIEW_WIDTH));
     let hflex = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Min(40), Constraint::Percentage(100)])
+        .constraints([Constraint::Length(files_width), Constraint::Min(0)])
         .split(vflex[1]);
 
     ui_text(frame, hflex, res);
@@ -100,25 +238,700 @@ fn get_cursor_shade_from_condition(cond: bool) -> Style {
     }
 }
 
-fn get_lines_from_buffer(res: &Resource, hflex: Rect) -> Vec<Line> {
-    let curr_index = res.file_list_state().index();
-    let Some(curr_buff) = res.files().get_file_buff(curr_index) else {
+fn get_lines_from_buffer(res: &Resource, file: usize, hflex: Rect) -> Vec<Line<'_>> {
+    let Some(curr_buff) = res.files().get_file_buff(file) else {
         return Default::default(); // Return default
     };
+    let search = res.search();
+    // A substitution is previewed on the file it's typed for.
+    let substitution = res
+        .substitution()
+        .filter(|_| file == res.file_list_state().index());
+    // Distances to the cursor line don't tell much with lines filtered out.
+    let numbers = match res.config().line_numbers() {
+        LineNumbers::Relative if curr_buff.view().is_filtered() => LineNumbers::Absolute,
+        numbers => numbers,
+    };
+    let current = curr_buff.view().cursor() + 1;
+    // Wide enough for the lines not read yet, once they're counted.
+    let lines = curr_buff.line_count().unwrap_or(curr_buff.len());
+    let gutter = Gutter::new(numbers, lines, current);
+    let minimap = match res.config().minimap() {
+        true => MINIMAP_WIDTH as usize + 1, // 1 (spacing)
+        false => 0,
+    };
+    let width = (hflex.width as usize).saturating_sub(2 + gutter.width() + minimap); // 2 (borders)
+    let height = (hflex.height as usize).saturating_sub(2);
+    let tab_width = res.config().tab_width(curr_buff.path());
+    let invisibles = res.config().invisibles();
+    let crlf = curr_buff.is_crlf();
+
+    // A wrapped cursor line may reach below the View pane, scroll until the
+    // row holding its first match is in sight.
+    let mut skipped = 0;
+    if curr_buff.view().is_wrap() && !curr_buff.is_nulled() {
+        let text = |index: usize| {
+            let line = curr_buff.lines().get(index).map_or("", String::as_str);
+            let (shown, raw) = match substitution.and_then(|s| s.apply(line)) {
+                Some((text, _)) => (Cow::Owned(text), true),
+                None => match curr_buff.rendered_line(index) {
+                    Some((text, _)) => (Cow::Owned(text), false),
+                    None => (Cow::Borrowed(line), true),
+                },
+            };
+            let shown = match shown.chars().any(caret::is_control) {
+                true => Cow::Owned(caret::notate_line(&shown).into_owned()),
+                false => shown,
+            };
+            match invisibles && raw {
+                true => {
+                    let glyphs = (INVISIBLE_FG, TRAILING_BG);
+                    let revealed =
+                        invisible::reveal(&shown, Vec::new(), tab_width, crlf, BLOCK, glyphs);
+                    Cow::Owned(revealed.0)
+                }
+                false => shown,
+            }
+        };
+        let cursor_line = text(current - 1);
+        let first_match = search.matches(&cursor_line).into_iter().take(1);
+        let (cursor_line, first_match) = tab::expand(
+            &cursor_line,
+            first_match.map(|m| (m, ())).collect(),
+            tab_width,
+        );
+        let parts = wrap_line(&cursor_line, width);
+        let first_match = first_match.first().map(|(m, _)| m.end);
+        let match_row = first_match.map_or(0, |end| row_of(&parts, end.saturating_sub(1)));
+        let rows = (match_row + 1).max(parts.len().min(height));
+        curr_buff.follow_wrapped(
+            |index| wrap_line(&tab::expand_line(&text(index), tab_width), width).len(),
+            rows,
+        );
+        // Rows of a cursor line taller than the View pane are skipped from
+        // its top.
+        if curr_buff.view().shown() == current - 1 {
+            skipped = rows.saturating_sub(height);
+        }
+    }
+
     let (buffer, nulled) = curr_buff.buffer(hflex);
-    buffer
-        .into_iter()
-        .map(|l| Line::styled(l, if nulled { LOG_MSG } else { BLOCK }))
-        .collect::<Vec<Line>>()
+    if nulled {
+        return buffer
+            .iter()
+            .map(|l| Line::styled(l.as_str(), LOG_MSG))
+            .collect::<Vec<Line>>();
+    }
+    // Rather than its bytes garbling the screen.
+    if curr_buff.is_binary()
+        && !curr_buff.is_hex()
+        && curr_buff.image().is_none()
+        && !curr_buff.is_pdf()
+        && !curr_buff.is_archive()
+    {
+        return vec![Line::styled("Binary file, press X for its hex dump", FADE)];
+    }
+
+    let view = curr_buff.view();
+    let (start, hscroll, wrap) = (view.shown(), view.hscroll(), view.is_wrap());
+    let (folds, highlighted) = (view.folds(), view.highlighted());
+    let selected = |index| {
+        view.selection()
+            .is_some_and(|(s, e)| s <= index && index <= e)
+    };
+
+    // The word under the cursor, if its occurrences are highlighted.
+    let word = match res.config().highlight_word() {
+        true => curr_buff
+            .lines()
+            .get(current - 1)
+            .and_then(|line| word_at(line, view.word())),
+        false => None,
+    };
+
+    let mut lines = Vec::new();
+    let mut index = match folds.is_filtered_out(start) {
+        true => folds.next_visible(start),
+        false => start,
+    };
+    while let Some(l) = buffer.get(index - start) {
+        if lines.len() >= height {
+            break;
+        }
+        let style = if highlighted == Some(index) {
+            HIGHLIGHT
+        } else if selected(index) {
+            SELECTION
+        } else if current == index + 1 {
+            CURSORLINE
+        } else {
+            BLOCK
+        };
+        let number = index + 1;
+        // The matches on the cursor line are the current ones.
+        let match_style = if current == number {
+            CURRENT_MATCH
+        } else {
+            MATCH
+        };
+        // Lines a substitution would change are shown as they would end up.
+        let substituted = substitution.and_then(|s| s.apply(l));
+        let rendered = match substituted {
+            Some(_) => None,
+            None => curr_buff.rendered_line(index),
+        };
+        let (l, matches) = match (&substituted, &rendered) {
+            (Some((text, replaced)), _) => {
+                let replaced = replaced.iter().map(|r| (r.clone(), SUBSTITUTED)).collect();
+                (text, replaced)
+            }
+            // Searches go by the text as it's shown.
+            (None, Some((text, marks))) => {
+                let matches = search.matches(text).into_iter().map(|m| (m, match_style));
+                let marks = marks
+                    .iter()
+                    .map(|(r, mark)| (r.clone(), mark_style(*mark, style)));
+                (text, matches.chain(marks).collect())
+            }
+            (None, None) => {
+                // Groups go before the matches holding them.
+                let groups = search.groups(l).into_iter().map(|(range, group)| {
+                    let bg = GROUP_BGS[(group - 1) % GROUP_BGS.len()];
+                    (range, match_style.bg(bg))
+                });
+                let matches = search.matches(l).into_iter().map(|m| (m, match_style));
+                let mut matches = groups.chain(matches).collect::<Vec<_>>();
+                // Search matches go first, so they win where both overlap.
+                match &word {
+                    Some((range, _)) if current == number => {
+                        matches.push((range.clone(), CURRENT_WORD));
+                    }
+                    Some((_, word)) => {
+                        matches.extend(occurrences(l, word).into_iter().map(|r| (r, WORD)));
+                    }
+                    None => {}
+                }
+                // Colored by the language, under anything else.
+                if res.config().syntax() {
+                    if curr_buff.is_log() {
+                        matches.extend(res.log_rules().matches(l, style));
+                    }
+                    if let Some(change) = curr_buff.change(index) {
+                        matches.push((0..l.chars().count(), change_style(change, style)));
+                    } else {
+                        #[cfg(feature = "syntax")]
+                        matches.extend(
+                            curr_buff
+                                .tokens(index)
+                                .into_iter()
+                                .map(|(range, token)| (range, style.fg(token_fg(token)))),
+                        );
+                    }
+                }
+                (l, matches)
+            }
+        };
+        // Control characters are shown in caret notation rather than sent to
+        // the terminal, and tabs go to their tab stops, the matches over
+        // them moving along.
+        let (notated, matches) = caret::notate(l, matches, style, CONTROL_FG);
+        let (l, matches) = match invisibles && rendered.is_none() {
+            true => {
+                let shown = (INVISIBLE_FG, TRAILING_BG);
+                let (l, matches) =
+                    invisible::reveal(&notated, matches, tab_width, crlf, style, shown);
+                (Cow::Owned(l), matches)
+            }
+            false => tab::expand(&notated, matches, tab_width),
+        };
+        let mut rows = match wrap {
+            false => vec![get_line(
+                &gutter, number, &l, hscroll, width, style, &matches,
+            )],
+            true => {
+                let mut offset = 0;
+                wrap_line(&l, width)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, part)| {
+                        let number = (i == 0).then_some(number);
+                        let len = part.chars().count();
+                        let columns = part.width();
+                        let mut spans = vec![gutter.span(number, '|', style)];
+                        spans.append(&mut highlight_matches(
+                            part.to_string(),
+                            offset,
+                            &matches,
+                            style,
+                        ));
+                        // Padded apart from the text, so that a match going
+                        // on in the next row doesn't paint the padding.
+                        if style != BLOCK {
+                            let pad = width.saturating_sub(columns);
+                            spans.push(Span::styled(" ".repeat(pad), style));
+                        }
+                        offset += len;
+                        Line::from(spans)
+                    })
+                    .skip(std::mem::take(&mut skipped))
+                    .collect()
+            }
+        };
+        // Folded lines are replaced by a note on their header.
+        if let Some(hidden) = folds.hidden(index) {
+            if let Some(row) = rows.last_mut() {
+                row.spans
+                    .push(Span::styled(format!(" {{{hidden} lines folded}}"), FADE));
+            }
+        }
+        lines.append(&mut rows);
+        index = folds.next_visible(index);
+    }
+    lines.truncate(height);
+
+    // Keep the block the View pane is scrolled into in sight on the first row.
+    // The header row of a table is always in sight.
+    let context = match res.config().sticky_header() && !folds.is_filtered() {
+        _ if curr_buff.is_table() => Some(0).filter(|_| start > 0),
+        true => context_of(curr_buff.lines(), start).filter(|&header| header < start),
+        false => None,
+    };
+    if let (Some(header), Some(first)) = (context, lines.first_mut()) {
+        let (header_line, marks) = match curr_buff.rendered_line(header) {
+            Some((text, marks)) => {
+                let marks = marks
+                    .into_iter()
+                    .map(|(r, mark)| (r, mark_style(mark, STICKY)));
+                (Cow::Owned(text), marks.collect())
+            }
+            None => (
+                Cow::Borrowed(curr_buff.lines()[header].as_str()),
+                Vec::new(),
+            ),
+        };
+        let (header_line, marks) = caret::notate(&header_line, marks, STICKY, CONTROL_FG);
+        let (header_line, marks) = tab::expand(&header_line, marks, tab_width);
+        *first = get_line(
+            &gutter,
+            header + 1,
+            &header_line,
+            hscroll,
+            width,
+            STICKY,
+            &marks,
+        );
+    }
+    lines
+}
+
+fn change_style(change: Change, style: Style) -> Style {
+    match change {
+        Change::Added => style.fg(ADDED_FG),
+        Change::Removed => style.fg(REMOVED_FG),
+        Change::Hunk => style.fg(TYPE_FG),
+        Change::Header => style.add_modifier(Modifier::BOLD),
+    }
+}
+
+#[cfg(feature = "syntax")]
+fn token_fg(token: Token) -> Color {
+    match token {
+        Token::Keyword | Token::Section => KEYWORD_FG,
+        Token::Key => KEY_FG,
+        Token::Type => TYPE_FG,
+        Token::String => STRING_FG,
+        Token::Number => NUMBER_FG,
+        Token::Comment => COMMENT_FG,
+    }
+}
+
+// How a part of a rendered Markdown line is shown on a row in `style`.
+fn mark_style(mark: Mark, style: Style) -> Style {
+    match mark {
+        Mark::Heading(1) => style
+            .fg(KEYWORD_FG)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        Mark::Heading(2) => style.fg(KEYWORD_FG).add_modifier(Modifier::BOLD),
+        Mark::Heading(_) => style.fg(TYPE_FG).add_modifier(Modifier::BOLD),
+        Mark::Bold => style.add_modifier(Modifier::BOLD),
+        Mark::Italic => style.add_modifier(Modifier::ITALIC),
+        Mark::Code | Mark::CodeBlock => style.fg(STRING_FG),
+        Mark::Link => style.fg(TYPE_FG).add_modifier(Modifier::UNDERLINED),
+        Mark::Decoration => style.fg(COMMENT_FG),
+        Mark::Styled(styled) => style.patch(styled),
+    }
+}
+
+/// Line number column in front of every row of the View pane. Its width
+/// depends on the number of lines in the file so that numbers stay aligned
+/// while scrolling.
+struct Gutter {
+    mode: LineNumbers,
+    digits: usize,
+    current: usize,
+}
+
+impl Gutter {
+    fn new(mode: LineNumbers, lines: usize, current: usize) -> Self {
+        let digits = lines.max(1).ilog10() as usize + 1;
+        Gutter {
+            mode,
+            digits,
+            current,
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self.mode {
+            LineNumbers::Off => 0,
+            _ => self.digits + 3, // 1 (separator) + 2 (padding)
+        }
+    }
+
+    // Continuation rows of a wrapped line don't carry a number.
+    fn span(&self, number: Option<usize>, separator: char, style: Style) -> Span<'static> {
+        let number = match (self.mode, number) {
+            (LineNumbers::Off, _) => return Span::raw(""),
+            (_, None) => String::new(),
+            (LineNumbers::Relative, Some(n)) if n != self.current => {
+                n.abs_diff(self.current).to_string()
+            }
+            (_, Some(n)) => n.to_string(),
+        };
+        let digits = self.digits;
+        let style = if style == BLOCK { FADE } else { style };
+        Span::styled(format!("{number:>digits$}{separator}  "), style)
+    }
+}
+
+// A single numbered line cut down to the visible columns. Arrows on either
+// side tell that the line continues off-screen.
+fn get_line(
+    gutter: &Gutter,
+    number: usize,
+    line: &str,
+    hscroll: usize,
+    width: usize,
+    style: Style,
+    matches: &[(Range<usize>, Style)],
+) -> Line<'static> {
+    let left = if hscroll > 0 && !line.is_empty() {
+        '«'
+    } else {
+        '|'
+    };
+    // The characters scrolled past, by the columns they take up. A wide one
+    // cut by the left edge leaves a space, so that those after it keep their
+    // places for the matches.
+    let mut chars = line.chars().peekable();
+    let (mut skipped, mut column) = (0, 0);
+    while column < hscroll || column > 0 && chars.peek().is_some_and(|&c| c.width() == Some(0)) {
+        let Some(c) = chars.next() else {
+            break;
+        };
+        column += c.width().unwrap_or_default();
+        skipped += 1;
+    }
+    let cut = column.saturating_sub(hscroll).min(1);
+    let rest = chars.collect::<Vec<_>>();
+    let overflow = cut
+        + rest
+            .iter()
+            .map(|c| c.width().unwrap_or_default())
+            .sum::<usize>()
+        > width;
+    // The last column is left for the arrow telling there's more.
+    let room = if overflow {
+        width.saturating_sub(1)
+    } else {
+        width
+    };
+    let mut text = " ".repeat(cut);
+    let mut used = cut;
+    for c in rest {
+        let columns = c.width().unwrap_or_default();
+        if used + columns > room {
+            break;
+        }
+        text.push(c);
+        used += columns;
+    }
+    if overflow || style != BLOCK {
+        // Paint the whole row, not only the text on it.
+        text.extend(std::iter::repeat_n(' ', room.saturating_sub(used)));
+    }
+
+    let mut spans = vec![gutter.span(Some(number), left, style)];
+    spans.append(&mut highlight_matches(text, skipped - cut, matches, style));
+    if overflow {
+        spans.push(Span::styled("»", FADE));
+    }
+    Line::from(spans)
+}
+
+// Split the part of a line starting at character `offset` into spans, so that
+// the search matches on it stand out in their own style.
+fn highlight_matches(
+    text: String,
+    offset: usize,
+    matches: &[(Range<usize>, Style)],
+    style: Style,
+) -> Vec<Span<'static>> {
+    if matches.is_empty() {
+        return vec![Span::styled(text, style)];
+    }
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = String::new();
+    let mut run_style = style;
+    for (i, c) in text.chars().enumerate() {
+        let char_style = matches
+            .iter()
+            .find(|(m, _)| m.contains(&(offset + i)))
+            .map_or(style, |&(_, s)| s);
+        if char_style != run_style && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_style = char_style;
+        run.push(c);
+    }
+    spans.push(Span::styled(run, run_style));
+    spans
+}
+
+// Split a line into rows of at most `width` columns, breaking after the last
+// whitespace of a row when there is one.
+fn wrap_line(line: &str, width: usize) -> Vec<&str> {
+    let mut rows = Vec::new();
+    let mut rest = line;
+    while let Some(cut) = wrap_at(rest, width.max(1)) {
+        let brk = rest[..cut]
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(cut);
+        rows.push(&rest[..brk]);
+        rest = &rest[brk..];
+    }
+    rows.push(rest);
+    rows
+}
+
+// The byte offset of the first character of `line` going past `width`
+// columns, none if it all fits. A row takes at least one character, if it's
+// wider than the row.
+fn wrap_at(line: &str, width: usize) -> Option<usize> {
+    let mut used = 0;
+    for (i, c) in line.char_indices() {
+        used += c.width().unwrap_or_default();
+        if used > width {
+            return Some(i)
+                .filter(|&i| i > 0)
+                .or_else(|| line.char_indices().nth(1).map(|(i, _)| i));
+        }
+    }
+    None
+}
+
+// The row of `rows`, as split by `wrap_line`, holding character `index`.
+fn row_of(rows: &[&str], index: usize) -> usize {
+    let mut start = 0;
+    for (row, part) in rows.iter().enumerate() {
+        start += part.chars().count();
+        if index < start {
+            return row;
+        }
+    }
+    rows.len().saturating_sub(1)
+}
+
+// Position of the cursor line within the file. The percentage is only known
+// once the file has been read to its end.
+fn get_status(res: &Resource, file: usize) -> String {
+    let Some(curr_buff) = res.files().get_file_buff(file) else {
+        return String::new();
+    };
+    let (line, len) = (curr_buff.view().cursor() + 1, curr_buff.len());
+    let position = if let Some((start, end)) = curr_buff.view().selection() {
+        format!(" {line}/{len}  {} selected ", end - start + 1)
+    } else if res.autoscroll().is_running() {
+        let rate = res.autoscroll().rate();
+        format!(" {line}/{len}  auto {rate:.1} l/s ")
+    } else if curr_buff.is_tail() {
+        format!(" {line}/{len}  follow ")
+    } else if !curr_buff.is_loaded() {
+        format!(" {line}/{len}+  --% ")
+    } else {
+        format!(" {line}/{len}  {}% ", line * 100 / len.max(1))
+    };
+    // What the control characters of the cursor line are, shown in caret
+    // notation on it.
+    let controls = match curr_buff.rendered_line(line - 1) {
+        Some(_) => None,
+        None => curr_buff
+            .lines()
+            .get(line - 1)
+            .and_then(|l| caret::describe(l)),
+    };
+    let position = match controls {
+        Some(controls) => format!(" {controls} {position}"),
+        None => position,
+    };
+    if !res.search().is_active() {
+        return position;
+    }
+    let count = curr_buff.match_count(res.search());
+    let filtered = match curr_buff.view().is_filtered() {
+        true => ", filtered",
+        false => "",
+    };
+    match curr_buff.match_index(res.search()) {
+        Some(index) => format!(" match {index}/{count}{filtered} {position}"),
+        None if count == 1 => format!(" 1 match{filtered} {position}"),
+        None => format!(" {count} matches{filtered} {position}"),
+    }
+}
+
+fn get_toast(res: &Resource) -> Span<'_> {
+    match res.toast().message() {
+        Some(message) => Span::styled(format!(" {message} "), HIGHLIGHT),
+        None => Span::raw(""),
+    }
+}
+
+// The file at `file` in a split of the View area, the status and toasts
+// going along with the focused split.
+fn ui_file(frame: &mut Frame, area: Rect, res: &Resource, file: usize, focused: bool) {
+    let toast = match focused {
+        true => get_toast(res),
+        false => Span::raw(""),
+    };
+    let mut block = Block::default()
+        .borders(border!(ALL))
+        .title(
+            Title::from(get_status(res, file))
+                .position(Position::Bottom)
+                .alignment(Alignment::Right),
+        )
+        .title(
+            Title::from(toast)
+                .position(Position::Bottom)
+                .alignment(Alignment::Left),
+        )
+        .border_style(get_cursor_shade_from_condition(
+            focused && res.pointer().cursor_at::<View>(),
+        ))
+        .border_type(BORDER)
+        .style(Style::default().bg(RBG).fg(RFG));
+    // Splits tell apart the files they show.
+    if res.splits().count() > 1 {
+        if let Some(buff) = res.files().get_file_buff(file) {
+            block = block
+                .title(format!(" {} ", buff.name()))
+                .title_alignment(Alignment::Center);
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(get_lines_from_buffer(res, file, area)).block(block),
+        area,
+    );
+
+    ui_ruler(frame, area, res, file);
+    if res.config().minimap() {
+        ui_minimap(frame, area, res, file);
+    }
+    ui_scrollbar(frame, area, res, file);
+}
+
+// Every tab by the name of the file its focused split shows, the current
+// one lit.
+fn ui_tabs(frame: &mut Frame, area: Rect, res: &Resource) {
+    let current = res.tabs().current();
+    let name = |tab: usize| {
+        let file = match tab == current {
+            true => res.files().get_file_buff(res.file_list_state().index()),
+            false => res
+                .tabs()
+                .path(tab)
+                .and_then(|path| res.files().iter().find(|f| f.path() == path)),
+        };
+        file.map_or("", |f| f.name())
+    };
+    let tabs = (0..res.tabs().count())
+        .map(|tab| {
+            let style = if tab == current { CURSORLINE } else { FADE };
+            Span::styled(format!(" {} {} ", tab + 1, name(tab)), style)
+        })
+        .collect::<Vec<_>>();
+    frame.render_widget(
+        Paragraph::new(Line::from(tabs)).style(Style::default().bg(RBG).fg(RFG)),
+        area,
+    );
+}
+
+// A split the focus isn't on. Its view goes into the file buffer it shows
+// for as long as it's drawn.
+fn ui_split(frame: &mut Frame, area: Rect, res: &mut Resource, split: usize) {
+    let Some(pane) = res.splits_mut().pane_mut(split) else {
+        return;
+    };
+    let mut view = std::mem::take(&mut pane.view);
+    let path = pane.path.clone();
+    match res.files().iter().position(|f| f.path() == path) {
+        Some(file) => {
+            res.files()[file].swap_view(&mut view);
+            ui_file(frame, area, res, file, false);
+            res.files()[file].swap_view(&mut view);
+        }
+        None => {
+            let message = format!("{} was closed", path.display());
+            frame.render_widget(
+                Paragraph::new(Line::styled(message, FADE)).block(
+                    Block::default()
+                        .borders(border!(ALL))
+                        .border_style(get_cursor_shade_from_condition(false))
+                        .border_type(BORDER)
+                        .style(Style::default().bg(RBG).fg(RFG)),
+                ),
+                area,
+            );
+        }
+    }
+    if let Some(pane) = res.splits_mut().pane_mut(split) {
+        pane.view = view;
+    }
 }
 
 fn ui_text(frame: &mut Frame, hflex: RectVec, res: &mut Resource) {
+    // The quickfix pane takes the bottom of the View pane's column.
+    let (view_area, quickfix_area) = match res.quickfix().is_visible() {
+        true => {
+            let vflex = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(QUICKFIX_HEIGHT)])
+                .split(hflex[1]);
+            (vflex[0], Some(vflex[1]))
+        }
+        false => (hflex[1], None),
+    };
     let cursor = res.pointer();
 
     frame.render_widget(
         Block::default()
             .borders(border!(ALL))
-            .title(" Files ")
+            .title({
+                let mut order = res
+                    .files()
+                    .sort()
+                    .map_or_else(String::new, |sort| format!(" by {}", sort.name()));
+                if let Some(globs) = res.file_list_state().globs() {
+                    order.push_str(&format!(" [{}]", globs.text()));
+                }
+                match res.file_list_state().filter() {
+                    _ if res.tree().is_visible() => " Tree ".to_string(),
+                    "" => format!(" Files{order} "),
+                    filter => format!(" Files{order}: {filter} "),
+                }
+            })
             .title_alignment(Alignment::Center)
             .border_style(get_cursor_shade_from_condition(cursor.cursor_at::<Files>()))
             .border_type(BORDER)
@@ -126,20 +939,340 @@ fn ui_text(frame: &mut Frame, hflex: RectVec, res: &mut Resource) {
         hflex[0],
     );
 
-    frame.render_widget(
-        Paragraph::new(get_lines_from_buffer(res, hflex[1]))
-            .block(
+    if res.compare().is_some() {
+        ui_compare(frame, view_area, res);
+        if let Some(area) = quickfix_area {
+            ui_quickfix(frame, area, res);
+        }
+        ui_list_box(frame, hflex[0], res);
+        return;
+    }
+
+    // The tabs go above the View area, once there's more than one.
+    let view_area = match res.tabs().count() > 1 {
+        true => {
+            let vflex = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(3)])
+                .split(view_area);
+            ui_tabs(frame, vflex[0], res);
+            vflex[1]
+        }
+        false => view_area,
+    };
+
+    // Every split of the View area but the focused one shows the file it was
+    // left on.
+    let areas = res.splits().areas(view_area);
+    let focused = res.splits().focused();
+    for (split, &area) in areas.iter().enumerate() {
+        if split != focused {
+            ui_split(frame, area, res, split);
+        }
+    }
+    let view_area = areas.get(focused).copied().unwrap_or(view_area);
+    let curr_index = res.file_list_state().index();
+    ui_file(frame, view_area, res, curr_index, true);
+    ui_image(view_area, res);
+    res.mouse_mut().set_view(view_area);
+    if let Some(area) = quickfix_area {
+        ui_quickfix(frame, area, res);
+    }
+    ui_list_box(frame, hflex[0], res);
+}
+
+// Two files side by side, the rows where they differ colored by how, and
+// the parts of the changed lines that differ more so.
+fn ui_compare(frame: &mut Frame, area: Rect, res: &mut Resource) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let toast = get_toast(res).content.into_owned();
+    let Some(compare) = res.compare.as_mut() else {
+        return;
+    };
+    let height = (area.height as usize).saturating_sub(2); // 2 (borders)
+    compare.set_height(height);
+
+    let (left_count, right_count) = compare.line_counts();
+    let gutters = [
+        Gutter::new(LineNumbers::Absolute, left_count, 0),
+        Gutter::new(LineNumbers::Absolute, right_count, 0),
+    ];
+    let widths = [0, 1].map(|i| (halves[i].width as usize).saturating_sub(2 + gutters[i].width()));
+    let hscroll = compare.hscroll();
+    let (left_name, right_name) = compare.names();
+    let tab_widths = [left_name, right_name].map(|name| res.config.tab_width(Path::new(name)));
+    let mut sides = [Vec::new(), Vec::new()];
+    for row in compare.rows().iter().take(height) {
+        let texts = [
+            row.left.map(|i| compare.left_line(i)),
+            row.right.map(|i| compare.right_line(i)),
+        ];
+        let changed = match (row.kind, texts) {
+            (Kind::Changed, [Some(left), Some(right)]) => {
+                let (left, right) = changed_ranges(left, right);
+                [Some(left), Some(right)]
+            }
+            _ => [None, None],
+        };
+        let styles = match row.kind {
+            Kind::Same => [BLOCK, BLOCK],
+            Kind::Removed | Kind::Added | Kind::Changed => {
+                [BLOCK.bg(REMOVED_BG), BLOCK.bg(ADDED_BG)]
+            }
+        };
+        let strong = [REMOVED_STRONG_BG, ADDED_STRONG_BG];
+        let numbers = [row.left, row.right];
+        for i in 0..2 {
+            let line = match (numbers[i], texts[i]) {
+                (Some(number), Some(text)) => {
+                    let matches = changed[i]
+                        .clone()
+                        .map(|range| (range, styles[i].bg(strong[i])))
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    let (text, matches) = caret::notate(text, matches, styles[i], CONTROL_FG);
+                    let (text, matches) = tab::expand(&text, matches, tab_widths[i]);
+                    get_line(
+                        &gutters[i],
+                        number + 1,
+                        &text,
+                        hscroll,
+                        widths[i],
+                        styles[i],
+                        &matches,
+                    )
+                }
+                // Lines on the other side only leave a gap on this one.
+                _ => Line::styled(
+                    format!(
+                        "{}{}",
+                        " ".repeat(gutters[i].width()),
+                        "╱".repeat(widths[i])
+                    ),
+                    FADE,
+                ),
+            };
+            sides[i].push(line);
+        }
+    }
+
+    let status = match compare.changes() {
+        1 => " 1 change ".to_string(),
+        changes => format!(" {changes} changes "),
+    };
+    let titles = [
+        (format!(" {left_name} "), toast),
+        (format!(" {right_name} "), status),
+    ];
+    for (i, ((name, bottom), lines)) in titles.into_iter().zip(sides).enumerate() {
+        let bottom = match i {
+            0 => Title::from(Span::styled(bottom, HIGHLIGHT))
+                .position(Position::Bottom)
+                .alignment(Alignment::Left),
+            _ => Title::from(bottom)
+                .position(Position::Bottom)
+                .alignment(Alignment::Right),
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(
                 Block::default()
                     .borders(border!(ALL))
-                    .border_style(get_cursor_shade_from_condition(cursor.cursor_at::<View>()))
+                    .title(name)
+                    .title_alignment(Alignment::Center)
+                    .title(bottom)
+                    .border_style(BLOCK)
                     .border_type(BORDER)
                     .style(Style::default().bg(RBG).fg(RFG)),
-            )
-            .wrap(Wrap { trim: false }),
-        hflex[1],
-    );
+            ),
+            halves[i],
+        );
+    }
+}
 
-    ui_list_box(frame, hflex[0], res);
+// Have the image shown below its description, unless it'd cover what's in
+// front of the View pane.
+fn ui_image(view_area: Rect, res: &mut Resource) {
+    if res.picker().kind().is_some() || res.confirm().is_some() {
+        return;
+    }
+    let curr_index = res.file_list_state().index();
+    let Some(curr_buff) = res.files.get_file_buff(curr_index) else {
+        return;
+    };
+    let Some(image) = curr_buff.image() else {
+        return;
+    };
+    let inner = view_area.inner(&Margin::new(1, 1));
+    let below = curr_buff.len().saturating_sub(curr_buff.view().shown()) as u16 + 1;
+    let area = Rect {
+        y: inner.y + below.min(inner.height),
+        height: inner.height.saturating_sub(below),
+        ..inner
+    };
+    res.preview.want(curr_buff.path(), image, area);
+}
+
+// A match of a search across files, the lines around it dimmed.
+fn hit_item(hit: &Hit) -> ListItem<'static> {
+    let context = |label: &String| Line::styled(label.clone(), FADE);
+    let lines = hit
+        .before
+        .iter()
+        .map(context)
+        .chain([Line::raw(hit.label.clone())])
+        .chain(hit.after.iter().map(context))
+        .collect::<Vec<Line>>();
+    ListItem::new(lines)
+}
+
+fn ui_quickfix(frame: &mut Frame, area: Rect, res: &mut Resource) {
+    let quickfix = res.quickfix();
+    let title = match quickfix.index() {
+        Some(index) => format!(" Quickfix ({}/{}) ", index + 1, quickfix.hits().len()),
+        None => format!(" Quickfix ({}) ", quickfix.hits().len()),
+    };
+    let items = quickfix
+        .hits()
+        .iter()
+        .map(hit_item)
+        .collect::<Vec<ListItem>>();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(border!(ALL))
+                .title(title)
+                .title_alignment(Alignment::Center)
+                .border_style(FADE)
+                .border_type(BORDER)
+                .style(Style::default().bg(RBG).fg(RFG)),
+        )
+        .highlight_symbol(" ► ")
+        .highlight_style(Style::default().fg(RBG).bg(RFG));
+    frame.render_stateful_widget(list, area, res.quickfix_mut().get_mut());
+}
+
+// Drawn over the right border of the View pane, the thumb spans the share of
+// the file that is on screen.
+fn ui_scrollbar(frame: &mut Frame, area: Rect, res: &Resource, file: usize) {
+    let Some(curr_buff) = res.files().get_file_buff(file) else {
+        return;
+    };
+    let (shown, height) = {
+        let view = curr_buff.view();
+        (view.shown(), view.height())
+    };
+    let len = curr_buff.len();
+    if len <= height {
+        return;
+    }
+    let mut state = ScrollbarState::new(len - height + 1)
+        .position(shown)
+        .viewport_content_length(height);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .track_symbol(None)
+        .thumb_symbol("█")
+        .thumb_style(Style::default().fg(DFG).bg(RBG));
+    frame.render_stateful_widget(scrollbar, area.inner(&Margin::new(0, 1)), &mut state);
+}
+
+// The column past the ruler of the file is shaded down the View pane, where
+// nothing else colors it, to tell the lines reaching beyond it.
+fn ui_ruler(frame: &mut Frame, area: Rect, res: &Resource, file: usize) {
+    let Some(curr_buff) = res.files().get_file_buff(file) else {
+        return;
+    };
+    let Some(ruler) = res.config().ruler(curr_buff.path()) else {
+        return;
+    };
+    if curr_buff.is_nulled()
+        || curr_buff.image().is_some()
+        || curr_buff.is_binary()
+            && !curr_buff.is_hex()
+            && !curr_buff.is_pdf()
+            && !curr_buff.is_archive()
+    {
+        return;
+    }
+    let view = curr_buff.view();
+    let hscroll = match view.is_wrap() {
+        true => 0,
+        false => view.hscroll(),
+    };
+    let lines = curr_buff.line_count().unwrap_or(curr_buff.len());
+    let gutter = Gutter::new(res.config().line_numbers(), lines, 0);
+    let minimap = match res.config().minimap() {
+        true => MINIMAP_WIDTH as usize + 1,
+        false => 0,
+    };
+    let inner = area.inner(&Margin::new(1, 1));
+    let width = (inner.width as usize).saturating_sub(gutter.width() + minimap);
+    let Some(column) = ruler.checked_sub(hscroll).filter(|&c| c < width) else {
+        return;
+    };
+    let x = inner.x + (gutter.width() + column) as u16;
+    let buf = frame.buffer_mut();
+    for y in inner.top()..inner.bottom() {
+        let cell = buf.get_mut(x, y);
+        if cell.bg == RBG {
+            cell.set_bg(RULER_BG);
+        }
+    }
+}
+
+// Every row of the minimap stands for an equal share of the file and is drawn
+// as a bar as long as the lines in that share. Rows within the viewport are lit.
+fn ui_minimap(frame: &mut Frame, area: Rect, res: &Resource, file: usize) {
+    let Some(curr_buff) = res.files().get_file_buff(file) else {
+        return;
+    };
+    let inner = area.inner(&Margin::new(1, 1));
+    let width = MINIMAP_WIDTH.min(inner.width);
+    let area = Rect::new(inner.right() - width, inner.y, width, inner.height);
+
+    let lines = curr_buff.lines();
+    let rows = area.height as usize;
+    if lines.is_empty() || rows == 0 {
+        return;
+    }
+    let per_row = lines.len().div_ceil(rows);
+    let (start, end) = {
+        let view = curr_buff.view();
+        (view.start(), view.end())
+    };
+
+    let minimap = lines
+        .chunks(per_row)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let first = row * per_row;
+            let step = (chunk.len() / MINIMAP_SAMPLES).max(1);
+            let (indent, len) = chunk
+                .iter()
+                .step_by(step)
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| (l.len() - l.trim_start().len(), l.trim_end().len()))
+                .fold((usize::MAX, 0), |(i, n), (li, ln)| (i.min(li), n.max(ln)));
+            let bar = match len {
+                0 => String::new(),
+                _ => {
+                    " ".repeat(indent / MINIMAP_SCALE)
+                        + &"▬".repeat((len - indent).div_ceil(MINIMAP_SCALE))
+                }
+            };
+            let style = match first < end && start < first + chunk.len() {
+                true => Style::default().fg(RFG).bg(DFG),
+                false => FADE,
+            };
+            Line::styled(format!("{bar:<w$.w$}", w = width as usize), style)
+        })
+        .collect::<Vec<Line>>();
+
+    frame.render_widget(Paragraph::new(minimap), area);
 }
 
 fn get_list<'a>(items: &[&'a str]) -> List<'a> {
@@ -163,31 +1296,119 @@ fn ui_list_box(frame: &mut Frame, hflex: Rect, res: &mut Resource) {
         .constraints([Constraint::Percentage(100), Constraint::Min(3)])
         .split(hflex);
 
-    let list_items = res.files().names();
-    let list = get_list(&list_items);
+    if res.tree().is_visible() {
+        let labels = res.tree().labels();
+        let labels = labels.iter().map(String::as_str).collect::<Vec<_>>();
+        let list = get_list(&labels);
+        frame.render_stateful_widget(list, lflex[0], res.tree_mut().get_mut());
+        ui_entry_box(frame, lflex[1], res);
+        return;
+    }
+
+    // The file shown in the View pane stands out from the others.
+    let names = res.files().names();
+    let current = res.file_list_state().index();
+    let selected = res.file_list_state().selected();
+    let icons = res.config().icons();
+    let item = |i: usize, grouped: bool| {
+        let style = match i == current {
+            true => Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            false => Style::default(),
+        };
+        let file = res.files().get_file_buff(i)?;
+        let pin = if file.is_pinned() { "★ " } else { "" };
+        // Changed on disk since it was read, worth reloading.
+        let stale = if file.is_stale() { " ●" } else { "" };
+        let indent = if grouped { "  " } else { "" };
+        let mut spans = vec![Span::raw(format!("{indent}{pin}"))];
+        if let Some((glyph, color)) = icon(file.path(), icons) {
+            spans.push(Span::styled(
+                format!("{glyph} "),
+                Style::default().fg(color),
+            ));
+        }
+        spans.push(Span::raw(format!("{}{stale}", names.get(i)?)));
+        if let Some(encoding) = file.encoding() {
+            // The lines read so far until the whole file is counted.
+            let lines = file.line_count().unwrap_or(file.len());
+            let mut detail = format!("  {} {}", short_count(lines), encoding.name());
+            // A compressed file notes its size on disk too.
+            if let Some((compression, size)) = file.compression() {
+                let size = short_count(size as usize);
+                detail.push_str(&format!(" ({} {size}B)", compression.suffix()));
+            }
+            spans.push(Span::styled(detail, Style::default().fg(DFG)));
+        }
+        // Why a file couldn't be read shows once it's highlighted.
+        if let Some(error) = file.error() {
+            spans.push(Span::styled(" ✗", Style::default().fg(EFG)));
+            if i == selected {
+                spans.push(Span::styled(format!(" {error}"), Style::default().fg(EFG)));
+            }
+        }
+        Some(ListItem::new(Line::from(spans)).style(style))
+    };
+    let list_items = res
+        .file_list_state()
+        .rows()
+        .iter()
+        .filter_map(|row| match row {
+            FileRow::Group {
+                name,
+                files,
+                collapsed,
+            } => {
+                let marker = if *collapsed { "▸" } else { "▾" };
+                let header = format!("{marker} {name} ({})", files.len());
+                Some(ListItem::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+            }
+            FileRow::File { index, grouped } => item(*index, *grouped),
+        })
+        .collect::<Vec<_>>();
+    let list = List::new(list_items)
+        .block(Block::default().border_style(INVISIBLE))
+        .highlight_symbol(" ► ")
+        .highlight_style(Style::default().fg(RBG).bg(RFG));
 
     frame.render_stateful_widget(list, lflex[0], res.file_list_state_mut().get_mut());
+    res.mouse_mut().set_files(lflex[0]);
 
     ui_entry_box(frame, lflex[1], res);
 }
 
+// A count of lines or bytes shortened to fit next to a file name, such as
+// 1.2k or 34M.
+fn short_count(count: usize) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=9_999 => format!("{:.1}k", count as f64 / 1e3),
+        10_000..=999_999 => format!("{}k", count / 1_000),
+        1_000_000..=9_999_999 => format!("{:.1}M", count as f64 / 1e6),
+        _ => format!("{}M", count / 1_000_000),
+    }
+}
+
 fn ui_entry_box(frame: &mut Frame, lflex: Rect, res: &mut Resource) {
     if !res.entry_box().is_visible() {
         return;
     }
 
     let width = lflex.width.saturating_sub(3) as usize; // 2 (borders) + 1 (char)
-    let len = res.entry_box().len().min(width);
-
     let is_err = res.entry_box().is_err();
 
-    let entry_text = res.entry_box().get_span(width);
+    let (entry_text, column) = res.entry_box().get_span(width);
     let entry_box = Paragraph::new(entry_text)
         .block(
             Block::default()
                 .borders(border!(ALL))
                 .border_style([BLOCK, ERR][is_err as usize])
-                .title(" Filename ")
+                .title(match res.entry_box().prompt() {
+                    prompt if prompt.is_search() => res.search().title(prompt.title().trim()),
+                    Prompt::Goto if Substitution::is_command(res.entry_box().get()) => {
+                        " Substitute (preview) ".to_string()
+                    }
+                    prompt => prompt.title().to_string(),
+                })
                 .title_alignment(Alignment::Left)
                 .border_type(BORDER),
         )
@@ -196,7 +1417,115 @@ fn ui_entry_box(frame: &mut Frame, lflex: Rect, res: &mut Resource) {
 
     frame.render_widget(entry_box, lflex);
     frame.set_cursor(
-        lflex.left().saturating_add(len.saturating_add(1) as u16),
+        lflex.left().saturating_add(column.saturating_add(1) as u16),
         lflex.top() + 1,
     );
 }
+
+// A rectangle of the given percentage size in the middle of `area`.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let vflex = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - height) / 2),
+            Constraint::Percentage(height),
+            Constraint::Percentage((100 - height) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - width) / 2),
+            Constraint::Percentage(width),
+            Constraint::Percentage((100 - width) / 2),
+        ])
+        .split(vflex[1])[1]
+}
+
+// The question in a box fitting it, in the middle of the screen.
+fn ui_confirm(frame: &mut Frame, size: Rect, res: &Resource) {
+    let Some(confirm) = res.confirm() else {
+        return;
+    };
+    let text = format!(" {} (y/n) ", confirm.question());
+    let width = (text.chars().count() as u16 + 2).min(size.width);
+    let height = 3.min(size.height);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+    let block = Block::default()
+        .borders(border!(ALL))
+        .title(" Confirm ")
+        .title_alignment(Alignment::Center)
+        .border_type(BORDER)
+        .border_style(BLOCK)
+        .style(Style::default().bg(RBG).fg(RFG));
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(text).block(block), area);
+}
+
+fn get_picker_title(kind: PickerKind, res: &Resource) -> String {
+    if kind != PickerKind::GrepHits {
+        return kind.title().to_string();
+    }
+    let grep = res.grep();
+    let more = if grep.is_running() { "…" } else { "" };
+    format!("{}({}{more}) ", kind.title(), grep.len())
+}
+
+fn ui_picker(frame: &mut Frame, size: Rect, res: &mut Resource) {
+    let Some(kind) = res.picker().kind() else {
+        return;
+    };
+    let area = centered(size, 60, 60);
+    let block = Block::default()
+        .borders(border!(ALL))
+        .title(get_picker_title(kind, res))
+        .title_alignment(Alignment::Center)
+        .border_type(BORDER)
+        .border_style(BLOCK)
+        .style(Style::default().bg(RBG).fg(RFG));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    // The query line goes above the entries it narrows down.
+    let list_area = match res.picker().query() {
+        Some(query) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(inner);
+            let width = rows[0].width.saturating_sub(QUERY_PREFIX.len() as u16 + 1) as usize;
+            let (text, column) = query.get_span(width);
+            let line = Line::from(vec![Span::styled(QUERY_PREFIX, BLOCK), Span::raw(text)]);
+            frame.render_widget(Paragraph::new(line), rows[0]);
+            frame.set_cursor(rows[0].x + (QUERY_PREFIX.len() + column) as u16, rows[0].y);
+            rows[1]
+        }
+        None => inner,
+    };
+
+    // Matches come with the lines around them.
+    let picker = res.picker();
+    let items = picker
+        .indices()
+        .iter()
+        .zip(picker.items())
+        .map(|(&index, item)| {
+            let hit = match kind {
+                PickerKind::SearchHits => res.search().hit(index),
+                PickerKind::GrepHits => res.grep().hit(index),
+                _ => None,
+            };
+            hit.map_or_else(|| ListItem::new(item.to_string()), hit_item)
+        })
+        .collect::<Vec<ListItem>>();
+    let list = List::new(items)
+        .highlight_symbol(" ► ")
+        .highlight_style(Style::default().fg(RBG).bg(RFG));
+    frame.render_stateful_widget(list, list_area, res.picker_mut().get_mut());
+}
@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::fold::*;
+
+/// How long a line stays highlighted after jumping onto it.
+const HIGHLIGHT_DURATION: Duration = Duration::from_millis(800);
+
+/// Fraction of the remaining distance a smooth scroll covers every frame.
+const SCROLL_EASING: usize = 3;
+
+/// Where the cursor line is put in the View pane by `zt`, `zz` and `zb`.
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Scroll position and cursor line of a single file buffer inside the View
+/// pane. Every `FileBuf` owns one, so both are tracked per file.
+#[derive(Clone, Default)]
+pub struct ViewState {
+    offset: usize,
+    cursor: usize,
+    // The offset currently on screen, trailing `offset` while scrolling
+    // smoothly.
+    shown: usize,
+    height: usize,
+    hscroll: usize,
+    wrap: bool,
+    highlight: Option<(usize, Instant)>,
+    marks: HashMap<char, usize>,
+    folds: Folds,
+    // The line a selection was started on, it spans up to the cursor line.
+    selection: Option<usize>,
+    // Which word of the cursor line is the one under the cursor.
+    word: usize,
+}
+
+impl ViewState {
+    #[inline]
+    pub fn new() -> Self {
+        ViewState::default()
+    }
+
+    /// Update the number of lines that fit into the View pane.
+    pub fn resize(&mut self, height: usize) {
+        self.height = height;
+    }
+
+    /// Scroll back up if the View pane isn't filled although the file has
+    /// `len` lines, as after growing the pane or jumping before it was shown.
+    pub fn fit(&mut self, len: usize) {
+        if self.offset > self.max_offset(len) {
+            let offset = self.max_offset(len);
+            self.offset = self.folds.header_of(offset).unwrap_or(offset);
+        }
+    }
+
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.offset
+    }
+
+    /// The line at the top of the View pane in the current frame.
+    #[inline]
+    pub fn shown(&self) -> usize {
+        self.shown
+    }
+
+    /// Move the shown offset a step closer to the actual one. The step shrinks
+    /// on the way, so large jumps ease out over a few frames.
+    pub fn animate(&mut self, smooth: bool) {
+        if !smooth {
+            self.shown = self.offset;
+            return;
+        }
+        let step = self.shown.abs_diff(self.offset).div_ceil(SCROLL_EASING);
+        self.shown = match self.shown < self.offset {
+            true => self.shown + step,
+            false => self.shown - step,
+        };
+        // Don't come to rest in the middle of a fold.
+        if let Some(header) = self.folds.header_of(self.shown) {
+            self.shown = match self.shown < self.offset {
+                true => self.folds.next_visible(header).min(self.offset),
+                false => header.max(self.offset),
+            };
+        }
+    }
+
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.offset.saturating_add(self.height)
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Move the cursor line down, scrolling once it leaves the View pane.
+    /// Folded lines are skipped over as a whole.
+    pub fn cursor_down(&mut self, lines: usize, len: usize) {
+        for _ in 0..lines {
+            let next = self.folds.next_visible(self.cursor);
+            if next >= len {
+                break;
+            }
+            self.cursor = next;
+        }
+        self.follow_cursor();
+    }
+
+    pub fn cursor_up(&mut self, lines: usize) {
+        for _ in 0..lines {
+            if self.cursor == 0 {
+                break;
+            }
+            self.cursor = self.folds.prev_visible(self.cursor);
+        }
+        self.follow_cursor();
+    }
+
+    /// Scroll the View pane without moving the cursor line, unless it would
+    /// get out of sight.
+    pub fn scroll_down(&mut self, lines: usize, len: usize) {
+        self.shift_down(lines, len);
+        self.clamp_cursor();
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.shift_up(lines);
+        self.clamp_cursor();
+    }
+
+    /// Number of columns hidden on the left of the View pane.
+    #[inline]
+    pub fn hscroll(&self) -> usize {
+        self.hscroll
+    }
+
+    #[inline]
+    pub fn is_wrap(&self) -> bool {
+        self.wrap
+    }
+
+    /// Toggle soft wrapping. Wrapped lines never hide columns on the left.
+    pub fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.hscroll = 0;
+    }
+
+    pub fn scroll_left(&mut self, cols: usize) {
+        self.hscroll = self.hscroll.saturating_sub(cols);
+    }
+
+    pub fn scroll_right(&mut self, cols: usize, longest: usize) {
+        let max = longest.saturating_sub(1);
+        self.hscroll = self.hscroll.saturating_add(cols).min(max.max(self.hscroll));
+    }
+
+    // Pages move both the View pane and the cursor line, so the cursor keeps
+    // its row until an end of the file is reached.
+    pub fn page_down(&mut self, len: usize) {
+        self.shift_down(self.height.max(1), len);
+        self.cursor_down(self.height.max(1), len);
+    }
+
+    pub fn page_up(&mut self) {
+        self.shift_up(self.height.max(1));
+        self.cursor_up(self.height.max(1));
+    }
+
+    /// Scroll by half of the last rendered height of the View pane.
+    pub fn half_page_down(&mut self, len: usize) {
+        self.shift_down((self.height / 2).max(1), len);
+        self.cursor_down((self.height / 2).max(1), len);
+    }
+
+    pub fn half_page_up(&mut self) {
+        self.shift_up((self.height / 2).max(1));
+        self.cursor_up((self.height / 2).max(1));
+    }
+
+    pub fn top(&mut self) {
+        self.offset = 0;
+        self.cursor = 0;
+    }
+
+    pub fn bottom(&mut self, len: usize) {
+        let last = len.saturating_sub(1);
+        self.cursor = self.folds.header_of(last).unwrap_or(last);
+        let offset = self.max_offset(len);
+        self.offset = self.folds.header_of(offset).unwrap_or(offset);
+        self.follow_cursor();
+    }
+
+    /// Scroll to `step` of `steps` even steps from the top of the file to its
+    /// end, such as the rows of a scrollbar.
+    pub fn scroll_to(&mut self, step: usize, steps: usize, len: usize) {
+        let offset = self.max_offset(len) * step / steps.saturating_sub(1).max(1);
+        let offset = offset.min(self.max_offset(len));
+        self.offset = self.folds.header_of(offset).unwrap_or(offset);
+        self.clamp_cursor();
+    }
+
+    /// Scroll so that the cursor line ends up at the given part of the View
+    /// pane, as far as the file allows.
+    pub fn anchor_cursor(&mut self, anchor: Anchor, len: usize) {
+        let row = match anchor {
+            Anchor::Top => 0,
+            Anchor::Center => self.height / 2,
+            Anchor::Bottom => self.height.saturating_sub(1),
+        };
+        self.align_cursor(row, len);
+    }
+
+    // Put the cursor line on the given row, as far as the file allows.
+    fn align_cursor(&mut self, row: usize, len: usize) {
+        let offset = self.walk_up(self.cursor, row).min(self.max_offset(len));
+        self.offset = self.folds.header_of(offset).unwrap_or(offset);
+    }
+
+    /// Put the cursor on `line` (0-based), scrolling only as far as needed.
+    pub fn move_cursor(&mut self, line: usize, len: usize) {
+        let line = line.min(len.saturating_sub(1));
+        self.folds.reveal(line);
+        self.cursor = line;
+        self.follow_cursor();
+    }
+
+    /// Put the cursor on `line` (0-based), scroll it to the top of the View
+    /// pane and briefly highlight it.
+    pub fn goto(&mut self, line: usize, len: usize) {
+        let line = line.min(len.saturating_sub(1));
+        self.folds.reveal(line);
+        self.cursor = line;
+        self.offset = line.min(self.max_offset(len));
+        self.highlight = Some((line, Instant::now()));
+    }
+
+    /// The highlighted line if it hasn't faded out yet.
+    pub fn highlighted(&self) -> Option<usize> {
+        self.highlight
+            .filter(|(_, since)| since.elapsed() < HIGHLIGHT_DURATION)
+            .map(|(line, _)| line)
+    }
+
+    /// Remember the cursor line under `name`.
+    pub fn set_mark(&mut self, name: char) {
+        self.marks.insert(name, self.cursor);
+    }
+
+    #[inline]
+    pub fn mark(&self, name: char) -> Option<usize> {
+        self.marks.get(&name).copied()
+    }
+
+    /// Start selecting lines from the cursor line, or drop the selection.
+    pub fn toggle_selection(&mut self) {
+        self.selection = match self.selection {
+            Some(_) => None,
+            None => Some(self.cursor),
+        };
+    }
+
+    /// Drop the selection, telling whether there was one.
+    pub fn clear_selection(&mut self) -> bool {
+        self.selection.take().is_some()
+    }
+
+    /// First and last selected line.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.selection?;
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    /// Index of the word under the cursor among the words of the cursor
+    /// line. It can go past the last word, which stands for it then.
+    #[inline]
+    pub fn word(&self) -> usize {
+        self.word
+    }
+
+    /// Put the cursor on the next word of the cursor line, having `words`.
+    pub fn next_word(&mut self, words: usize) {
+        self.word = (self.word + 1).min(words.saturating_sub(1));
+    }
+
+    pub fn prev_word(&mut self, words: usize) {
+        self.word = self.word.min(words.saturating_sub(1)).saturating_sub(1);
+    }
+
+    #[inline]
+    pub fn folds(&self) -> &Folds {
+        &self.folds
+    }
+
+    /// Toggle the fold at the cursor line.
+    pub fn toggle_fold(&mut self, lines: &[String], nesting: Nesting) {
+        if let Some(header) = self.folds.toggle(self.cursor, lines, nesting) {
+            self.cursor = header;
+            self.follow_cursor();
+        }
+    }
+
+    pub fn open_folds(&mut self) {
+        self.folds.open_all();
+    }
+
+    #[inline]
+    pub fn is_filtered(&self) -> bool {
+        self.folds.is_filtered()
+    }
+
+    /// Show only `lines`, which have to be in ascending order.
+    pub fn set_filter(&mut self, lines: Vec<usize>) {
+        self.folds.set_filter(lines);
+        self.snap();
+    }
+
+    /// Show `lines` as well, which have to come after the ones shown so far.
+    pub fn extend_filter(&mut self, lines: Vec<usize>) {
+        self.folds.extend_filter(lines);
+        self.snap();
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.folds.clear_filter();
+        self.follow_cursor();
+    }
+
+    /// Move the cursor and the View pane off the lines that are hidden.
+    pub fn snap(&mut self) {
+        let (cursor, offset) = (self.cursor, self.offset);
+        self.cursor = self.folds.header_of(cursor).unwrap_or(cursor);
+        self.offset = self.folds.header_of(offset).unwrap_or(offset);
+        if (self.cursor, self.offset) != (cursor, offset) {
+            self.follow_cursor();
+        }
+    }
+
+    /// Scroll down until the first `rows` rows of the cursor line fit below
+    /// the lines above it, `line_rows` telling how many rows a line is
+    /// wrapped onto. Only needed while wrapping, when a line may take more
+    /// than one row.
+    pub fn follow_wrapped(&mut self, line_rows: impl Fn(usize) -> usize, rows: usize) {
+        if self.height == 0 || self.cursor < self.offset {
+            return;
+        }
+        let offset = self.offset;
+        let mut line = self.offset;
+        let mut used = rows;
+        while line < self.cursor {
+            used += line_rows(line);
+            line = self.folds.next_visible(line);
+        }
+        while used > self.height && self.offset < self.cursor {
+            used -= line_rows(self.offset);
+            self.offset = self.folds.next_visible(self.offset);
+        }
+        // Not scrolling smoothly, so the current frame shows it already.
+        if self.shown == offset {
+            self.shown = self.offset;
+        }
+    }
+
+    // Folded lines are skipped over as a whole.
+    fn shift_down(&mut self, lines: usize, len: usize) {
+        for _ in 0..lines {
+            let next = self.folds.next_visible(self.offset);
+            if next > self.max_offset(len) {
+                break;
+            }
+            self.offset = next;
+        }
+    }
+
+    fn shift_up(&mut self, lines: usize) {
+        for _ in 0..lines {
+            if self.offset == 0 {
+                break;
+            }
+            self.offset = self.folds.prev_visible(self.offset);
+        }
+    }
+
+    // Scroll just enough for the cursor line to be visible.
+    fn follow_cursor(&mut self) {
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+            return;
+        }
+        let top = self.walk_up(self.cursor, self.height.saturating_sub(1));
+        self.offset = self.offset.max(top);
+    }
+
+    // The visible line `lines` lines above `line`.
+    fn walk_up(&self, mut line: usize, lines: usize) -> usize {
+        for _ in 0..lines {
+            if line == 0 {
+                break;
+            }
+            line = self.folds.prev_visible(line);
+        }
+        line
+    }
+
+    // Move the cursor line back into the View pane.
+    fn clamp_cursor(&mut self) {
+        let mut last = self.offset;
+        for _ in 1..self.height.max(1) {
+            last = self.folds.next_visible(last);
+        }
+        self.cursor = self.cursor.clamp(self.offset, last.max(self.offset));
+    }
+
+    // The last offset at which the View pane is still completely filled.
+    fn max_offset(&self, len: usize) -> usize {
+        let last = len.saturating_sub(1);
+        let last = self.folds.header_of(last).unwrap_or(last);
+        self.walk_up(last, self.height.saturating_sub(1))
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+use crate::gitignore::*;
+
+/// Call `visit` with every file below `dir` in name order, skipping hidden
+/// files and directories. Walking stops as soon as `visit` returns false.
+pub fn walk(dir: &Path, visit: &mut dyn FnMut(&Path) -> bool) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return true;
+    };
+    let mut entries = entries
+        .filter_map(Result::ok)
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        // Paths below the working directory are shown without a leading `./`.
+        let path = path.strip_prefix(".").unwrap_or(&path);
+        let keep_going = match file_type.is_dir() {
+            true => walk(path, visit),
+            false if file_type.is_file() => visit(path),
+            false => true,
+        };
+        if !keep_going {
+            return false;
+        }
+    }
+    true
+}
+
+/// Call `visit` with every file below `dir` as `walk` does, leaving out the
+/// files and directories the `.gitignore` and `.ignore` files on the way
+/// down list, along with those of the directories above `dir` up to the
+/// root of the git repository holding it.
+pub fn walk_unignored(dir: &Path, visit: &mut dyn FnMut(&Path) -> bool) -> bool {
+    walk_with(dir, &mut ignores_above(dir), visit)
+}
+
+// The ignore files of the directories above `dir` up to the one holding
+// `.git`, outermost first. A directory outside a repository has none.
+fn ignores_above(dir: &Path) -> Vec<Ignore> {
+    let Ok(full) = dir.canonicalize() else {
+        return Vec::new();
+    };
+    let Some(root) = full.ancestors().find(|a| a.join(".git").exists()) else {
+        return Vec::new();
+    };
+    let mut ignores = full
+        .ancestors()
+        .skip(1)
+        .take_while(|above| above.starts_with(root))
+        .map(|above| Ignore::above(above, dir, &full))
+        .filter(|ignore| !ignore.is_empty())
+        .collect::<Vec<_>>();
+    ignores.reverse();
+    ignores
+}
+
+// Walk `dir` with the ignore files of the directories above it, deepest last.
+fn walk_with(dir: &Path, ignores: &mut Vec<Ignore>, visit: &mut dyn FnMut(&Path) -> bool) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return true;
+    };
+    let ignore = Ignore::load(dir);
+    let loaded = !ignore.is_empty();
+    if loaded {
+        ignores.push(ignore);
+    }
+    let mut entries = entries
+        .filter_map(Result::ok)
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut keep_going = true;
+    for entry in entries {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        let path = path.strip_prefix(".").unwrap_or(&path);
+        let is_ignored = ignores
+            .iter()
+            .rev()
+            .find_map(|ignore| ignore.matched(path, file_type.is_dir()))
+            .unwrap_or(false);
+        if is_ignored {
+            continue;
+        }
+        keep_going = match file_type.is_dir() {
+            true => walk_with(path, ignores, visit),
+            false if file_type.is_file() => visit(path),
+            false => true,
+        };
+        if !keep_going {
+            break;
+        }
+    }
+    if loaded {
+        ignores.pop();
+    }
+    keep_going
+}
@@ -0,0 +1,46 @@
+use std::ops::Range;
+
+// Letters, digits and underscores make up words.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The words of `line` as ranges of characters.
+pub fn words(line: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut count = 0;
+    for (i, c) in line.chars().enumerate() {
+        match (is_word_char(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+        count = i + 1;
+    }
+    if let Some(s) = start {
+        words.push(s..count);
+    }
+    words
+}
+
+/// The `index`th word of `line`, the last one if there are fewer.
+pub fn word_at(line: &str, index: usize) -> Option<(Range<usize>, String)> {
+    let words = words(line);
+    let range = words.get(index).or(words.last())?.clone();
+    let word = line.chars().skip(range.start).take(range.len()).collect();
+    Some((range, word))
+}
+
+/// Where `word` shows up in `line` as a whole word, as ranges of characters.
+pub fn occurrences(line: &str, word: &str) -> Vec<Range<usize>> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let len = word.chars().count();
+    words(line)
+        .into_iter()
+        .filter(|r| r.len() == len && chars[r.clone()].iter().copied().eq(word.chars()))
+        .collect()
+}
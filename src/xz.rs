@@ -0,0 +1,524 @@
+use anyhow::anyhow;
+
+use crate::inflate::MAX_INFLATED;
+
+const MAGIC: &[u8] = b"\xFD7zXZ\0";
+const FOOTER_MAGIC: &[u8] = b"YZ";
+const LZMA2_FILTER: u64 = 0x21;
+// Bytes the check of each block takes, by the check type of the stream.
+const CHECK_SIZES: [usize; 16] = [0, 4, 4, 4, 8, 8, 8, 16, 16, 16, 32, 32, 32, 64, 64, 64];
+
+// The parts of the models of LZMA.
+const STATES: usize = 12;
+const POS_STATES: usize = 16;
+const LEN_STATES: usize = 4;
+const END_POS_MODEL: u32 = 14;
+const FULL_DISTANCES: usize = 128;
+const ALIGN_BITS: u32 = 4;
+// Probabilities of the distances spelled out below `FULL_DISTANCES`, the
+// trees of the slots after the first four laid out one after the other.
+const SPECIAL_DISTANCES: usize = FULL_DISTANCES - END_POS_MODEL as usize + 1;
+// A probability that a bit is 0, out of 2^11, starting at an even chance.
+const PROBABILITY_BITS: u32 = 11;
+const EVEN: u16 = 1 << (PROBABILITY_BITS - 1);
+
+/// The data of an xz file, its streams one after the other. Only blocks
+/// filtered with LZMA2 alone, as `xz` writes them by default, are
+/// decompressed, and the checks of the blocks aren't verified.
+pub fn decompress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut at = 0;
+    while at < data.len() {
+        let header = data
+            .get(at..at + 12)
+            .filter(|header| header.starts_with(MAGIC))
+            .ok_or_else(|| anyhow!("Not an xz file"))?;
+        let check = CHECK_SIZES[usize::from(header[7] & 0x0F)];
+        at += 12;
+        // Blocks until the index, which starts with a zero.
+        while *data
+            .get(at)
+            .ok_or_else(|| anyhow!("Xz stream runs past the end"))?
+            != 0
+        {
+            at = block(data, at, check, &mut out)?;
+        }
+        at = skip_index(data, at)?;
+        let footer = data
+            .get(at..at + 12)
+            .ok_or_else(|| anyhow!("Xz stream runs past the end"))?;
+        if !footer.ends_with(FOOTER_MAGIC) {
+            return Err(anyhow!("Damaged xz stream footer"));
+        }
+        at += 12;
+        // Streams may be padded with zeros.
+        while data.get(at) == Some(&0) {
+            at += 1;
+        }
+    }
+    Ok(out)
+}
+
+// A number of the headers and the index, 7 bits to a byte.
+fn varint(data: &[u8], at: &mut usize) -> anyhow::Result<u64> {
+    let mut value = 0;
+    for i in 0..9 {
+        let byte = *data
+            .get(*at)
+            .ok_or_else(|| anyhow!("Xz header runs past the end"))?;
+        *at += 1;
+        value |= u64::from(byte & 0x7F) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("Damaged xz number"))
+}
+
+// Decompress the block at `at`, returning where the next one starts.
+fn block(data: &[u8], at: usize, check: usize, out: &mut Vec<u8>) -> anyhow::Result<usize> {
+    let size = (usize::from(data[at]) + 1) * 4;
+    let header = data
+        .get(at..at + size)
+        .ok_or_else(|| anyhow!("Xz block header runs past the end"))?;
+    let flags = header[1];
+    let mut i = 2;
+    if flags & 0x40 != 0 {
+        varint(header, &mut i)?;
+    }
+    if flags & 0x80 != 0 {
+        varint(header, &mut i)?;
+    }
+    let filters = usize::from(flags & 3) + 1;
+    let filter = varint(header, &mut i)?;
+    if filters > 1 || filter != LZMA2_FILTER {
+        return Err(anyhow!("Xz filter {filter:#x} isn't supported"));
+    }
+    let start = at + size;
+    let read = lzma2(data.get(start..).unwrap_or_default(), out)?;
+    // The data is padded to four bytes, and its check follows.
+    Ok(start + read.next_multiple_of(4) + check)
+}
+
+// Skip the index at `at`, which lists the sizes of the blocks.
+fn skip_index(data: &[u8], at: usize) -> anyhow::Result<usize> {
+    let mut i = at + 1;
+    let records = varint(data, &mut i)?;
+    for _ in 0..records * 2 {
+        varint(data, &mut i)?;
+    }
+    // Padded to four bytes, with a CRC after.
+    Ok((i - at).next_multiple_of(4) + at + 4)
+}
+
+// Decompress the chunks of LZMA2 data at the start of `data`, returning the
+// bytes they took.
+fn lzma2(data: &[u8], out: &mut Vec<u8>) -> anyhow::Result<usize> {
+    let mut lzma = None::<Lzma>;
+    let mut at = 0;
+    // Where the dictionary matches are found in starts, as reset by chunks.
+    let mut start = out.len();
+    loop {
+        let control = *data
+            .get(at)
+            .ok_or_else(|| anyhow!("LZMA2 data runs past the end"))?;
+        at += 1;
+        let size = |at: usize| -> anyhow::Result<usize> {
+            let bytes = data
+                .get(at..at + 2)
+                .ok_or_else(|| anyhow!("LZMA2 chunk runs past the end"))?;
+            Ok((usize::from(bytes[0]) << 8 | usize::from(bytes[1])) + 1)
+        };
+        match control {
+            0 => return Ok(at),
+            1 | 2 => {
+                if control == 1 {
+                    start = out.len();
+                }
+                let len = size(at)?;
+                let chunk = data
+                    .get(at + 2..at + 2 + len)
+                    .ok_or_else(|| anyhow!("LZMA2 chunk runs past the end"))?;
+                out.extend_from_slice(chunk);
+                at += 2 + len;
+            }
+            0x80.. => {
+                let unpacked = (usize::from(control & 0x1F) << 16) + size(at)?;
+                let packed = size(at + 2)?;
+                at += 4;
+                if control >= 0xE0 {
+                    start = out.len();
+                }
+                // New properties, and then also fresh models, start a chunk
+                // as the control byte tells.
+                if control >= 0xC0 {
+                    let props = *data
+                        .get(at)
+                        .ok_or_else(|| anyhow!("LZMA2 chunk runs past the end"))?;
+                    at += 1;
+                    lzma = Some(Lzma::new(props)?);
+                } else if control >= 0xA0 {
+                    let props = lzma
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("LZMA2 chunk without properties"))?
+                        .props;
+                    lzma = Some(Lzma::new(props)?);
+                }
+                let lzma = lzma
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("LZMA2 chunk without properties"))?;
+                let chunk = data
+                    .get(at..at + packed)
+                    .ok_or_else(|| anyhow!("LZMA2 chunk runs past the end"))?;
+                let mut range = Range::new(chunk)?;
+                lzma.decode(&mut range, out, start, out.len() + unpacked)?;
+                at += packed;
+            }
+            _ => return Err(anyhow!("Bad LZMA2 control byte {control:#x}")),
+        }
+        if out.len() > MAX_INFLATED {
+            return Err(anyhow!("Decompresses to more than {MAX_INFLATED} bytes"));
+        }
+    }
+}
+
+// The range decoder the bits of LZMA are read through.
+struct Range<'a> {
+    data: &'a [u8],
+    at: usize,
+    range: u32,
+    code: u32,
+}
+
+impl<'a> Range<'a> {
+    fn new(data: &'a [u8]) -> anyhow::Result<Self> {
+        let head = data
+            .get(..5)
+            .filter(|head| head[0] == 0)
+            .ok_or_else(|| anyhow!("Bad LZMA range coder start"))?;
+        let code = head[1..].iter().fold(0, |n, &b| n << 8 | u32::from(b));
+        Ok(Range {
+            data,
+            at: 5,
+            range: u32::MAX,
+            code,
+        })
+    }
+
+    fn normalize(&mut self) -> anyhow::Result<()> {
+        if self.range < 1 << 24 {
+            let byte = *self
+                .data
+                .get(self.at)
+                .ok_or_else(|| anyhow!("LZMA chunk runs past the end"))?;
+            self.at += 1;
+            self.range <<= 8;
+            self.code = self.code << 8 | u32::from(byte);
+        }
+        Ok(())
+    }
+
+    // A bit by its probability, which learns from it.
+    fn bit(&mut self, probability: &mut u16) -> anyhow::Result<u32> {
+        self.normalize()?;
+        let bound = (self.range >> PROBABILITY_BITS) * u32::from(*probability);
+        if self.code < bound {
+            self.range = bound;
+            *probability += ((1 << PROBABILITY_BITS) - *probability) >> 5;
+            Ok(0)
+        } else {
+            self.range -= bound;
+            self.code -= bound;
+            *probability -= *probability >> 5;
+            Ok(1)
+        }
+    }
+
+    // Bits with even chances, the most significant first.
+    fn direct(&mut self, count: u32) -> anyhow::Result<u32> {
+        let mut value = 0;
+        for _ in 0..count {
+            self.normalize()?;
+            self.range >>= 1;
+            let bit = u32::from(self.code >= self.range);
+            if bit == 1 {
+                self.code -= self.range;
+            }
+            value = value << 1 | bit;
+        }
+        Ok(value)
+    }
+
+    // A number of `count` bits read down a tree of probabilities, the most
+    // significant first.
+    fn tree(&mut self, probabilities: &mut [u16], count: u32) -> anyhow::Result<u32> {
+        let mut m = 1;
+        for _ in 0..count {
+            m = m << 1 | self.bit(&mut probabilities[m as usize])?;
+        }
+        Ok(m - (1 << count))
+    }
+
+    // Likewise, the least significant bit first.
+    fn reverse_tree(&mut self, probabilities: &mut [u16], count: u32) -> anyhow::Result<u32> {
+        let (mut m, mut value) = (1, 0);
+        for i in 0..count {
+            let bit = self.bit(&mut probabilities[m as usize])?;
+            m = m << 1 | bit;
+            value |= bit << i;
+        }
+        Ok(value)
+    }
+}
+
+// The probabilities lengths of matches are read by.
+struct Lengths {
+    choice: u16,
+    choice2: u16,
+    low: [[u16; 8]; POS_STATES],
+    mid: [[u16; 8]; POS_STATES],
+    high: [u16; 256],
+}
+
+impl Lengths {
+    fn new() -> Self {
+        Lengths {
+            choice: EVEN,
+            choice2: EVEN,
+            low: [[EVEN; 8]; POS_STATES],
+            mid: [[EVEN; 8]; POS_STATES],
+            high: [EVEN; 256],
+        }
+    }
+
+    // A length less the shortest one of 2.
+    fn decode(&mut self, range: &mut Range, pos_state: usize) -> anyhow::Result<usize> {
+        if range.bit(&mut self.choice)? == 0 {
+            return Ok(range.tree(&mut self.low[pos_state], 3)? as usize);
+        }
+        if range.bit(&mut self.choice2)? == 0 {
+            return Ok(8 + range.tree(&mut self.mid[pos_state], 3)? as usize);
+        }
+        Ok(16 + range.tree(&mut self.high, 8)? as usize)
+    }
+}
+
+// The models of LZMA, along with the state and the distances of the last
+// matches, which carry over from chunk to chunk.
+struct Lzma {
+    props: u8,
+    lc: u32,
+    lp: u32,
+    pb: u32,
+    state: usize,
+    reps: [usize; 4],
+    is_match: [u16; STATES * POS_STATES],
+    is_rep: [u16; STATES],
+    is_rep0: [u16; STATES],
+    is_rep1: [u16; STATES],
+    is_rep2: [u16; STATES],
+    is_rep0_long: [u16; STATES * POS_STATES],
+    literals: Vec<u16>,
+    slots: [[u16; 64]; LEN_STATES],
+    special: [u16; SPECIAL_DISTANCES],
+    align: [u16; 1 << ALIGN_BITS],
+    lengths: Lengths,
+    rep_lengths: Lengths,
+}
+
+impl Lzma {
+    fn new(props: u8) -> anyhow::Result<Self> {
+        let (lc, lp, pb) = (props % 9, props / 9 % 5, props / 45);
+        if props >= 225 || lc + lp > 4 {
+            return Err(anyhow!("Bad LZMA properties {props:#x}"));
+        }
+        Ok(Lzma {
+            props,
+            lc: u32::from(lc),
+            lp: u32::from(lp),
+            pb: u32::from(pb),
+            state: 0,
+            reps: [0; 4],
+            is_match: [EVEN; STATES * POS_STATES],
+            is_rep: [EVEN; STATES],
+            is_rep0: [EVEN; STATES],
+            is_rep1: [EVEN; STATES],
+            is_rep2: [EVEN; STATES],
+            is_rep0_long: [EVEN; STATES * POS_STATES],
+            literals: vec![EVEN; 0x300 << (lc + lp)],
+            slots: [[EVEN; 64]; LEN_STATES],
+            special: [EVEN; SPECIAL_DISTANCES],
+            align: [EVEN; 1 << ALIGN_BITS],
+            lengths: Lengths::new(),
+            rep_lengths: Lengths::new(),
+        })
+    }
+
+    // Decode literals and matches until `out` reaches `end` bytes, its
+    // dictionary starting at `start`.
+    fn decode(
+        &mut self,
+        range: &mut Range,
+        out: &mut Vec<u8>,
+        start: usize,
+        end: usize,
+    ) -> anyhow::Result<()> {
+        while out.len() < end {
+            let pos_state = (out.len() - start) & ((1 << self.pb) - 1);
+            let state = self.state;
+            if range.bit(&mut self.is_match[state * POS_STATES + pos_state])? == 0 {
+                self.literal(range, out, start)?;
+                continue;
+            }
+            let len = if range.bit(&mut self.is_rep[state])? == 0 {
+                let len = self.lengths.decode(range, pos_state)?;
+                self.state = if state < 7 { 7 } else { 10 };
+                let distance = self.distance(range, len)?;
+                self.reps = [distance, self.reps[0], self.reps[1], self.reps[2]];
+                len
+            } else {
+                if range.bit(&mut self.is_rep0[state])? == 0 {
+                    // A single byte from the last distance.
+                    if range.bit(&mut self.is_rep0_long[state * POS_STATES + pos_state])? == 0 {
+                        self.state = if state < 7 { 9 } else { 11 };
+                        let byte = *(out.len() - start)
+                            .checked_sub(self.reps[0] + 1)
+                            .and_then(|i| out.get(start + i))
+                            .ok_or_else(|| anyhow!("LZMA distance reaches before the start"))?;
+                        out.push(byte);
+                        continue;
+                    }
+                } else {
+                    let distance = if range.bit(&mut self.is_rep1[state])? == 0 {
+                        self.reps[1]
+                    } else {
+                        let distance = if range.bit(&mut self.is_rep2[state])? == 0 {
+                            self.reps[2]
+                        } else {
+                            let distance = self.reps[3];
+                            self.reps[3] = self.reps[2];
+                            distance
+                        };
+                        self.reps[2] = self.reps[1];
+                        distance
+                    };
+                    self.reps[1] = self.reps[0];
+                    self.reps[0] = distance;
+                }
+                self.state = if state < 7 { 8 } else { 11 };
+                self.rep_lengths.decode(range, pos_state)?
+            };
+            let from = (out.len() - start)
+                .checked_sub(self.reps[0] + 1)
+                .ok_or_else(|| anyhow!("LZMA distance reaches before the start"))?;
+            // Copied a byte at a time, as the copy may overlap itself.
+            for k in 0..len + 2 {
+                out.push(out[start + from + k]);
+            }
+        }
+        Ok(())
+    }
+
+    fn literal(
+        &mut self,
+        range: &mut Range,
+        out: &mut Vec<u8>,
+        start: usize,
+    ) -> anyhow::Result<()> {
+        let dictionary = &out[start..];
+        let previous = u32::from(dictionary.last().copied().unwrap_or_default());
+        let position = dictionary.len() as u32 & ((1 << self.lp) - 1);
+        let index = ((position << self.lc) + (previous >> (8 - self.lc))) as usize;
+        let probabilities = &mut self.literals[0x300 * index..0x300 * (index + 1)];
+        let mut symbol = 1;
+        // After a match, the byte at the last distance guides the bits.
+        if self.state >= 7 {
+            let mut matched = dictionary
+                .len()
+                .checked_sub(self.reps[0] + 1)
+                .and_then(|i| dictionary.get(i))
+                .map_or(0, |&b| u32::from(b));
+            while symbol < 0x100 {
+                let matched_bit = matched >> 7 & 1;
+                matched <<= 1;
+                let bit =
+                    range.bit(&mut probabilities[(((1 + matched_bit) << 8) + symbol) as usize])?;
+                symbol = symbol << 1 | bit;
+                if matched_bit != bit {
+                    break;
+                }
+            }
+        }
+        while symbol < 0x100 {
+            symbol = symbol << 1 | range.bit(&mut probabilities[symbol as usize])?;
+        }
+        out.push(symbol as u8);
+        self.state = match self.state {
+            0..=3 => 0,
+            4..=9 => self.state - 3,
+            _ => self.state - 6,
+        };
+        Ok(())
+    }
+
+    // The distance of a match less one, for a length `len` less 2.
+    fn distance(&mut self, range: &mut Range, len: usize) -> anyhow::Result<usize> {
+        let slot = range.tree(&mut self.slots[len.min(LEN_STATES - 1)], 6)?;
+        if slot < 4 {
+            return Ok(slot as usize);
+        }
+        let direct = (slot >> 1) - 1;
+        let mut distance = (2 | (slot & 1)) << direct;
+        if slot < END_POS_MODEL {
+            let base = (distance - slot) as usize;
+            distance += range.reverse_tree(&mut self.special[base..], direct)?;
+        } else {
+            distance += range.direct(direct - ALIGN_BITS)? << ALIGN_BITS;
+            distance += range.reverse_tree(&mut self.align, ALIGN_BITS)?;
+        }
+        Ok(distance as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `hello hello hello hello xz\n` as `xz -9 --check=crc32` writes it.
+    const HELLO: &[u8] = &[
+        0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x01, 0x69, 0x22, 0xde, 0x36, 0x04, 0xc0, 0x17,
+        0x1b, 0x21, 0x01, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x2a,
+        0xcf, 0x93, 0xe0, 0x00, 0x1a, 0x00, 0x0f, 0x5d, 0x00, 0x34, 0x19, 0x49, 0xee, 0x8d, 0xe9,
+        0x56, 0x0c, 0x61, 0x3c, 0x50, 0xd9, 0x98, 0x88, 0x00, 0x00, 0x00, 0xda, 0x2c, 0xc4, 0xbe,
+        0x00, 0x01, 0x2f, 0x1b, 0xaa, 0x44, 0xff, 0xb8, 0x90, 0x42, 0x99, 0x0d, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x01, 0x59, 0x5a,
+    ];
+
+    #[test]
+    fn decompresses_a_stream() {
+        let out = decompress(HELLO).unwrap();
+        assert_eq!(out, b"hello hello hello hello xz\n");
+    }
+
+    #[test]
+    fn rejects_a_truncated_stream() {
+        for len in [4, 12, 30, 45, HELLO.len() - 5] {
+            assert!(decompress(&HELLO[..len]).is_err(), "cut at {len}");
+        }
+    }
+
+    #[test]
+    fn rejects_a_corrupt_stream() {
+        let mut data = HELLO.to_vec();
+        data[0] ^= 0xFF;
+        assert!(decompress(&data).is_err());
+        let mut data = HELLO.to_vec();
+        // A filter other than LZMA2.
+        data[16] = 0x03;
+        assert!(decompress(&data).is_err());
+        let mut data = HELLO.to_vec();
+        // An LZMA2 chunk control byte that doesn't exist.
+        data[32] = 0x03;
+        assert!(decompress(&data).is_err());
+    }
+}
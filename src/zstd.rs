@@ -0,0 +1,799 @@
+use anyhow::anyhow;
+
+use crate::inflate::MAX_INFLATED;
+
+const MAGIC: u32 = 0xFD2F_B528;
+// Frames of other data a zstd file may hold, to be skipped.
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+const MAX_HUFFMAN_BITS: u32 = 11;
+
+// The lengths of literals and matches each code of theirs stands for, the
+// least of them and the extra bits making up the rest.
+const LITERAL_LENGTHS: [(u32, u32); 36] = [
+    (0, 0),
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 0),
+    (12, 0),
+    (13, 0),
+    (14, 0),
+    (15, 0),
+    (16, 1),
+    (18, 1),
+    (20, 1),
+    (22, 1),
+    (24, 2),
+    (28, 2),
+    (32, 3),
+    (40, 3),
+    (48, 4),
+    (64, 6),
+    (128, 7),
+    (256, 8),
+    (512, 9),
+    (1024, 10),
+    (2048, 11),
+    (4096, 12),
+    (8192, 13),
+    (16384, 14),
+    (32768, 15),
+    (65536, 16),
+];
+const MATCH_LENGTHS: [(u32, u32); 53] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 0),
+    (12, 0),
+    (13, 0),
+    (14, 0),
+    (15, 0),
+    (16, 0),
+    (17, 0),
+    (18, 0),
+    (19, 0),
+    (20, 0),
+    (21, 0),
+    (22, 0),
+    (23, 0),
+    (24, 0),
+    (25, 0),
+    (26, 0),
+    (27, 0),
+    (28, 0),
+    (29, 0),
+    (30, 0),
+    (31, 0),
+    (32, 0),
+    (33, 0),
+    (34, 0),
+    (35, 1),
+    (37, 1),
+    (39, 1),
+    (41, 1),
+    (43, 2),
+    (47, 2),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 5),
+    (131, 7),
+    (259, 8),
+    (515, 9),
+    (1027, 10),
+    (2051, 11),
+    (4099, 12),
+    (8195, 13),
+    (16387, 14),
+    (32771, 15),
+    (65539, 16),
+];
+// The distributions the codes are taken to follow unless a block gives its
+// own, with their accuracy.
+const LITERAL_LENGTH_DEFAULT: (&[i16], u32) = (
+    &[
+        4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1,
+        1, 1, -1, -1, -1, -1,
+    ],
+    6,
+);
+const MATCH_LENGTH_DEFAULT: (&[i16], u32) = (
+    &[
+        1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+    ],
+    6,
+);
+const OFFSET_DEFAULT: (&[i16], u32) = (
+    &[
+        1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+    ],
+    5,
+);
+
+/// The data of a zstd file, its frames one after the other. Frames needing
+/// a dictionary can't be decompressed, and their checksums aren't
+/// verified.
+pub fn decompress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut at = 0;
+    while at < data.len() {
+        let magic = u32_at(data, at)?;
+        if magic & 0xFFFF_FFF0 == SKIPPABLE_MAGIC {
+            at += 8 + u32_at(data, at + 4)? as usize;
+            continue;
+        }
+        if magic != MAGIC {
+            return Err(anyhow!("Not a zstd file"));
+        }
+        at = frame(data, at + 4, &mut out)?;
+    }
+    Ok(out)
+}
+
+fn u32_at(data: &[u8], at: usize) -> anyhow::Result<u32> {
+    let bytes = data
+        .get(at..at + 4)
+        .ok_or_else(|| anyhow!("Zstd frame runs past the end"))?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn byte_at(data: &[u8], at: usize) -> anyhow::Result<u8> {
+    data.get(at)
+        .copied()
+        .ok_or_else(|| anyhow!("Zstd frame runs past the end"))
+}
+
+// Decompress the frame whose header is at `at`, returning where the next
+// one starts.
+fn frame(data: &[u8], mut at: usize, out: &mut Vec<u8>) -> anyhow::Result<usize> {
+    let descriptor = byte_at(data, at)?;
+    at += 1;
+    let single_segment = descriptor & 0x20 != 0;
+    let checksum = descriptor & 0x04 != 0;
+    if !single_segment {
+        at += 1;
+    }
+    let dictionary = [0, 1, 2, 4][usize::from(descriptor & 3)];
+    if data
+        .get(at..at + dictionary)
+        .is_some_and(|id| id.iter().any(|&b| b != 0))
+    {
+        return Err(anyhow!("Zstd frames needing a dictionary aren't supported"));
+    }
+    at += dictionary;
+    at += match descriptor >> 6 {
+        0 => usize::from(single_segment),
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    let mut context = Context::new();
+    loop {
+        let header = data
+            .get(at..at + 3)
+            .ok_or_else(|| anyhow!("Zstd block runs past the end"))?;
+        let header = u32::from(header[0]) | u32::from(header[1]) << 8 | u32::from(header[2]) << 16;
+        at += 3;
+        let size = (header >> 3) as usize;
+        match header >> 1 & 3 {
+            0 => {
+                let raw = data
+                    .get(at..at + size)
+                    .ok_or_else(|| anyhow!("Zstd block runs past the end"))?;
+                out.extend_from_slice(raw);
+                at += size;
+            }
+            1 => {
+                out.extend(std::iter::repeat_n(byte_at(data, at)?, size));
+                at += 1;
+            }
+            2 => {
+                let block = data
+                    .get(at..at + size)
+                    .ok_or_else(|| anyhow!("Zstd block runs past the end"))?;
+                context.block(block, out)?;
+                at += size;
+            }
+            _ => return Err(anyhow!("Bad zstd block type")),
+        }
+        if out.len() > MAX_INFLATED {
+            return Err(anyhow!("Decompresses to more than {MAX_INFLATED} bytes"));
+        }
+        if header & 1 != 0 {
+            break;
+        }
+    }
+    Ok(at + if checksum { 4 } else { 0 })
+}
+
+// What the blocks of a frame may take over from the ones before them.
+struct Context {
+    huffman: Option<Huffman>,
+    literal_lengths: Option<Fse>,
+    offsets: Option<Fse>,
+    match_lengths: Option<Fse>,
+    reps: [usize; 3],
+}
+
+impl Context {
+    fn new() -> Self {
+        Context {
+            huffman: None,
+            literal_lengths: None,
+            offsets: None,
+            match_lengths: None,
+            reps: [1, 4, 8],
+        }
+    }
+
+    fn block(&mut self, block: &[u8], out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let (literals, read) = self.literals(block)?;
+        let block = &block[read..];
+        let count = match block {
+            [] | [0, ..] => 0,
+            [n @ 0..=127, ..] => usize::from(*n),
+            [255, a, b, ..] => usize::from(*a) + (usize::from(*b) << 8) + 0x7F00,
+            [n, a, ..] => ((usize::from(*n) - 128) << 8) + usize::from(*a),
+            _ => return Err(anyhow!("Zstd sequences run past the end")),
+        };
+        if count == 0 {
+            out.extend_from_slice(&literals);
+            return Ok(());
+        }
+        let mut at = match block[0] {
+            0..=127 => 1,
+            255 => 3,
+            _ => 2,
+        };
+        let modes = byte_at(block, at)?;
+        at += 1;
+        for (i, (table, default, max_symbol, max_log)) in [
+            (&mut self.literal_lengths, LITERAL_LENGTH_DEFAULT, 35, 9),
+            (&mut self.offsets, OFFSET_DEFAULT, 31, 8),
+            (&mut self.match_lengths, MATCH_LENGTH_DEFAULT, 52, 9),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            match modes >> (6 - 2 * i) & 3 {
+                0 => *table = Some(Fse::new(default.0, default.1)?),
+                1 => {
+                    *table = Some(Fse::rle(byte_at(block, at)?));
+                    at += 1;
+                }
+                2 => {
+                    let (distribution, log, read) =
+                        distribution(&block[at..], max_symbol, max_log)?;
+                    *table = Some(Fse::new(&distribution, log)?);
+                    at += read;
+                }
+                _ if table.is_some() => {}
+                _ => return Err(anyhow!("Zstd table repeated before it's given")),
+            }
+        }
+        let (Some(literal_lengths), Some(offsets), Some(match_lengths)) =
+            (&self.literal_lengths, &self.offsets, &self.match_lengths)
+        else {
+            return Err(anyhow!("Zstd tables missing"));
+        };
+        let mut bits = Backward::new(block.get(at..).unwrap_or_default())?;
+        let mut literal_state = literal_lengths.init(&mut bits);
+        let mut offset_state = offsets.init(&mut bits);
+        let mut match_state = match_lengths.init(&mut bits);
+        let mut literals = literals.as_slice();
+        for i in 0..count {
+            let offset_code = u32::from(offsets.symbol(offset_state));
+            // An RLE table can give any byte, well past the longest offsets.
+            if offset_code > 31 {
+                return Err(anyhow!("Bad zstd offset code {offset_code}"));
+            }
+            let match_code = match_lengths.symbol(match_state) as usize;
+            let literal_code = literal_lengths.symbol(literal_state) as usize;
+            let offset = (1usize << offset_code) + bits.read(offset_code) as usize;
+            let (base, extra) = *MATCH_LENGTHS
+                .get(match_code)
+                .ok_or_else(|| anyhow!("Bad zstd match length code"))?;
+            let match_len = (base + bits.read(extra) as u32) as usize;
+            let (base, extra) = *LITERAL_LENGTHS
+                .get(literal_code)
+                .ok_or_else(|| anyhow!("Bad zstd literal length code"))?;
+            let literal_len = (base + bits.read(extra) as u32) as usize;
+            if i + 1 < count {
+                literal_state = literal_lengths.update(literal_state, &mut bits);
+                match_state = match_lengths.update(match_state, &mut bits);
+                offset_state = offsets.update(offset_state, &mut bits);
+            }
+            let offset = repeat(&mut self.reps, offset, literal_len);
+            let (shown, rest) = literals
+                .split_at_checked(literal_len)
+                .ok_or_else(|| anyhow!("Zstd literals run out"))?;
+            out.extend_from_slice(shown);
+            literals = rest;
+            let start = out
+                .len()
+                .checked_sub(offset)
+                .ok_or_else(|| anyhow!("Zstd offset reaches before the start"))?;
+            // Copied a byte at a time, as the copy may overlap itself.
+            for k in 0..match_len {
+                out.push(out[start + k]);
+            }
+        }
+        out.extend_from_slice(literals);
+        Ok(())
+    }
+
+    // The literals of a block, and the bytes they took up.
+    fn literals(&mut self, block: &[u8]) -> anyhow::Result<(Vec<u8>, usize)> {
+        let b0 = byte_at(block, 0)?;
+        let kind = b0 & 3;
+        let format = b0 >> 2 & 3;
+        let header = |len: usize| -> anyhow::Result<u64> {
+            let bytes = block
+                .get(..len)
+                .ok_or_else(|| anyhow!("Zstd literals run past the end"))?;
+            Ok(bytes.iter().rev().fold(0, |n, &b| n << 8 | u64::from(b)))
+        };
+        if kind < 2 {
+            let (size, read) = match format {
+                0 | 2 => (usize::from(b0 >> 3), 1),
+                1 => ((header(2)? >> 4) as usize, 2),
+                _ => ((header(3)? >> 4) as usize, 3),
+            };
+            let literals = match kind {
+                0 => block
+                    .get(read..read + size)
+                    .ok_or_else(|| anyhow!("Zstd literals run past the end"))?
+                    .to_vec(),
+                _ => vec![byte_at(block, read)?; size],
+            };
+            return Ok((literals, read + if kind == 0 { size } else { 1 }));
+        }
+        let (read, bits, streams) = match format {
+            0 => (3, 10, 1),
+            1 => (3, 10, 4),
+            2 => (4, 14, 4),
+            _ => (5, 18, 4),
+        };
+        let value = header(read)?;
+        let mask = (1 << bits) - 1;
+        let size = (value >> 4 & mask) as usize;
+        let compressed = (value >> (4 + bits) & mask) as usize;
+        let mut data = block
+            .get(read..read + compressed)
+            .ok_or_else(|| anyhow!("Zstd literals run past the end"))?;
+        if kind == 2 {
+            let (huffman, used) = Huffman::read(data)?;
+            self.huffman = Some(huffman);
+            data = &data[used..];
+        }
+        let huffman = self
+            .huffman
+            .as_ref()
+            .ok_or_else(|| anyhow!("Zstd Huffman table repeated before it's given"))?;
+        let mut literals = Vec::with_capacity(size);
+        if streams == 1 {
+            huffman.decode(data, size, &mut literals)?;
+        } else {
+            let jumps = data
+                .get(..6)
+                .ok_or_else(|| anyhow!("Zstd literals run past the end"))?;
+            let len = |i: usize| usize::from(jumps[i]) | usize::from(jumps[i + 1]) << 8;
+            let (a, b, c) = (len(0), len(2), len(4));
+            let each = size.div_ceil(4);
+            let mut at = 6;
+            for (i, len) in [a, b, c, data.len().saturating_sub(6 + a + b + c)]
+                .into_iter()
+                .enumerate()
+            {
+                let stream = data
+                    .get(at..at + len)
+                    .ok_or_else(|| anyhow!("Zstd literal stream runs past the end"))?;
+                let count = if i < 3 {
+                    each
+                } else {
+                    size.saturating_sub(3 * each)
+                };
+                huffman.decode(stream, count, &mut literals)?;
+                at += len;
+            }
+        }
+        Ok((literals, read + compressed))
+    }
+}
+
+// The offset a sequence gives as `value`, which may stand for one of the
+// last three offsets, `reps`.
+fn repeat(reps: &mut [usize; 3], value: usize, literal_len: usize) -> usize {
+    if value > 3 {
+        *reps = [value - 3, reps[0], reps[1]];
+        return reps[0];
+    }
+    // Without literals before it, the repeated offsets count from the
+    // second one.
+    let index = value - 1 + usize::from(literal_len == 0);
+    let offset = match index {
+        0 => return reps[0],
+        1 => reps[1],
+        2 => reps[2],
+        _ => reps[0].saturating_sub(1).max(1),
+    };
+    match index {
+        1 => *reps = [offset, reps[0], reps[2]],
+        _ => *reps = [offset, reps[0], reps[1]],
+    }
+    offset
+}
+
+// Reads a stream of bits from its end backwards, as zstd writes them. Bits
+// read past its start are zeros.
+struct Backward<'a> {
+    data: &'a [u8],
+    // Bits not yet read, counting from the start.
+    left: isize,
+}
+
+impl<'a> Backward<'a> {
+    fn new(data: &'a [u8]) -> anyhow::Result<Self> {
+        // The last byte starts with a marking 1 bit.
+        let last = *data
+            .last()
+            .filter(|&&b| b != 0)
+            .ok_or_else(|| anyhow!("Zstd bit stream without its end mark"))?;
+        let left = data.len() * 8 - 1 - last.leading_zeros() as usize;
+        Ok(Backward {
+            data,
+            left: left as isize,
+        })
+    }
+
+    fn read(&mut self, count: u32) -> u64 {
+        if count == 0 {
+            return 0;
+        }
+        self.left -= count as isize;
+        let (start, count, shift) = match self.left {
+            left if left >= 0 => (left as usize, count, 0),
+            left => {
+                let missing = left.unsigned_abs().min(count as usize) as u32;
+                (0, count - missing, missing)
+            }
+        };
+        if count == 0 {
+            return 0;
+        }
+        let byte = start / 8;
+        let end = (byte + 8).min(self.data.len());
+        let window = self.data[byte..end]
+            .iter()
+            .rev()
+            .fold(0u64, |n, &b| n << 8 | u64::from(b));
+        (window >> (start % 8) & ((1 << count) - 1)) << shift
+    }
+
+    fn is_done(&self) -> bool {
+        self.left <= 0
+    }
+
+    fn is_overflowed(&self) -> bool {
+        self.left < 0
+    }
+}
+
+// A table of finite state entropy decoding: the symbol of each state, the
+// bits read to move on from it and the state those count from.
+struct Fse {
+    log: u32,
+    symbols: Vec<u8>,
+    bits: Vec<u8>,
+    bases: Vec<u16>,
+}
+
+impl Fse {
+    // The table for a distribution of `1 << log` in all, where -1 stands
+    // for a chance of less than 1.
+    fn new(distribution: &[i16], log: u32) -> anyhow::Result<Self> {
+        let size = 1usize << log;
+        let mut symbols = vec![0u8; size];
+        let mut high = size;
+        let mut next = vec![0u32; distribution.len()];
+        for (symbol, &count) in distribution.iter().enumerate() {
+            if count == -1 {
+                high -= 1;
+                symbols[high] = symbol as u8;
+                next[symbol] = 1;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut position = 0;
+        for (symbol, &count) in distribution.iter().enumerate() {
+            if count <= 0 {
+                continue;
+            }
+            next[symbol] = count as u32;
+            for _ in 0..count {
+                symbols[position] = symbol as u8;
+                position = (position + step) & (size - 1);
+                while position >= high {
+                    position = (position + step) & (size - 1);
+                }
+            }
+        }
+        if position != 0 {
+            return Err(anyhow!("Bad zstd distribution"));
+        }
+        let mut bits = vec![0u8; size];
+        let mut bases = vec![0u16; size];
+        for state in 0..size {
+            let symbol = usize::from(symbols[state]);
+            let n = next[symbol];
+            next[symbol] += 1;
+            let width = log - n.ilog2();
+            bits[state] = width as u8;
+            bases[state] = ((n << width) as usize - size) as u16;
+        }
+        Ok(Fse {
+            log,
+            symbols,
+            bits,
+            bases,
+        })
+    }
+
+    // The table always giving `symbol`.
+    fn rle(symbol: u8) -> Self {
+        Fse {
+            log: 0,
+            symbols: vec![symbol],
+            bits: vec![0],
+            bases: vec![0],
+        }
+    }
+
+    fn init(&self, bits: &mut Backward) -> usize {
+        bits.read(self.log) as usize
+    }
+
+    fn symbol(&self, state: usize) -> u8 {
+        self.symbols[state]
+    }
+
+    fn update(&self, state: usize, bits: &mut Backward) -> usize {
+        usize::from(self.bases[state]) + bits.read(u32::from(self.bits[state])) as usize
+    }
+}
+
+// The distribution an FSE table is built from, as written at the start of
+// `data`, with its accuracy and the bytes it took up.
+fn distribution(
+    data: &[u8],
+    max_symbol: usize,
+    max_log: u32,
+) -> anyhow::Result<(Vec<i16>, u32, usize)> {
+    let mut at = 0usize;
+    let mut read = |count: u32, peek: bool| -> u32 {
+        let byte = at / 8;
+        let window = data
+            .get(byte..(byte + 4).min(data.len()))
+            .unwrap_or_default()
+            .iter()
+            .rev()
+            .fold(0u32, |n, &b| n << 8 | u32::from(b));
+        let value = (window >> (at % 8)) & ((1 << count) - 1);
+        if !peek {
+            at += count as usize;
+        }
+        value
+    };
+    let log = read(4, false) + 5;
+    if log > max_log {
+        return Err(anyhow!("Zstd distribution too accurate"));
+    }
+    let mut remaining = 1i32 << log;
+    let mut distribution = Vec::new();
+    while remaining > 0 && distribution.len() <= max_symbol {
+        let bits = (remaining + 1).ilog2() + 1;
+        let lower = (1u32 << (bits - 1)) - 1;
+        let threshold = (1u32 << bits) - 1 - (remaining as u32 + 1);
+        let mut value = read(bits, true);
+        if value & lower < threshold {
+            value &= lower;
+            read(bits - 1, false);
+        } else {
+            if value > lower {
+                value -= threshold;
+            }
+            read(bits, false);
+        }
+        let count = value as i16 - 1;
+        remaining -= i32::from(count.abs());
+        distribution.push(count);
+        if count == 0 {
+            loop {
+                let repeat = read(2, false);
+                distribution.extend(std::iter::repeat_n(0, repeat as usize));
+                if repeat != 3 {
+                    break;
+                }
+            }
+        }
+    }
+    if remaining != 0 || distribution.len() > max_symbol + 1 {
+        return Err(anyhow!("Bad zstd distribution"));
+    }
+    Ok((distribution, log, at.div_ceil(8)))
+}
+
+// A Huffman table of literals, looked up by the next `log` bits.
+struct Huffman {
+    log: u32,
+    symbols: Vec<u8>,
+    bits: Vec<u8>,
+}
+
+impl Huffman {
+    // The table described at the start of `data`, and the bytes it took.
+    fn read(data: &[u8]) -> anyhow::Result<(Self, usize)> {
+        let header = usize::from(byte_at(data, 0)?);
+        let (mut weights, read) = if header < 128 {
+            // The weights are compressed by FSE, two states taking turns.
+            let described = data
+                .get(1..1 + header)
+                .ok_or_else(|| anyhow!("Zstd Huffman weights run past the end"))?;
+            let (distribution, log, used) = distribution(described, 255, 6)?;
+            let fse = Fse::new(&distribution, log)?;
+            let mut bits = Backward::new(&described[used..])?;
+            let mut states = [fse.init(&mut bits), fse.init(&mut bits)];
+            let mut weights = Vec::new();
+            'decode: loop {
+                for i in 0..2 {
+                    weights.push(fse.symbol(states[i]));
+                    states[i] = fse.update(states[i], &mut bits);
+                    if bits.is_overflowed() {
+                        weights.push(fse.symbol(states[1 - i]));
+                        break 'decode;
+                    }
+                }
+                if weights.len() > 255 {
+                    return Err(anyhow!("Too many zstd Huffman weights"));
+                }
+            }
+            (weights, 1 + header)
+        } else {
+            let count = header - 127;
+            let packed = data
+                .get(1..1 + count.div_ceil(2))
+                .ok_or_else(|| anyhow!("Zstd Huffman weights run past the end"))?;
+            let weights = packed
+                .iter()
+                .flat_map(|&b| [b >> 4, b & 0x0F])
+                .take(count)
+                .collect();
+            (weights, 1 + count.div_ceil(2))
+        };
+        // The weight of the last symbol is what makes the sum a power of 2.
+        let sum = weights
+            .iter()
+            .filter(|&&w| w > 0)
+            .map(|&w| 1u32 << (w - 1))
+            .sum::<u32>();
+        if sum == 0 {
+            return Err(anyhow!("Bad zstd Huffman weights"));
+        }
+        let log = sum.ilog2() + 1;
+        let left = (1 << log) - sum;
+        if log > MAX_HUFFMAN_BITS || !left.is_power_of_two() {
+            return Err(anyhow!("Bad zstd Huffman weights"));
+        }
+        weights.push(left.ilog2() as u8 + 1);
+        // Codes are given out from the longest ones, each symbol taking
+        // entries for all the bits after its code.
+        let size = 1usize << log;
+        let mut counts = [0usize; MAX_HUFFMAN_BITS as usize + 2];
+        for &w in &weights {
+            if w > 0 {
+                counts[usize::from(w)] += 1;
+            }
+        }
+        let mut starts = [0usize; MAX_HUFFMAN_BITS as usize + 2];
+        let mut next = 0;
+        for w in 1..=log as usize {
+            starts[w] = next;
+            next += counts[w] << (w - 1);
+        }
+        let mut symbols = vec![0u8; size];
+        let mut bits = vec![0u8; size];
+        for (symbol, &w) in weights.iter().enumerate() {
+            if w == 0 {
+                continue;
+            }
+            let w = usize::from(w);
+            let len = 1 << (w - 1);
+            let start = starts[w];
+            symbols[start..start + len].fill(symbol as u8);
+            bits[start..start + len].fill((log as usize + 1 - w) as u8);
+            starts[w] += len;
+        }
+        Ok((Huffman { log, symbols, bits }, read))
+    }
+
+    // Decode `count` literals from the stream `data`.
+    fn decode(&self, data: &[u8], count: usize, out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let mut bits = Backward::new(data)?;
+        let mask = (1 << self.log) - 1;
+        let mut state = bits.read(self.log) as usize;
+        for _ in 0..count {
+            out.push(self.symbols[state]);
+            let width = u32::from(self.bits[state]);
+            state = ((state << width) & mask) | bits.read(width) as usize;
+        }
+        if !bits.is_done() {
+            log::trace!("Zstd literal stream has bits left over");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `hello hello hello hello zstd\n` as `zstd -19` writes it, a single
+    // block with a repeat in it.
+    const HELLO: &[u8] = &[
+        0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x68, 0x8d, 0x00, 0x00, 0x58, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+        0x20, 0x7a, 0x73, 0x74, 0x64, 0x0a, 0x01, 0x00, 0xf1, 0x4a, 0x11, 0x6c, 0x29, 0x97, 0x3c,
+    ];
+
+    #[test]
+    fn decompresses_a_frame() {
+        let out = decompress(HELLO).unwrap();
+        assert_eq!(out, b"hello hello hello hello zstd\n");
+    }
+
+    #[test]
+    fn rejects_a_truncated_frame() {
+        for len in [3, 8, 20, HELLO.len() - 5] {
+            assert!(decompress(&HELLO[..len]).is_err(), "cut at {len}");
+        }
+    }
+
+    #[test]
+    fn rejects_a_corrupt_frame() {
+        let mut data = HELLO.to_vec();
+        data[0] ^= 0xFF;
+        assert!(decompress(&data).is_err());
+        let mut data = HELLO.to_vec();
+        // A block type that doesn't exist.
+        data[6] |= 0x06;
+        assert!(decompress(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_an_rle_offset_code_too_large() {
+        let data = [
+            0x28, 0xb5, 0x2f, 0xfd, // magic
+            0x20, 0x08, // single segment of 8 bytes
+            0x3d, 0x00, 0x00, // last compressed block of 7 bytes
+            0x00, // no literals
+            0x01, // a single sequence
+            0x54, 0x00, 0xff, 0x00, // RLE tables, offset code 0xFF
+            0x01, // bit stream holding only its end mark
+        ];
+        assert!(decompress(&data).is_err());
+    }
+}